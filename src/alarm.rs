@@ -0,0 +1,114 @@
+//! `--at 07:00`: the player waits paused until then and plays the song it's at (the one
+//! of `--start-track`), its volume rising from silence over [`RAMP`], like an alarm clock
+//!
+//! The waiting and the ramp go through [`crate::remote`], as any remote control would;
+//! pausing during the ramp stops it, at the full volume.
+
+use std::fmt::{Display, Formatter};
+use std::thread::{sleep, spawn};
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::remote::{player_status, send_command, PlaybackState, RemoteCommand};
+
+/// How long the volume takes to rise to where it was
+pub const RAMP: Duration = Duration::from_secs(60);
+/// How often the volume is raised during the ramp
+const RAMP_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest sleep while waiting, so a changed clock or a suspended machine is caught up
+/// with soon
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+const SECONDS_A_DAY: u32 = 24 * 60 * 60;
+
+/// A time of the day, in local time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlarmTime {
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl AlarmTime {
+    /// Seconds from `now` (seconds since midnight) until it's next this time; zero if it's
+    /// this minute
+    pub fn seconds_from(&self, now: u32) -> u32 {
+        let at = self.hour * 3600 + self.minute * 60;
+        match now.checked_sub(at) {
+            Some(x) if x < 60 => 0,
+            _ => (at + SECONDS_A_DAY - now % SECONDS_A_DAY) % SECONDS_A_DAY,
+        }
+    }
+}
+
+impl Display for AlarmTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// The volume `elapsed` into the ramp up to `volume`
+pub fn ramp_volume(elapsed: Duration, volume: f64) -> f64 {
+    let progress = (elapsed.as_secs_f64() / RAMP.as_secs_f64()).min(1.0);
+    // rounded like the volume steps, for what's shown
+    (volume * progress * 100.0).round() / 100.0
+}
+
+/// Seconds since the local midnight
+#[cfg(unix)]
+fn local_time_of_day() -> u32 {
+    let mut tm = unsafe { std::mem::zeroed::<libc::tm>() };
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    unsafe { libc::localtime_r(&now, &mut tm) };
+    (tm.tm_hour * 3600 + tm.tm_min * 60 + tm.tm_sec) as u32
+}
+
+/// In UTC, without a way to tell the local time
+#[cfg(not(unix))]
+fn local_time_of_day() -> u32 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    (now.as_secs() % SECONDS_A_DAY as u64) as u32
+}
+
+/// How long until `at`, like `7 h 12 min`, rounded up
+pub fn time_until(at: AlarmTime) -> String {
+    let minutes = at.seconds_from(local_time_of_day()).div_ceil(60);
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => "less than a minute".into(),
+        (0, m) => format!("{m} min"),
+        (h, 0) => format!("{h} h"),
+        (h, m) => format!("{h} h {m} min"),
+    }
+}
+
+/// Plays at `at` on a thread of its own, ramping the volume up
+pub fn start(at: AlarmTime) {
+    spawn(move || {
+        info!("Alarm at {}, in {}", at, time_until(at));
+        loop {
+            let seconds = at.seconds_from(local_time_of_day());
+            if seconds == 0 {
+                break;
+            }
+            sleep(Duration::from_secs(seconds as u64).min(MAX_SLEEP));
+        }
+        let volume = player_status().volume;
+        info!("Alarm: playing, up to volume {:.2}", volume);
+        send_command(RemoteCommand::SetVolume(0.0));
+        send_command(RemoteCommand::Play);
+        let started = Instant::now();
+        while started.elapsed() < RAMP {
+            sleep(RAMP_INTERVAL);
+            // the status takes a moment to say it's playing
+            let paused = player_status().state != PlaybackState::Playing;
+            if paused && started.elapsed() > Duration::from_secs(2) {
+                info!("Alarm: paused during the ramp");
+                break;
+            }
+            let ramped = ramp_volume(started.elapsed(), volume);
+            send_command(RemoteCommand::SetVolume(ramped));
+        }
+        send_command(RemoteCommand::SetVolume(volume));
+    });
+}
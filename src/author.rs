@@ -0,0 +1,488 @@
+//! `author`: builds the session images of a pseudo-CD from audio files
+//!
+//! 44.1 kHz WAV files (integer or float PCM, mono or stereo, plain or
+//! WAVE_FORMAT_EXTENSIBLE) are converted here; anything else is decoded with `ffmpeg`,
+//! which has to be installed for it. There's no decoding crate like symphonia among the
+//! dependencies, so other formats are left to ffmpeg, an external program like the
+//! cdrskin this feature already needs.
+//!
+//! Every session is written as `<session number>.bin`, padded to whole sectors, so
+//! burning the files in name order gives the layout the player reads: the meta info in
+//! session 1 (as JSON, then a NUL) and one song in each following session. The meta info
+//! records a SHA-256 hash of each song, for `check`. Before anything is burnt, the images
+//! are read back as the player reads the disc.
+//!
+//! The songs come from the command line, named after their files, or from an
+//! [`AuthorPlan`], as [`init`] writes one.
+
+pub mod init;
+
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context};
+use byteorder::{ReadBytesExt, LE};
+use serde::{Deserialize, Serialize};
+
+use crate::burn::clear_burnt;
+use crate::chapters::{parse_chapter_list, Chapter};
+use crate::integrity::{check_song, SongHash};
+use crate::locale::Localized;
+use crate::playback::{duration_from_bytes, AUDIO_SAMPLE_RATE};
+use crate::sha256::Sha256;
+use crate::source;
+use crate::source::DiscSource;
+use crate::{extract_meta_info, format_duration, MetaInfo, Track, SECTOR_SIZE};
+
+/// Decodes any other format to the disc's audio format
+const DECODER: &str = "ffmpeg";
+
+/// The `fmt ` chunk of a WAV file, as far as it's needed
+#[derive(Debug, Clone, Copy)]
+struct WavFormat {
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+    float: bool,
+}
+
+impl WavFormat {
+    /// Whether [`convert_wav`] can handle it; other formats go to [`DECODER`]
+    fn convertible(&self) -> bool {
+        let depth_ok = match self.float {
+            true => matches!(self.bits_per_sample, 32 | 64),
+            false => matches!(self.bits_per_sample, 8 | 16 | 24 | 32),
+        };
+        depth_ok && matches!(self.channels, 1 | 2) && self.sample_rate == AUDIO_SAMPLE_RATE
+    }
+
+    /// Reads one sample from `reader`, as a 16-bit one
+    fn read_sample(&self, reader: &mut impl Read) -> io::Result<i16> {
+        Ok(match (self.float, self.bits_per_sample) {
+            (false, 8) => (reader.read_u8()? as i16 - 128) << 8,
+            (false, 16) => reader.read_i16::<LE>()?,
+            (false, 24) => (reader.read_i24::<LE>()? >> 8) as i16,
+            (false, 32) => (reader.read_i32::<LE>()? >> 16) as i16,
+            (true, 32) => float_sample(reader.read_f32::<LE>()? as f64),
+            (true, 64) => float_sample(reader.read_f64::<LE>()?),
+            _ => unreachable!(),
+        })
+    }
+}
+
+fn float_sample(x: f64) -> i16 {
+    (x * i16::MAX as f64)
+        .round()
+        .clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+/// Reads the header of a WAV file up to its data; `Ok(None)` if it's not a WAV file
+fn read_wav_header(reader: &mut impl Read) -> io::Result<Option<(WavFormat, u32)>> {
+    let mut riff = [0_u8; 12];
+    match reader.read_exact(&mut riff) {
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        r => r?,
+    }
+    if &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+        return Ok(None);
+    }
+    let mut format = None;
+    loop {
+        let mut id = [0_u8; 4];
+        reader.read_exact(&mut id)?;
+        let size = reader.read_u32::<LE>()?;
+        match &id {
+            b"fmt " => {
+                if size < 16 {
+                    return Err(io::Error::other("fmt chunk too short"));
+                }
+                let mut chunk = vec![0_u8; size as usize];
+                reader.read_exact(&mut chunk)?;
+                let mut chunk = &chunk[..];
+                let tag = chunk.read_u16::<LE>()?;
+                let channels = chunk.read_u16::<LE>()?;
+                let sample_rate = chunk.read_u32::<LE>()?;
+                // byte rate and block align
+                chunk = &chunk[6..];
+                let bits_per_sample = chunk.read_u16::<LE>()?;
+                // WAVE_FORMAT_EXTENSIBLE keeps the real tag at the start of its sub-format GUID
+                let tag = match tag {
+                    0xfffe if chunk.len() >= 10 => (&chunk[8..]).read_u16::<LE>()?,
+                    tag => tag,
+                };
+                format = Some(WavFormat {
+                    channels,
+                    sample_rate,
+                    bits_per_sample,
+                    // 1 is integer PCM; anything else that's not float is left to the decoder
+                    float: match tag {
+                        1 => false,
+                        3 => true,
+                        _ => return Ok(None),
+                    },
+                });
+            }
+            b"data" => {
+                return match format {
+                    Some(f) => Ok(Some((f, size))),
+                    None => Err(io::Error::other("data chunk before fmt chunk")),
+                };
+            }
+            _ => {
+                // chunks are padded to even sizes
+                let skip = size as u64 + size as u64 % 2;
+                io::copy(&mut reader.take(skip), &mut io::sink())?;
+            }
+        }
+    }
+}
+
+/// Converts WAV data in `format` to the disc's audio format; returns the bytes written
+fn convert_wav(
+    reader: &mut impl Read,
+    format: WavFormat,
+    writer: &mut impl Write,
+) -> io::Result<u64> {
+    let mut written = 0_u64;
+    loop {
+        let mut frame = [0_i16; 2];
+        for sample in frame.iter_mut().take(format.channels as usize) {
+            *sample = match format.read_sample(reader) {
+                Ok(x) => x,
+                // a partial frame at the end is dropped
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(written),
+                Err(e) => return Err(e),
+            };
+        }
+        if format.channels == 1 {
+            frame[1] = frame[0];
+        }
+        for sample in frame {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+        written += 4;
+    }
+}
+
+/// Decodes `path` with [`DECODER`]; returns the bytes written
+fn decode_external(path: &Path, writer: &mut impl Write) -> anyhow::Result<u64> {
+    let mut child = Command::new(DECODER)
+        .args(["-v", "error", "-nostdin", "-i"])
+        .arg(path)
+        .args(["-f", "s16le", "-acodec", "pcm_s16le", "-ar"])
+        .arg(AUDIO_SAMPLE_RATE.to_string())
+        .args(["-ac", "2", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| {
+            anyhow!(
+                "{} isn't a 44.1 kHz WAV file, and decoding it needs {DECODER}: {e}",
+                path.display()
+            )
+        })?;
+    let written = io::copy(child.stdout.as_mut().unwrap(), writer)?;
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{DECODER} failed on {}: {status}", path.display());
+    }
+    Ok(written)
+}
+
+/// Writes zeros after `written` bytes up to the next sector boundary
+fn pad_to_sector(writer: &mut impl Write, written: u64) -> io::Result<()> {
+    let padding = (SECTOR_SIZE - written % SECTOR_SIZE) % SECTOR_SIZE;
+    io::copy(&mut io::repeat(0).take(padding), writer)?;
+    Ok(())
+}
+
+/// Passes writes on to `inner`, hashing them
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes the PCM data of `input` to `output`; returns its hash, of the data before the
+/// padding
+fn write_audio_session(input: &Path, output: &Path) -> anyhow::Result<SongHash> {
+    let mut writer = HashingWriter {
+        inner: BufWriter::new(File::create(output)?),
+        hasher: Sha256::new(),
+    };
+    let mut reader = BufReader::new(File::open(input)?);
+    let written = match read_wav_header(&mut reader)? {
+        Some((format, data_size)) if format.convertible() => {
+            // some writers leave the size 0 or 0xffffffff when streaming; read to the end then
+            let data_size = match data_size {
+                0 | u32::MAX => u64::MAX,
+                x => x as u64,
+            };
+            convert_wav(&mut (&mut reader).take(data_size), format, &mut writer)?
+        }
+        _ => decode_external(input, &mut writer)?,
+    };
+    if written == 0 {
+        bail!("{} has no audio", input.display());
+    }
+    let HashingWriter { mut inner, hasher } = writer;
+    pad_to_sector(&mut inner, written)?;
+    inner.flush()?;
+    Ok(SongHash {
+        sha256: hasher.finish_hex(),
+        size: written,
+    })
+}
+
+/// A song is named after its file, without the extension
+fn song_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into()
+}
+
+/// What [`author`] builds the images from
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AuthorPlan {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// `title` in other languages; see [`crate::locale`]
+    #[serde(default, skip_serializing_if = "Localized::is_empty")]
+    pub titles: Localized,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub performer: Option<String>,
+    /// In disc order
+    pub songs: Vec<PlannedSong>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlannedSong {
+    pub name: String,
+    /// `name` in other languages
+    #[serde(default, skip_serializing_if = "Localized::is_empty")]
+    pub names: Localized,
+    /// The audio file; relative to the plan file, if read from one
+    pub file: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub performer: Option<String>,
+}
+
+impl AuthorPlan {
+    /// A plan of `files`, each song named after its file
+    pub fn from_files(files: &[PathBuf]) -> Self {
+        Self {
+            title: None,
+            titles: Localized::new(),
+            performer: None,
+            songs: files
+                .iter()
+                .map(|file| PlannedSong {
+                    name: song_name(file),
+                    names: Localized::new(),
+                    file: file.clone(),
+                    performer: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Reads a plan file
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+        let mut plan: Self =
+            serde_json::from_str(&text).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        if plan.songs.is_empty() {
+            bail!("{} has no songs", path.display());
+        }
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for song in &mut plan.songs {
+            song.file = dir.join(&song.file);
+        }
+        Ok(plan)
+    }
+
+    /// The meta info the disc will have, but for the hashes and creation time, which are
+    /// only known once the images are built
+    pub fn meta_info(&self) -> anyhow::Result<MetaInfo> {
+        let mut meta_info = MetaInfo::new(
+            self.title.clone(),
+            self.songs.iter().map(|x| x.name.clone()),
+        );
+        meta_info.titles = self.titles.clone();
+        meta_info.performer = self.performer.clone();
+        for (info, song) in meta_info.list.iter_mut().zip(&self.songs) {
+            info.names = song.names.clone();
+            info.performer = song.performer.clone();
+            info.chapters = read_chapters(&song.file)?;
+        }
+        Ok(meta_info)
+    }
+}
+
+/// The file a session is written to
+fn session_file(output_dir: &Path, session_no: usize) -> PathBuf {
+    output_dir.join(format!("{session_no:02}.bin"))
+}
+
+/// The image at `path` as the only track of a disc, as the player would read it once
+/// burnt
+fn image_track(path: &Path) -> anyhow::Result<(Arc<dyn DiscSource>, Track)> {
+    let source = source::open(path)?;
+    let size = source.len() / SECTOR_SIZE;
+    if size * SECTOR_SIZE != source.len() || size == 0 {
+        bail!(
+            "{} has {} bytes, not whole sectors",
+            path.display(),
+            source.len()
+        );
+    }
+    let track = Track {
+        track_no: 1,
+        session_no: 1,
+        start_addr: 0,
+        end_addr: size - 1,
+        size,
+        audio: false,
+    };
+    Ok((source, track))
+}
+
+/// Reads the images in `output_dir` back the way the player reads the disc: the meta
+/// info up to its NUL, which has to be `meta_info`, and each song, against its hash
+fn check_images(output_dir: &Path, meta_info: &MetaInfo) -> anyhow::Result<()> {
+    let meta_file = session_file(output_dir, 1);
+    let (source, track) = image_track(&meta_file)?;
+    let read_back = extract_meta_info(&source, track)
+        .map_err(|e| anyhow!("{} doesn't read back: {e}", meta_file.display()))?;
+    if serde_json::to_value(&read_back)? != serde_json::to_value(meta_info)? {
+        bail!("{} reads back as different meta info", meta_file.display());
+    }
+    for song in &meta_info.list {
+        let path = session_file(output_dir, song.session_no);
+        let (source, track) = image_track(&path)?;
+        if let Some(hash) = &song.hash {
+            let check = check_song(&source, track, hash);
+            if !check.is_intact() {
+                bail!("{} doesn't read back: {check}", path.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The session images in `dir` (as [`author`] writes them), in session order
+///
+/// They have to be numbered from one without gaps.
+pub fn authored_sessions(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut numbered = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let number = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.strip_suffix(".bin"))
+            .and_then(|x| x.parse::<usize>().ok());
+        if let Some(n) = number {
+            numbered.push((n, path));
+        }
+    }
+    if numbered.is_empty() {
+        bail!(
+            "No session images (01.bin, 02.bin, ...) in {}",
+            dir.display()
+        );
+    }
+    numbered.sort();
+    for (i, (n, path)) in numbered.iter().enumerate() {
+        if *n != i + 1 {
+            bail!(
+                "Expected session {} but found {}; sessions have to be numbered from 1 without gaps",
+                i + 1,
+                path.display()
+            );
+        }
+    }
+    Ok(numbered.into_iter().map(|(_, path)| path).collect())
+}
+
+/// The chapters of the song in `input`, from `<input>.chapters` if there's one (see
+/// [`crate::chapters`])
+fn read_chapters(input: &Path) -> anyhow::Result<Vec<Chapter>> {
+    let mut path = input.as_os_str().to_owned();
+    path.push(".chapters");
+    let path = PathBuf::from(path);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(x) => x,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(anyhow!("Failed to read {}: {e}", path.display())),
+    };
+    parse_chapter_list(&text).map_err(|e| anyhow!("{}: {e}", path.display()))
+}
+
+/// Builds the session images of `plan`, one song each, into `output_dir`
+pub fn author(plan: &AuthorPlan, output_dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(output_dir)?;
+    clear_burnt(output_dir)?;
+    let mut meta_info = plan.meta_info()?;
+    let count = plan.songs.len();
+    for (i, (song, info)) in plan.songs.iter().zip(&mut meta_info.list).enumerate() {
+        let input = &song.file;
+        let output = session_file(output_dir, info.session_no);
+        let hash = write_audio_session(input, &output)
+            .with_context(|| format!("Failed to convert {}", input.display()))?;
+        println!(
+            "[{}/{count}] {}: {}, {}{}",
+            i + 1,
+            output.display(),
+            info.name,
+            format_duration(duration_from_bytes(hash.size) as u32),
+            match info.chapters.len() {
+                0 => String::new(),
+                n => format!(", {n} chapters"),
+            }
+        );
+        info.hash = Some(hash);
+    }
+
+    let creation_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    meta_info.creation_time = Some(creation_time);
+    let output = session_file(output_dir, 1);
+    let mut writer = BufWriter::new(File::create(&output)?);
+    let json = serde_json::to_vec_pretty(&meta_info)?;
+    writer.write_all(&json)?;
+    // the player reads the meta info up to a NUL
+    writer.write_all(b"\0")?;
+    pad_to_sector(&mut writer, json.len() as u64 + 1)?;
+    writer.flush()?;
+    println!("{}: meta info", output.display());
+
+    check_images(output_dir, &meta_info)?;
+    println!("Checked the images: the meta info reads back, and so do the songs");
+    println!(
+        "Burn the sessions in order, like:\n  for f in {}/*.bin; do cdrskin -v -multi -data \"$f\"; done",
+        output_dir.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! `author init`: asks for the title and performer of the disc, and the file and name of
+//! each song, then writes them as an [`AuthorPlan`] for `author --plan`, along with the
+//! meta info the disc will have
+//!
+//! The tags of the audio files (read with `ffprobe`, if installed) are offered as the
+//! answers, so for a tagged album it's mostly a matter of pressing Enter.
+
+use std::io;
+use std::io::{BufRead, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+
+use crate::author::{song_name, AuthorPlan, PlannedSong};
+use crate::locale::Localized;
+
+/// Reads the tags of audio files
+const PROBE: &str = "ffprobe";
+/// The plan `init` writes
+pub const PLAN_FILE: &str = "plan.json";
+/// The meta info `init` writes, as the disc will have it but for the hashes
+pub const META_FILE: &str = "meta.json";
+
+/// The tags of an audio file that go into a plan
+#[derive(Debug, Clone, Default)]
+pub struct Tags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
+/// The tags of `path`, read with [`PROBE`]; none if it isn't installed or fails on it
+pub fn read_tags(path: &Path) -> Tags {
+    let output = Command::new(PROBE)
+        .args(["-v", "error", "-show_entries", "format_tags", "-of", "json"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(x) if x.status.success() => parse_probe_output(&x.stdout),
+        _ => Tags::default(),
+    }
+}
+
+fn parse_probe_output(output: &[u8]) -> Tags {
+    let Ok(json) = serde_json::from_slice::<Value>(output) else {
+        return Tags::default();
+    };
+    let Some(tags) = json["format"]["tags"].as_object() else {
+        return Tags::default();
+    };
+    // the case of tag names depends on the format
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .and_then(|(_, v)| v.as_str())
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(String::from)
+    };
+    Tags {
+        title: tag("title"),
+        artist: tag("artist"),
+        album: tag("album"),
+    }
+}
+
+/// Asks its questions on `output` and reads the answers, one per line, from `input`
+struct Wizard<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Wizard<R, W> {
+    /// The answer to `question`, or `default` if it's left empty
+    fn ask(&mut self, question: &str, default: Option<&str>) -> io::Result<Option<String>> {
+        match default {
+            Some(x) => write!(self.output, "{question} [{x}]: ")?,
+            None => write!(self.output, "{question}: ")?,
+        }
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Input ended"));
+        }
+        Ok(match line.trim() {
+            "" => default.map(String::from),
+            x => Some(x.into()),
+        })
+    }
+
+    /// Asks for audio files until an empty answer
+    fn ask_files(&mut self) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        loop {
+            let question = format!("File of song {} (empty when done)", files.len() + 1);
+            let Some(answer) = self.ask(&question, None)? else {
+                return Ok(files);
+            };
+            let path = PathBuf::from(answer);
+            if path.is_file() {
+                files.push(path);
+            } else {
+                writeln!(self.output, "{} isn't a file", path.display())?;
+            }
+        }
+    }
+}
+
+/// Walks through the plan of a disc: its songs are `files`, or asked for if there are
+/// none, and their tags come from `tags`
+pub fn run(
+    input: impl BufRead,
+    output: impl Write,
+    files: &[PathBuf],
+    tags: impl Fn(&Path) -> Tags,
+) -> anyhow::Result<AuthorPlan> {
+    let mut wizard = Wizard { input, output };
+    let files = match files {
+        [] => wizard.ask_files()?,
+        x => x.to_vec(),
+    };
+    if files.is_empty() {
+        bail!("No songs");
+    }
+    let tags = files.iter().map(|x| tags(x)).collect::<Vec<_>>();
+    // offered for the disc if all the files agree on it
+    let common = |tag: fn(&Tags) -> &Option<String>| match tag(&tags[0]) {
+        Some(x) if tags.iter().all(|t| tag(t).as_ref() == Some(x)) => Some(x.clone()),
+        _ => None,
+    };
+
+    let title = wizard.ask("Title of the disc", common(|x| &x.album).as_deref())?;
+    let performer = wizard.ask("Performer of the disc", common(|x| &x.artist).as_deref())?;
+    let mut songs = Vec::new();
+    for (i, (file, tags)) in files.iter().zip(&tags).enumerate() {
+        let default = tags.title.clone().unwrap_or_else(|| song_name(file));
+        let question = format!("Name of song {} ({})", i + 1, file.display());
+        let name = wizard.ask(&question, Some(&default))?.unwrap_or(default);
+        songs.push(PlannedSong {
+            name,
+            names: Localized::new(),
+            file: file.clone(),
+            // a guest on a song
+            performer: tags
+                .artist
+                .clone()
+                .filter(|x| Some(x) != performer.as_ref()),
+        });
+    }
+    Ok(AuthorPlan {
+        title,
+        titles: Localized::new(),
+        performer,
+        songs,
+    })
+}
+
+/// Runs the wizard on the terminal, and writes [`PLAN_FILE`] and [`META_FILE`] into
+/// `output_dir`
+pub fn init(files: &[PathBuf], import_tags: bool, output_dir: &Path) -> anyhow::Result<()> {
+    let plan_file = output_dir.join(PLAN_FILE);
+    if plan_file.exists() {
+        bail!(
+            "{} already exists; remove it or pick another directory",
+            plan_file.display()
+        );
+    }
+    for file in files {
+        if !file.is_file() {
+            bail!("{} isn't a file", file.display());
+        }
+    }
+    let tags = |path: &Path| match import_tags {
+        true => read_tags(path),
+        false => Tags::default(),
+    };
+    let mut plan = run(io::stdin().lock(), io::stdout(), files, tags)?;
+    // so the plan doesn't depend on where it's used from
+    for song in &mut plan.songs {
+        song.file = std::fs::canonicalize(&song.file)
+            .map_err(|e| anyhow!("{}: {e}", song.file.display()))?;
+    }
+
+    std::fs::create_dir_all(output_dir)?;
+    std::fs::write(&plan_file, serde_json::to_string_pretty(&plan)?)?;
+    let meta_file = output_dir.join(META_FILE);
+    std::fs::write(
+        &meta_file,
+        serde_json::to_string_pretty(&plan.meta_info()?)?,
+    )?;
+    println!("Wrote {} and {}", plan_file.display(), meta_file.display());
+    println!(
+        "Build the images with:\n  pseudo-cd-player author --plan {} -o images",
+        plan_file.display()
+    );
+    Ok(())
+}
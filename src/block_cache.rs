@@ -0,0 +1,148 @@
+//! A [`DiscSource`] read a block at a time from somewhere slow, like a web server
+//! ([`crate::http_source`]) or another machine ([`crate::ssh_source`])
+//!
+//! Blocks are kept in memory, and the one after the block read last is fetched ahead in
+//! the background, so playback doesn't wait on the network at every block boundary.
+
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::spawn;
+
+use log::debug;
+
+use crate::mutex_lock;
+use crate::source::{read_bytes_at, DiscSource};
+
+/// Bytes fetched in a request
+const BLOCK_SIZE: u64 = 1024 * 1024;
+/// Blocks kept in memory, the least recently read ones dropped first
+const CACHED_BLOCKS: usize = 32;
+
+/// Fetches ranges of the disc for a [`BlockCache`]
+pub(crate) trait Fetch: Send + Sync + 'static {
+    /// `len` bytes from `offset`, which are all within the disc
+    fn fetch(&self, offset: u64, len: u64) -> io::Result<Vec<u8>>;
+
+    /// Where the disc is, for messages
+    fn location(&self) -> &str;
+}
+
+struct Cache {
+    /// The most recently read at the back
+    blocks: VecDeque<(u64, Arc<Vec<u8>>)>,
+    /// Blocks being fetched now, by a read or ahead of one
+    fetching: HashSet<u64>,
+}
+
+struct Shared<F> {
+    fetcher: F,
+    len: u64,
+    cache: Mutex<Cache>,
+    /// Notified whenever a fetch ends
+    fetched: Condvar,
+}
+
+impl<F: Fetch> Shared<F> {
+    /// Block `n`, from the cache, or fetched (or waited for, if it's being fetched)
+    fn block(&self, n: u64) -> io::Result<Arc<Vec<u8>>> {
+        let mut cache = mutex_lock!(self.cache);
+        loop {
+            if let Some(i) = cache.blocks.iter().position(|(x, _)| *x == n) {
+                let entry = cache.blocks.remove(i).unwrap();
+                let block = Arc::clone(&entry.1);
+                cache.blocks.push_back(entry);
+                return Ok(block);
+            }
+            if !cache.fetching.contains(&n) {
+                break;
+            }
+            cache = self.fetched.wait(cache).unwrap();
+        }
+        cache.fetching.insert(n);
+        drop(cache);
+
+        let result = self.fetch(n);
+        let mut cache = mutex_lock!(self.cache);
+        cache.fetching.remove(&n);
+        if let Ok(block) = &result {
+            if cache.blocks.len() == CACHED_BLOCKS {
+                cache.blocks.pop_front();
+            }
+            cache.blocks.push_back((n, Arc::clone(block)));
+        }
+        self.fetched.notify_all();
+        result
+    }
+
+    fn fetch(&self, n: u64) -> io::Result<Arc<Vec<u8>>> {
+        let start = n * BLOCK_SIZE;
+        let len = BLOCK_SIZE.min(self.len - start);
+        let data = self.fetcher.fetch(start, len)?;
+        if data.len() as u64 != len {
+            return Err(io::Error::other(format!(
+                "Got {} of the {len} bytes at {start} of {}",
+                data.len(),
+                self.fetcher.location()
+            )));
+        }
+        Ok(Arc::new(data))
+    }
+
+    /// Starts fetching block `n` in the background, unless it's there or coming already
+    fn prefetch(self: &Arc<Self>, n: u64) {
+        if n * BLOCK_SIZE >= self.len {
+            return;
+        }
+        {
+            let cache = mutex_lock!(self.cache);
+            if cache.fetching.contains(&n) || cache.blocks.iter().any(|(x, _)| *x == n) {
+                return;
+            }
+        }
+        let shared = Arc::clone(self);
+        spawn(move || {
+            if let Err(e) = shared.block(n) {
+                debug!("Fetching block {} ahead failed: {}", n, e);
+            }
+        });
+    }
+}
+
+/// A disc of `len` bytes, read through a [`Fetch`]
+pub(crate) struct BlockCache<F> {
+    shared: Arc<Shared<F>>,
+}
+
+impl<F: Fetch> BlockCache<F> {
+    pub(crate) fn new(fetcher: F, len: u64) -> Self {
+        debug!("{} has {} bytes", fetcher.location(), len);
+        Self {
+            shared: Arc::new(Shared {
+                fetcher,
+                len,
+                cache: Mutex::new(Cache {
+                    blocks: VecDeque::new(),
+                    fetching: HashSet::new(),
+                }),
+                fetched: Condvar::new(),
+            }),
+        }
+    }
+}
+
+impl<F: Fetch> DiscSource for BlockCache<F> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.shared.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let n = offset / BLOCK_SIZE;
+        let block = self.shared.block(n)?;
+        self.shared.prefetch(n + 1);
+        Ok(read_bytes_at(&block, buf, offset - n * BLOCK_SIZE))
+    }
+
+    fn len(&self) -> u64 {
+        self.shared.len
+    }
+}
@@ -0,0 +1,84 @@
+//! Named points within the songs of each disc, to jump back to, like chapters of a long
+//! mix
+//!
+//! Bookmarks are kept in `bookmarks.json` next to the config file, keyed by
+//! [`crate::disc_id`], and written out on every change.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_dir;
+use crate::format_duration;
+
+const BOOKMARKS_FILE_NAME: &str = "bookmarks.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    /// Index into [`crate::MetaInfo::list`]
+    pub song_idx: usize,
+    /// In seconds
+    pub position: u32,
+}
+
+impl Bookmark {
+    /// Like `3 01:23`, for one given no name
+    pub fn default_name(song_idx: usize, position: u32) -> String {
+        format!("{} {}", song_idx + 1, format_duration(position))
+    }
+}
+
+fn bookmarks_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(BOOKMARKS_FILE_NAME))
+}
+
+fn read_all() -> io::Result<HashMap<String, Vec<Bookmark>>> {
+    let Some(path) = bookmarks_file_path().filter(|x| x.exists()) else {
+        return Ok(Default::default());
+    };
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(io::Error::other)
+}
+
+/// The bookmarks of the disc, in disc order
+pub fn load(disc_id: &str) -> Vec<Bookmark> {
+    match read_all() {
+        Ok(mut all) => all.remove(disc_id).unwrap_or_default(),
+        Err(e) => {
+            warn!("Failed to read bookmarks: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Replaces the bookmarks of the disc with `bookmarks`
+pub fn store(disc_id: &str, bookmarks: &[Bookmark]) -> io::Result<()> {
+    let Some(path) = bookmarks_file_path() else {
+        return Ok(());
+    };
+    // unlike resume points, the other discs' bookmarks aren't dropped for a broken file
+    let mut all = read_all()?;
+    match bookmarks.is_empty() {
+        true => all.remove(disc_id),
+        false => all.insert(disc_id.into(), bookmarks.to_vec()),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &all).map_err(io::Error::other)
+}
+
+/// Puts `bookmark` into `bookmarks` in disc order; returns where it went
+pub fn insert(bookmarks: &mut Vec<Bookmark>, bookmark: Bookmark) -> usize {
+    let key = |x: &Bookmark| (x.song_idx, x.position);
+    let i = bookmarks.partition_point(|x| key(x) <= key(&bookmark));
+    bookmarks.insert(i, bookmark);
+    i
+}
@@ -0,0 +1,170 @@
+//! `burn`: writes the session images of [`crate::author`] to a blank disc with cdrskin
+//!
+//! Every image becomes one session, in order. Afterwards, the disc's TOC is checked
+//! against them, and every session is read back and compared with its image, as by
+//! `verify`; only if all of them match is the directory marked as burnt ([`BURNT_FILE`]).
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail};
+
+use crate::author::authored_sessions;
+use crate::cli::DiscArgs;
+use crate::source;
+use crate::verify::compare_sessions;
+use crate::{minfo, parse_meta_info, read_meta_info, MetaInfo, SECTOR_SIZE};
+
+const BURN_PROGRAM: &str = "cdrskin";
+/// Written into the directory of the images once they're burnt and read back intact: when,
+/// and to which drive
+pub const BURNT_FILE: &str = "burnt";
+
+/// Removes [`BURNT_FILE`] from `dir`, as its images are about to change
+pub(crate) fn clear_burnt(dir: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(dir.join(BURNT_FILE)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the meta info image and the songs it refers to; returns it
+pub(crate) fn check_sessions(
+    sessions: &[PathBuf],
+    meta_info_track: usize,
+) -> anyhow::Result<MetaInfo> {
+    let meta_file = sessions
+        .get(meta_info_track.wrapping_sub(1))
+        .ok_or_else(|| {
+            anyhow!(
+                "The meta info track is {meta_info_track}, but there are only {} sessions",
+                sessions.len()
+            )
+        })?;
+    let meta_info = parse_meta_info(&std::fs::read(meta_file)?)
+        .map_err(|e| anyhow!("{} isn't meta info: {e}", meta_file.display()))?;
+    for song in &meta_info.list {
+        if song.session_no == meta_info_track || !(1..=sessions.len()).contains(&song.session_no) {
+            bail!(
+                "Song {:?} refers to session {}, which isn't an audio image here",
+                song.name,
+                song.session_no
+            );
+        }
+    }
+    Ok(meta_info)
+}
+
+/// What's in the session, for progress lines
+pub(crate) fn session_description(
+    meta_info: &MetaInfo,
+    session_no: usize,
+    meta_info_track: usize,
+) -> String {
+    if session_no == meta_info_track {
+        return "meta info".into();
+    }
+    match meta_info.list.iter().find(|x| x.session_no == session_no) {
+        Some(song) => song.name.clone(),
+        None => "not in the song list".into(),
+    }
+}
+
+/// Burns the images in `input_dir` to the drive of `disc`
+pub fn burn(disc: &DiscArgs, input_dir: &Path) -> anyhow::Result<()> {
+    if let Some(source) = &disc.source {
+        bail!("Can't burn to {source}; burning needs a drive, given with --drive");
+    }
+    let sessions = authored_sessions(input_dir)?;
+    let (drive, meta_info_track) = (&disc.drive, disc.meta_info_track);
+    let meta_info = check_sessions(&sessions, meta_info_track)?;
+    if let Ok(burnt) = std::fs::read_to_string(input_dir.join(BURNT_FILE)) {
+        println!("These images were burnt before: {}", burnt.trim());
+    }
+
+    // session numbers are fixed at authoring, so they can't go after existing ones
+    let existing = minfo::minfo_track_info(disc).unwrap_or_default();
+    if !existing.is_empty() {
+        bail!(
+            "The disc in {} already has {} sessions; a blank one is needed",
+            drive.display(),
+            existing.len()
+        );
+    }
+
+    let count = sessions.len();
+    for (i, path) in sessions.iter().enumerate() {
+        let session_no = i + 1;
+        println!(
+            "[{session_no}/{count}] Burning {} ({})",
+            path.display(),
+            session_description(&meta_info, session_no, meta_info_track)
+        );
+        let status = Command::new(BURN_PROGRAM)
+            .arg("-v")
+            .arg(format!("dev={}", drive.display()))
+            .args(["-multi", "-data"])
+            .arg(path)
+            .status()
+            .map_err(|e| anyhow!("Failed to run {BURN_PROGRAM}: {e}"))?;
+        if !status.success() {
+            bail!(
+                "{BURN_PROGRAM} failed on {} ({status}); the disc has {} of {count} sessions",
+                path.display(),
+                i
+            );
+        }
+    }
+
+    println!("Checking the TOC...");
+    let tracks = minfo::minfo_track_info(disc)?;
+    if tracks.len() != count {
+        bail!("The disc has {} sessions instead of {count}", tracks.len());
+    }
+    for (track, path) in tracks.iter().zip(&sessions) {
+        // burnt sessions may be padded, but never shorter than their image
+        let image_sectors = std::fs::metadata(path)?.len().div_ceil(SECTOR_SIZE);
+        if track.size < image_sectors {
+            bail!(
+                "Session {} has {} sectors, but {} has {image_sectors}",
+                track.session_no,
+                track.size,
+                path.display()
+            );
+        }
+    }
+    let burnt_meta_info = read_meta_info(disc, &source::open(drive)?, &tracks)?;
+    if burnt_meta_info.list.len() != meta_info.list.len() {
+        bail!(
+            "The disc lists {} songs instead of {}",
+            burnt_meta_info.list.len(),
+            meta_info.list.len()
+        );
+    }
+
+    println!("Reading the sessions back...");
+    let failed = compare_sessions(disc, &tracks, &sessions, &meta_info)?;
+    if failed != 0 {
+        bail!("{failed} of {count} sessions don't read back as their images; the disc is bad");
+    }
+    std::fs::write(
+        input_dir.join(BURNT_FILE),
+        format!(
+            "{} to {}\n",
+            humantime::format_rfc3339_seconds(SystemTime::now()),
+            drive.display()
+        ),
+    )?;
+    println!(
+        "Done: {count} sessions, {} songs{}",
+        meta_info.list.len(),
+        meta_info
+            .title
+            .as_ref()
+            .map(|x| format!(" of {x}"))
+            .unwrap_or_default()
+    );
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! The audio tracks of mixed-mode discs, like CD-Extra (audio tracks, then data
+//! sessions), read with `cdparanoia`
+//!
+//! The drive reads only data sectors as a block device, so [`WithCdda`] sends reads at
+//! [`CDDA_OFFSET`] and past (where [`Track::start_offset`] puts audio tracks) to
+//! cdparanoia, a block at a time through [`crate::block_cache`], and the rest to the
+//! drive. Audio tracks hold the same PCM as songs do, just in bigger sectors.
+
+use std::io;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use log::debug;
+
+use crate::block_cache::{BlockCache, Fetch};
+use crate::source::DiscSource;
+use crate::{Track, CDDA_FRAME_SIZE, CDDA_OFFSET};
+
+/// cdparanoia writes a WAV; this much of it comes before the PCM
+const WAV_HEADER_SIZE: usize = 44;
+
+/// Reads sectors of the audio tracks with cdparanoia
+struct Paranoia {
+    drive: String,
+    /// The audio tracks, in disc order
+    tracks: Vec<Track>,
+}
+
+impl Paranoia {
+    /// `count` sectors of `track`, from `offset` sectors into it
+    fn read(&self, track: &Track, offset: u64, count: u64) -> io::Result<Vec<u8>> {
+        let span = format!(
+            "{0}[.{1}]-{0}[.{2}]",
+            track.track_no,
+            offset,
+            offset + count - 1
+        );
+        debug!("Reading {} of {} with cdparanoia", span, self.drive);
+        let output = Command::new("cdparanoia")
+            .args([
+                "--quiet",
+                "--output-wav",
+                "--force-cdrom-device",
+                &self.drive,
+            ])
+            .args(["--", &span, "-"])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Failed to run cdparanoia, which audio tracks are read with: {e}"),
+                )
+            })?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "cdparanoia failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let pcm = output.stdout.get(WAV_HEADER_SIZE..).unwrap_or_default();
+        if pcm.len() as u64 != count * CDDA_FRAME_SIZE {
+            return Err(io::Error::other(format!(
+                "cdparanoia read {} bytes of {span}, not {}",
+                pcm.len(),
+                count * CDDA_FRAME_SIZE
+            )));
+        }
+        Ok(pcm.to_vec())
+    }
+}
+
+impl Fetch for Paranoia {
+    fn fetch(&self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        let first = offset / CDDA_FRAME_SIZE;
+        let end = (offset + len).div_ceil(CDDA_FRAME_SIZE);
+        let mut pcm = Vec::with_capacity(((end - first) * CDDA_FRAME_SIZE) as usize);
+        let mut sector = first;
+        while sector < end {
+            let track = self
+                .tracks
+                .iter()
+                .find(|x| (x.start_addr..=x.end_addr).contains(&sector));
+            match track {
+                Some(track) => {
+                    let count = (track.end_addr + 1).min(end) - sector;
+                    pcm.extend(self.read(track, sector - track.start_addr, count)?);
+                    sector += count;
+                }
+                // not in any track, like before the first one; there's nothing but silence
+                None => {
+                    let next = self
+                        .tracks
+                        .iter()
+                        .map(|x| x.start_addr)
+                        .filter(|&x| x > sector)
+                        .min()
+                        .unwrap_or(end)
+                        .min(end);
+                    pcm.resize(pcm.len() + ((next - sector) * CDDA_FRAME_SIZE) as usize, 0);
+                    sector = next;
+                }
+            }
+        }
+        let skip = (offset - first * CDDA_FRAME_SIZE) as usize;
+        Ok(pcm[skip..skip + len as usize].to_vec())
+    }
+
+    fn location(&self) -> &str {
+        &self.drive
+    }
+}
+
+/// The drive, with its audio tracks read by cdparanoia
+pub struct WithCdda {
+    drive: Arc<dyn DiscSource>,
+    cdda: BlockCache<Paranoia>,
+}
+
+impl WithCdda {
+    /// `drive`, opened at `path`, if `tracks` has audio tracks; `None` if it hasn't
+    pub fn new(path: &str, drive: Arc<dyn DiscSource>, tracks: &[Track]) -> Option<Self> {
+        let mut tracks = tracks
+            .iter()
+            .filter(|x| x.audio)
+            .copied()
+            .collect::<Vec<_>>();
+        tracks.sort_by_key(|x| x.start_addr);
+        let len = tracks.last()?.end_offset() - CDDA_OFFSET;
+        let paranoia = Paranoia {
+            drive: path.into(),
+            tracks,
+        };
+        Some(Self {
+            drive,
+            cdda: BlockCache::new(paranoia, len),
+        })
+    }
+}
+
+impl DiscSource for WithCdda {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        match offset.checked_sub(CDDA_OFFSET) {
+            Some(offset) => self.cdda.read_at(buf, offset),
+            None => {
+                // not into the audio tracks, even if the drive had that much
+                let n = (CDDA_OFFSET - offset).min(buf.len() as u64) as usize;
+                self.drive.read_at(&mut buf[..n], offset)
+            }
+        }
+    }
+
+    /// Of the data; the audio tracks are past it
+    fn len(&self) -> u64 {
+        self.drive.len()
+    }
+
+    fn lock_door(&self, locked: bool) -> io::Result<()> {
+        self.drive.lock_door(locked)
+    }
+
+    fn spin_down(&self) -> io::Result<()> {
+        self.drive.spin_down()
+    }
+}
@@ -0,0 +1,70 @@
+//! Named points within a song, like the tracks of a DJ mix or the segments of a radio
+//! show, listed with the song in the meta info
+//!
+//! `author` takes them from a `<audio file>.chapters` file next to the song, with a line
+//! per chapter: where it starts and then its name, like `1:02:03 Encore`. In the TUI, `[`
+//! and `]` skip between them, and the playing one is shown under the progress gauge.
+
+use serde::{Deserialize, Serialize};
+
+/// Going back within this many seconds of a chapter's start goes to the one before it
+const RESTART_THRESHOLD: u32 = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub name: String,
+    /// Seconds from the start of the song
+    pub offset: u32,
+}
+
+/// Index of the chapter playing at `position`, of chapters sorted by offset; `None`
+/// before the first one
+pub fn chapter_at(chapters: &[Chapter], position: u32) -> Option<usize> {
+    chapters
+        .partition_point(|x| x.offset <= position)
+        .checked_sub(1)
+}
+
+/// Where to skip to from `position`: the start of the next chapter, or going back, the
+/// start of the playing one (of the one before, right after it started); `None` past
+/// the last
+pub fn skip_target(chapters: &[Chapter], position: u32, forward: bool) -> Option<u32> {
+    let next = chapters.partition_point(|x| x.offset <= position);
+    if forward {
+        return chapters.get(next).map(|x| x.offset);
+    }
+    let playing = next.checked_sub(1);
+    let target = match playing {
+        Some(i) if position >= chapters[i].offset + RESTART_THRESHOLD => Some(i),
+        Some(i) => i.checked_sub(1),
+        None => None,
+    };
+    Some(target.map(|i| chapters[i].offset).unwrap_or_default())
+}
+
+/// `SS`, `MM:SS` or `H:MM:SS` in seconds
+fn parse_time(time: &str) -> Option<u32> {
+    time.split(':').try_fold(0_u32, |acc, x| {
+        acc.checked_mul(60)?.checked_add(x.parse().ok()?)
+    })
+}
+
+/// Reads the lines of a `.chapters` file, sorted by offset; empty lines are skipped
+pub fn parse_chapter_list(text: &str) -> Result<Vec<Chapter>, String> {
+    let mut chapters = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (time, name) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let offset = parse_time(time)
+            .ok_or_else(|| format!("line {}: {time:?} isn't a time like 12:34", i + 1))?;
+        chapters.push(Chapter {
+            name: name.trim().into(),
+            offset,
+        });
+    }
+    chapters.sort_by_key(|x| x.offset);
+    Ok(chapters)
+}
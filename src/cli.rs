@@ -1,10 +1,231 @@
+use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::time::Duration;
 
-use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::alarm::AlarmTime;
+use crate::on_finish::OnFinish;
+use crate::play_mode::RepeatMode;
 
 #[derive(clap::Parser, Debug, Default)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+    #[command(flatten)]
+    pub disc: DiscArgs,
+    #[command(flatten)]
+    pub ui: UiArgs,
+    /// Program log will output to this if present
+    #[arg(short, long, global = true)]
+    pub log_file: Option<PathBuf>,
+    /// Least severe messages logged; info by default
+    #[arg(value_enum, long, global = true)]
+    pub log_level: Option<LogLevel>,
+    /// Log level of a module, like `minfo=debug` or `playback=trace`; can be repeated
+    ///
+    /// Module names are as in the source (`pseudo_cd_player::` may be left out), or those
+    /// of dependencies like `cpal`.
+    #[arg(long, global = true, value_name = "MODULE=LEVEL", value_parser = parse_log_filter)]
+    pub log_filter: Vec<LogFilter>,
+    /// Also log to stderr; not with the TUI, which it would garble
+    #[arg(long, global = true)]
+    pub log_stderr: bool,
+    /// On failure, print a JSON object describing it to stderr instead of the message
+    ///
+    /// Its `error` is one of `no_drive`, `no_medium`, `minfo_program_missing`,
+    /// `bad_meta_info`, `no_audio_device` and `other`, each with an exit code of its own.
+    #[arg(long, global = true)]
+    pub errors_json: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Commands {
+    /// Play the disc in the TUI; this is the default without a subcommand
+    Play {
+        #[command(flatten)]
+        disc: DiscArgs,
+        #[command(flatten)]
+        ui: UiArgs,
+    },
+    /// Print the song list
+    List {
+        #[command(flatten)]
+        disc: DiscArgs,
+        #[arg(value_enum, long, default_value = "text")]
+        format: OutputFormat,
+    },
+    /// Print the disc meta info and its tracks table
+    Info {
+        #[command(flatten)]
+        disc: DiscArgs,
+        #[arg(value_enum, long, default_value = "text")]
+        format: OutputFormat,
+    },
+    /// Extract every song into a WAV or FLAC file, with an M3U8 playlist of them
+    Rip {
+        #[command(flatten)]
+        disc: DiscArgs,
+        /// Directory the files are written to; created if missing
+        #[arg(short, long, default_value = ".")]
+        output_dir: PathBuf,
+        /// FLAC files are tagged with the song names, the disc title and its year
+        #[arg(value_enum, long, default_value = "wav")]
+        format: RipFormat,
+        /// Threads encoding FLAC files; the number of CPUs by default
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Image copied along as `cover.jpg` (or `.png`, ...), the album's cover art
+        #[arg(long, value_name = "FILE")]
+        cover: Option<PathBuf>,
+    },
+    /// Write a CUE sheet of the songs, for other players and CD burning software
+    ///
+    /// It refers to the WAV files `rip` writes next to it, or with `--image`, to a raw
+    /// image of all the songs, extracted along with it. Chapters are INDEX points.
+    Cue {
+        #[command(flatten)]
+        disc: DiscArgs,
+        /// Directory the files are written to; created if missing
+        #[arg(short, long, default_value = ".")]
+        output_dir: PathBuf,
+        /// Extract the songs into a raw image for the sheet to refer to
+        #[arg(long)]
+        image: bool,
+    },
+    /// Burn the session images written by `author` to a blank disc with cdrskin
+    ///
+    /// The sessions are burnt in order, then the disc's TOC is checked against them and
+    /// every session is read back and compared with its image, as by `verify`.
+    Burn {
+        #[command(flatten)]
+        disc: DiscArgs,
+        /// Directory `author` wrote the images to
+        #[arg(short, long, default_value = ".")]
+        input_dir: PathBuf,
+    },
+    /// Compare every session on the disc with its image written by `author`
+    Verify {
+        #[command(flatten)]
+        disc: DiscArgs,
+        /// Directory `author` wrote the images to
+        #[arg(short, long, default_value = ".")]
+        input_dir: PathBuf,
+    },
+    /// Read every song back from the disc and check it against its SHA-256 hash
+    ///
+    /// The hashes are recorded by `author`; discs authored before that have none.
+    Check {
+        #[command(flatten)]
+        disc: DiscArgs,
+    },
+    /// Look for silent gaps in a song and propose chapters starting after them
+    ///
+    /// For discs authored with a whole album or concert as one session. The chapters are
+    /// printed as a `.chapters` file for `author`; with `--save`, they're kept for the
+    /// disc on this machine, and played as if the meta info listed them.
+    Split {
+        #[command(flatten)]
+        disc: DiscArgs,
+        /// Number (starts from one) of the song to scan; the longest one by default
+        #[arg(short, long)]
+        song: Option<usize>,
+        /// Level (RMS, in dBFS) below which the audio is taken as silent
+        #[arg(
+            long,
+            default_value = "-50",
+            allow_negative_numbers = true,
+            value_name = "DB"
+        )]
+        threshold: f64,
+        /// Shortest silence, in seconds, taken as a gap between two parts
+        #[arg(long, default_value = "2", value_name = "SECONDS", value_parser = parse_seconds)]
+        min_gap: Duration,
+        /// Keep the chapters for the song, in place of those of the meta info
+        #[arg(long)]
+        save: bool,
+    },
+    /// Measure the loudness of every song, so the player plays them all equally loud
+    ///
+    /// The gains are kept for the disc on this machine, or with `--export`, written into
+    /// a copy of the meta info, to author the disc again with.
+    ScanLoudness {
+        #[command(flatten)]
+        disc: DiscArgs,
+        /// Write the meta info with the gains to this file instead
+        #[arg(long, value_name = "PATH")]
+        export: Option<PathBuf>,
+    },
+    /// Print the most played songs of each disc, from the listening history
+    ///
+    /// Play counts and listening time are recorded while playing, unless `record_history`
+    /// is off in the config file; no disc is needed.
+    Stats {
+        /// Songs listed for each disc
+        #[arg(long, default_value = "10", value_name = "N")]
+        top: usize,
+        #[arg(value_enum, long, default_value = "text")]
+        format: OutputFormat,
+    },
+    /// Print the names of the audio output devices, for `--audio-device`
+    Devices,
+    /// Bring up a TUI for a player playing elsewhere, like one started with `--daemon`
+    ///
+    /// It talks to the player over its control socket: quitting it (q) leaves the player
+    /// playing, and Q stops the player. Any number of them can attach at once.
+    Attach {
+        /// Path of the player's control socket; defaults to `$XDG_RUNTIME_DIR/pseudo-cd.sock`
+        #[arg(long, value_name = "PATH")]
+        control_socket: Option<PathBuf>,
+    },
+    /// Build the session images of a disc from audio files, one song each
+    ///
+    /// 44.1 kHz WAV files are converted directly; other formats are decoded with ffmpeg.
+    /// Session N is written as `NN.bin`; burn them in name order.
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Author {
+        #[command(subcommand)]
+        command: Option<AuthorCommand>,
+        /// Audio files, in disc order; songs are named after them
+        #[arg(required_unless_present = "plan")]
+        files: Vec<PathBuf>,
+        /// Build the songs of a plan written by `author init`, instead of FILES
+        #[arg(long, conflicts_with = "files")]
+        plan: Option<PathBuf>,
+        /// Title of the disc
+        #[arg(short, long)]
+        title: Option<String>,
+        /// Performer of the disc, like the artist of an album
+        #[arg(short, long)]
+        performer: Option<String>,
+        /// Directory the images are written to; created if missing
+        #[arg(short, long, default_value = ".")]
+        output_dir: PathBuf,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum AuthorCommand {
+    /// Ask for the title of the disc and the name of each song, and write them as a plan
+    /// for `author --plan`
+    ///
+    /// Tags of the audio files (read with ffprobe, if installed) are offered as answers.
+    /// Also writes the meta info the disc will have, but for the hashes, as `meta.json`.
+    Init {
+        /// Audio files, in disc order; asked for if none are given
+        files: Vec<PathBuf>,
+        /// Don't offer the tags of the files as answers
+        #[arg(long)]
+        no_tags: bool,
+        /// Directory `plan.json` and `meta.json` are written to; created if missing
+        #[arg(short, long, default_value = ".")]
+        output_dir: PathBuf,
+    },
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct DiscArgs {
     /// Path of the disc drive (like /dev/sr0 on Linux)
     /// TODO: on platforms other than *nix?
     #[arg(default_value = "/dev/sr0")]
@@ -17,28 +238,372 @@ pub struct Args {
     /// On true, assume all tracks are PCM data.
     #[arg(long, default_value = "false")]
     pub no_meta: bool,
-    /// Program to fetch optical medium info
-    #[arg(value_enum, long, default_value = "cdrskin")]
-    pub minfo_program: MinfoCli,
-    /// Program log will output to this if present
-    #[arg(short, long)]
-    pub log_file: Option<PathBuf>,
+    /// Program to fetch optical medium info; by default the one found to work last time
+    ///
+    /// If it isn't installed, the others are tried, in the order cdrskin, cdrecord, wodim.
+    #[arg(value_enum, long)]
+    pub minfo_program: Option<MinfoCli>,
+    /// Read the disc from an image at this URL (or path), or another machine's drive, instead
+    /// of the drive
+    ///
+    /// http(s) images are fetched with range requests, a block at a time as they're played.
+    /// The tracks table is read from `<URL>.minfo`, the minfo program's output saved along
+    /// with the image. `ssh://[user@]host[:port]/dev/sr0` reads a drive (or an image) of
+    /// another machine with `dd`, and runs the minfo program there. Images may be compressed
+    /// as seekable zstd or BGZF (`bgzip`).
+    #[arg(long, value_name = "URL")]
+    pub source: Option<String>,
+    /// Use a generated disc of sine tones instead of the drive, to try the player out
+    ///
+    /// The disc is made at startup and kept in memory; no minfo program is needed.
+    #[arg(long)]
+    pub demo: bool,
+}
+
+/// The defaults of the command line
+impl Default for DiscArgs {
+    fn default() -> Self {
+        Self {
+            drive: "/dev/sr0".into(),
+            meta_info_track: 1,
+            no_meta: false,
+            minfo_program: None,
+            source: None,
+            demo: false,
+        }
+    }
+}
+
+impl DiscArgs {
+    /// Where the disc is read from: the `--source` image, or the drive
+    pub fn location(&self) -> String {
+        match &self.source {
+            Some(x) => x.clone(),
+            None => self.drive.display().to_string(),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Default, Clone)]
+pub struct UiArgs {
+    /// Render just one status line (title, position and volume) instead of the full-screen UI
+    ///
+    /// The alternate screen is not used, so this fits in tmux status panes or tiny splits.
+    #[arg(long, conflicts_with = "screen_reader")]
+    pub mini: bool,
+    /// Print state changes (the playing song, pausing, volume, ...) as plain lines instead of
+    /// drawing a screen
+    ///
+    /// Nothing is redrawn in place and no box-drawing characters are used, so terminal screen
+    /// readers can follow playback. Keys work as in the full UI; `i` tells the current position.
+    #[arg(long)]
+    pub screen_reader: bool,
+    /// Play without the TUI, printing plain progress lines
+    ///
+    /// Single keys on stdin control playback (Space, n, p, h, l, H, L, `,`, `.`, `<`, `>`, z,
+    /// R, i and q); when stdin isn't a terminal, each byte read is taken as a key. For ssh
+    /// sessions, serial consoles and scripts.
+    #[arg(long, conflicts_with_all = ["mini", "screen_reader"])]
+    pub no_tui: bool,
+    /// Play in the background, without the TUI; `attach` brings up one for it
+    ///
+    /// This returns once the player is playing, and the player keeps playing after the
+    /// terminal closes. It's controlled over the control socket (and MPRIS, HTTP or MPD).
+    #[arg(long, conflicts_with_all = ["mini", "screen_reader", "no_tui", "no_control_socket"])]
+    pub daemon: bool,
+    /// Number (starts from one) of the song to start at, instead of the first one
+    ///
+    /// Given this, the player doesn't offer to resume where the disc was left off.
+    #[arg(long, value_name = "SONG")]
+    pub start_track: Option<usize>,
+    /// Volume change in percent of `,` and `.` (`<` and `>` change it by five times that),
+    /// and of `volume up`/`down` on remote controls (`volume_step` in the config file)
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub volume_step: Option<u32>,
+    /// Seconds `h` and `l` seek by (`seek_step` in the config file); 5 by default
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub seek_step: Option<u32>,
+    /// Seconds `H` and `L` (and Shift + arrow keys) seek by (`long_seek_step` in the config
+    /// file); 30 by default
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub long_seek_step: Option<u32>,
+    /// Minutes paused after which the drive is spun down (`spin_down_minutes` in the
+    /// config file); 10 by default, 0 for never
+    #[arg(long, value_name = "MINUTES")]
+    pub spin_down_after: Option<u32>,
+    /// Play on the audio output device of this name (`devices` lists them), which is
+    /// remembered for the next runs
+    ///
+    /// `default` goes back to the system's default device. While the remembered one isn't
+    /// there, like a DAC that's unplugged, the default one plays instead.
+    #[arg(long, value_name = "NAME")]
+    pub audio_device: Option<String>,
+    /// With no disc in the drive, wait for one to be put in instead of failing
+    #[arg(long, conflicts_with_all = ["source", "demo"])]
+    pub wait_for_disc: bool,
+    /// Start paused instead of playing right away
+    #[arg(long)]
+    pub paused: bool,
+    /// Wait paused until this time of the day, `HH:MM`, then play, the volume rising from
+    /// silence over a minute, like an alarm clock
+    ///
+    /// The song played is the first one, or the one of `--start-track`.
+    #[arg(long, value_name = "HH:MM", value_parser = parse_alarm_time)]
+    pub at: Option<AlarmTime>,
+    /// Play the songs in a random order (`shuffle` in the config file)
+    #[arg(long)]
+    pub shuffle: bool,
+    /// What happens when a song ends (`repeat` in the config file)
+    #[arg(value_enum, long, value_name = "MODE")]
+    pub repeat: Option<RepeatMode>,
+    /// Play only the songs marked as favorites (with `*` in the TUI) of a disc that has some
+    /// (`favorites_only` in the config file)
+    #[arg(long)]
+    pub favorites_only: bool,
+    /// Stay at the last song on next, and at the first one on previous, instead of wrapping
+    /// around (`wrap_around` in the config file)
+    #[arg(long)]
+    pub no_wrap_around: bool,
+    /// What's done once the playlist has played through with repeat off: `exit`, `eject`
+    /// (the disc, then exit), `suspend` (the machine) or `command:<shell command>`
+    ///
+    /// After `suspend` or a command, the player stays paused at the first song.
+    #[arg(long, value_name = "ACTION", value_parser = parse_on_finish)]
+    pub on_finish: Option<OnFinish>,
+    /// Don't offer MPRIS control on the D-Bus session bus
+    ///
+    /// By default, the player registers as `org.mpris.MediaPlayer2.pseudo_cd`, so media keys
+    /// and tools like `playerctl` can control it.
+    #[arg(long)]
+    pub no_mpris: bool,
+    /// Read the media keys (play/pause, next and previous) straight from the keyboards, for
+    /// sessions where nothing passes them on over MPRIS, like a bare window manager or the
+    /// console
+    ///
+    /// Linux only; it takes reading `/dev/input/event*`, which usually means being in the
+    /// `input` group. Under a desktop that handles media keys itself, each press counts twice.
+    #[arg(long)]
+    pub media_keys: bool,
+    /// Path of the control socket, which takes line commands like `pause` or `goto 5`
+    ///
+    /// Defaults to `$XDG_RUNTIME_DIR/pseudo-cd.sock`.
+    #[arg(long, value_name = "PATH")]
+    pub control_socket: Option<PathBuf>,
+    /// Don't listen on the control socket
+    #[arg(long, conflicts_with = "control_socket")]
+    pub no_control_socket: bool,
+    /// Serve a REST API and a control page on this address (like `127.0.0.1:8080`)
+    ///
+    /// There's no authentication; see the README for the endpoints.
+    #[arg(long, value_name = "ADDR")]
+    pub http: Option<SocketAddr>,
+    /// Speak a subset of the MPD protocol on this address (like `127.0.0.1:6600`), so MPD
+    /// clients can control the player
+    #[arg(long, value_name = "ADDR")]
+    pub mpd: Option<SocketAddr>,
 }
 
-#[derive(clap::ValueEnum, Debug, Eq, PartialEq, Copy, Clone)]
+impl UiArgs {
+    /// Index of the song to start at, checking [`UiArgs::start_track`] against `song_count`
+    pub fn start_song_idx(&self, song_count: usize) -> anyhow::Result<usize> {
+        match self.start_track {
+            None => Ok(0),
+            Some(n) if (1..=song_count).contains(&n) => Ok(n - 1),
+            Some(n) => Err(anyhow::anyhow!(
+                "--start-track {n} is out of range; the disc has {song_count} songs"
+            )),
+        }
+    }
+}
+
+impl Args {
+    /// Whether the player runs with a TUI (the full one, `--mini` or `--screen-reader`)
+    pub fn runs_tui(&self) -> bool {
+        matches!(self.command, None | Some(Commands::Play { .. }))
+            && !self.ui.no_tui
+            && !self.ui.daemon
+    }
+
+    /// Moves the disc and UI options given to a subcommand up to [`Args::disc`] and
+    /// [`Args::ui`], where the rest of the program reads them
+    pub fn hoist_subcommand_args(&mut self) {
+        match &self.command {
+            Some(Commands::Play { disc, ui }) => {
+                self.disc = disc.clone();
+                self.ui = ui.clone();
+            }
+            Some(
+                Commands::List { disc, .. }
+                | Commands::Info { disc, .. }
+                | Commands::Rip { disc, .. }
+                | Commands::Cue { disc, .. }
+                | Commands::Burn { disc, .. }
+                | Commands::Verify { disc, .. }
+                | Commands::Check { disc }
+                | Commands::Split { disc, .. }
+                | Commands::ScanLoudness { disc, .. },
+            ) => {
+                self.disc = disc.clone();
+            }
+            Some(
+                Commands::Author { .. }
+                | Commands::Attach { .. }
+                | Commands::Stats { .. }
+                | Commands::Devices,
+            )
+            | None => {}
+        }
+    }
+
+    /// The log level and module filters, from [`LOG_ENV`] and then the arguments, which
+    /// win over it
+    pub fn log_filters(&self) -> Result<(LogLevel, Vec<LogFilter>), String> {
+        let (mut level, mut filters) = match std::env::var(LOG_ENV) {
+            Ok(spec) => parse_log_spec(&spec).map_err(|e| format!("{LOG_ENV}: {e}"))?,
+            Err(_) => Default::default(),
+        };
+        if let Some(x) = self.log_level {
+            level = Some(x);
+        }
+        filters.extend(self.log_filter.iter().cloned());
+        Ok((level.unwrap_or_default(), filters))
+    }
+}
+
+/// Output format of `list`, `info` and `stats`
+#[derive(clap::ValueEnum, Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum OutputFormat {
+    /// For reading
+    #[default]
+    Text,
+    /// For other programs
+    Json,
+}
+
+#[derive(clap::ValueEnum, Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn filter(&self) -> log::LevelFilter {
+        match self {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// A `--log-filter`
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    pub module: String,
+    pub level: LogLevel,
+}
+
+fn parse_log_filter(arg: &str) -> Result<LogFilter, String> {
+    let (module, level) = arg
+        .split_once('=')
+        .ok_or_else(|| String::from("expected MODULE=LEVEL"))?;
+    let level = <LogLevel as clap::ValueEnum>::from_str(level, true)
+        .map_err(|_| format!("unknown level {level:?}"))?;
+    Ok(LogFilter {
+        module: module.into(),
+        level,
+    })
+}
+
+/// Parses `HH:MM`, in 24 hours
+fn parse_alarm_time(arg: &str) -> Result<AlarmTime, String> {
+    let error = || String::from("expected HH:MM, like 07:00");
+    let (hour, minute) = arg.split_once(':').ok_or_else(error)?;
+    let hour = hour
+        .parse::<u32>()
+        .ok()
+        .filter(|&x| x < 24)
+        .ok_or_else(error)?;
+    let minute = minute
+        .parse::<u32>()
+        .ok()
+        .filter(|&x| x < 60 && minute.len() == 2)
+        .ok_or_else(error)?;
+    Ok(AlarmTime { hour, minute })
+}
+
+/// Parses a non-negative number of seconds, like `1.5`
+fn parse_seconds(arg: &str) -> Result<Duration, String> {
+    arg.parse::<f64>()
+        .ok()
+        .and_then(|x| Duration::try_from_secs_f64(x).ok())
+        .ok_or_else(|| String::from("expected seconds, like 1.5"))
+}
+
+fn parse_on_finish(arg: &str) -> Result<OnFinish, String> {
+    if let Some(command) = arg.strip_prefix("command:") {
+        return match command.trim().is_empty() {
+            true => Err("the command is empty".into()),
+            false => Ok(OnFinish::Command(command.into())),
+        };
+    }
+    match arg {
+        "exit" => Ok(OnFinish::Exit),
+        "eject" => Ok(OnFinish::Eject),
+        "suspend" => Ok(OnFinish::Suspend),
+        _ => Err("expected exit, eject, suspend or command:<shell command>".into()),
+    }
+}
+
+/// Environment variable with a level and module filters to log with, like `debug` or
+/// `warn,minfo=trace,playback=debug`
+pub const LOG_ENV: &str = "PSEUDO_CD_LOG";
+
+/// Parses a comma-separated list of levels and `MODULE=LEVEL`s, the last level winning
+fn parse_log_spec(spec: &str) -> Result<(Option<LogLevel>, Vec<LogFilter>), String> {
+    let mut level = None;
+    let mut filters = Vec::new();
+    for directive in spec.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        if directive.contains('=') {
+            filters.push(parse_log_filter(directive)?);
+        } else {
+            level = Some(
+                <LogLevel as clap::ValueEnum>::from_str(directive, true)
+                    .map_err(|_| format!("unknown level {directive:?}"))?,
+            );
+        }
+    }
+    Ok((level, filters))
+}
+
+/// File format of `rip`
+#[derive(clap::ValueEnum, Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum RipFormat {
+    #[default]
+    Wav,
+    Flac,
+}
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
 pub enum MinfoCli {
     Cdrskin,
     Cdrecord,
     Wodim,
 }
 
-impl Default for MinfoCli {
-    fn default() -> Self {
-        Self::Cdrskin
-    }
-}
-
 impl MinfoCli {
+    /// In the order they're tried
+    pub const ALL: [MinfoCli; 3] = [MinfoCli::Cdrskin, MinfoCli::Cdrecord, MinfoCli::Wodim];
+
     pub fn name(&self) -> &'static str {
         match self {
             MinfoCli::Cdrskin => "cdrskin",
@@ -47,9 +612,3 @@ impl MinfoCli {
         }
     }
 }
-
-pub static ARGS: Lazy<Mutex<Args>> = Lazy::new(|| {
-    Mutex::new(
-        Default::default(), /* this is just a placeholder dummy value */
-    )
-});
@@ -0,0 +1,97 @@
+//! Copying text to the system clipboard
+//!
+//! On a desktop session, with `wl-copy`, `xclip` or `xsel` (`pbcopy` on macOS); over
+//! ssh, or with none of them, with an OSC 52 escape sequence written to the terminal,
+//! which has the terminal emulator put the text on the clipboard of its own machine
+//! (if it supports that).
+
+use std::env::var_os;
+use std::io;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use log::debug;
+
+/// How the text went to the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Copied {
+    Program(&'static str),
+    Osc52,
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |acc, (i, &x)| acc | (x as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+/// The OSC 52 sequence setting the clipboard to `text`
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Clipboard programs to try, with their arguments, for the session running the player
+fn programs() -> Vec<(&'static str, &'static [&'static str])> {
+    // their clipboard would be of the remote machine, or of the X server it forwards
+    if var_os("SSH_CONNECTION").is_some() || var_os("SSH_TTY").is_some() {
+        return Vec::new();
+    }
+    let mut programs = Vec::new();
+    if cfg!(target_os = "macos") {
+        programs.push(("pbcopy", &[][..]));
+    }
+    if var_os("WAYLAND_DISPLAY").is_some() {
+        programs.push(("wl-copy", &[][..]));
+    }
+    if var_os("DISPLAY").is_some() {
+        programs.push(("xclip", &["-selection", "clipboard"][..]));
+        programs.push(("xsel", &["--clipboard", "--input"][..]));
+    }
+    programs
+}
+
+/// Runs `program`, giving it `text` on stdin
+fn run(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    // the X and Wayland ones stay in the background to serve the clipboard, so their
+    // output isn't waited on
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let written = child.stdin.take().unwrap().write_all(text.as_bytes());
+    let status = child.wait()?;
+    written?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!("{program} failed ({status})"))),
+    }
+}
+
+/// Puts `text` on the clipboard, writing OSC 52 to `terminal` when no program can
+pub fn copy(text: &str, terminal: &mut impl Write) -> io::Result<Copied> {
+    for (program, args) in programs() {
+        match run(program, args, text) {
+            Ok(()) => return Ok(Copied::Program(program)),
+            Err(e) => debug!("Clipboard: {}: {}", program, e),
+        }
+    }
+    terminal.write_all(osc52(text).as_bytes())?;
+    terminal.flush()?;
+    Ok(Copied::Osc52)
+}
@@ -0,0 +1,692 @@
+//! Subcommands other than `play`, which work without the TUI
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::mpsc::sync_channel;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail};
+use serde::Serialize;
+
+use crate::cli::{DiscArgs, OutputFormat, RipFormat};
+#[cfg(feature = "audio")]
+use crate::config::CONFIG;
+use crate::cue::{cue_sheet, CueFiles};
+use crate::history::DiscHistory;
+use crate::integrity::{check_song, Check};
+use crate::m3u::{m3u8, PlaylistEntry};
+#[cfg(feature = "audio")]
+use crate::playback;
+use crate::playback::{AUDIO_BIT_DEPTH, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+use crate::silence::{find_gaps, proposed_chapters, SilenceOptions};
+use crate::source::{DiscSource, SourceReader};
+use crate::{
+    cue, disc_id, flac, format_duration, history, loudness, minfo, mutex_lock, read_meta_info,
+    sidecar, source, MetaInfo, SongInfo, Track,
+};
+
+/// Fetches the tracks info and the meta info of the disc of `disc`
+fn read_disc(disc: &DiscArgs) -> anyhow::Result<(Vec<Track>, MetaInfo, Arc<dyn DiscSource>)> {
+    let tracks = minfo::minfo_track_info(disc)?;
+    let source = source::open_disc(disc, &tracks)?;
+    let meta_info = read_meta_info(disc, &source, &tracks)?;
+    Ok((tracks, meta_info, source))
+}
+
+/// A song as printed by `--format json`
+#[derive(Serialize)]
+struct SongOutput<'a> {
+    /// Starts from one
+    number: usize,
+    name: &'a str,
+    /// In seconds
+    duration: u32,
+    /// The track (session) number the song is stored in
+    track: usize,
+}
+
+/// The disc as printed by `info --format json`
+#[derive(Serialize)]
+struct DiscOutput<'a> {
+    drive: String,
+    title: Option<&'a str>,
+    /// Unix timestamp
+    creation_time: Option<u64>,
+    /// [`Self::creation_time`] in RFC 3339
+    created: Option<String>,
+    disc_id: String,
+    /// Of all the songs, in seconds
+    duration: u32,
+    songs: Vec<SongOutput<'a>>,
+    tracks: &'a [Track],
+}
+
+fn song_outputs<'a>(meta_info: &'a MetaInfo, tracks: &[Track]) -> Vec<SongOutput<'a>> {
+    let durations = meta_info.song_durations(tracks);
+    meta_info
+        .list
+        .iter()
+        .zip(durations)
+        .enumerate()
+        .map(|(i, (song, duration))| SongOutput {
+            number: i + 1,
+            name: &song.name,
+            duration,
+            track: song.session_no,
+        })
+        .collect()
+}
+
+fn print_json(value: &impl Serialize) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints one line per song: its number, name and duration
+pub fn list(disc: &DiscArgs, format: OutputFormat) -> anyhow::Result<()> {
+    let (tracks, meta_info, _) = read_disc(disc)?;
+    let songs = song_outputs(&meta_info, &tracks);
+    if format == OutputFormat::Json {
+        return print_json(&songs);
+    }
+    for song in songs {
+        println!(
+            "{}\t{}\t{}",
+            song.number,
+            format_duration(song.duration),
+            song.name
+        );
+    }
+    Ok(())
+}
+
+/// Prints the meta info and the tracks table
+pub fn info(disc: &DiscArgs, format: OutputFormat) -> anyhow::Result<()> {
+    let (tracks, meta_info, _) = read_disc(disc)?;
+    if format == OutputFormat::Json {
+        let songs = song_outputs(&meta_info, &tracks);
+        return print_json(&DiscOutput {
+            drive: disc.location(),
+            title: meta_info.title.as_deref(),
+            creation_time: meta_info.creation_time,
+            created: meta_info.creation_time_string(),
+            disc_id: disc_id(&tracks),
+            duration: songs.iter().map(|x| x.duration).sum(),
+            songs,
+            tracks: &tracks,
+        });
+    }
+    let total_duration: u32 = meta_info.song_durations(&tracks).iter().sum();
+    let unknown = || String::from("unknown");
+    println!("Drive: {}", disc.location());
+    println!("Title: {}", meta_info.title.clone().unwrap_or_else(unknown));
+    println!(
+        "Created: {}",
+        meta_info.creation_time_string().unwrap_or_else(unknown)
+    );
+    println!("Disc ID: {}", disc_id(&tracks));
+    println!(
+        "Songs: {}, {}",
+        meta_info.list.len(),
+        format_duration(total_duration)
+    );
+    println!();
+    println!(
+        "{:>5} {:>4} {:<5} {:>10} {:>10} {:>8}",
+        "Track", "Sess", "Type", "Start", "End", "Size"
+    );
+    for t in &tracks {
+        println!(
+            "{:>5} {:>4} {:<5} {:>10} {:>10} {:>8}",
+            t.track_no,
+            t.session_no,
+            t.type_name(),
+            t.start_addr,
+            t.end_addr,
+            t.size
+        );
+    }
+    Ok(())
+}
+
+/// Extracts every song of the disc of `disc` into `output_dir`, as
+/// `<number> - <name>.wav` or `.flac` files, and writes a playlist of them (see
+/// [`write_playlist`])
+///
+/// FLAC files are encoded by `jobs` threads (the number of CPUs by default) while the
+/// disc is read in order, and tagged from the meta info.
+pub fn rip(
+    disc: &DiscArgs,
+    output_dir: &Path,
+    format: RipFormat,
+    jobs: Option<usize>,
+    cover: Option<&Path>,
+) -> anyhow::Result<()> {
+    // before the long part
+    if let Some(cover) = cover.filter(|x| !x.is_file()) {
+        bail!("The cover art {} isn't a file", cover.display());
+    }
+    let (tracks, meta_info, source) = read_disc(disc)?;
+    let songs = meta_info
+        .list
+        .iter()
+        .enumerate()
+        .map(|(i, song)| {
+            let track = song
+                .session_no
+                .checked_sub(1)
+                .and_then(|i| tracks.get(i))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Song {} refers to track #{}, which doesn't exist",
+                        i + 1,
+                        song.session_no
+                    )
+                })?;
+            Ok((song, *track))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    std::fs::create_dir_all(output_dir)?;
+    let mut disc_reader = SourceReader::new(source);
+    let count = songs.len();
+    let file_name = |i: usize, song: &SongInfo| rip_file_name(i, song, format);
+
+    if format == RipFormat::Wav {
+        for (i, &(song, track)) in songs.iter().enumerate() {
+            let file_name = file_name(i, song);
+            println!("[{}/{count}] {file_name}", i + 1);
+
+            // checked before the file is created
+            let mut header = Vec::new();
+            write_wav_header(&mut header, track.size_bytes())
+                .map_err(|e| anyhow!("Can't rip song {} as WAV: {e}", i + 1))?;
+            disc_reader.seek(SeekFrom::Start(track.start_offset()))?;
+            let mut writer = BufWriter::new(File::create(output_dir.join(file_name))?);
+            writer.write_all(&header)?;
+            let copied = std::io::copy(
+                &mut (&mut disc_reader).take(track.size_bytes()),
+                &mut writer,
+            )?;
+            if copied != track.size_bytes() {
+                bail!(
+                    "Disc ended early: read {copied} of {} bytes",
+                    track.size_bytes()
+                );
+            }
+            writer.flush()?;
+        }
+        return write_playlist(&meta_info, &tracks, output_dir, format, cover);
+    }
+
+    let jobs = jobs
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |x| x.get()))
+        .max(1);
+    let mut album_tags = vec![("TRACKTOTAL", count.to_string())];
+    if let Some(title) = &meta_info.title {
+        album_tags.push(("ALBUM", title.clone()));
+    }
+    if let Some(created) = meta_info.creation_time_string() {
+        album_tags.push(("DATE", created[..4].to_string()));
+    }
+    // the disc is read on this thread, so at most `jobs` songs wait in memory
+    let (sender, receiver) = sync_channel::<(usize, Vec<u8>)>(jobs);
+    let receiver = Mutex::new(receiver);
+    std::thread::scope(|scope| {
+        let sender = sender;
+        let workers = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| -> anyhow::Result<()> {
+                    loop {
+                        let Ok((i, pcm)) = mutex_lock!(receiver).recv() else {
+                            return Ok(());
+                        };
+                        let song = songs[i].0;
+                        let file_name = file_name(i, song);
+                        let mut tags = vec![
+                            ("TITLE", song.name.clone()),
+                            ("TRACKNUMBER", (i + 1).to_string()),
+                        ];
+                        tags.extend(album_tags.iter().cloned());
+                        let mut writer = BufWriter::new(File::create(output_dir.join(&file_name))?);
+                        flac::encode(&pcm, &tags, &mut writer)?;
+                        writer.flush()?;
+                        println!("[{}/{count}] {file_name}", i + 1);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (i, &(_, track)) in songs.iter().enumerate() {
+            disc_reader.seek(SeekFrom::Start(track.start_offset()))?;
+            let mut pcm = Vec::with_capacity(track.size_bytes() as usize);
+            (&mut disc_reader)
+                .take(track.size_bytes())
+                .read_to_end(&mut pcm)?;
+            if pcm.len() as u64 != track.size_bytes() {
+                bail!(
+                    "Disc ended early: read {} of {} bytes",
+                    pcm.len(),
+                    track.size_bytes()
+                );
+            }
+            // fails only if all the workers stopped on errors, which are reported below
+            if sender.send((i, pcm)).is_err() {
+                break;
+            }
+        }
+        drop(sender);
+        for worker in workers {
+            worker.join().unwrap()?;
+        }
+        Ok(())
+    })?;
+    write_playlist(&meta_info, &tracks, output_dir, format, cover)
+}
+
+/// Writes an M3U8 playlist of the files `rip` wrote into `output_dir`, named after the
+/// disc title, and copies `cover` there as `cover.<extension>`, where players look for
+/// the cover art of an album
+fn write_playlist(
+    meta_info: &MetaInfo,
+    tracks: &[Track],
+    output_dir: &Path,
+    format: RipFormat,
+    cover: Option<&Path>,
+) -> anyhow::Result<()> {
+    let entries = meta_info
+        .list
+        .iter()
+        .zip(meta_info.song_durations(tracks))
+        .enumerate()
+        .map(|(i, (song, duration))| PlaylistEntry {
+            path: rip_file_name(i, song, format),
+            title: match meta_info.song_performer(i) {
+                Some(performer) => format!("{performer} - {}", song.name),
+                None => song.name.clone(),
+            },
+            duration,
+        })
+        .collect::<Vec<_>>();
+    let path = output_dir.join(format!("{}.m3u8", disc_file_stem(meta_info)));
+    std::fs::write(&path, m3u8(meta_info.title.as_deref(), &entries))?;
+    println!("Playlist: {}", path.display());
+    if let Some(cover) = cover {
+        let extension = cover
+            .extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or("jpg")
+            .to_lowercase();
+        let target = output_dir.join(format!("cover.{extension}"));
+        std::fs::copy(cover, &target)
+            .map_err(|e| anyhow!("Failed to copy {}: {e}", cover.display()))?;
+        println!("Cover: {}", target.display());
+    }
+    Ok(())
+}
+
+/// Name of the files about the whole disc, like its CUE sheet: the title, or `disc`
+fn disc_file_stem(meta_info: &MetaInfo) -> String {
+    meta_info
+        .title
+        .as_deref()
+        .map(sanitize_file_name)
+        .unwrap_or_else(|| String::from("disc"))
+}
+
+/// The name of the file `rip` writes song `i` (of the meta info's list) to
+fn rip_file_name(i: usize, song: &SongInfo, format: RipFormat) -> String {
+    let extension = match format {
+        RipFormat::Wav => "wav",
+        RipFormat::Flac => "flac",
+    };
+    format!(
+        "{:02} - {}.{extension}",
+        i + 1,
+        sanitize_file_name(&song.name)
+    )
+}
+
+/// Writes a CUE sheet of the songs into `output_dir`, named after the disc title,
+/// referring to the WAV files `rip` writes there, or with `image`, to a raw image of the
+/// songs it extracts next to it
+///
+/// Songs not on the disc (or empty) are left out.
+pub fn cue(disc: &DiscArgs, output_dir: &Path, image: bool) -> anyhow::Result<()> {
+    let (tracks, meta_info, source) = read_disc(disc)?;
+    let songs = (0..meta_info.list.len())
+        .filter_map(|i| Some((i, meta_info.playable_track(i, &tracks).ok()?)))
+        .collect::<Vec<_>>();
+    if songs.len() > cue::MAX_TRACKS {
+        bail!(
+            "The disc has {} songs, but a CUE sheet can have only {}",
+            songs.len(),
+            cue::MAX_TRACKS
+        );
+    }
+    std::fs::create_dir_all(output_dir)?;
+    let stem = disc_file_stem(&meta_info);
+    let files = match image {
+        true => CueFiles::Image(format!("{stem}.bin")),
+        false => CueFiles::Wave(
+            songs
+                .iter()
+                .map(|&(i, _)| rip_file_name(i, &meta_info.list[i], RipFormat::Wav))
+                .collect(),
+        ),
+    };
+    if let CueFiles::Image(name) = &files {
+        let mut disc_reader = SourceReader::new(source);
+        let mut writer = BufWriter::new(File::create(output_dir.join(name))?);
+        for (n, &(i, track)) in songs.iter().enumerate() {
+            println!("[{}/{}] {}", n + 1, songs.len(), meta_info.list[i].name);
+            disc_reader.seek(SeekFrom::Start(track.start_offset()))?;
+            let copied = std::io::copy(
+                &mut (&mut disc_reader).take(track.size_bytes()),
+                &mut writer,
+            )?;
+            if copied != track.size_bytes() {
+                bail!(
+                    "Disc ended early: read {copied} of {} bytes",
+                    track.size_bytes()
+                );
+            }
+            let padding = cue::image_size(copied) - copied;
+            std::io::copy(&mut std::io::repeat(0).take(padding), &mut writer)?;
+        }
+        writer.flush()?;
+    }
+    let path = output_dir.join(format!("{stem}.cue"));
+    std::fs::write(&path, cue_sheet(&meta_info, &songs, &files))?;
+    println!("Wrote {}", path.display());
+    if !image {
+        println!("It refers to the WAV files `rip` writes into the same directory");
+    }
+    Ok(())
+}
+
+/// Reads each song with a hash back from the disc and compares it with the hash
+pub fn check(disc: &DiscArgs) -> anyhow::Result<()> {
+    let (tracks, meta_info, source) = read_disc(disc)?;
+    if !meta_info.has_hashes() {
+        bail!("The meta info has no song hashes; author the disc again to have them");
+    }
+    let mut checked = 0;
+    let mut failed = 0;
+    for (i, song) in meta_info.list.iter().enumerate() {
+        let description = format!("Song {} ({})", i + 1, song.name);
+        let Some(hash) = &song.hash else {
+            println!("{description}: no hash, skipped");
+            continue;
+        };
+        checked += 1;
+        let track = song.session_no.checked_sub(1).and_then(|x| tracks.get(x));
+        let problem = match track {
+            None => Some(format!("track #{} isn't on the disc", song.session_no)),
+            Some(&track) => match check_song(&source, track, hash) {
+                Check::Intact => None,
+                x => Some(x.to_string()),
+            },
+        };
+        match problem {
+            None => println!("{description}: OK"),
+            Some(problem) => {
+                failed += 1;
+                println!("{description}: FAIL, {problem}");
+            }
+        }
+    }
+    if failed != 0 {
+        bail!("{failed} of {checked} songs failed the check");
+    }
+    println!("All {checked} songs are intact");
+    Ok(())
+}
+
+/// Prints the chapters proposed for the song `song` (from one; the longest one if
+/// `None`) from its silent gaps, as a `.chapters` file; with `save`, keeps them in the
+/// disc's [`sidecar`]
+pub fn split(
+    disc: &DiscArgs,
+    song: Option<usize>,
+    options: &SilenceOptions,
+    save: bool,
+) -> anyhow::Result<()> {
+    let (tracks, meta_info, source) = read_disc(disc)?;
+    let song_idx = match song {
+        Some(n) if (1..=meta_info.list.len()).contains(&n) => n - 1,
+        Some(n) => bail!(
+            "There's no song {n}; the disc has {} songs",
+            meta_info.list.len()
+        ),
+        None => (0..meta_info.list.len())
+            .max_by_key(|&i| meta_info.song_track(i, &tracks).map(|x| x.size_bytes()))
+            .ok_or_else(|| anyhow!("The disc has no songs"))?,
+    };
+    let track = meta_info
+        .playable_track(song_idx, &tracks)
+        .map_err(|problem| anyhow!("Song {} {problem}", song_idx + 1))?;
+    let mut reader = SourceReader::new(source);
+    reader.seek(SeekFrom::Start(track.start_offset()))?;
+    let gaps = find_gaps(BufReader::new(reader.take(track.size_bytes())), options)?;
+    let chapters = proposed_chapters(&gaps);
+    for chapter in &chapters {
+        println!("{} {}", format_duration(chapter.offset), chapter.name);
+    }
+    if save {
+        let disc_id = disc_id(&tracks);
+        let mut kept = sidecar::load(&disc_id);
+        kept.entry(song_idx).or_default().chapters = chapters;
+        sidecar::store(&disc_id, &kept)?;
+        eprintln!(
+            "Kept the chapters for song {} ({})",
+            song_idx + 1,
+            meta_info.list[song_idx].name
+        );
+    }
+    Ok(())
+}
+
+/// Measures the loudness of every song, printing it, and keeps the gains in the disc's
+/// [`sidecar`], or with `export`, writes the meta info with them there
+///
+/// Exported meta info leaves out the audio tracks of a mixed-mode disc, which aren't
+/// listed in it.
+pub fn scan_loudness(disc: &DiscArgs, export: Option<&Path>) -> anyhow::Result<()> {
+    let (tracks, mut meta_info, source) = read_disc(disc)?;
+    let mut reader = SourceReader::new(source);
+    let count = meta_info.list.len();
+    let mut kept = sidecar::load(&disc_id(&tracks));
+    for i in 0..count {
+        let song = &meta_info.list[i];
+        let Ok(track) = meta_info.playable_track(i, &tracks) else {
+            println!(
+                "[{}/{count}] {}: not on the disc, skipped",
+                i + 1,
+                song.name
+            );
+            continue;
+        };
+        reader.seek(SeekFrom::Start(track.start_offset()))?;
+        let loudness = loudness::measure(BufReader::new((&mut reader).take(track.size_bytes())))?;
+        let gain = loudness.gain();
+        println!(
+            "[{}/{count}] {}: {}, peak {:.1} dBFS, gain {:+.1} dB",
+            i + 1,
+            song.name,
+            loudness
+                .integrated
+                .map_or_else(|| String::from("silent"), |x| format!("{x:.1} LUFS")),
+            20.0 * gain.peak.log10(),
+            gain.gain_db
+        );
+        meta_info.list[i].gain = Some(gain);
+        kept.entry(i).or_default().gain = Some(gain);
+    }
+    match export {
+        Some(path) => {
+            meta_info.list.retain(|x| {
+                !tracks
+                    .get(x.session_no.wrapping_sub(1))
+                    .is_some_and(|x| x.audio)
+            });
+            let mut data = serde_json::to_vec_pretty(&meta_info)?;
+            // the player reads the meta info up to a NUL
+            data.push(0);
+            std::fs::write(path, data)?;
+            println!("Wrote the meta info to {}", path.display());
+        }
+        None => {
+            sidecar::store(&disc_id(&tracks), &kept)?;
+            println!("Kept the gains for the disc");
+        }
+    }
+    Ok(())
+}
+
+/// A disc as printed by `stats --format json`
+#[derive(Serialize)]
+struct DiscStatsOutput<'a> {
+    disc_id: &'a str,
+    title: Option<&'a str>,
+    plays: u32,
+    /// Seconds played in all
+    listened: u64,
+    /// The most played first
+    songs: Vec<SongStatsOutput<'a>>,
+}
+
+#[derive(Serialize)]
+struct SongStatsOutput<'a> {
+    /// Starts from one
+    number: usize,
+    name: &'a str,
+    plays: u32,
+    /// Seconds played in all
+    listened: u64,
+}
+
+/// Prints the `top` most played songs of each disc in the listening history, the most
+/// listened disc first
+pub fn stats(top: usize, format: OutputFormat) -> anyhow::Result<()> {
+    let history =
+        history::load().map_err(|e| anyhow!("Failed to read the listening history: {e}"))?;
+    let mut discs = history.iter().collect::<Vec<(&String, &DiscHistory)>>();
+    discs.sort_by_key(|(id, x)| (std::cmp::Reverse(x.listened()), *id));
+    let outputs = discs
+        .into_iter()
+        .map(|(id, disc)| DiscStatsOutput {
+            disc_id: id,
+            title: disc.title.as_deref(),
+            plays: disc.plays(),
+            listened: disc.listened(),
+            songs: disc
+                .most_played()
+                .into_iter()
+                .take(top)
+                .map(|(number, x)| SongStatsOutput {
+                    number,
+                    name: &x.name,
+                    plays: x.plays,
+                    listened: x.listened,
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    if format == OutputFormat::Json {
+        return print_json(&outputs);
+    }
+    if outputs.is_empty() {
+        println!("Nothing has been played yet");
+    }
+    let listened = |x: u64| format_duration(x.try_into().unwrap_or(u32::MAX));
+    for (i, disc) in outputs.iter().enumerate() {
+        if i != 0 {
+            println!();
+        }
+        println!(
+            "{} ({}): played {} in all\nPlays\tTime\tSong",
+            disc.title.unwrap_or("Untitled"),
+            disc.disc_id,
+            listened(disc.listened)
+        );
+        for song in &disc.songs {
+            println!(
+                "{:>5}\t{}\t{}: {}",
+                song.plays,
+                listened(song.listened),
+                song.number,
+                song.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Prints the names of the audio output devices, marking the default one and the one
+/// `--audio-device` chose
+#[cfg(feature = "audio")]
+pub fn devices() -> anyhow::Result<()> {
+    let names = playback::output_device_names()?;
+    if names.is_empty() {
+        println!("No audio output devices found");
+    }
+    let default = playback::default_output_device_name();
+    let chosen = mutex_lock!(CONFIG).audio_device.clone();
+    for name in &names {
+        let default = match Some(name) == default.as_ref() {
+            true => " (default)",
+            false => "",
+        };
+        let chosen = match Some(name) == chosen.as_ref() {
+            true => " (chosen)",
+            false => "",
+        };
+        println!("{name}{default}{chosen}");
+    }
+    if let Some(x) = chosen.filter(|x| !names.contains(x)) {
+        println!("\nThe chosen device, {x}, isn't there; the default one plays instead");
+    }
+    Ok(())
+}
+
+/// Replaces characters not allowed in file names
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\0' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// The 44-byte header of a PCM WAV file in the disc's audio format
+///
+/// Sizes in WAV files are 32-bit, so `data_size` of about 4 GiB or more is an
+/// [`InvalidInput`](std::io::ErrorKind::InvalidInput) error, with nothing written.
+pub fn write_wav_header(writer: &mut impl Write, data_size: u64) -> std::io::Result<()> {
+    let data_size = u32::try_from(data_size)
+        .ok()
+        .filter(|&x| x <= u32::MAX - 36)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{data_size} bytes of audio are too many for a WAV file"),
+            )
+        })?;
+    let block_align = (AUDIO_CHANNELS * AUDIO_BIT_DEPTH / 8) as u16;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16_u32.to_le_bytes())?;
+    // PCM
+    writer.write_all(&1_u16.to_le_bytes())?;
+    writer.write_all(&(AUDIO_CHANNELS as u16).to_le_bytes())?;
+    writer.write_all(&AUDIO_SAMPLE_RATE.to_le_bytes())?;
+    writer.write_all(&(AUDIO_SAMPLE_RATE * block_align as u32).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&(AUDIO_BIT_DEPTH as u16).to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,251 @@
+//! Disc images compressed so they can be read in windows: seekable zstd, or gzip made
+//! by `bgzip` (BGZF)
+//!
+//! Both are a series of independently compressed frames whose sizes can be learnt
+//! without decompressing anything; a read decompresses only the frames it covers, with
+//! the `zstd` or `gzip` program, a block at a time through [`crate::block_cache`].
+
+use std::io;
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread::spawn;
+
+use log::debug;
+
+use crate::block_cache::{BlockCache, Fetch};
+use crate::source::DiscSource;
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The end of the seek table of seekable zstd
+const SEEKABLE_MAGIC: u32 = 0x8f92eab1;
+/// The magic of the skippable frame holding that table
+const SEEK_TABLE_FRAME_MAGIC: u32 = 0x184d2a5e;
+/// The header, an empty deflate block and the trailer
+const BGZF_MIN_BLOCK_SIZE: u64 = 28;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Format {
+    Zstd,
+    Bgzf,
+}
+
+impl Format {
+    fn program(self) -> &'static str {
+        match self {
+            Format::Zstd => "zstd",
+            Format::Bgzf => "gzip",
+        }
+    }
+}
+
+/// A compressed frame, at `offset` of the file and `start` of the image
+#[derive(Debug, Copy, Clone)]
+struct Frame {
+    offset: u64,
+    size: u64,
+    start: u64,
+    len: u64,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+fn read_exact_at(file: &dyn DiscSource, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read_at(&mut buf[read..], offset + read as u64)? {
+            0 => return Err(ErrorKind::UnexpectedEof.into()),
+            n => read += n,
+        }
+    }
+    Ok(())
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap())
+}
+
+/// Frames of seekable zstd, from the seek table at the end
+fn zstd_frames(file: &dyn DiscSource) -> io::Result<Vec<Frame>> {
+    let not_seekable =
+        || invalid("the zstd image isn't seekable; compress it in seekable frames".into());
+    let mut footer = [0_u8; 9];
+    let footer_offset = file.len().checked_sub(9).ok_or_else(not_seekable)?;
+    read_exact_at(file, &mut footer, footer_offset)?;
+    if u32_at(&footer, 5) != SEEKABLE_MAGIC {
+        return Err(not_seekable());
+    }
+    let count = u32_at(&footer, 0) as u64;
+    let entry_size = if footer[4] & 0x80 != 0 { 12 } else { 8 };
+    let table_offset = footer_offset
+        .checked_sub(count * entry_size)
+        .ok_or_else(not_seekable)?;
+    let mut table = vec![0_u8; (count * entry_size) as usize];
+    read_exact_at(file, &mut table, table_offset)?;
+
+    let mut frame_header = [0_u8; 4];
+    read_exact_at(file, &mut frame_header, table_offset.saturating_sub(8))?;
+    if u32::from_le_bytes(frame_header) != SEEK_TABLE_FRAME_MAGIC {
+        return Err(not_seekable());
+    }
+
+    let (mut offset, mut start) = (0, 0);
+    let mut frames = Vec::new();
+    for entry in table.chunks(entry_size as usize) {
+        let frame = Frame {
+            offset,
+            size: u32_at(entry, 0).into(),
+            start,
+            len: u32_at(entry, 4).into(),
+        };
+        offset += frame.size;
+        start += frame.len;
+        frames.push(frame);
+    }
+    if offset > table_offset.saturating_sub(8) {
+        return Err(invalid("the zstd seek table is past its frames".into()));
+    }
+    Ok(frames)
+}
+
+/// Frames of BGZF, from the block size each one's header has, and the decompressed size
+/// at its end
+fn bgzf_frames(file: &dyn DiscSource) -> io::Result<Vec<Frame>> {
+    let (mut offset, mut start) = (0, 0);
+    let mut frames = Vec::new();
+    while offset < file.len() {
+        let mut header = [0_u8; 18];
+        read_exact_at(file, &mut header, offset)?;
+        // FEXTRA set, and a `BC` subfield first, with the block size less one
+        let size = u16::from_le_bytes([header[16], header[17]]) as u64 + 1;
+        let is_bgzf = header[..2] == GZIP_MAGIC
+            && header[3] & 0x04 != 0
+            && header[12..14] == *b"BC"
+            && header[14..16] == [2, 0]
+            && size >= BGZF_MIN_BLOCK_SIZE;
+        if !is_bgzf {
+            return Err(invalid(format!(
+                "not a BGZF block at offset {offset}; compress the image with bgzip"
+            )));
+        }
+        let mut isize = [0_u8; 4];
+        read_exact_at(file, &mut isize, offset + size - 4)?;
+        let len = u32::from_le_bytes(isize) as u64;
+        frames.push(Frame {
+            offset,
+            size,
+            start,
+            len,
+        });
+        offset += size;
+        start += len;
+    }
+    Ok(frames)
+}
+
+/// Decompresses frames of a compressed image with its program
+struct Decompressor {
+    location: String,
+    file: Arc<dyn DiscSource>,
+    format: Format,
+    frames: Vec<Frame>,
+}
+
+impl Decompressor {
+    /// `data`, whole frames one after another, decompressed by the program
+    fn decompress(&self, data: Vec<u8>) -> io::Result<Vec<u8>> {
+        let program = self.format.program();
+        let mut child = Command::new(program)
+            .args(["-d", "-c"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to run {program}: {e}")))?;
+        // written on a thread of its own so neither side blocks on a full pipe
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = spawn(move || stdin.write_all(&data));
+        let output = child.wait_with_output()?;
+        writer.join().unwrap()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{program} failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl Fetch for Decompressor {
+    fn fetch(&self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        let first = self.frames.partition_point(|x| x.start + x.len <= offset);
+        let last = self.frames.partition_point(|x| x.start < offset + len);
+        let frames = &self.frames[first..last];
+        let (Some(head), Some(tail)) = (frames.first(), frames.last()) else {
+            return Ok(Vec::new());
+        };
+        let mut data = vec![0_u8; (tail.offset + tail.size - head.offset) as usize];
+        read_exact_at(&*self.file, &mut data, head.offset)?;
+        debug!(
+            "Decompressing {} frames ({} bytes) of {}",
+            frames.len(),
+            data.len(),
+            self.location
+        );
+        let output = self.decompress(data)?;
+        let skip = (offset - head.start) as usize;
+        Ok(output
+            .get(skip..)
+            .map(|x| x[..x.len().min(len as usize)].to_vec())
+            .unwrap_or_default())
+    }
+
+    fn location(&self) -> &str {
+        &self.location
+    }
+}
+
+/// A compressed disc image
+pub struct CompressedImage(BlockCache<Decompressor>);
+
+impl CompressedImage {
+    /// Indexes `file` if it's a compressed image; `None` if it isn't
+    pub fn new(location: &str, file: Arc<dyn DiscSource>) -> io::Result<Option<Self>> {
+        let mut magic = [0_u8; 4];
+        if file.len() < magic.len() as u64 {
+            return Ok(None);
+        }
+        read_exact_at(&*file, &mut magic, 0)?;
+        let (format, frames) = if magic == ZSTD_MAGIC {
+            (Format::Zstd, zstd_frames(&*file)?)
+        } else if magic[..2] == GZIP_MAGIC {
+            (Format::Bgzf, bgzf_frames(&*file)?)
+        } else {
+            return Ok(None);
+        };
+        let len = frames.last().map(|x| x.start + x.len).unwrap_or_default();
+        debug!("{} is {:?} of {} frames", location, format, frames.len());
+        let decompressor = Decompressor {
+            location: location.into(),
+            file,
+            format,
+            frames,
+        };
+        Ok(Some(Self(BlockCache::new(decompressor, len))))
+    }
+}
+
+impl DiscSource for CompressedImage {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.0.read_at(buf, offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.0.len()
+    }
+}
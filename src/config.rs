@@ -0,0 +1,196 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::MinfoCli;
+use crate::palette::Palette;
+use crate::play_mode::RepeatMode;
+use crate::scrobble::ScrobbleConfig;
+use crate::{mutex_lock, MetaInfo, Track};
+
+const CONFIG_DIR_NAME: &str = "pseudo-cd";
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// User preferences remembered across runs
+///
+/// Missing fields fall back to their defaults, so old config files keep working.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Show `-remaining/total` instead of `elapsed/total` on the progress gauge
+    pub show_remaining_time: bool,
+    /// While playing, quitting requires pressing the quit key twice
+    pub confirm_quit: bool,
+    pub palette: Palette,
+    /// Play the songs in a random order
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+    /// List and play only the favorite songs of discs that have some
+    pub favorites_only: bool,
+    /// Next on the last song goes to the first one, and previous on the first one to the
+    /// last; without it, they stay
+    pub wrap_around: bool,
+    /// Volume change in percent of one `,`/`.` press, or a remote `volume up`/`down`;
+    /// `<`/`>` change it by five steps
+    pub volume_step: u32,
+    /// Seconds of one `h`/`l` seek
+    pub seek_step: u32,
+    /// Seconds of one `H`/`L` (or Shift + arrow key) seek, for long songs like DJ mixes
+    pub long_seek_step: u32,
+    /// Minutes paused after which the drive is spun down, to be quiet and save power; 0
+    /// never does
+    pub spin_down_minutes: u32,
+    /// Most frames the TUI draws per second, in 1..=60; lower saves power, and traffic over
+    /// slow ssh links
+    pub max_fps: u32,
+    /// Songs with a gain measured by `scan-loudness` are played at the same loudness; see
+    /// [`crate::loudness`]
+    pub normalize_loudness: bool,
+    /// Play counts and listening time are kept for `stats`; see [`crate::history`]
+    pub record_history: bool,
+    /// Opt-in; see [`crate::scrobble`]
+    pub scrobble: ScrobbleConfig,
+    /// The audio output device chosen with `--audio-device`, opened on each launch while
+    /// it's there; the system's default one otherwise
+    pub audio_device: Option<String>,
+    /// The minfo program found to work last time, tried first
+    pub minfo_program: Option<MinfoCli>,
+    /// Saves running `--version` on each launch
+    pub minfo_version: Option<MinfoVersion>,
+}
+
+/// The version line of a minfo program, good while its file is unchanged
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MinfoVersion {
+    pub program: MinfoCli,
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    pub version: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            show_remaining_time: false,
+            confirm_quit: true,
+            palette: Palette::Default,
+            shuffle: false,
+            repeat: RepeatMode::All,
+            favorites_only: false,
+            wrap_around: true,
+            volume_step: 1,
+            seek_step: 5,
+            long_seek_step: 30,
+            spin_down_minutes: 10,
+            max_fps: 20,
+            normalize_loudness: true,
+            record_history: true,
+            scrobble: Default::default(),
+            audio_device: None,
+            minfo_program: None,
+            minfo_version: None,
+        }
+    }
+}
+
+pub static CONFIG: Lazy<Mutex<Config>> = Lazy::new(|| Mutex::new(Default::default()));
+
+/// Volume change of one step, in 0..1: `cli` (`--volume-step`), or else the config file's
+pub fn volume_step(cli: Option<u32>) -> f64 {
+    let percent = cli.unwrap_or_else(|| mutex_lock!(CONFIG).volume_step);
+    percent.clamp(1, 100) as f64 / 100.0
+}
+
+/// Seconds of a seek, or of a `long` one: `cli` (`--seek-step` or `--long-seek-step`), or
+/// else the config file's
+pub fn seek_step(cli: Option<u32>, long: bool) -> f64 {
+    let seconds = cli.unwrap_or_else(|| {
+        let config = mutex_lock!(CONFIG);
+        match long {
+            true => config.long_seek_step,
+            false => config.seek_step,
+        }
+    });
+    seconds.max(1) as f64
+}
+
+/// How long a pause lasts before the drive is spun down: `cli` (`--spin-down-after`), or
+/// else the config file's; `None` for never
+pub fn spin_down_after(cli: Option<u32>) -> Option<Duration> {
+    let minutes = cli.unwrap_or_else(|| mutex_lock!(CONFIG).spin_down_minutes);
+    (minutes > 0).then(|| Duration::from_secs(minutes as u64 * 60))
+}
+
+/// The levels songs are played at ([`MetaInfo::song_levels`]); none with
+/// `normalize_loudness` off
+pub fn song_levels(meta_info: &MetaInfo, tracks: &[Track]) -> Vec<(Track, f64)> {
+    match mutex_lock!(CONFIG).normalize_loudness {
+        true => meta_info.song_levels(tracks),
+        false => Vec::new(),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/pseudo-cd`, or `~/.config/pseudo-cd`
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(x) if !x.is_empty() => PathBuf::from(x),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join(CONFIG_DIR_NAME))
+}
+
+/// `config.json` in [`config_dir`]
+pub fn config_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(CONFIG_FILE_NAME))
+}
+
+fn read_config() -> io::Result<Config> {
+    let Some(path) = config_file_path() else {
+        return Ok(Default::default());
+    };
+    if !path.exists() {
+        return Ok(Default::default());
+    }
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(io::Error::other)
+}
+
+fn write_config(config: &Config) -> io::Result<()> {
+    let Some(path) = config_file_path() else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, config).map_err(io::Error::other)
+}
+
+/// Loads the config file into [`CONFIG`]
+///
+/// A broken config file is not fatal; defaults are used instead.
+pub fn load_config() {
+    match read_config() {
+        Ok(config) => *mutex_lock!(CONFIG) = config,
+        Err(e) => warn!("Failed to read config file: {}", e),
+    }
+}
+
+/// Applies `f` to [`CONFIG`] and writes the result back to the config file
+pub fn update_config(f: impl FnOnce(&mut Config)) {
+    let config = {
+        let mut guard = mutex_lock!(CONFIG);
+        f(&mut guard);
+        guard.clone()
+    };
+    if let Err(e) = write_config(&config) {
+        warn!("Failed to write config file: {}", e);
+    }
+}
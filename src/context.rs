@@ -0,0 +1,24 @@
+//! The settings a player runs with, passed down from where it's started
+//!
+//! The binary makes one from its command line; a program embedding the library makes
+//! its own, one for each disc it plays if it likes.
+
+use crate::cli::{Args, DiscArgs, UiArgs};
+
+#[derive(Debug, Default, Clone)]
+pub struct AppContext {
+    pub disc: DiscArgs,
+    pub ui: UiArgs,
+    /// Failures are reported as JSON (see [`crate::failure`])
+    pub errors_json: bool,
+}
+
+impl From<&Args> for AppContext {
+    fn from(args: &Args) -> Self {
+        Self {
+            disc: args.disc.clone(),
+            ui: args.ui.clone(),
+            errors_json: args.errors_json,
+        }
+    }
+}
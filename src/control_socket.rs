@@ -0,0 +1,185 @@
+//! A Unix socket taking line commands, for window manager key bindings and scripts
+//!
+//! Every line gets a line of JSON back: `{"ok":true}`, `{"ok":false,"error":"..."}`, or for
+//! `status`, `{"ok":true,"status":{...}}` (a [`PlayerStatus`]). A session may look like:
+//!
+//! ```text
+//! $ echo 'goto 5' | nc -U $XDG_RUNTIME_DIR/pseudo-cd.sock
+//! {"ok":true}
+//! ```
+
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread::spawn;
+
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::mutex_lock;
+use crate::remote::{player_status, send_command, PlayerStatus, RemoteCommand};
+
+/// File name of the socket in `$XDG_RUNTIME_DIR`
+const SOCKET_FILE_NAME: &str = "pseudo-cd.sock";
+
+const COMMANDS_HELP: &str = "Commands: play, pause, toggle, next, prev, goto <song number>, \
+seek <seconds>|+<seconds>|-<seconds>, volume <0..100>|up|down, status, quit";
+
+/// The socket file this process created, removed on exit
+static SOCKET_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Serialize, Debug, Default)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<PlayerStatus>,
+}
+
+/// `$XDG_RUNTIME_DIR/pseudo-cd.sock`, or `/tmp/pseudo-cd-<uid>.sock` without that variable
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join(SOCKET_FILE_NAME),
+        // SAFETY: `getuid` always succeeds
+        None => format!("/tmp/pseudo-cd-{}.sock", unsafe { libc::getuid() }).into(),
+    }
+}
+
+/// Parses a command line; `Ok(None)` is `status`
+fn parse_command(line: &str) -> Result<Option<RemoteCommand>, String> {
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let argument = words.next();
+    if words.next().is_some() {
+        return Err(format!("Too many arguments to {name}"));
+    }
+    let number = |what: &str| -> Result<f64, String> {
+        let argument = argument.ok_or_else(|| format!("{name} needs {what}"))?;
+        argument
+            .parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite())
+            .ok_or_else(|| format!("Invalid argument to {name}: {argument}"))
+    };
+    let command = match name {
+        "status" => return Ok(None),
+        "play" => RemoteCommand::Play,
+        "pause" => RemoteCommand::Pause,
+        "toggle" => RemoteCommand::TogglePause,
+        "next" => RemoteCommand::Next,
+        "prev" | "previous" => RemoteCommand::Previous,
+        "goto" => {
+            let n = number("a song number")?;
+            if n < 1.0 || n.fract() != 0.0 {
+                return Err(format!("Invalid song number: {n}"));
+            }
+            RemoteCommand::Goto(n as usize)
+        }
+        "seek" => {
+            let seconds = number("seconds")?;
+            match argument.unwrap().starts_with(['+', '-']) {
+                true => RemoteCommand::SeekBy(seconds),
+                false => RemoteCommand::Seek(seconds),
+            }
+        }
+        "volume" => match argument {
+            Some("up") => RemoteCommand::StepVolume(1),
+            Some("down") => RemoteCommand::StepVolume(-1),
+            _ => RemoteCommand::SetVolume(number("a volume")?.clamp(0.0, 100.0) / 100.0),
+        },
+        "quit" => RemoteCommand::Quit,
+        "" => return Err(COMMANDS_HELP.into()),
+        _ => return Err(format!("Unknown command: {name}. {COMMANDS_HELP}")),
+    };
+    if argument.is_some() && !matches!(name, "goto" | "seek" | "volume") {
+        return Err(format!("{name} takes no arguments"));
+    }
+    Ok(Some(command))
+}
+
+fn handle_line(line: &str) -> Response {
+    let command = parse_command(line).and_then(|command| match command {
+        Some(RemoteCommand::Goto(n)) => player_status().check_song_number(n).map(|_| command),
+        _ => Ok(command),
+    });
+    match command {
+        Ok(None) => Response {
+            ok: true,
+            status: Some(player_status()),
+            ..Default::default()
+        },
+        Ok(Some(command)) => {
+            debug!("Control socket command: {:?}", command);
+            send_command(command);
+            Response {
+                ok: true,
+                ..Default::default()
+            }
+        }
+        Err(error) => Response {
+            ok: false,
+            error: Some(error),
+            ..Default::default()
+        },
+    }
+}
+
+fn serve_client(stream: UnixStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let response = handle_line(&line?);
+        writeln!(writer, "{}", serde_json::to_string(&response).unwrap())?;
+    }
+    Ok(())
+}
+
+/// Binds `path`, taking over a socket file left behind by a process that's gone
+fn bind(path: &PathBuf) -> io::Result<UnixListener> {
+    let stale = std::fs::metadata(path).is_ok_and(|x| x.file_type().is_socket());
+    if stale {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::other("it's in use by another player"));
+        }
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path)
+}
+
+/// Listens on `path` on a thread of its own
+pub fn start(path: PathBuf) -> io::Result<()> {
+    let listener = bind(&path).map_err(|e| {
+        io::Error::other(format!(
+            "Control socket {} is unavailable: {e}",
+            path.display()
+        ))
+    })?;
+    info!("Control socket: {}", path.display());
+    *mutex_lock!(SOCKET_PATH) = Some(path);
+    spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    spawn(move || {
+                        if let Err(e) = serve_client(stream) {
+                            debug!("Control socket client: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Control socket: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Removes the socket file [`start`] created, if any
+pub fn remove_socket_file() {
+    if let Some(path) = mutex_lock!(SOCKET_PATH).take() {
+        let _ = std::fs::remove_file(path);
+    }
+}
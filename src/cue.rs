@@ -0,0 +1,92 @@
+//! CUE sheets describing the songs of a disc, so other players and CD burning software
+//! can use them; see `cue`
+//!
+//! A sheet's tracks are the songs, read from one raw image of them all ([`CueFiles::Image`],
+//! little-endian PCM, as the disc has it) or from the WAV files `rip` writes
+//! ([`CueFiles::Wave`]). The chapters of a song are its INDEX points after 01.
+
+use std::fmt::Write;
+
+use crate::{MetaInfo, Track, CDDA_FRAME_SIZE};
+
+/// CD frames, the unit of CUE times, in a second
+const FRAMES_PER_SECOND: u64 = 75;
+/// Tracks a CUE sheet can have
+pub const MAX_TRACKS: usize = 99;
+
+/// What the tracks of a CUE sheet are read from
+pub enum CueFiles {
+    /// The file name of an image of all the songs, each padded to whole CD frames (see
+    /// [`image_size`])
+    Image(String),
+    /// The file name of each song
+    Wave(Vec<String>),
+}
+
+/// Bytes a song of `size` bytes takes in an image: whole CD frames, as a track has to
+/// start on one
+pub fn image_size(size: u64) -> u64 {
+    size.div_ceil(CDDA_FRAME_SIZE) * CDDA_FRAME_SIZE
+}
+
+/// `mm:ss:ff` of CD frames
+fn cue_time(frames: u64) -> String {
+    let seconds = frames / FRAMES_PER_SECOND;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 60,
+        seconds % 60,
+        frames % FRAMES_PER_SECOND
+    )
+}
+
+/// CUE strings can't hold double quotes
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "'"))
+}
+
+/// The CUE sheet of `songs`, indices into `meta_info.list` with their tracks, in order
+pub fn cue_sheet(meta_info: &MetaInfo, songs: &[(usize, Track)], files: &CueFiles) -> String {
+    let mut sheet = String::new();
+    // writing into a String doesn't fail
+    let mut line = |x: String| writeln!(sheet, "{x}").unwrap();
+    line("REM COMMENT \"pseudo-cd-player\"".into());
+    if let Some(created) = meta_info.creation_time_string() {
+        line(format!("REM DATE {}", &created[..4]));
+    }
+    if let Some(performer) = &meta_info.performer {
+        line(format!("PERFORMER {}", quoted(performer)));
+    }
+    if let Some(title) = &meta_info.title {
+        line(format!("TITLE {}", quoted(title)));
+    }
+    if let CueFiles::Image(name) = files {
+        line(format!("FILE {} BINARY", quoted(name)));
+    }
+    // where the song starts in its file, in CD frames
+    let mut start = 0;
+    for (number, &(idx, track)) in songs.iter().enumerate() {
+        if let CueFiles::Wave(names) = files {
+            line(format!("FILE {} WAVE", quoted(&names[number])));
+            start = 0;
+        }
+        let song = &meta_info.list[idx];
+        line(format!("  TRACK {:02} AUDIO", number + 1));
+        line(format!("    TITLE {}", quoted(&song.name)));
+        if let Some(performer) = song.performer.as_ref() {
+            line(format!("    PERFORMER {}", quoted(performer)));
+        }
+        line(format!("    INDEX 01 {}", cue_time(start)));
+        let chapters = song.chapters.iter().filter(|x| x.offset > 0);
+        for (i, chapter) in chapters.enumerate().take(MAX_TRACKS - 1) {
+            let offset = chapter.offset as u64 * FRAMES_PER_SECOND;
+            line(format!(
+                "    INDEX {:02} {}",
+                i + 2,
+                cue_time(start + offset)
+            ));
+        }
+        start += image_size(track.size_bytes()) / CDDA_FRAME_SIZE;
+    }
+    sheet
+}
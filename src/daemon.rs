@@ -0,0 +1,112 @@
+//! `--daemon`: the player forks into the background and plays headless there
+//!
+//! The parent waits and shows what went wrong if the player fails to start; once it's
+//! playing, the parent returns, and `attach` brings up a TUI for it over the control socket.
+
+use std::fs::File;
+use std::io;
+use std::io::{stderr, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::process::exit;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Written to the pipe to the parent once the player is up; anything else is what the
+/// player wrote to stderr, like error messages
+const READY_MARK: &[u8] = b"\0ready\n";
+
+/// In the forked player with the parent still waiting, the write end of the pipe to it;
+/// stderr is a copy of it till then
+static READY_FD: AtomicI32 = AtomicI32::new(-1);
+
+fn check(result: libc::c_int) -> io::Result<libc::c_int> {
+    match result {
+        -1 => Err(io::Error::last_os_error()),
+        x => Ok(x),
+    }
+}
+
+/// Forks; the parent exits once the child is [`notify_ready`] (or gone), and the child goes
+/// on in a session of its own, with stdin and stdout on /dev/null
+///
+/// It has to be called before any thread is started.
+pub fn daemonize(errors_json: bool) -> io::Result<()> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors
+    check(unsafe { libc::pipe(fds.as_mut_ptr()) })?;
+    let [read_fd, write_fd] = fds;
+    // SAFETY: there's only one thread yet
+    let pid = check(unsafe { libc::fork() })?;
+    if pid != 0 {
+        // SAFETY: the parent owns the read end, and closes the write end it doesn't use
+        let mut reader = unsafe {
+            libc::close(write_fd);
+            File::from_raw_fd(read_fd)
+        };
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output)?;
+        let ready = output
+            .windows(READY_MARK.len())
+            .position(|x| x == READY_MARK);
+        match ready {
+            Some(i) => {
+                // a log line written just as stderr was switched away may come after it
+                stderr().write_all(&output[..i])?;
+                stderr().write_all(&output[i + READY_MARK.len()..])?;
+                println!(
+                    "Playing in the background (PID {pid}); `pseudo-cd-player attach` brings up a TUI"
+                );
+                exit(0);
+            }
+            None => {
+                stderr().write_all(&output)?;
+                // the player's exit code tells what failed
+                let mut status = 0;
+                // SAFETY: `status` is a valid pointer, and `pid` the child's
+                check(unsafe { libc::waitpid(pid, &mut status, 0) })?;
+                let code = match libc::WIFEXITED(status) {
+                    true => libc::WEXITSTATUS(status),
+                    false => 1,
+                };
+                // with `--errors-json`, only the JSON goes to stderr
+                if !errors_json {
+                    eprintln!("The player in the background quit");
+                }
+                exit(code);
+            }
+        }
+    }
+
+    check(unsafe { libc::setsid() })?;
+    let null = File::options().read(true).write(true).open("/dev/null")?;
+    // SAFETY: only standard descriptors are replaced, and the pipe's read end closed
+    unsafe {
+        libc::close(read_fd);
+        check(libc::dup2(null.as_raw_fd(), libc::STDIN_FILENO))?;
+        check(libc::dup2(null.as_raw_fd(), libc::STDOUT_FILENO))?;
+        check(libc::dup2(write_fd, libc::STDERR_FILENO))?;
+        // kept for `notify_ready`, but not by the programs the player runs
+        check(libc::fcntl(write_fd, libc::F_SETFD, libc::FD_CLOEXEC))?;
+    }
+    READY_FD.store(write_fd, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Lets the parent of [`daemonize`] exit; does nothing in a player that isn't a daemon
+pub fn notify_ready() {
+    let fd = READY_FD.swap(-1, Ordering::SeqCst);
+    if fd == -1 {
+        return;
+    }
+    // stderr is switched away first, so what other threads log (like with
+    // `--log-stderr`) can't be taken for the end of the output
+    if let Ok(null) = File::options().write(true).open("/dev/null") {
+        // SAFETY: stderr is replaced with a valid descriptor
+        unsafe {
+            libc::dup2(null.as_raw_fd(), libc::STDERR_FILENO);
+        }
+    }
+    // SAFETY: the write end is only used here from now on; dropping it closes the pipe,
+    // so the parent reads to its end
+    let mut pipe = unsafe { File::from_raw_fd(fd) };
+    let _ = pipe.write_all(READY_MARK);
+}
@@ -0,0 +1,185 @@
+//! `--demo`: a generated disc, for trying the player (or UI changes) without a drive
+//!
+//! The disc has the usual layout: the meta info in session 1 and a song of sine tones in
+//! each following one. On Linux its image is kept in memory (a memfd); elsewhere it's
+//! written to a temporary file. The drive of the [`DiscArgs`] is pointed at it, and
+//! [`minfo`](crate::minfo) returns its tracks instead of asking the minfo program.
+
+use std::f64::consts::TAU;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+
+use crate::cli::DiscArgs;
+use crate::playback::AUDIO_SAMPLE_RATE;
+use crate::virtual_disc::VirtualDisc;
+use crate::{mutex_lock, MetaInfo, Track};
+
+const TITLE: &str = "Pseudo-CD Demo";
+
+/// A song: notes (MIDI note numbers sounding together, and seconds), played `repeat` times
+struct DemoSong {
+    name: &'static str,
+    notes: &'static [(&'static [u8], f64)],
+    repeat: usize,
+    /// -1 is all left, 1 all right
+    pan: f64,
+}
+
+const SONGS: [DemoSong; 5] = [
+    DemoSong {
+        name: "Concert A",
+        notes: &[(&[69], 20.0)],
+        repeat: 1,
+        pan: 0.0,
+    },
+    DemoSong {
+        name: "C Major Scale",
+        notes: &[
+            (&[60], 0.4),
+            (&[62], 0.4),
+            (&[64], 0.4),
+            (&[65], 0.4),
+            (&[67], 0.4),
+            (&[69], 0.4),
+            (&[71], 0.4),
+            (&[72], 0.8),
+            (&[71], 0.4),
+            (&[69], 0.4),
+            (&[67], 0.4),
+            (&[65], 0.4),
+            (&[64], 0.4),
+            (&[62], 0.4),
+            (&[60], 0.8),
+        ],
+        repeat: 4,
+        pan: -0.5,
+    },
+    DemoSong {
+        name: "Arpeggios in A Minor",
+        notes: &[
+            (&[57], 0.25),
+            (&[60], 0.25),
+            (&[64], 0.25),
+            (&[69], 0.25),
+            (&[64], 0.25),
+            (&[60], 0.25),
+        ],
+        repeat: 20,
+        pan: 0.5,
+    },
+    DemoSong {
+        name: "ハ長調の和音 (Chords in C)",
+        notes: &[
+            (&[60, 64, 67], 1.5),
+            (&[57, 60, 64], 1.5),
+            (&[53, 57, 60], 1.5),
+            (&[55, 59, 62], 1.5),
+        ],
+        repeat: 5,
+        pan: 0.0,
+    },
+    DemoSong {
+        name: "A Title Long Enough to Show How Names Scroll When They Don't Fit in the List",
+        notes: &[(&[64], 0.5), (&[67], 0.5), (&[72], 1.0)],
+        repeat: 12,
+        pan: 0.0,
+    },
+];
+
+struct DemoDisc {
+    /// Kept open for the lifetime of the process, which a memfd needs
+    _image: File,
+    tracks: Vec<Track>,
+}
+
+static DEMO_DISC: Lazy<Mutex<Option<DemoDisc>>> = Lazy::new(|| Mutex::new(None));
+
+fn note_frequency(note: u8) -> f64 {
+    440.0 * 2_f64.powf((note as f64 - 69.0) / 12.0)
+}
+
+/// Renders `song` as PCM in the disc's audio format
+fn render(song: &DemoSong) -> Vec<u8> {
+    let rate = AUDIO_SAMPLE_RATE as f64;
+    // short fades keep notes from clicking
+    let fade = 0.01 * rate;
+    let left_gain = (1.0 - song.pan).min(1.0);
+    let right_gain = (1.0 + song.pan).min(1.0);
+    let mut pcm = Vec::new();
+    for _ in 0..song.repeat {
+        for (notes, seconds) in song.notes {
+            let length = (seconds * rate) as usize;
+            for i in 0..length {
+                let t = i as f64 / rate;
+                let envelope = (i as f64 / fade).min((length - i) as f64 / fade).min(1.0);
+                let value = notes
+                    .iter()
+                    .map(|&x| (TAU * note_frequency(x) * t).sin())
+                    .sum::<f64>()
+                    / notes.len() as f64
+                    * envelope
+                    * 0.3
+                    * i16::MAX as f64;
+                pcm.extend(((value * left_gain) as i16).to_le_bytes());
+                pcm.extend(((value * right_gain) as i16).to_le_bytes());
+            }
+        }
+    }
+    pcm
+}
+
+/// The file the image is written to, and the path it's opened by
+fn create_image_file() -> io::Result<(File, PathBuf)> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::FromRawFd;
+        // SAFETY: the name is NUL-terminated, and the new descriptor is owned by the `File`
+        let fd = unsafe { libc::memfd_create(c"pseudo-cd-demo".as_ptr(), 0) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let file = unsafe { File::from_raw_fd(fd) };
+        Ok((file, format!("/proc/self/fd/{fd}").into()))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let path = std::env::temp_dir().join("pseudo-cd-demo.img");
+        Ok((File::create(&path)?, path))
+    }
+}
+
+/// Builds the demo disc and points `disc` at it
+pub fn set_up(disc: &mut DiscArgs) -> io::Result<()> {
+    let mut meta_info = MetaInfo::new(Some(TITLE.into()), SONGS.iter().map(|x| x.name.into()));
+    meta_info.creation_time = Some(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    );
+    let VirtualDisc { tracks, image } =
+        VirtualDisc::pseudo_cd(&meta_info, SONGS.iter().map(render))?;
+
+    let (mut file, path) = create_image_file()?;
+    file.write_all(&image)?;
+
+    disc.drive = path;
+    disc.meta_info_track = 1;
+    disc.no_meta = false;
+    *mutex_lock!(DEMO_DISC) = Some(DemoDisc {
+        _image: file,
+        tracks,
+    });
+    Ok(())
+}
+
+/// The tracks of the demo disc, if it's [`set_up`]
+pub fn tracks() -> Option<Vec<Track>> {
+    mutex_lock!(DEMO_DISC).as_ref().map(|x| x.tracks.clone())
+}
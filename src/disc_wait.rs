@@ -0,0 +1,150 @@
+//! `--wait-for-disc`: starting with an empty drive waits for a disc to be put in, then
+//! reads it like any other, instead of failing
+//!
+//! The drive is asked whether it has a disc on each uevent of the kernel (which udev
+//! listens to as well), like the media change of a disc going in, and every few seconds
+//! in case those don't come, as without the kernel polling the drive.
+
+#[cfg(target_os = "linux")]
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use log::{debug, info};
+
+/// How often the drive is asked without a uevent
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// If the drive has a disc, ready to be read; `true` if it can't tell, like for an
+/// image file, so reading it reports what's wrong
+#[cfg(target_os = "linux")]
+pub fn has_disc(drive: &Path) -> bool {
+    use std::fs::OpenOptions;
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+    const CDROM_DRIVE_STATUS: libc::c_ulong = 0x5326;
+    const CDSL_CURRENT: libc::c_int = i32::MAX;
+    const CDS_NO_DISC: libc::c_int = 1;
+    const CDS_TRAY_OPEN: libc::c_int = 2;
+    const CDS_DRIVE_NOT_READY: libc::c_int = 3;
+    // without a disc, opening the drive only works without blocking
+    let Ok(file) = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(drive)
+    else {
+        return true;
+    };
+    let status = unsafe { libc::ioctl(file.as_raw_fd(), CDROM_DRIVE_STATUS as _, CDSL_CURRENT) };
+    !matches!(status, CDS_NO_DISC | CDS_TRAY_OPEN | CDS_DRIVE_NOT_READY)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn has_disc(_drive: &Path) -> bool {
+    true
+}
+
+/// A socket of the kernel's uevents
+#[cfg(target_os = "linux")]
+struct Uevents(std::os::fd::OwnedFd);
+
+#[cfg(target_os = "linux")]
+impl Uevents {
+    fn open() -> io::Result<Self> {
+        use std::mem::{size_of, zeroed};
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_KOBJECT_UEVENT,
+            )
+        };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+        let mut address: libc::sockaddr_nl = unsafe { zeroed() };
+        address.nl_family = libc::AF_NETLINK as _;
+        // the kernel's own events, rather than udev's
+        address.nl_groups = 1;
+        let result = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                size_of::<libc::sockaddr_nl>() as _,
+            )
+        };
+        match result {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(Self(socket)),
+        }
+    }
+
+    /// Waits up to `timeout` for a uevent; returns if one of `device` came
+    fn wait(&self, device: &str, timeout: Duration) -> io::Result<bool> {
+        use std::os::fd::AsRawFd;
+        let mut poll_fd = libc::pollfd {
+            fd: self.0.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as _) } {
+            -1 => return Err(io::Error::last_os_error()),
+            0 => return Ok(false),
+            _ => {}
+        }
+        let mut buf = [0_u8; 8192];
+        let n = unsafe { libc::recv(poll_fd.fd, buf.as_mut_ptr().cast(), buf.len(), 0) };
+        if n == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(is_uevent_of(&buf[..n as usize], device))
+    }
+}
+
+/// If the uevent `message` (`ACTION@DEVPATH`, then `KEY=VALUE`s, all NUL-terminated) is
+/// of the block device `device`, like `sr0`
+pub fn is_uevent_of(message: &[u8], device: &str) -> bool {
+    let mut fields = message.split(|&x| x == 0).map(String::from_utf8_lossy);
+    let header = fields.next().unwrap_or_default();
+    let path_matches = header.rsplit('/').next() == Some(device);
+    path_matches || fields.any(|x| x.strip_prefix("DEVNAME=") == Some(device))
+}
+
+/// Returns once `drive` has a disc (see [`has_disc`]), or `cancelled` is set
+pub fn wait_for_disc(drive: &Path, cancelled: &AtomicBool) {
+    if has_disc(drive) {
+        return;
+    }
+    info!("Waiting for a disc in {}", drive.display());
+    #[cfg(target_os = "linux")]
+    let uevents = Uevents::open()
+        .inspect_err(|e| debug!("No uevents, only polling the drive: {}", e))
+        .ok();
+    #[cfg(target_os = "linux")]
+    let device = drive
+        .canonicalize()
+        .ok()
+        .and_then(|x| Some(x.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    while !cancelled.load(Ordering::SeqCst) {
+        #[cfg(target_os = "linux")]
+        match uevents.as_ref().map(|x| x.wait(&device, POLL_INTERVAL)) {
+            Some(Ok(true)) => debug!("Uevent of {}", device),
+            Some(Ok(false)) => {}
+            Some(Err(e)) => {
+                debug!("Waiting for uevents failed: {}", e);
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            None => std::thread::sleep(POLL_INTERVAL),
+        }
+        #[cfg(not(target_os = "linux"))]
+        std::thread::sleep(POLL_INTERVAL);
+        if has_disc(drive) {
+            info!("A disc is in {}", drive.display());
+            return;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Locking the drive's tray while a song plays, so a press of its eject button doesn't
+//! pull the disc out from under the player mid-song
+//!
+//! It's unlocked on pausing and on quitting, on every way out of the player (quitting,
+//! signals and panics, through [`unlock`]). Images and other sources have no door to
+//! lock.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+
+use crate::mutex_lock;
+use crate::playback::{EventBus, PlayerCallbackEvent};
+use crate::source::DiscSource;
+
+/// The source whose door is locked now
+static LOCKED: Lazy<Mutex<Option<Arc<dyn DiscSource>>>> = Lazy::new(|| Mutex::new(None));
+
+fn set_locked(source: &Arc<dyn DiscSource>, locked: bool) {
+    let mut guard = mutex_lock!(LOCKED);
+    if guard.is_some() == locked {
+        return;
+    }
+    match source.lock_door(locked) {
+        Ok(()) => {
+            debug!("Door {}", if locked { "locked" } else { "unlocked" });
+            *guard = locked.then(|| Arc::clone(source));
+        }
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+        Err(e) => {
+            warn!(
+                "Failed to {} the drive's door: {}",
+                if locked { "lock" } else { "unlock" },
+                e
+            );
+            // a failed unlock still has the kernel unlock it once the drive is closed
+            *guard = None;
+        }
+    }
+}
+
+/// Has `events` lock the door of `source` while playing and unlock it while paused
+pub fn follow(events: &EventBus, source: &Arc<dyn DiscSource>) {
+    let source = Arc::clone(source);
+    events.subscribe(move |event| {
+        if let PlayerCallbackEvent::Paused(paused) = event {
+            set_locked(&source, !paused);
+        }
+    });
+}
+
+/// Unlocks the door if it's locked; for right before exiting
+pub fn unlock() {
+    let source = mutex_lock!(LOCKED).clone();
+    if let Some(source) = source {
+        set_locked(&source, false);
+    }
+}
@@ -0,0 +1,187 @@
+//! The effects samples go through in the player thread before they're played
+//!
+//! An [`EffectChain`] runs the song's loudness normalization and the volume (both a
+//! [`Gain`]), then the [`Equalizer`], then the
+//! [`Limiter`], then any effects of the user's, in this order. Effects are given
+//! interleaved stereo samples, a whole number of frames at a time.
+
+use std::f64::consts::{SQRT_2, TAU};
+
+use crate::playback::{AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+
+pub trait AudioEffect: Send {
+    /// Processes interleaved stereo samples in place
+    fn process(&mut self, samples: &mut [i16]);
+}
+
+fn to_sample(x: f64) -> i16 {
+    x.clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+/// Scales the samples by a level, in 0..1 for the volume; samples turned up past full
+/// scale are clipped
+pub struct Gain {
+    pub level: f64,
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Self { level: 1.0 }
+    }
+}
+
+impl AudioEffect for Gain {
+    fn process(&mut self, samples: &mut [i16]) {
+        if self.level == 1.0 {
+            return;
+        }
+        for x in samples {
+            *x = to_sample(*x as f64 * self.level);
+        }
+    }
+}
+
+/// A biquad filter, with the state of each channel
+#[derive(Clone, Default)]
+struct Biquad {
+    /// b0, b1, b2, a1, a2, normalized by a0
+    coefficients: [f64; 5],
+    /// x1, x2, y1, y2 of each channel
+    state: [[f64; 4]; AUDIO_CHANNELS as usize],
+}
+
+impl Biquad {
+    /// A shelf of `gain_db` below (`low`) or above `frequency`, from the Audio EQ
+    /// Cookbook with a slope of one
+    fn shelf(low: bool, frequency: f64, gain_db: f64) -> Self {
+        let a = 10_f64.powf(gain_db / 40.0);
+        let w0 = TAU * frequency / AUDIO_SAMPLE_RATE as f64;
+        let cos = w0.cos();
+        let alpha = w0.sin() / 2.0 * SQRT_2;
+        let beta = 2.0 * a.sqrt() * alpha;
+        // the high shelf is the low one with the signs of the odd terms flipped
+        let sign = if low { 1.0 } else { -1.0 };
+        let b0 = a * ((a + 1.0) - sign * (a - 1.0) * cos + beta);
+        let b1 = sign * 2.0 * a * ((a - 1.0) - sign * (a + 1.0) * cos);
+        let b2 = a * ((a + 1.0) - sign * (a - 1.0) * cos - beta);
+        let a0 = (a + 1.0) + sign * (a - 1.0) * cos + beta;
+        let a1 = -sign * 2.0 * ((a - 1.0) + sign * (a + 1.0) * cos);
+        let a2 = (a + 1.0) + sign * (a - 1.0) * cos - beta;
+        Self {
+            coefficients: [b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0],
+            state: Default::default(),
+        }
+    }
+
+    fn process(&mut self, channel: usize, x: f64) -> f64 {
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+        let [x1, x2, y1, y2] = self.state[channel];
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        self.state[channel] = [x, x1, y, y1];
+        y
+    }
+}
+
+/// Bass and treble shelves, each turned up or down in dB; samples boosted past full
+/// scale are clipped
+#[derive(Clone, Default)]
+pub struct Equalizer {
+    /// None when flat
+    shelves: Option<(Biquad, Biquad)>,
+}
+
+impl Equalizer {
+    const BASS_FREQUENCY: f64 = 200.0;
+    const TREBLE_FREQUENCY: f64 = 3000.0;
+
+    pub fn new(bass_db: f64, treble_db: f64) -> Self {
+        if bass_db == 0.0 && treble_db == 0.0 {
+            return Self::default();
+        }
+        Self {
+            shelves: Some((
+                Biquad::shelf(true, Self::BASS_FREQUENCY, bass_db),
+                Biquad::shelf(false, Self::TREBLE_FREQUENCY, treble_db),
+            )),
+        }
+    }
+}
+
+impl AudioEffect for Equalizer {
+    fn process(&mut self, samples: &mut [i16]) {
+        let Some((bass, treble)) = &mut self.shelves else {
+            return;
+        };
+        for (i, x) in samples.iter_mut().enumerate() {
+            let channel = i % AUDIO_CHANNELS as usize;
+            let y = treble.process(channel, bass.process(channel, *x as f64));
+            *x = to_sample(y);
+        }
+    }
+}
+
+/// Turns the volume down right away on peaks over a threshold, and back up slowly
+/// after them, so loud passages don't clip; off by default
+pub struct Limiter {
+    pub enabled: bool,
+    /// The gain applied now, in 0..1
+    gain: f64,
+}
+
+impl Limiter {
+    /// Peaks are kept under this fraction of full scale
+    const THRESHOLD: f64 = 0.9;
+    /// Each frame, the gain goes this much of the way back up to one
+    const RELEASE: f64 = 1.0 / 4410.0;
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gain: 1.0,
+        }
+    }
+}
+
+impl AudioEffect for Limiter {
+    fn process(&mut self, samples: &mut [i16]) {
+        if !self.enabled {
+            return;
+        }
+        let threshold = Self::THRESHOLD * i16::MAX as f64;
+        for frame in samples.chunks_mut(AUDIO_CHANNELS as usize) {
+            let peak = frame.iter().map(|&x| (x as f64).abs()).fold(0.0, f64::max);
+            self.gain += (1.0 - self.gain) * Self::RELEASE;
+            if peak * self.gain > threshold {
+                self.gain = threshold / peak;
+            }
+            for x in frame {
+                *x = to_sample(*x as f64 * self.gain);
+            }
+        }
+    }
+}
+
+/// The effects of the player thread; see the [module docs](self)
+#[derive(Default)]
+pub struct EffectChain {
+    /// The playing song's level; see [`crate::loudness`]
+    pub normalization: Gain,
+    pub gain: Gain,
+    pub equalizer: Equalizer,
+    pub limiter: Limiter,
+    pub user: Vec<Box<dyn AudioEffect>>,
+}
+
+impl AudioEffect for EffectChain {
+    fn process(&mut self, samples: &mut [i16]) {
+        self.normalization.process(samples);
+        self.gain.process(samples);
+        self.equalizer.process(samples);
+        self.limiter.process(samples);
+        for effect in &mut self.user {
+            effect.process(samples);
+        }
+    }
+}
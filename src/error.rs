@@ -0,0 +1,190 @@
+//! Errors of reading a disc and setting up playback, for callers to match on
+//!
+//! The subcommands and the TUI wrap them in `anyhow` with their own context;
+//! [`crate::failure`] finds them again for the exit code.
+//!
+//! The `Display`, `Error` and `From` impls are written out by hand rather than derived
+//! with `thiserror`: the crate isn't among the dependencies this tree is built with.
+
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use crate::failure::FailureKind;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The drive path doesn't exist
+    NoDrive(PathBuf),
+    /// None of these minfo programs are installed
+    MinfoProgramMissing(Vec<String>),
+    /// The minfo program couldn't be run, or its output read
+    MinfoIo(String, io::Error),
+    /// The minfo program exited with a failure, which it does when the drive has no
+    /// (readable) disc
+    MinfoFailed {
+        program: String,
+        exit_status: ExitStatus,
+        /// As the program wrote it, in the encoding of its locale, which needn't be UTF-8
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+    /// The minfo program's output has no tracks table
+    MinfoNoTrackTable,
+    /// A line of the tracks table (numbered from one in the whole output) isn't a track
+    MinfoBadTrackLine { line_no: usize, line: String },
+    /// The meta info track (numbered from one) isn't on the disc
+    MetadataMissing { track: usize, tracks: usize },
+    /// The meta info track isn't valid meta info
+    MetadataInvalid(serde_json::Error),
+    /// The meta info track isn't valid meta info, but these other tracks are, the likeliest
+    /// first
+    MetadataElsewhere {
+        track: usize,
+        candidates: Vec<usize>,
+        error: serde_json::Error,
+    },
+    /// Opening the drive failed
+    DriveOpen(PathBuf, io::Error),
+    /// Reading the disc failed
+    DriveRead(io::Error),
+    /// The image of `--source`, or its tracks table, couldn't be read
+    SourceFetch(String, io::Error),
+    /// No audio output fit for the disc's format could be set up
+    AudioInit(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NoDrive(drive) => write!(f, "The drive {} doesn't exist", drive.display()),
+            Error::MinfoProgramMissing(programs) => match programs.as_slice() {
+                [program] => write!(f, "Command `{program}` not found"),
+                _ => {
+                    let programs = programs
+                        .iter()
+                        .map(|x| format!("`{x}`"))
+                        .collect::<Vec<_>>();
+                    write!(f, "None of the commands {} found", programs.join(", "))
+                }
+            },
+            Error::MinfoIo(program, e) => write!(f, "Failed to run `{program}`: {e}"),
+            Error::MinfoFailed {
+                program,
+                exit_status,
+                stdout,
+                stderr,
+            } => {
+                writeln!(
+                    f,
+                    "`{program}` failed; non-zero exit status: {:?}",
+                    exit_status.code()
+                )?;
+                writeln!(f)?;
+                writeln!(f, "Stderr:")?;
+                writeln!(f, "{}\n\n", String::from_utf8_lossy(stderr))?;
+                writeln!(f, "Stdout:")?;
+                writeln!(f, "{}\n", String::from_utf8_lossy(stdout))
+            }
+            Error::MinfoNoTrackTable => write!(f, "No tracks table in the minfo output"),
+            Error::MinfoBadTrackLine { line_no, line } => {
+                write!(f, "Unknown line {line_no} in the tracks table: {line:?}")
+            }
+            Error::MetadataMissing { track, tracks } => write!(
+                f,
+                "Meta info track {track} is out-of-index; Number of tracks: {tracks}"
+            ),
+            Error::MetadataInvalid(e) => write!(f, "Invalid meta info: {e}"),
+            Error::MetadataElsewhere {
+                track,
+                candidates,
+                error,
+            } => {
+                let candidates = candidates.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                let found = match candidates.as_slice() {
+                    [x] => format!("track {x} has it"),
+                    _ => format!("tracks {} have it", candidates.join(", ")),
+                };
+                write!(f, "Track {track} isn't meta info ({error}), but {found}")
+            }
+            Error::DriveOpen(drive, e) => write!(f, "Failed to open {}: {e}", drive.display()),
+            Error::DriveRead(e) => write!(f, "Failed to read the disc: {e}"),
+            Error::SourceFetch(url, e) => write!(f, "Failed to read {url}: {e}"),
+            Error::AudioInit(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::MinfoIo(_, e)
+            | Error::DriveOpen(_, e)
+            | Error::DriveRead(e)
+            | Error::SourceFetch(_, e) => Some(e),
+            Error::MetadataInvalid(e) | Error::MetadataElsewhere { error: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            Error::NoDrive(_) => FailureKind::NoDrive,
+            Error::MinfoProgramMissing(_) => FailureKind::MinfoProgramMissing,
+            Error::MinfoIo(..) | Error::MinfoNoTrackTable | Error::MinfoBadTrackLine { .. } => {
+                FailureKind::Other
+            }
+            Error::MinfoFailed { .. }
+            | Error::DriveOpen(..)
+            | Error::DriveRead(_)
+            | Error::SourceFetch(..) => FailureKind::NoMedium,
+            Error::MetadataMissing { .. }
+            | Error::MetadataInvalid(_)
+            | Error::MetadataElsewhere { .. } => FailureKind::BadMetaInfo,
+            Error::AudioInit(_) => FailureKind::NoAudioDevice,
+        }
+    }
+
+    /// What the user can do about it, in a sentence
+    pub fn guidance(&self) -> &'static str {
+        match self {
+            Error::NoDrive(_) => "Give the path of the optical drive, like /dev/sr0.",
+            Error::MinfoProgramMissing(_) => "Install cdrskin (or cdrecord, or wodim).",
+            Error::MinfoIo(..) => "Check that the minfo program can be run.",
+            Error::MinfoFailed { .. } => {
+                "Check that there's a disc in the drive, and see what the minfo program says."
+            }
+            Error::MinfoNoTrackTable | Error::MinfoBadTrackLine { .. } => {
+                "The minfo program's output isn't in a known format; try another one with \
+                 --minfo-program."
+            }
+            Error::MetadataMissing { .. } => {
+                "Choose the session holding the meta info with --meta-info-track, or play \
+                 the tracks without it with --no-meta."
+            }
+            Error::MetadataInvalid(_) => {
+                "The disc may not be a Pseudo-CD; --no-meta plays its tracks anyway."
+            }
+            Error::MetadataElsewhere { .. } => {
+                "Read the meta info from the track that has it with --meta-info-track."
+            }
+            Error::DriveOpen(..) => {
+                "Check that there's a disc in the drive, and that you may read it (like \
+                 being in the `cdrom` group)."
+            }
+            Error::DriveRead(_) => "The disc may be scratched or dirty; clean it and retry.",
+            Error::SourceFetch(..) => {
+                "Check the URL. A web server has to answer range requests, with the tracks \
+                 table next to the image as <image>.minfo; an ssh:// machine has to let you \
+                 in without a password, and have dd and cdrskin."
+            }
+            Error::AudioInit(_) => {
+                "Check that an audio output device is connected, and that it plays 44.1 kHz \
+                 16-bit stereo."
+            }
+        }
+    }
+}
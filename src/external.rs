@@ -0,0 +1,197 @@
+//! Running the external programs discs are read with, like `cdrskin`, and picking the
+//! minfo program among those installed
+
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::iter::once;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{sleep, spawn};
+use std::time::{Duration, SystemTime};
+
+use log::{debug, info, trace};
+use once_cell::sync::Lazy;
+
+use crate::cli::{DiscArgs, MinfoCli};
+use crate::config::{update_config, MinfoVersion, CONFIG};
+use crate::error::Error;
+use crate::mutex_lock;
+
+/// External commands that are still running, so they can be killed on exit
+static RUNNING_COMMANDS: Lazy<Mutex<Vec<Arc<Mutex<Child>>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Kills all external commands started by [`execute_command_with_output`] that are still running
+pub fn kill_running_commands() {
+    for child in mutex_lock!(RUNNING_COMMANDS).drain(..) {
+        let _ = mutex_lock!(child).kill();
+    }
+}
+
+fn execute_command_with_output(cmd: &[&str]) -> Result<String, Error> {
+    assert!(!cmd.is_empty());
+    debug!("Running {:?}", cmd);
+    let program = cmd[0];
+    let io_error = |e: io::Error| Error::MinfoIo(program.into(), e);
+    let mut child = Command::new(program)
+        .args(cmd.iter().skip(1))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::MinfoProgramMissing(vec![program.into()]),
+            _ => io_error(e),
+        })?;
+
+    // drain the pipes on their own threads so the child never blocks on a full pipe
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    let stdout_thread = spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+    let stderr_thread = spawn(move || {
+        let mut buf = Vec::new();
+        stderr.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let child = Arc::new(Mutex::new(child));
+    mutex_lock!(RUNNING_COMMANDS).push(Arc::clone(&child));
+    let status = loop {
+        if let Some(status) = mutex_lock!(child).try_wait().map_err(io_error)? {
+            break status;
+        }
+        sleep(Duration::from_millis(10));
+    };
+    mutex_lock!(RUNNING_COMMANDS).retain(|x| !Arc::ptr_eq(x, &child));
+
+    let stdout = stdout_thread.join().unwrap().map_err(io_error)?;
+    let stderr = stderr_thread.join().unwrap().map_err(io_error)?;
+    debug!("{} exited with {}", program, status);
+    trace!("{} stdout:\n{}", program, String::from_utf8_lossy(&stdout));
+    trace!("{} stderr:\n{}", program, String::from_utf8_lossy(&stderr));
+    if !status.success() {
+        return Err(Error::MinfoFailed {
+            program: program.into(),
+            exit_status: status,
+            stdout,
+            stderr,
+        });
+    }
+    // the tracks table is ASCII; anything else in the output, like messages in the
+    // locale's encoding, doesn't matter
+    Ok(String::from_utf8_lossy(&stdout).into())
+}
+
+/// The minfo program found to work in this run, for discs not asking for one
+static FOUND_PROGRAM: Lazy<Mutex<Option<MinfoCli>>> = Lazy::new(|| Mutex::new(None));
+
+/// Minfo programs in the order they're tried: `--minfo-program`, the one found already,
+/// the one recorded in the config, then the rest of [`MinfoCli::ALL`]
+///
+/// What's asked for comes first even if another one was found, as players loaded in the
+/// same process may ask for different ones.
+fn candidates(disc: &DiscArgs) -> Vec<MinfoCli> {
+    let found = *mutex_lock!(FOUND_PROGRAM);
+    let recorded = mutex_lock!(CONFIG).minfo_program;
+    let mut list = Vec::new();
+    for x in disc
+        .minfo_program
+        .into_iter()
+        .chain(found)
+        .chain(recorded)
+        .chain(MinfoCli::ALL)
+    {
+        if !list.contains(&x) {
+            list.push(x);
+        }
+    }
+    list
+}
+
+/// The minfo program that's run next
+pub fn minfo_program(disc: &DiscArgs) -> MinfoCli {
+    candidates(disc)[0]
+}
+
+/// Runs the minfo program with `args`, trying the next of [`candidates`] while one isn't
+/// installed; the one found is kept for the rest of the run and recorded in the config
+pub(crate) fn run_minfo(disc: &DiscArgs, args: &[&str]) -> Result<String, Error> {
+    let candidates = candidates(disc);
+    for &program in &candidates {
+        let cmd = once(program.name())
+            .chain(args.iter().copied())
+            .collect::<Vec<_>>();
+        match execute_command_with_output(&cmd) {
+            Err(Error::MinfoProgramMissing(_)) => {
+                info!("`{}` not found", program.name());
+            }
+            result => {
+                found_program(program);
+                return result;
+            }
+        }
+    }
+    let names = candidates.iter().map(|x| x.name().into()).collect();
+    Err(Error::MinfoProgramMissing(names))
+}
+
+fn found_program(program: MinfoCli) {
+    if mutex_lock!(FOUND_PROGRAM).replace(program) == Some(program) {
+        return;
+    }
+    let recorded = mutex_lock!(CONFIG).minfo_program;
+    if recorded != Some(program) {
+        info!("Using `{}` from now on", program.name());
+        update_config(|x| {
+            x.minfo_program = Some(program);
+            x.minfo_version = None;
+        });
+    }
+}
+
+/// Where `program` is found in `PATH`, with symlinks resolved, and when it was modified
+fn program_file(program: MinfoCli) -> Option<(PathBuf, SystemTime)> {
+    let path = std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|x| x.join(program.name()))
+        .find(|x| x.is_file())?;
+    let path = fs::canonicalize(&path).unwrap_or(path);
+    let modified = fs::metadata(&path).and_then(|x| x.modified()).ok()?;
+    Some((path, modified))
+}
+
+/// The version line recorded in the config for the minfo program that's run next, if
+/// its file hasn't changed since
+pub(crate) fn cached_version(disc: &DiscArgs) -> Option<String> {
+    let cached = mutex_lock!(CONFIG).minfo_version.clone()?;
+    let program = minfo_program(disc);
+    if cached.program != program {
+        return None;
+    }
+    let (path, modified) = program_file(program)?;
+    if (path, modified) != (cached.path, cached.modified) {
+        debug!("`{}` changed since its version was checked", program.name());
+        return None;
+    }
+    debug!("Using the cached version of `{}`", program.name());
+    found_program(program);
+    Some(cached.version)
+}
+
+/// Records `version`, just read from the minfo program found, for [`cached_version`]
+pub(crate) fn cache_version(disc: &DiscArgs, version: &str) {
+    let program = minfo_program(disc);
+    let Some((path, modified)) = program_file(program) else {
+        return;
+    };
+    let entry = MinfoVersion {
+        program,
+        path,
+        modified,
+        version: version.into(),
+    };
+    if mutex_lock!(CONFIG).minfo_version.as_ref() != Some(&entry) {
+        update_config(|x| x.minfo_version = Some(entry));
+    }
+}
@@ -0,0 +1,113 @@
+//! What went wrong, for exit codes and `--errors-json`
+//!
+//! The common failures are [`Error`]s of the library; [`classify`] finds one in an
+//! error's chain, so wrapper scripts can tell them apart without reading the message.
+
+use std::io::{stderr, Write};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::mutex_lock;
+
+/// Exit code 2 is taken by clap, for bad arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Anything not listed below
+    Other,
+    /// The drive path doesn't exist
+    NoDrive,
+    /// The minfo program ran but couldn't read a disc in the drive
+    NoMedium,
+    /// The minfo program (cdrskin, cdrecord or wodim) isn't installed
+    MinfoProgramMissing,
+    /// The meta info track is missing or isn't valid meta info
+    BadMetaInfo,
+    /// No audio output device, or none playing the disc's format
+    NoAudioDevice,
+}
+
+impl FailureKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureKind::Other => 1,
+            FailureKind::NoDrive => 3,
+            FailureKind::NoMedium => 4,
+            FailureKind::MinfoProgramMissing => 5,
+            FailureKind::BadMetaInfo => 6,
+            FailureKind::NoAudioDevice => 7,
+        }
+    }
+}
+
+/// The kind of the outermost [`Error`] in `e`'s chain
+pub fn classify(e: &anyhow::Error) -> FailureKind {
+    e.chain()
+        .find_map(|x| x.downcast_ref::<Error>())
+        .map_or(FailureKind::Other, Error::failure_kind)
+}
+
+/// A failure as printed by `--errors-json`
+#[derive(Serialize)]
+struct FailureReport {
+    error: FailureKind,
+    exit_code: i32,
+    message: String,
+    /// The errors that led to it, outermost first
+    causes: Vec<String>,
+}
+
+impl FailureReport {
+    fn new(e: &anyhow::Error) -> Self {
+        let kind = classify(e);
+        Self {
+            error: kind,
+            exit_code: kind.exit_code(),
+            message: e.to_string(),
+            causes: e.chain().skip(1).map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+/// Prints `e` to stderr (as JSON with `--errors-json`); returns the exit code for it
+pub fn report(e: &anyhow::Error, json: bool) -> i32 {
+    let report = FailureReport::new(e);
+    let mut stderr = stderr().lock();
+    if json {
+        let json = serde_json::to_string(&report).expect("Failed to serialize the error");
+        let _ = writeln!(stderr, "{json}");
+    } else {
+        let _ = writeln!(stderr, "Error: {e:?}");
+    }
+    report.exit_code
+}
+
+/// The error the TUI shows on its Error screen, reported if it quits from there, and
+/// whether it's printed as JSON
+static PENDING: Lazy<Mutex<Option<(FailureReport, bool)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Keeps `e` to be reported on exit (the TUI has shown it already)
+pub fn set_pending(e: &anyhow::Error, json: bool) {
+    *mutex_lock!(PENDING) = Some((FailureReport::new(e), json));
+}
+
+pub fn clear_pending() {
+    *mutex_lock!(PENDING) = None;
+}
+
+/// Prints the pending error with `--errors-json`; returns the exit code for it, or 0 if
+/// there's none
+pub fn report_pending() -> i32 {
+    let Some((report, json)) = mutex_lock!(PENDING).take() else {
+        return 0;
+    };
+    if json {
+        if let Ok(json) = serde_json::to_string(&report) {
+            eprintln!("{json}");
+        }
+    }
+    report.exit_code
+}
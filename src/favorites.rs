@@ -0,0 +1,69 @@
+//! Songs of each disc marked as favorites, to list or play only them
+//!
+//! They're kept in `favorites.json` next to the config file, keyed by [`crate::disc_id`],
+//! and written out on every change.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+use log::warn;
+
+use crate::config::config_dir;
+
+const FAVORITES_FILE_NAME: &str = "favorites.json";
+
+fn favorites_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(FAVORITES_FILE_NAME))
+}
+
+fn read_all() -> io::Result<HashMap<String, BTreeSet<usize>>> {
+    let Some(path) = favorites_file_path().filter(|x| x.exists()) else {
+        return Ok(Default::default());
+    };
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(io::Error::other)
+}
+
+/// The favorite songs of the disc, as indices into [`crate::MetaInfo::list`]
+pub fn load(disc_id: &str) -> BTreeSet<usize> {
+    match read_all() {
+        Ok(mut all) => all.remove(disc_id).unwrap_or_default(),
+        Err(e) => {
+            warn!("Failed to read favorites: {}", e);
+            Default::default()
+        }
+    }
+}
+
+/// Replaces the favorite songs of the disc with `favorites`
+pub fn store(disc_id: &str, favorites: &BTreeSet<usize>) -> io::Result<()> {
+    let Some(path) = favorites_file_path() else {
+        return Ok(());
+    };
+    let mut all = read_all()?;
+    match favorites.is_empty() {
+        true => all.remove(disc_id),
+        false => all.insert(disc_id.into(), favorites.clone()),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &all).map_err(io::Error::other)
+}
+
+/// The songs played out of `count`: the favorites with `favorites_only`, unless there are
+/// none, or else all of them
+pub fn playable_songs(
+    count: usize,
+    favorites: &BTreeSet<usize>,
+    favorites_only: bool,
+) -> Vec<usize> {
+    let only = favorites_only && favorites.iter().any(|&x| x < count);
+    (0..count)
+        .filter(|x| !only || favorites.contains(x))
+        .collect()
+}
@@ -0,0 +1,397 @@
+//! A small FLAC encoder for the disc's audio format (16-bit stereo, 44.1 kHz)
+//!
+//! Only the fixed predictors and Rice-coded residuals are used; that gets close to what
+//! `flac` does by default, without a codec library. The MD5 in STREAMINFO is left zero,
+//! which FLAC allows as "not computed".
+
+use std::io;
+use std::io::Write;
+
+use crate::playback::{AUDIO_BIT_DEPTH, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+
+/// Samples per channel in a frame, except the last one
+const BLOCK_SIZE: usize = 4096;
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_PARTITION_ORDER: u32 = 6;
+/// Rice parameters are written in 5 bits; 31 would mean an escape code
+const MAX_RICE_PARAM: u32 = 30;
+
+/// Writes bits MSB first
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Pending bits, fewer than 8
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    /// Writes the low `bits` bits of `value`; at most 32
+    fn write(&mut self, value: u64, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+        self.acc = (self.acc << bits) | (value & ((1 << bits) - 1));
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1 << self.bits) - 1;
+    }
+
+    /// `q` zeros and a one
+    fn write_unary(&mut self, mut q: u64) {
+        while q >= 32 {
+            self.write(0, 32);
+            q -= 32;
+        }
+        self.write(1, q as u32 + 1);
+    }
+
+    /// Pads with zeros to a byte boundary
+    fn align(&mut self) {
+        if self.bits != 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0_u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0_u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Frame numbers are coded like UTF-8, extended to 36 bits
+fn write_utf8_number(writer: &mut BitWriter, n: u64) {
+    if n < 0x80 {
+        writer.write(n, 8);
+        return;
+    }
+    let mut len = 2;
+    while n >= 1 << (5 * len + 1) {
+        len += 1;
+    }
+    let prefix = !(0xff_u64 >> len) & 0xff;
+    writer.write(prefix | (n >> (6 * (len - 1))), 8);
+    for i in (0..len - 1).rev() {
+        writer.write(0x80 | ((n >> (6 * i)) & 0x3f), 8);
+    }
+}
+
+/// Maps signed residuals to unsigned ones: 0, -1, 1, -2, ...
+fn fold(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+/// The Rice parameter and estimated bit count for a partition of `len` residuals that
+/// fold to `sum`
+fn rice_param(sum: u64, len: u64) -> (u32, u64) {
+    let mean = sum / len.max(1);
+    let guess = match mean {
+        0 => 0,
+        x => (63 - x.leading_zeros()).min(MAX_RICE_PARAM),
+    };
+    (guess.saturating_sub(1)..=(guess + 1).min(MAX_RICE_PARAM))
+        .map(|k| (k, len * (k as u64 + 1) + (sum >> k)))
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap()
+}
+
+enum Subframe {
+    Constant,
+    Verbatim,
+    Fixed {
+        order: usize,
+        partition_order: u32,
+        params: Vec<u32>,
+    },
+}
+
+/// Samples after applying the fixed predictor of `order`; the first `order` are kept as is
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    let mut residual = samples.to_vec();
+    for k in 0..order {
+        for i in (k + 1..residual.len()).rev() {
+            residual[i] -= residual[i - 1];
+        }
+    }
+    residual
+}
+
+/// The cheapest partitioning of `residual` (from index `order`) and its estimated size
+fn plan_partitions(residual: &[i64], order: usize) -> (u32, Vec<u32>, u64) {
+    let n = residual.len();
+    let mut max_order = MAX_PARTITION_ORDER.min(n.trailing_zeros());
+    while max_order > 0 && n >> max_order <= order {
+        max_order -= 1;
+    }
+    let partition_len = n >> max_order;
+    let mut sums = (0..1 << max_order)
+        .map(|i| {
+            residual[(i * partition_len).max(order)..(i + 1) * partition_len]
+                .iter()
+                .map(|&x| fold(x))
+                .sum::<u64>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut best: Option<(u32, Vec<u32>, u64)> = None;
+    for partition_order in (0..=max_order).rev() {
+        let len = (n >> partition_order) as u64;
+        let mut params = Vec::with_capacity(sums.len());
+        // the method and the partition order
+        let mut bits = 6;
+        for (i, &sum) in sums.iter().enumerate() {
+            let len = if i == 0 { len - order as u64 } else { len };
+            let (param, partition_bits) = rice_param(sum, len);
+            params.push(param);
+            bits += 5 + partition_bits;
+        }
+        if best.as_ref().is_none_or(|x| bits < x.2) {
+            best = Some((partition_order, params, bits));
+        }
+        sums = sums.chunks(2).map(|x| x.iter().sum()).collect();
+    }
+    best.unwrap()
+}
+
+/// The smallest way to code `samples` of `bps` bits, and its size in bits
+fn plan_subframe(samples: &[i64], bps: u32) -> (Subframe, u64) {
+    let n = samples.len();
+    // the subframe header
+    let header = 8;
+    if samples.iter().all(|&x| x == samples[0]) {
+        return (Subframe::Constant, header + bps as u64);
+    }
+    let mut best = (Subframe::Verbatim, header + n as u64 * bps as u64);
+    for order in 0..=MAX_FIXED_ORDER.min(n - 1) {
+        let residual = fixed_residual(samples, order);
+        let (partition_order, params, bits) = plan_partitions(&residual, order);
+        let bits = header + order as u64 * bps as u64 + bits;
+        if bits < best.1 {
+            best = (
+                Subframe::Fixed {
+                    order,
+                    partition_order,
+                    params,
+                },
+                bits,
+            );
+        }
+    }
+    best
+}
+
+fn write_subframe(writer: &mut BitWriter, samples: &[i64], bps: u32, subframe: &Subframe) {
+    // zero padding bit, then the type; no wasted bits
+    writer.write(0, 1);
+    match subframe {
+        Subframe::Constant => {
+            writer.write(0, 6);
+            writer.write(0, 1);
+            writer.write(samples[0] as u64, bps);
+        }
+        Subframe::Verbatim => {
+            writer.write(1, 6);
+            writer.write(0, 1);
+            for &x in samples {
+                writer.write(x as u64, bps);
+            }
+        }
+        Subframe::Fixed {
+            order,
+            partition_order,
+            params,
+        } => {
+            writer.write(0b001000 | *order as u64, 6);
+            writer.write(0, 1);
+            for &x in &samples[..*order] {
+                writer.write(x as u64, bps);
+            }
+            let residual = fixed_residual(samples, *order);
+            // Rice coding with 5-bit parameters
+            writer.write(1, 2);
+            writer.write(*partition_order as u64, 4);
+            let partition_len = samples.len() >> partition_order;
+            for (i, &param) in params.iter().enumerate() {
+                writer.write(param as u64, 5);
+                let start = (i * partition_len).max(*order);
+                for &x in &residual[start..(i + 1) * partition_len] {
+                    let u = fold(x);
+                    writer.write_unary(u >> param);
+                    writer.write(u, param);
+                }
+            }
+        }
+    }
+}
+
+/// Encodes one frame of `left` and `right`; returns its bytes
+fn encode_frame(frame_no: u64, left: &[i64], right: &[i64]) -> Vec<u8> {
+    let bps = AUDIO_BIT_DEPTH;
+    let side = left
+        .iter()
+        .zip(right)
+        .map(|(l, r)| l - r)
+        .collect::<Vec<_>>();
+    let mid = left
+        .iter()
+        .zip(right)
+        .map(|(l, r)| (l + r) >> 1)
+        .collect::<Vec<_>>();
+    let left_plan = plan_subframe(left, bps);
+    let right_plan = plan_subframe(right, bps);
+    // the side channel needs one more bit
+    let side_plan = plan_subframe(&side, bps + 1);
+    let mid_plan = plan_subframe(&mid, bps);
+
+    // (channel assignment, first channel, second channel)
+    let choices = [
+        (1, (left, bps, &left_plan), (right, bps, &right_plan)),
+        (8, (left, bps, &left_plan), (&side[..], bps + 1, &side_plan)),
+        (
+            9,
+            (&side[..], bps + 1, &side_plan),
+            (right, bps, &right_plan),
+        ),
+        (
+            10,
+            (&mid[..], bps, &mid_plan),
+            (&side[..], bps + 1, &side_plan),
+        ),
+    ];
+    let (assignment, first, second) = choices
+        .iter()
+        .min_by_key(|(_, a, b)| a.2 .1 + b.2 .1)
+        .unwrap();
+
+    let n = left.len();
+    let mut writer = BitWriter::new();
+    // sync code, then fixed block size
+    writer.write(0b11111111111110, 14);
+    writer.write(0, 2);
+    // block size: 4096, or 16 bits at the end of the header
+    writer.write(if n == BLOCK_SIZE { 12 } else { 7 }, 4);
+    // 44.1 kHz
+    writer.write(9, 4);
+    writer.write(*assignment, 4);
+    // 16 bits per sample
+    writer.write(4, 3);
+    writer.write(0, 1);
+    write_utf8_number(&mut writer, frame_no);
+    if n != BLOCK_SIZE {
+        writer.write(n as u64 - 1, 16);
+    }
+    let crc = crc8(&writer.bytes);
+    writer.write(crc as u64, 8);
+
+    for (samples, bps, plan) in [first, second] {
+        write_subframe(&mut writer, samples, *bps, &plan.0);
+    }
+    writer.align();
+    let crc = crc16(&writer.bytes);
+    writer.write(crc as u64, 16);
+    writer.bytes
+}
+
+/// Writes raw PCM in the disc's audio format as a FLAC file, with `tags` as its Vorbis
+/// comments
+pub fn encode(pcm: &[u8], tags: &[(&str, String)], writer: &mut impl Write) -> io::Result<()> {
+    assert_eq!(
+        (AUDIO_CHANNELS, AUDIO_BIT_DEPTH, AUDIO_SAMPLE_RATE),
+        (2, 16, 44100)
+    );
+    // a partial sample frame at the end is dropped
+    let frame_bytes = AUDIO_CHANNELS as usize * 2;
+    let total = pcm.len() / frame_bytes;
+    let sample = |x: &[u8]| i16::from_le_bytes([x[0], x[1]]) as i64;
+    let frames = pcm[..total * frame_bytes]
+        .chunks(BLOCK_SIZE * frame_bytes)
+        .enumerate()
+        .map(|(i, block)| {
+            let (left, right): (Vec<_>, Vec<_>) = block
+                .chunks_exact(frame_bytes)
+                .map(|x| (sample(&x[..2]), sample(&x[2..])))
+                .unzip();
+            encode_frame(i as u64, &left, &right)
+        })
+        .collect::<Vec<_>>();
+    let frame_sizes = frames.iter().map(|x| x.len() as u64);
+    let min_frame = frame_sizes.clone().min().unwrap_or(0);
+    let max_frame = frame_sizes.max().unwrap_or(0);
+    // the last block doesn't count for the minimum, unless it's the only one
+    let block_size = total.min(BLOCK_SIZE) as u64;
+
+    writer.write_all(b"fLaC")?;
+    let mut info = BitWriter::new();
+    // last-block flag, type 0 (STREAMINFO), length
+    info.write(0, 1);
+    info.write(0, 7);
+    info.write(34, 24);
+    info.write(block_size, 16);
+    info.write(block_size, 16);
+    info.write(min_frame, 24);
+    info.write(max_frame, 24);
+    info.write(AUDIO_SAMPLE_RATE as u64, 20);
+    info.write(AUDIO_CHANNELS as u64 - 1, 3);
+    info.write(AUDIO_BIT_DEPTH as u64 - 1, 5);
+    info.write(total as u64 >> 32, 4);
+    info.write(total as u64, 32);
+    writer.write_all(&info.bytes)?;
+    // MD5, not computed
+    writer.write_all(&[0; 16])?;
+
+    // the Vorbis comment block is little-endian, unlike the rest
+    let mut comment = Vec::new();
+    let vendor = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+    comment.extend((vendor.len() as u32).to_le_bytes());
+    comment.extend(vendor.as_bytes());
+    comment.extend((tags.len() as u32).to_le_bytes());
+    for (key, value) in tags {
+        let field = format!("{key}={value}");
+        comment.extend((field.len() as u32).to_le_bytes());
+        comment.extend(field.as_bytes());
+    }
+    // last-block flag and type 4 (VORBIS_COMMENT)
+    writer.write_all(&[0x80 | 4])?;
+    writer.write_all(&(comment.len() as u32).to_be_bytes()[1..])?;
+    writer.write_all(&comment)?;
+
+    for frame in frames {
+        writer.write_all(&frame)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,571 @@
+//! `--no-tui`: plays the disc printing plain progress lines, controlled by single keys on stdin
+//!
+//! When stdin is a terminal, it's put into raw mode so keys work without Enter;
+//! otherwise (a pipe, a serial line, ...) every byte read is taken as a key.
+
+use std::io::{stdin, stdout, IsTerminal, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::spawn;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::enable_raw_mode;
+
+use crate::alarm;
+use crate::cli::DiscArgs;
+use crate::config::{seek_step, song_levels, spin_down_after, update_config, volume_step};
+use crate::context::AppContext;
+use crate::disc_wait;
+use crate::door_lock;
+use crate::favorites::{self, playable_songs};
+use crate::on_finish::{self, OnFinish};
+use crate::play_mode::{
+    initial_favorites_only, initial_modes, initial_wrap_around, playlist_finished, shuffled,
+    skipped_song, song_after_end, RepeatMode,
+};
+use crate::playback::{
+    duration_from_bytes, playback_handle, set_global_playback_handle, start_global_playback_thread,
+    stepped_volume, EventBus, PlayerCallbackEvent, PlayerCommand, MISSING_AUDIO_DEVICE,
+};
+use crate::remote::{PlaybackState, PlayerStatus, RemoteCommand, SongStatus};
+use crate::resume::ResumePoint;
+use crate::source;
+use crate::tui::clean_up_and_exit;
+use crate::{
+    disc_id, format_duration, minfo, mutex_lock, read_meta_info, remote, resume, MetaInfo, Track,
+};
+
+/// A progress line is printed once per this many seconds of playback
+const PROGRESS_INTERVAL: u32 = 10;
+/// Volume steps (see [`volume_step`]) of `<`/`>`
+const VOLUME_COARSE_STEPS: i32 = 5;
+
+/// Whether stdin is in raw mode, where output lines need an explicit carriage return
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+
+const KEYS_HELP: &str = "Keys: Space pause/resume, n next, p previous, h/l and H/L seek, ,/. and \
+</> volume, z shuffle, R repeat mode, i info, q quit";
+
+fn say(line: &str) {
+    let mut stdout = stdout().lock();
+    let _ = match RAW_MODE.load(Ordering::SeqCst) {
+        true => write!(stdout, "{line}\r\n"),
+        false => writeln!(stdout, "{line}"),
+    };
+    let _ = stdout.flush();
+}
+
+struct HeadlessState {
+    tracks: Vec<Track>,
+    meta_info: MetaInfo,
+    playing_song_idx: usize,
+    paused: bool,
+    position: u32,
+    total_duration: u32,
+    volume: f64,
+    /// Volume change of one step; see [`volume_step`]
+    volume_step: f64,
+    /// Seconds of `h`/`l` and of `H`/`L`; see [`seek_step`]
+    seek_steps: (f64, f64),
+    disc_id: String,
+    /// Where this disc was left off last time, until it's resumed with `r` or the song changes
+    resume_offer: Option<ResumePoint>,
+    shuffle: bool,
+    /// The songs played, all of them or the favorites (see [`playable_songs`])
+    songs: Vec<usize>,
+    /// [`Self::songs`] in play order: shuffled, or as listed in the meta info
+    play_order: Vec<usize>,
+    repeat: RepeatMode,
+    /// Whether next and previous wrap around at the ends of [`Self::play_order`]
+    wrap_around: bool,
+}
+
+impl HeadlessState {
+    /// See [`MetaInfo::playable_track`]
+    fn song_track(&self, idx: usize) -> Result<Track, &'static str> {
+        self.meta_info.playable_track(idx, &self.tracks)
+    }
+
+    fn song_line(&self) -> String {
+        let idx = self.playing_song_idx;
+        format!(
+            "{}/{}: {}",
+            idx + 1,
+            self.meta_info.list.len(),
+            self.meta_info.list[idx].name
+        )
+    }
+
+    fn progress_line(&self) -> String {
+        format!(
+            "[{}/{}] {}",
+            format_duration(self.position),
+            format_duration(self.total_duration),
+            self.song_line()
+        )
+    }
+
+    /// Makes `idx` the playing song and returns its track
+    fn set_song(&mut self, idx: usize) -> Result<Track, &'static str> {
+        if idx != self.playing_song_idx {
+            self.resume_offer = None;
+        }
+        self.playing_song_idx = idx;
+        self.song_track(idx)
+    }
+
+    fn play_position(&self) -> usize {
+        self.play_order
+            .iter()
+            .position(|&x| x == self.playing_song_idx)
+            .unwrap_or_default()
+    }
+
+    /// Moves `delta` songs forward (or backwards) in play order, wrapping around, and returns
+    /// the new track
+    fn step_song(&mut self, delta: isize) -> Result<Track, &'static str> {
+        let count = self.play_order.len() as isize;
+        let position = (self.play_position() as isize + delta).rem_euclid(count);
+        self.set_song(self.play_order[position as usize])
+    }
+
+    fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        self.play_order = match shuffle {
+            true => shuffled(self.songs.clone(), Some(self.playing_song_idx)),
+            false => self.songs.clone(),
+        };
+    }
+
+    /// Tells remote controls the current state (see [`crate::remote`])
+    fn publish_status(&self) {
+        remote::publish_status(PlayerStatus {
+            state: match self.paused {
+                true => PlaybackState::Paused,
+                false => PlaybackState::Playing,
+            },
+            disc_title: self.meta_info.title.clone(),
+            disc_id: Some(self.disc_id.clone()),
+            song_number: Some(self.playing_song_idx + 1),
+            position: self.position,
+            volume: self.volume,
+            shuffle: self.shuffle,
+            repeat: self.repeat,
+            songs: self
+                .meta_info
+                .list
+                .iter()
+                .zip(self.meta_info.song_durations(&self.tracks))
+                .map(|(song, duration)| SongStatus {
+                    name: song.name.clone(),
+                    duration,
+                })
+                .collect(),
+        });
+    }
+}
+
+fn player_send(cmd: PlayerCommand) {
+    playback_handle().unwrap().send(cmd);
+}
+
+/// Goes to the song `delta` songs away from the playing one, announcing it, or that
+/// there's none at an end of the play order without wrapping around
+fn step_song(state: &Mutex<HeadlessState>, delta: isize) {
+    let song = {
+        let guard = mutex_lock!(state);
+        let (order, playing) = (&guard.play_order, guard.playing_song_idx);
+        skipped_song(order, playing, delta, guard.wrap_around)
+    };
+    match song {
+        Some(idx) => go_to_song(state, idx),
+        None if delta > 0 => say("Already at the last song"),
+        None => say("Already at the first song"),
+    }
+}
+
+/// Goes to the song by its index, announcing it
+fn go_to_song(state: &Mutex<HeadlessState>, idx: usize) {
+    change_song(state, |x| x.set_song(idx), true);
+}
+
+/// Goes to the next song as the repeat mode says, once the playing one ends; after the
+/// last one, does `on_finish`
+fn song_finished(state: &Mutex<HeadlessState>, on_finish: Option<&OnFinish>, disc: &DiscArgs) {
+    let ((idx, play), finished) = {
+        let guard = mutex_lock!(state);
+        let (order, playing) = (&guard.play_order, guard.playing_song_idx);
+        (
+            song_after_end(order, playing, guard.repeat),
+            playlist_finished(order, playing, guard.repeat),
+        )
+    };
+    if let Some(action) = on_finish.filter(|_| finished) {
+        on_finish::playlist_finished(action, disc);
+    }
+    change_song(state, |x| x.set_song(idx), play);
+}
+
+/// Changes the playing song by `pick` and announces it; it stays paused on `play` being false
+///
+/// A song whose track doesn't exist, or is empty, is reported and not played.
+fn change_song(
+    state: &Mutex<HeadlessState>,
+    pick: impl FnOnce(&mut HeadlessState) -> Result<Track, &'static str>,
+    play: bool,
+) {
+    // not holding the lock while sending, as the player thread may be waiting for it
+    let (line, track) = {
+        let mut guard = mutex_lock!(state);
+        let track = pick(&mut guard);
+        guard.publish_status();
+        (guard.song_line(), track)
+    };
+    match track {
+        Ok(track) => {
+            let verb = if play { "Playing" } else { "Paused at" };
+            say(&format!("{verb} {line}"));
+            player_send(PlayerCommand::Goto(track, play));
+        }
+        Err(problem) => say(&format!("Song {line} {problem}")),
+    }
+}
+
+pub fn run(context: &AppContext) -> anyhow::Result<()> {
+    let drive = &context.disc.drive;
+    if context.ui.wait_for_disc && !disc_wait::has_disc(drive) {
+        say(&format!("Waiting for a disc in {}...", drive.display()));
+        disc_wait::wait_for_disc(drive, &AtomicBool::new(false));
+    }
+    say("Fetching tracks info...");
+    let tracks = minfo::minfo_track_info(&context.disc)?;
+    let source = source::open_disc(&context.disc, &tracks)?;
+    let meta_info = read_meta_info(&context.disc, &source, &tracks)?;
+    let total_duration: u32 = meta_info.song_durations(&tracks).iter().sum();
+    say(&format!(
+        "{}{} songs, {}",
+        meta_info
+            .title
+            .as_ref()
+            .map(|x| format!("{x}: "))
+            .unwrap_or_default(),
+        meta_info.list.len(),
+        format_duration(total_duration)
+    ));
+    if meta_info.list.is_empty() {
+        say("No songs found on the disc");
+        return Ok(());
+    }
+
+    let ui = &context.ui;
+    let (start_song_idx, start_track_given, start_paused) = (
+        ui.start_song_idx(meta_info.list.len())?,
+        ui.start_track.is_some(),
+        ui.paused || ui.at.is_some(),
+    );
+
+    let disc_id = disc_id(&tracks);
+    let (shuffle, repeat) = initial_modes(ui);
+    let mut songs = playable_songs(
+        meta_info.list.len(),
+        &favorites::load(&disc_id),
+        initial_favorites_only(ui),
+    );
+    // songs in sessions the disc hasn't, or in empty tracks, are skipped
+    songs.retain(|&x| meta_info.playable_track(x, &tracks).is_ok());
+    if songs.is_empty() {
+        say("None of the songs can be played");
+        return Ok(());
+    }
+    let start_problem = meta_info.playable_track(start_song_idx, &tracks).err();
+    if let Some(problem) = start_problem.filter(|_| start_track_given) {
+        say(&format!("Song {} {problem}", start_song_idx + 1));
+    }
+    let start_missing = start_problem.is_some();
+    let (play_order, start_song_idx) = match shuffle {
+        true => {
+            // without a song asked for, shuffling starts at a random one
+            let order = shuffled(songs.clone(), start_track_given.then_some(start_song_idx));
+            let first = order[0];
+            (order, first)
+        }
+        // without a song asked for, only favorites start
+        false if (!start_track_given || start_missing) && !songs.contains(&start_song_idx) => {
+            (songs.clone(), songs[0])
+        }
+        false => (songs.clone(), start_song_idx),
+    };
+
+    // an explicit start song (or an alarm's) beats the old point
+    let resume_offer = resume::load(&disc_id)
+        .filter(|x| x.is_worth_resuming() && meta_info.playable_track(x.song_idx, &tracks).is_ok())
+        .filter(|_| !start_track_given && ui.at.is_none());
+    let song_levels = song_levels(&meta_info, &tracks);
+    let state = Arc::new(Mutex::new(HeadlessState {
+        tracks,
+        meta_info,
+        playing_song_idx: start_song_idx,
+        paused: start_paused,
+        position: 0,
+        total_duration: 0,
+        volume: 1.0,
+        volume_step: volume_step(ui.volume_step),
+        seek_steps: (
+            seek_step(ui.seek_step, false),
+            seek_step(ui.long_seek_step, true),
+        ),
+        disc_id,
+        resume_offer,
+        shuffle,
+        songs,
+        play_order,
+        repeat,
+        wrap_around: initial_wrap_around(ui),
+    }));
+    let events = EventBus::new();
+    // the next song is gone to on a thread of its own, as the player thread running the
+    // subscriber below would wait on itself sending it while another command is waiting
+    let finished_events = events.channel();
+    let state_for_finished = Arc::clone(&state);
+    let (on_finish, disc) = (ui.on_finish.clone(), context.disc.clone());
+    spawn(move || {
+        for event in finished_events {
+            if event == PlayerCallbackEvent::Finished {
+                song_finished(&state_for_finished, on_finish.as_ref(), &disc);
+            }
+        }
+    });
+    let state_for_events = Arc::clone(&state);
+    events.subscribe(move |event| {
+        let state = &state_for_events;
+        match event {
+            PlayerCallbackEvent::Finished => {}
+            PlayerCallbackEvent::Paused(paused) => {
+                let mut guard = mutex_lock!(state);
+                guard.paused = paused;
+                guard.publish_status();
+            }
+            PlayerCallbackEvent::Progress(current, total) => {
+                let mut guard = mutex_lock!(state);
+                guard.position = current;
+                guard.total_duration = total;
+                guard.publish_status();
+                // the old point stays until it's decided whether to resume at it
+                if guard.resume_offer.is_none() {
+                    resume::record(&guard.disc_id, guard.playing_song_idx, current);
+                }
+                if current > 0 && current % PROGRESS_INTERVAL == 0 {
+                    say(&guard.progress_line());
+                }
+            }
+            PlayerCallbackEvent::SectorSkipped(sector) => {
+                say(&format!("Read error, sector {sector} skipped"));
+            }
+            PlayerCallbackEvent::ReadErrors(x) => {
+                if let Some(summary) = x.summary() {
+                    say(&format!("Read errors so far: {summary}"));
+                }
+            }
+            PlayerCallbackEvent::NotAudio(content) => {
+                let song = mutex_lock!(state).playing_song_idx + 1;
+                say(&format!(
+                    "Song {song} looks like {content}, not audio; paused"
+                ));
+            }
+        }
+    });
+    door_lock::follow(&events, &source);
+    let playback_handle = start_global_playback_thread(source, events)?;
+    if let Some(name) = mutex_lock!(MISSING_AUDIO_DEVICE).take() {
+        say(&format!(
+            "{name} not found; playing on the default audio device"
+        ));
+    }
+    let spin_down = spin_down_after(context.ui.spin_down_after);
+    playback_handle.send(PlayerCommand::SetSpinDown(spin_down));
+    playback_handle.send(PlayerCommand::SetSongLevels(song_levels));
+    set_global_playback_handle(playback_handle);
+    player_send(PlayerCommand::Start);
+    change_song(&state, |x| x.step_song(0), !start_paused);
+    let remote_state = Arc::clone(&state);
+    spawn(move || serve_remote_commands(&remote_state));
+    #[cfg(unix)]
+    crate::daemon::notify_ready();
+
+    say(KEYS_HELP);
+    if let Some(at) = context.ui.at {
+        say(&format!("Playing at {at}, in {}", alarm::time_until(at)));
+        alarm::start(at);
+    }
+    if let Some(point) = resume_offer {
+        say(&format!(
+            "Press r to resume at {}: {}, {}",
+            point.song_idx + 1,
+            mutex_lock!(state).meta_info.list[point.song_idx].name,
+            format_duration(point.position)
+        ));
+    }
+    if stdin().is_terminal() {
+        enable_raw_mode()?;
+        RAW_MODE.store(true, Ordering::SeqCst);
+        loop {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                let ctrl_c =
+                    key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+                let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                match key.code {
+                    _ if ctrl_c => handle_key(&state, 'q'),
+                    KeyCode::Char(c) => handle_key(&state, c),
+                    KeyCode::Left if shift => handle_key(&state, 'H'),
+                    KeyCode::Right if shift => handle_key(&state, 'L'),
+                    KeyCode::Left => handle_key(&state, 'h'),
+                    KeyCode::Right => handle_key(&state, 'l'),
+                    _ => {}
+                }
+            }
+        }
+    } else {
+        // keys from a pipe; at its end, playback just goes on
+        for byte in stdin().lock().bytes() {
+            handle_key(&state, byte? as char);
+        }
+        loop {
+            std::thread::park();
+        }
+    }
+}
+
+fn handle_key(state: &Arc<Mutex<HeadlessState>>, key: char) {
+    match key {
+        'q' => clean_up_and_exit(),
+        ' ' => {
+            let paused = !mutex_lock!(state).paused;
+            set_paused(paused);
+        }
+        'n' => step_song(state, 1),
+        'p' => step_song(state, -1),
+        'h' | 'l' | 'H' | 'L' => {
+            let (step, long_step) = mutex_lock!(state).seek_steps;
+            let offset = match key {
+                'h' => -step,
+                'l' => step,
+                'H' => -long_step,
+                _ => long_step,
+            };
+            seek(state, |p| p + offset);
+        }
+        ',' => step_volume(state, -1),
+        '.' => step_volume(state, 1),
+        '<' => step_volume(state, -VOLUME_COARSE_STEPS),
+        '>' => step_volume(state, VOLUME_COARSE_STEPS),
+        'i' => {
+            let guard = mutex_lock!(state);
+            let paused = if guard.paused { ", paused" } else { "" };
+            say(&format!(
+                "{}{paused}, volume {} %",
+                guard.progress_line(),
+                (guard.volume * 100.0).round()
+            ));
+        }
+        'r' => {
+            let Some(point) = mutex_lock!(state).resume_offer.take() else {
+                return;
+            };
+            go_to_song(state, point.song_idx);
+            player_send(PlayerCommand::Seek(point.position as f64));
+            say(&format!("Resumed at {}", format_duration(point.position)));
+        }
+        'z' => {
+            let shuffle = {
+                let mut guard = mutex_lock!(state);
+                let shuffle = !guard.shuffle;
+                guard.set_shuffle(shuffle);
+                guard.publish_status();
+                shuffle
+            };
+            update_config(|c| c.shuffle = shuffle);
+            say(if shuffle { "Shuffle on" } else { "Shuffle off" });
+        }
+        'R' => {
+            let repeat = {
+                let mut guard = mutex_lock!(state);
+                guard.repeat = guard.repeat.next();
+                guard.publish_status();
+                guard.repeat
+            };
+            update_config(|c| c.repeat = repeat);
+            say(&format!("Repeat {}", repeat.name()));
+        }
+        '?' => say(KEYS_HELP),
+        _ => {}
+    }
+}
+
+fn set_paused(paused: bool) {
+    player_send(PlayerCommand::SetPaused(paused));
+    say(if paused { "Paused" } else { "Resumed" });
+}
+
+/// Seeks to `target(current position)`, kept inside the playing song
+fn seek(state: &Mutex<HeadlessState>, target: impl FnOnce(f64) -> f64) {
+    let Some(p) = playback_handle().unwrap().position() else {
+        return;
+    };
+    let track = {
+        let guard = mutex_lock!(state);
+        guard.song_track(guard.playing_song_idx)
+    };
+    let Ok(track) = track else {
+        return;
+    };
+    let max_position = duration_from_bytes(track.size_bytes()) - 1.0;
+    let target = target(p).min(max_position).max(0.0);
+    player_send(PlayerCommand::Seek(target));
+    say(&format!("Seek to {}", format_duration(target as u32)));
+}
+
+/// Changes the volume by `steps` volume steps
+fn step_volume(state: &Mutex<HeadlessState>, steps: i32) {
+    let volume = {
+        let guard = mutex_lock!(state);
+        stepped_volume(guard.volume, steps, guard.volume_step)
+    };
+    set_volume(state, volume);
+}
+
+fn set_volume(state: &Mutex<HeadlessState>, volume: f64) {
+    let volume = {
+        let mut guard = mutex_lock!(state);
+        guard.volume = (volume.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        guard.publish_status();
+        guard.volume
+    };
+    player_send(PlayerCommand::ChangeVolume(volume));
+    say(&format!("Volume {} %", (volume * 100.0).round()));
+}
+
+/// Applies the commands of remote controls (see [`crate::remote`]) as they come
+fn serve_remote_commands(state: &Arc<Mutex<HeadlessState>>) {
+    loop {
+        match remote::recv_command() {
+            RemoteCommand::Play => set_paused(false),
+            RemoteCommand::Pause => set_paused(true),
+            RemoteCommand::TogglePause => handle_key(state, ' '),
+            RemoteCommand::Next => handle_key(state, 'n'),
+            RemoteCommand::Previous => handle_key(state, 'p'),
+            RemoteCommand::Goto(n) => {
+                if (1..=mutex_lock!(state).meta_info.list.len()).contains(&n) {
+                    go_to_song(state, n - 1);
+                }
+            }
+            RemoteCommand::Seek(p) => seek(state, |_| p),
+            RemoteCommand::SeekBy(delta) => seek(state, |p| p + delta),
+            RemoteCommand::SetVolume(volume) => set_volume(state, volume),
+            RemoteCommand::StepVolume(steps) => step_volume(state, steps),
+            RemoteCommand::Quit => handle_key(state, 'q'),
+        }
+    }
+}
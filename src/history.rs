@@ -0,0 +1,205 @@
+//! Play counts and listening time of each song, kept locally in `history.json` next to
+//! the config file, for `pseudo-cd-player stats`
+//!
+//! Like [`crate::scrobble`], the published [`PlayerStatus`] is watched: a song counts as
+//! played once it has played for half its duration or four minutes, and every second spent
+//! playing adds to its listening time. The file is written when the song changes, and
+//! every half minute in between, so quitting loses little.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{config_dir, CONFIG};
+use crate::mutex_lock;
+use crate::remote::{player_status, PlaybackState, PlayerStatus};
+use crate::scrobble::LISTEN_THRESHOLD;
+
+const HISTORY_FILE_NAME: &str = "history.json";
+/// The player status is checked once per this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Listening time is written out at least this often while playing, in seconds
+const FLUSH_INTERVAL: u32 = 30;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[serde(default)]
+pub struct SongHistory {
+    /// As last played
+    pub name: String,
+    pub plays: u32,
+    /// Seconds played in all
+    pub listened: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[serde(default)]
+pub struct DiscHistory {
+    /// As last played
+    pub title: Option<String>,
+    /// By song number (from one)
+    pub songs: BTreeMap<usize, SongHistory>,
+}
+
+impl DiscHistory {
+    pub fn plays(&self) -> u32 {
+        self.songs.values().map(|x| x.plays).sum()
+    }
+
+    /// Seconds played in all
+    pub fn listened(&self) -> u64 {
+        self.songs.values().map(|x| x.listened).sum()
+    }
+
+    /// Song numbers and their history, the most played first (the longest listened to
+    /// among equals)
+    pub fn most_played(&self) -> Vec<(usize, &SongHistory)> {
+        let mut songs = self.songs.iter().map(|(&n, x)| (n, x)).collect::<Vec<_>>();
+        songs.sort_by_key(|(n, x)| (std::cmp::Reverse((x.plays, x.listened)), *n));
+        songs
+    }
+}
+
+fn history_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(HISTORY_FILE_NAME))
+}
+
+/// The history of every disc, by [`crate::disc_id`]
+pub fn load() -> io::Result<HashMap<String, DiscHistory>> {
+    let Some(path) = history_file_path().filter(|x| x.exists()) else {
+        return Ok(Default::default());
+    };
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(io::Error::other)
+}
+
+fn store(all: &HashMap<String, DiscHistory>) -> io::Result<()> {
+    let Some(path) = history_file_path() else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, all).map_err(io::Error::other)
+}
+
+/// Whether `played` seconds of a song of `duration` make a play of it
+pub fn counts_as_played(duration: u32, played: u32) -> bool {
+    played >= duration / 2 || played >= LISTEN_THRESHOLD
+}
+
+/// The song being followed
+struct Playing {
+    disc_id: String,
+    disc_title: Option<String>,
+    song_number: usize,
+    name: String,
+    duration: u32,
+    /// Seconds played since it started
+    played: u32,
+    /// Seconds played and not written out yet
+    unrecorded: u32,
+    /// The play is counted already
+    counted: bool,
+}
+
+impl Playing {
+    fn new(status: &PlayerStatus) -> Option<Self> {
+        let song = status.playing_song()?;
+        Some(Self {
+            disc_id: status.disc_id.clone()?,
+            disc_title: status.disc_title.clone(),
+            song_number: status.song_number?,
+            name: song.name.clone(),
+            duration: song.duration,
+            played: 0,
+            unrecorded: 0,
+            counted: false,
+        })
+    }
+
+    /// Adds what's new since the last time to the history file
+    fn record(&mut self) {
+        let new_play = !self.counted && counts_as_played(self.duration, self.played);
+        if self.unrecorded == 0 && !new_play {
+            return;
+        }
+        let result = load().and_then(|mut all| {
+            let disc = all.entry(self.disc_id.clone()).or_default();
+            disc.title.clone_from(&self.disc_title);
+            let song = disc.songs.entry(self.song_number).or_default();
+            song.name.clone_from(&self.name);
+            song.plays += new_play as u32;
+            song.listened += self.unrecorded as u64;
+            store(&all)
+        });
+        match result {
+            Ok(()) => {
+                debug!(
+                    "Recorded {} s of song {}{}",
+                    self.unrecorded,
+                    self.song_number,
+                    if new_play { " and a play" } else { "" }
+                );
+                self.unrecorded = 0;
+                self.counted |= new_play;
+            }
+            // a broken file is better left alone; the time is retried at the next write
+            Err(e) => warn!("Failed to record the listening history: {}", e),
+        }
+    }
+}
+
+fn run() {
+    let mut playing: Option<Playing> = None;
+    let mut last_song = None;
+    let mut last_position = 0;
+    loop {
+        sleep(POLL_INTERVAL);
+        let status = player_status();
+        let song = status
+            .disc_id
+            .clone()
+            .zip(status.song_number)
+            .zip(status.playing_song().cloned());
+        // back at the start of a song played already, as with repeat one, is another play
+        let restarted = playing
+            .as_ref()
+            .is_some_and(|x| x.counted && status.position <= 1 && last_position > 1);
+        last_position = status.position;
+        if song != last_song || restarted {
+            last_song = song;
+            if let Some(mut x) = playing.take() {
+                x.record();
+            }
+            playing = Playing::new(&status);
+        }
+        if let Some(x) = playing
+            .as_mut()
+            .filter(|_| status.state == PlaybackState::Playing)
+        {
+            let seconds = POLL_INTERVAL.as_secs() as u32;
+            x.played += seconds;
+            x.unrecorded += seconds;
+            let new_play = !x.counted && counts_as_played(x.duration, x.played);
+            if new_play || x.unrecorded >= FLUSH_INTERVAL {
+                x.record();
+            }
+        }
+    }
+}
+
+/// Starts following playback for the history, unless `record_history` is off in the config
+pub fn start() {
+    if !mutex_lock!(CONFIG).record_history {
+        return;
+    }
+    spawn(run);
+}
@@ -0,0 +1,407 @@
+//! `--http`: a small REST API on plain HTTP/1.1, plus a control page for phone browsers
+//!
+//! - `GET /status`: the [`PlayerStatus`]
+//! - `POST /play`, `/pause`, `/toggle`, `/next`, `/previous`, `/quit`
+//! - `POST /goto` with `{"song": <number>}`
+//! - `POST /seek` with `{"position": <seconds>}` or `{"offset": <seconds>}`
+//! - `POST /volume` with `{"volume": <0..1>}` or `{"steps": <volume steps>}` (negative
+//!   ones turn it down)
+//!
+//! Control requests answer `{"ok":true}`, or `{"ok":false,"error":"..."}` with a 4xx status.
+//! There's no authentication; bind to an address only trusted hosts can reach.
+//!
+//! Browsers are a way in even from a trusted host: any page the user opens could post to
+//! the API. So control requests have to be `Content-Type: application/json`, which a page
+//! of another site can't send without asking first (a CORS preflight, which isn't
+//! answered), and ones with the `Origin` of another site are refused. A site can still
+//! have its domain resolve to this host (DNS rebinding), making its requests look
+//! same-origin; so only a `Host` of the address the API is reached at, or `localhost`,
+//! is served.
+
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::thread::spawn;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::remote::{player_status, send_command, RemoteCommand};
+
+/// Requests with bigger bodies are refused
+const MAX_BODY_SIZE: usize = 4096;
+/// Requests with a longer request line or header line, or more header lines, are refused
+const MAX_LINE_LENGTH: usize = 8192;
+const MAX_HEADERS: usize = 100;
+/// A client has to send its request within this duration
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// After the response, what's left of a refused request is read and dropped, up to this
+/// much for this long, so closing doesn't reset the connection before it's read
+const LINGER_SIZE: u64 = 64 * 1024;
+const LINGER_TIMEOUT: Duration = Duration::from_secs(1);
+
+const CONTROL_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Pseudo-CD Player</title>
+<style>
+body { font-family: sans-serif; max-width: 30em; margin: 1em auto; padding: 0 1em; }
+button { font-size: 1.5em; margin: 0.2em; min-width: 3em; }
+input { width: 100%; }
+</style>
+</head>
+<body>
+<h2 id="title">Pseudo-CD Player</h2>
+<p id="song"></p>
+<p id="position"></p>
+<p>
+<button onclick="post('previous')">&#x23EE;</button>
+<button onclick="post('seek', {offset: -10})">-10s</button>
+<button onclick="post('toggle')">&#x23EF;</button>
+<button onclick="post('seek', {offset: 10})">+10s</button>
+<button onclick="post('next')">&#x23ED;</button>
+</p>
+<p>Volume <button onclick="post('volume', {steps: -1})">-</button>
+<input id="volume" type="range" min="0" max="100"
+  onchange="post('volume', {volume: this.value / 100})">
+<button onclick="post('volume', {steps: 1})">+</button></p>
+<script>
+function pad(n) { return String(n).padStart(2, '0'); }
+function time(s) { return pad(Math.floor(s / 60)) + ':' + pad(s % 60); }
+function post(path, body) {
+  fetch('/' + path, {
+    method: 'POST',
+    headers: {'Content-Type': 'application/json'},
+    body: JSON.stringify(body || {}),
+  }).then(refresh);
+}
+function refresh() {
+  fetch('/status').then(r => r.json()).then(s => {
+    document.getElementById('title').textContent = s.disc_title || 'Pseudo-CD Player';
+    const song = s.song_number ? s.songs[s.song_number - 1] : null;
+    document.getElementById('song').textContent =
+      song ? s.song_number + '. ' + song.name + (s.state === 'paused' ? ' (paused)' : '') : s.state;
+    document.getElementById('position').textContent =
+      song ? time(s.position) + ' / ' + time(song.duration) : '';
+    const volume = document.getElementById('volume');
+    if (document.activeElement !== volume) volume.value = Math.round(s.volume * 100);
+  });
+}
+refresh();
+setInterval(refresh, 1000);
+</script>
+</body>
+</html>
+"#;
+
+#[derive(Deserialize)]
+struct GotoBody {
+    song: usize,
+}
+
+#[derive(Deserialize)]
+struct SeekBody {
+    position: Option<f64>,
+    offset: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct VolumeBody {
+    volume: Option<f64>,
+    steps: Option<i32>,
+}
+
+/// The answer to control requests
+#[derive(Serialize)]
+struct Outcome {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    host: Option<String>,
+    /// Sent by browsers
+    origin: Option<String>,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Whether a browser sent it from a page of another site than this one; other
+    /// clients send no `Origin`
+    fn foreign_origin(&self) -> bool {
+        let Some(origin) = &self.origin else {
+            return false;
+        };
+        let origin_host = origin.strip_prefix("http://");
+        match (origin_host, &self.host) {
+            (Some(x), Some(host)) => !x.eq_ignore_ascii_case(host),
+            _ => true,
+        }
+    }
+
+    /// Whether `Host` is `local`, the address the request came to, or `localhost` at its
+    /// port; other names may be a site's own, resolving here
+    fn known_host(&self, local: SocketAddr) -> bool {
+        let Some(host) = &self.host else {
+            return false;
+        };
+        // an IPv6 address is in brackets, so a colon after them is the port's
+        let (name, port) = match host.rsplit_once(':') {
+            Some((name, port)) if !host.ends_with(']') => (name, port.parse().ok()),
+            _ => (host.as_str(), Some(80)),
+        };
+        let name = name.trim_start_matches('[').trim_end_matches(']');
+        let name_ok = match name.parse::<IpAddr>() {
+            Ok(ip) => ip.to_canonical() == local.ip().to_canonical(),
+            Err(_) => name.eq_ignore_ascii_case("localhost"),
+        };
+        name_ok && port == Some(local.port())
+    }
+
+    fn is_json(&self) -> bool {
+        let media_type = self
+            .content_type
+            .as_deref()
+            .and_then(|x| x.split(';').next());
+        media_type.is_some_and(|x| x.trim().eq_ignore_ascii_case("application/json"))
+    }
+}
+
+struct Response {
+    code: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json(code: u16, body: impl Serialize) -> Self {
+        Self {
+            code,
+            content_type: "application/json",
+            body: serde_json::to_string(&body).unwrap(),
+        }
+    }
+
+    fn error(code: u16, error: impl Into<String>) -> Self {
+        Self::json(
+            code,
+            Outcome {
+                ok: false,
+                error: Some(error.into()),
+            },
+        )
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.code {
+            200 => "OK",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            414 => "URI Too Long",
+            415 => "Unsupported Media Type",
+            431 => "Request Header Fields Too Large",
+            _ => "",
+        }
+    }
+}
+
+/// Reads a line of at most [`MAX_LINE_LENGTH`] bytes; `None` if it's longer
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    let n = reader
+        .take(MAX_LINE_LENGTH as u64 + 1)
+        .read_line(&mut line)?;
+    Ok((n <= MAX_LINE_LENGTH).then_some(line))
+}
+
+fn read_request(stream: &TcpStream) -> io::Result<Result<Request, Response>> {
+    let mut reader = BufReader::new(stream);
+    let Some(request_line) = read_line(&mut reader)? else {
+        return Ok(Err(Response::error(414, "Request line too long")));
+    };
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(Err(Response::error(400, "Malformed request line")));
+    };
+    // queries aren't used
+    let path = target.split('?').next().unwrap_or_default();
+
+    let mut content_length = 0;
+    let (mut host, mut origin, mut content_type) = (None, None, None);
+    for i in 0.. {
+        let line = match read_line(&mut reader)? {
+            Some(x) if i < MAX_HEADERS => x,
+            _ => return Ok(Err(Response::error(431, "Request headers too large"))),
+        };
+        if line.trim_end().is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => match value.parse() {
+                Ok(x) => content_length = x,
+                // its body can't be told from the next request
+                Err(_) => return Ok(Err(Response::error(400, "Malformed Content-Length"))),
+            },
+            "host" => host = Some(value.into()),
+            "origin" => origin = Some(value.into()),
+            "content-type" => content_type = Some(value.into()),
+            _ => {}
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Ok(Err(Response::error(413, "Request body too large")));
+    }
+    let mut body = vec![0_u8; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(Request {
+        method: method.into(),
+        path: path.into(),
+        host,
+        origin,
+        content_type,
+        body,
+    }))
+}
+
+/// Parses the JSON body into `T`; an empty body counts as `{}`
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, Response> {
+    let body = if body.is_empty() { b"{}" } else { body };
+    serde_json::from_slice(body).map_err(|e| Response::error(400, format!("Invalid body: {e}")))
+}
+
+fn route(request: &Request) -> Response {
+    if request.method == "POST" {
+        if request.foreign_origin() {
+            return Response::error(403, "Requests from other sites are refused");
+        }
+        if !request.is_json() {
+            return Response::error(415, "Control requests need Content-Type: application/json");
+        }
+    }
+    let command = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => {
+            return Response {
+                code: 200,
+                content_type: "text/html; charset=utf-8",
+                body: CONTROL_PAGE.into(),
+            };
+        }
+        ("GET", "/status") => {
+            return Response::json(200, player_status());
+        }
+        ("POST", "/play") => RemoteCommand::Play,
+        ("POST", "/pause") => RemoteCommand::Pause,
+        ("POST", "/toggle") => RemoteCommand::TogglePause,
+        ("POST", "/next") => RemoteCommand::Next,
+        ("POST", "/previous") => RemoteCommand::Previous,
+        ("POST", "/quit") => RemoteCommand::Quit,
+        ("POST", "/goto") => match parse_body::<GotoBody>(&request.body) {
+            Ok(x) => match player_status().check_song_number(x.song) {
+                Ok(()) => RemoteCommand::Goto(x.song),
+                Err(e) => return Response::error(400, e),
+            },
+            Err(e) => return e,
+        },
+        ("POST", "/seek") => match parse_body::<SeekBody>(&request.body) {
+            Ok(SeekBody {
+                position: Some(p),
+                offset: None,
+            }) => RemoteCommand::Seek(p),
+            Ok(SeekBody {
+                position: None,
+                offset: Some(x),
+            }) => RemoteCommand::SeekBy(x),
+            Ok(_) => return Response::error(400, "Give one of position and offset"),
+            Err(e) => return e,
+        },
+        ("POST", "/volume") => match parse_body::<VolumeBody>(&request.body) {
+            Ok(VolumeBody {
+                volume: Some(x),
+                steps: None,
+            }) => RemoteCommand::SetVolume(x.clamp(0.0, 1.0)),
+            Ok(VolumeBody {
+                volume: None,
+                steps: Some(x),
+            }) => RemoteCommand::StepVolume(x),
+            Ok(_) => return Response::error(400, "Give one of volume and steps"),
+            Err(e) => return e,
+        },
+        (
+            _,
+            "/" | "/status" | "/play" | "/pause" | "/toggle" | "/next" | "/previous" | "/quit"
+            | "/goto" | "/seek" | "/volume",
+        ) => return Response::error(405, "Method not allowed"),
+        _ => return Response::error(404, "Not found"),
+    };
+    debug!("HTTP command: {:?}", command);
+    send_command(command);
+    Response::json(
+        200,
+        Outcome {
+            ok: true,
+            error: None,
+        },
+    )
+}
+
+fn serve_client(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let local = stream.local_addr()?;
+    let response = match read_request(&stream)? {
+        Ok(request) if !request.known_host(local) => Response::error(
+            403,
+            "Unknown Host; open the player by its address, or as localhost",
+        ),
+        Ok(request) => route(&request),
+        Err(response) => response,
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.code,
+        response.reason(),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()?;
+    stream.shutdown(Shutdown::Write)?;
+    stream.set_read_timeout(Some(LINGER_TIMEOUT))?;
+    io::copy(&mut (&stream).take(LINGER_SIZE), &mut io::sink())?;
+    Ok(())
+}
+
+/// Serves on `addr` on a thread of its own
+pub fn start(addr: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    info!("HTTP API: http://{}", listener.local_addr()?);
+    spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    spawn(move || {
+                        if let Err(e) = serve_client(stream) {
+                            debug!("HTTP client: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("HTTP API: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! `--source <URL>`: a disc image on a web server, read with HTTP range requests
+//!
+//! Requests are made with `curl`, a block at a time, kept by [`crate::block_cache`].
+
+use std::io;
+use std::process::{Command, Stdio};
+
+use log::debug;
+
+use crate::block_cache::{BlockCache, Fetch};
+use crate::source::DiscSource;
+
+/// Seconds a request may take, passed to curl's `--max-time`
+const REQUEST_TIMEOUT: u32 = 30;
+
+/// Whether `--source` is an http(s) URL
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Runs curl with the options every request has and `args`; returns its stdout
+fn curl(url: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+    debug!("Fetching {} ({})", url, args.join(" "));
+    let timeout = REQUEST_TIMEOUT.to_string();
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", &timeout])
+        .args(args)
+        .arg("--")
+        .arg(url)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("Failed to run curl: {e}")))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "curl failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Fetches a text file whole, like the tracks table next to an image
+pub fn fetch_text(url: &str) -> io::Result<String> {
+    Ok(String::from_utf8_lossy(&curl(url, &[])?).into())
+}
+
+/// The size of the resource at `url`, from the `Content-Range` of a one-byte range
+/// request; servers answering it with the whole resource aren't supported
+fn probe_len(url: &str) -> io::Result<u64> {
+    let headers = curl(
+        url,
+        &[
+            "--range",
+            "0-0",
+            "--dump-header",
+            "-",
+            "--output",
+            "/dev/null",
+        ],
+    )?;
+    let headers = String::from_utf8_lossy(&headers);
+    // after redirects, the headers of the last response count
+    let mut status = None;
+    let mut total = None;
+    for line in headers.lines() {
+        if line.starts_with("HTTP/") {
+            status = line.split_whitespace().nth(1).map(String::from);
+            total = None;
+        } else if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-range") {
+                total = value
+                    .trim()
+                    .rsplit_once('/')
+                    .map(|(_, x)| x.trim().to_string());
+            }
+        }
+    }
+    if status.as_deref() != Some("206") {
+        return Err(io::Error::other(format!(
+            "{url} doesn't answer range requests (status {})",
+            status.as_deref().unwrap_or("unknown")
+        )));
+    }
+    total
+        .and_then(|x| x.parse().ok())
+        .ok_or_else(|| io::Error::other(format!("{url} didn't tell its size")))
+}
+
+/// Range requests to a URL
+struct Http {
+    url: String,
+}
+
+impl Fetch for Http {
+    fn fetch(&self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        let range = format!("{}-{}", offset, offset + len - 1);
+        curl(&self.url, &["--range", &range])
+    }
+
+    fn location(&self) -> &str {
+        &self.url
+    }
+}
+
+/// A disc image at an `http://` or `https://` URL
+pub struct HttpSource(BlockCache<Http>);
+
+impl HttpSource {
+    /// Checks that the server takes range requests, and learns the image's size
+    pub fn new(url: &str) -> io::Result<Self> {
+        let len = probe_len(url)?;
+        Ok(Self(BlockCache::new(Http { url: url.into() }, len)))
+    }
+}
+
+impl DiscSource for HttpSource {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.0.read_at(buf, offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.0.len()
+    }
+}
@@ -0,0 +1,91 @@
+//! The SHA-256 hashes `author` records for each song, and checking the songs on a disc
+//! against them, for `check` and the TUI
+
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sha256::Sha256;
+use crate::source::DiscSource;
+use crate::Track;
+
+/// Bytes read at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The hash of a song's audio, without the padding to whole sectors after it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SongHash {
+    /// In lowercase hex
+    pub sha256: String,
+    /// Bytes hashed, from the start of the song's track
+    pub size: u64,
+}
+
+impl SongHash {
+    pub fn of(data: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        Self {
+            sha256: hasher.finish_hex(),
+            size: data.len() as u64,
+        }
+    }
+}
+
+/// How a song on the disc compares with its hash
+#[derive(Debug)]
+pub enum Check {
+    Intact,
+    /// The hash differs
+    Corrupt,
+    /// The track has fewer bytes than were hashed
+    TooShort(u64),
+    /// Reading the disc failed at the offset, from the start of the track
+    ReadError(u64, io::Error),
+}
+
+impl Check {
+    pub fn is_intact(&self) -> bool {
+        matches!(self, Check::Intact)
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::Intact => write!(f, "intact"),
+            Check::Corrupt => write!(f, "the audio differs from its hash"),
+            Check::TooShort(size) => write!(f, "the track has only {size} bytes"),
+            Check::ReadError(offset, e) => write!(f, "read error at offset {offset}: {e}"),
+        }
+    }
+}
+
+/// Reads the song in `track` back from `source` and compares it with `hash`
+pub fn check_song(source: &Arc<dyn DiscSource>, track: Track, hash: &SongHash) -> Check {
+    if track.size_bytes() < hash.size {
+        return Check::TooShort(track.size_bytes());
+    }
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0_u8; CHUNK_SIZE];
+    let mut read = 0_u64;
+    while read < hash.size {
+        let n = (hash.size - read).min(CHUNK_SIZE as u64) as usize;
+        match source.read_at(&mut buf[..n], track.start_offset() + read) {
+            Ok(0) => return Check::TooShort(read),
+            Ok(n) => {
+                hasher.update(&buf[..n]);
+                read += n as u64;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Check::ReadError(read, e),
+        }
+    }
+    match hasher.finish_hex() == hash.sha256 {
+        true => Check::Intact,
+        false => Check::Corrupt,
+    }
+}
@@ -1,128 +1,231 @@
-#![feature(try_blocks)]
-#![feature(decl_macro)]
-#![feature(yeet_expr)]
-#![feature(let_chains)]
-
 extern crate core;
 
-use std::fmt::{Display, Formatter};
-use std::fs::File;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
 use std::io;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
-use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
 
+use log::warn;
 use once_cell::sync::Lazy;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::cli::ARGS;
+use crate::chapters::Chapter;
+use crate::cli::{DiscArgs, LogFilter, LogLevel};
+use crate::error::Error;
+use crate::integrity::SongHash;
+use crate::locale::{pick, user_locales, Localized};
+use crate::loudness::SongGain;
+use crate::playback::duration_from_bytes;
+use crate::sniff::{non_audio_content, read_head};
+use crate::source::{DiscSource, SourceReader};
+use crate::span::Span;
 
+pub mod alarm;
+#[cfg(feature = "external")]
+pub mod author;
+#[cfg(feature = "external")]
+pub mod block_cache;
+pub mod bookmarks;
+#[cfg(feature = "external")]
+pub mod burn;
+#[cfg(feature = "external")]
+pub mod cdda;
+pub mod chapters;
 pub mod cli;
+pub mod clipboard;
+#[cfg(feature = "external")]
+pub mod commands;
+#[cfg(feature = "external")]
+pub mod compressed;
+pub mod config;
+pub mod context;
+#[cfg(unix)]
+pub mod control_socket;
+pub mod cue;
+#[cfg(unix)]
+pub mod daemon;
+pub mod demo;
+pub mod disc_wait;
+pub mod door_lock;
+pub mod effects;
+pub mod error;
+#[cfg(feature = "external")]
+pub mod external;
+pub mod failure;
+pub mod favorites;
+pub mod flac;
+#[cfg(feature = "tui")]
+pub mod headless;
+pub mod history;
+pub mod http;
+#[cfg(feature = "external")]
+pub mod http_source;
+pub mod integrity;
+pub mod locale;
+pub mod loudness;
+pub mod m3u;
+#[cfg(target_os = "linux")]
+pub mod media_keys;
+pub mod mpd;
+#[cfg(unix)]
+pub mod mpris;
+pub mod on_finish;
+pub mod palette;
+pub mod play_mode;
 pub mod playback;
+#[cfg(all(feature = "audio", feature = "external"))]
+pub mod player;
+pub mod remote;
+pub mod resume;
+pub mod scrobble;
+pub mod sha256;
+pub mod sidecar;
+pub mod silence;
+pub mod sniff;
+pub mod source;
+pub mod span;
+#[cfg(feature = "external")]
+pub mod ssh_source;
+pub mod text_width;
+#[cfg(feature = "tui")]
 pub mod tui;
+#[cfg(feature = "external")]
+pub mod verify;
+pub mod virtual_disc;
 pub mod minfo;
 
+#[cfg(feature = "external")]
+pub use external::kill_running_commands;
+
 /// The sector size optical discs use is 2048 bytes.
-const SECTOR_SIZE: u64 = 2048;
+pub const SECTOR_SIZE: u64 = 2048;
+/// Sectors of audio tracks hold this many bytes of PCM instead
+pub const CDDA_FRAME_SIZE: u64 = 2352;
+/// Where the bytes of audio tracks start in a [`DiscSource`], well past any data; the
+/// drive doesn't read them as data, so they're read with cdparanoia (see
+/// [`crate::cdda`])
+pub const CDDA_OFFSET: u64 = 1 << 48;
 
-macro lazy_regex($name:tt ,$regex:expr) {
-    static $name: Lazy<Regex> = Lazy::new(|| Regex::new($regex).unwrap());
+macro_rules! lazy_regex {
+    ($name:tt, $regex:expr) => {
+        static $name: once_cell::sync::Lazy<regex::Regex> =
+            once_cell::sync::Lazy::new(|| regex::Regex::new($regex).unwrap());
+    };
 }
+pub(crate) use lazy_regex;
 
-pub macro mutex_lock($m:expr) {
-    $m.lock().unwrap()
+#[macro_export]
+macro_rules! mutex_lock {
+    ($m:expr) => {
+        $m.lock().unwrap()
+    };
 }
 
 /// [start_addr], [end_addr] and [size] are in sectors (see [SECTOR_SIZE])
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Track {
     pub track_no: u32,
     pub session_no: u32,
     pub start_addr: u64,
     pub end_addr: u64,
     pub size: u64,
+    /// A CD audio track, of a mixed-mode disc, rather than data; its bytes are at
+    /// [`CDDA_OFFSET`] on ([`CDDA_FRAME_SIZE`] of them a sector)
+    pub audio: bool,
 }
 
 impl Track {
     /// Starting offset in bytes
     pub fn start_offset(&self) -> u64 {
-        self.start_addr * SECTOR_SIZE
+        match self.audio {
+            true => CDDA_OFFSET + self.start_addr * CDDA_FRAME_SIZE,
+            false => self.start_addr * SECTOR_SIZE,
+        }
     }
 
-    /// Ending offset in bytes
+    /// Ending offset in bytes, just past the last sector ([end_addr] is the last sector)
     pub fn end_offset(&self) -> u64 {
-        self.end_addr * SECTOR_SIZE
+        match self.audio {
+            true => CDDA_OFFSET + (self.end_addr + 1) * CDDA_FRAME_SIZE,
+            false => (self.end_addr + 1) * SECTOR_SIZE,
+        }
     }
 
     /// Track length in bytes
     pub fn size_bytes(&self) -> u64 {
-        self.size * SECTOR_SIZE
+        match self.audio {
+            true => self.size * CDDA_FRAME_SIZE,
+            false => self.size * SECTOR_SIZE,
+        }
     }
-}
 
-#[derive(Debug)]
-struct ProgramError {
-    stdout: String,
-    stderr: String,
-    exit_status: ExitStatus,
-}
+    /// Whether the track has no sectors, or is degenerate, ending before it starts
+    pub fn is_empty(&self) -> bool {
+        self.size == 0 || self.end_addr < self.start_addr
+    }
 
-impl ProgramError {
-    fn new(exit_status: ExitStatus, stderr: String, stdout: String) -> ProgramError {
-        Self {
-            exit_status,
-            stderr,
-            stdout,
+    /// As in the Type column of the minfo program's tracks table
+    pub fn type_name(&self) -> &'static str {
+        match self.audio {
+            true => "Audio",
+            false => "Data",
         }
     }
 }
 
-impl Display for ProgramError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        if !self.exit_status.success() {
-            writeln!(f, "Non-zero exit status: {:?}", self.exit_status.code())?;
+/// Identifies a disc by its track layout
+///
+/// This is a 64-bit FNV-1a hash over the session numbers and addresses of all
+/// tracks, so it's stable across runs and drives.
+pub fn disc_id(tracks: &[Track]) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    let mut hash = FNV_OFFSET_BASIS;
+    for t in tracks {
+        for field in [t.session_no as u64, t.start_addr, t.end_addr] {
+            for b in field.to_le_bytes() {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
         }
-        writeln!(f)?;
-        writeln!(f, "Stderr:")?;
-        writeln!(f, "{}\n\n", self.stderr)?;
-        writeln!(f, "Stdout:")?;
-        writeln!(f, "{}\n", self.stdout)?;
-        Ok(())
     }
+    format!("{:016x}", hash)
 }
 
-impl std::error::Error for ProgramError {}
-
-fn execute_command_with_output(cmd: &[&str]) -> io::Result<String> {
-    assert!(!cmd.is_empty());
-    let output = Command::new(cmd[0])
-        .args(cmd.iter().skip(1))
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?
-        .wait_with_output()?;
-
-    if !output.status.success() {
-        return Err(io::Error::other(ProgramError::new(
-            output.status,
-            format!("{}", String::from_utf8_lossy(&output.stderr)),
-            format!("{}", String::from_utf8_lossy(&output.stdout)),
-        )));
-    }
-    Ok(String::from_utf8(output.stdout).expect("Invalid UTF-8 met"))
-}
-
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct SongInfo {
     name: String,
+    /// `name` in other languages; see [`locale`]
+    #[serde(default, skip_serializing_if = "Localized::is_empty")]
+    names: Localized,
+    /// The disc's if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    performer: Option<String>,
     /// Session numbers start from one
     session_no: usize,
+    /// Recorded by `author`; see [`integrity`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<SongHash>,
+    /// Sorted by offset; see [`chapters`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    chapters: Vec<Chapter>,
+    /// Measured by `scan-loudness`; see [`loudness`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gain: Option<SongGain>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct MetaInfo {
     title: Option<String>,
+    /// `title` in other languages; see [`locale`]
+    #[serde(default, skip_serializing_if = "Localized::is_empty")]
+    titles: Localized,
+    /// Like the artist of an album
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    performer: Option<String>,
     creation_time: Option<u64>,
     list: Vec<SongInfo>,
 }
@@ -131,29 +234,290 @@ pub struct MetaInfo {
 ///
 /// The meta info is a JSON.
 /// Just read out all the text until a NUL ('\0').
-pub fn extract_meta_info(track: Track) -> io::Result<MetaInfo> {
-    let mut disc_file = File::open(&mutex_lock!(ARGS).drive)?;
-    disc_file.seek(SeekFrom::Start(track.start_addr * SECTOR_SIZE))?;
-    let bytes = disc_file
-        .bytes()
-        .take_while(|x| x.is_ok() && *x.as_ref().unwrap() != b'\0')
-        .collect::<io::Result<Vec<_>>>()?;
-    let bytes = bytes.trim_ascii_end();
-    serde_json::from_slice(bytes).map_err(io::Error::other)
+pub fn extract_meta_info(source: &Arc<dyn DiscSource>, track: Track) -> Result<MetaInfo, Error> {
+    let _span = Span::enter(format!("meta_info{{track={}}}", track.track_no));
+    let mut reader = BufReader::new(SourceReader::new(Arc::clone(source)));
+    let read = || {
+        reader.seek(SeekFrom::Start(track.start_addr * SECTOR_SIZE))?;
+        reader
+            .bytes()
+            .take_while(|x| x.is_ok() && *x.as_ref().unwrap() != b'\0')
+            .collect::<io::Result<Vec<_>>>()
+    };
+    let bytes = read().map_err(Error::DriveRead)?;
+    parse_meta_info(&bytes)
+}
+
+/// Parses the meta info as stored in its track: the JSON up to a NUL, or to the end
+pub fn parse_meta_info(data: &[u8]) -> Result<MetaInfo, Error> {
+    let json = data.split(|&x| x == b'\0').next().unwrap_or_default();
+    let mut meta_info: MetaInfo =
+        serde_json::from_slice(json.trim_ascii_end()).map_err(Error::MetadataInvalid)?;
+    for song in &mut meta_info.list {
+        song.chapters.sort_by_key(|x| x.offset);
+    }
+    Ok(meta_info)
+}
+
+/// The data tracks (numbered from one) other than `except` holding valid meta info, the
+/// one with the most songs on the disc first; for when the meta info track isn't one
+///
+/// Only tracks starting with JSON are read whole, so audio isn't read to its end.
+pub fn meta_info_candidates(
+    source: &Arc<dyn DiscSource>,
+    tracks: &[Track],
+    except: usize,
+) -> Vec<usize> {
+    let mut candidates = (1..=tracks.len())
+        .zip(tracks)
+        .filter(|&(i, x)| !x.audio && i != except)
+        .filter(|(_, x)| non_audio_content(&read_head(&**source, x)) == Some("JSON text"))
+        .filter_map(|(i, x)| {
+            let meta_info = extract_meta_info(source, *x).ok()?;
+            let playable = (0..meta_info.list.len())
+                .filter(|&idx| meta_info.playable_track(idx, tracks).is_ok())
+                .count();
+            Some((i, playable))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|&(_, playable)| Reverse(playable));
+    candidates.into_iter().map(|(i, _)| i).collect()
+}
+
+/// Reads the meta info from the meta info track, or with `--no-meta`, makes up one
+/// song named after its number for each track; what's kept in [`sidecar`] is applied
+///
+/// The audio tracks of a mixed-mode disc are songs after those of the meta info.
+pub fn read_meta_info(
+    disc: &DiscArgs,
+    source: &Arc<dyn DiscSource>,
+    tracks: &[Track],
+) -> Result<MetaInfo, Error> {
+    if disc.no_meta {
+        let list = (1..=tracks.len())
+            .map(|i| SongInfo::new(format!("{}", i), i))
+            .collect();
+        let mut meta_info = MetaInfo {
+            list,
+            ..Default::default()
+        };
+        sidecar::apply(&mut meta_info, &sidecar::load(&disc_id(tracks)));
+        return Ok(meta_info);
+    }
+
+    let data_tracks = tracks.iter().filter(|x| !x.audio).count();
+    // an audio CD has no meta info; its audio tracks are all there is
+    let mut meta_info = match data_tracks == 0 && !tracks.is_empty() {
+        true => MetaInfo::default(),
+        false => {
+            let track = tracks
+                .get(
+                    disc.meta_info_track.wrapping_sub(1), /* track number starts from one */
+                )
+                .filter(|x| !x.audio)
+                .ok_or(Error::MetadataMissing {
+                    track: disc.meta_info_track,
+                    tracks: data_tracks,
+                })?;
+            match extract_meta_info(source, *track) {
+                Err(Error::MetadataInvalid(error)) => {
+                    let candidates = meta_info_candidates(source, tracks, disc.meta_info_track);
+                    return Err(match candidates.is_empty() {
+                        true => Error::MetadataInvalid(error),
+                        false => Error::MetadataElsewhere {
+                            track: disc.meta_info_track,
+                            candidates,
+                            error,
+                        },
+                    });
+                }
+                x => x?,
+            }
+        }
+    };
+    // after the songs of the meta info, like the audio tracks after the data ones
+    let audio_songs = tracks
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| x.audio)
+        .map(|(i, x)| SongInfo::new(format!("Audio track {}", x.track_no), i + 1));
+    meta_info.list.extend(audio_songs);
+    for (i, song) in meta_info.list.iter().enumerate() {
+        if let Err(problem) = meta_info.playable_track(i, tracks) {
+            warn!(
+                "Song {} ({}, session {}) {problem}",
+                i + 1,
+                song.name,
+                song.session_no
+            );
+        }
+    }
+    meta_info.localize(&user_locales(|x| std::env::var(x).ok()));
+    sidecar::apply(&mut meta_info, &sidecar::load(&disc_id(tracks)));
+    Ok(meta_info)
+}
+
+impl SongInfo {
+    /// A song with just a name, in session `session_no`
+    fn new(name: String, session_no: usize) -> Self {
+        Self {
+            name,
+            session_no,
+            ..Default::default()
+        }
+    }
 }
 
-pub fn set_up_logging<P: AsRef<Path>>(file_path: P) -> anyhow::Result<()> {
-    fern::Dispatch::new()
-        .format(|out, message, record| {
+impl MetaInfo {
+    /// Meta info listing `songs` by name, in sessions from 2 on
+    pub fn new(title: Option<String>, songs: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            title,
+            titles: Localized::new(),
+            performer: None,
+            creation_time: None,
+            list: songs
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| SongInfo::new(name, i + 2))
+                .collect(),
+        }
+    }
+
+    /// The track of the song at `idx` of the list; `None` if its `session_no` isn't on
+    /// the disc (or there's no such song)
+    pub fn song_track(&self, idx: usize, tracks: &[Track]) -> Option<Track> {
+        let session_no = self.list.get(idx)?.session_no;
+        tracks.get(session_no.checked_sub(1)?).copied()
+    }
+
+    /// The track the song at `idx` of the list is played from, or why there's none, like
+    /// `isn't on the disc`
+    pub fn playable_track(&self, idx: usize, tracks: &[Track]) -> Result<Track, &'static str> {
+        match self.song_track(idx, tracks) {
+            None => Err("isn't on the disc"),
+            Some(x) if x.is_empty() => Err("is empty"),
+            Some(x) => Ok(x),
+        }
+    }
+
+    /// The performer of the song at `idx` of the list, or else the disc's
+    pub fn song_performer(&self, idx: usize) -> Option<&str> {
+        self.list
+            .get(idx)?
+            .performer
+            .as_deref()
+            .or(self.performer.as_deref())
+    }
+
+    /// Durations in seconds, one for each song in the list
+    ///
+    /// Songs whose `session_no` isn't on the disc get zero.
+    pub fn song_durations(&self, tracks: &[Track]) -> Vec<u32> {
+        (0..self.list.len())
+            .map(|i| {
+                self.song_track(i, tracks)
+                    .map(|t| duration_from_bytes(t.size_bytes()) as u32)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// The level (see [`SongGain::level`]) of each track holding a song with a gain, for
+    /// [`playback::PlayerCommand::SetSongLevels`]
+    pub fn song_levels(&self, tracks: &[Track]) -> Vec<(Track, f64)> {
+        (0..self.list.len())
+            .filter_map(|i| Some((self.song_track(i, tracks)?, self.list[i].gain?.level())))
+            .collect()
+    }
+
+    /// Shows the title and song names in the first of `locales` they have (see
+    /// [`locale::pick`]), in place of the plain ones
+    ///
+    /// Without a match the plain ones stay; if there's no plain title, that of any locale
+    /// is better than none.
+    pub fn localize(&mut self, locales: &[String]) {
+        if let Some(title) = pick(&self.titles, locales) {
+            self.title = Some(title.into());
+        } else if self.title.is_none() {
+            self.title = self.titles.values().next().cloned();
+        }
+        for song in &mut self.list {
+            if let Some(name) = pick(&song.names, locales) {
+                song.name = name.into();
+            }
+        }
+    }
+
+    /// Whether any song has a hash to be checked against; discs authored before they
+    /// were recorded have none
+    pub fn has_hashes(&self) -> bool {
+        self.list.iter().any(|x| x.hash.is_some())
+    }
+
+    /// `creation_time` (in seconds since the Unix epoch) in RFC 3339
+    pub fn creation_time_string(&self) -> Option<String> {
+        let time = UNIX_EPOCH + Duration::from_secs(self.creation_time?);
+        Some(humantime::format_rfc3339_seconds(time).to_string())
+    }
+}
+
+/// Formats seconds as `mm:ss`
+pub fn format_duration(seconds: u32) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Most recent log lines (of `--log-level` and above), shown on the TUI's Logs page
+pub static LOG_LINES: Lazy<Mutex<VecDeque<String>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+/// At most this many lines are kept in [`LOG_LINES`]
+const LOG_LINES_CAPACITY: usize = 1000;
+
+/// Logs into [`LOG_LINES`], and also into `file_path` and stderr if asked for
+///
+/// `filters` set the levels of single modules; each is applied both as given and inside
+/// this crate, so `minfo` and `cpal` both work.
+pub fn set_up_logging<P: AsRef<Path>>(
+    file_path: Option<P>,
+    level: LogLevel,
+    filters: &[LogFilter],
+    stderr: bool,
+) -> anyhow::Result<()> {
+    let memory_dispatch = fern::Dispatch::new()
+        .format(|out, message, record| out.finish(format_args!("{} {}", record.level(), message)))
+        .chain(fern::Output::call(|record| {
+            let mut guard = mutex_lock!(LOG_LINES);
+            if guard.len() == LOG_LINES_CAPACITY {
+                guard.pop_front();
+            }
+            guard.push_back(record.args().to_string());
+        }));
+    let timestamped = || {
+        fern::Dispatch::new().format(|out, message, record| {
             out.finish(format_args!(
-                "[{} {} {}] {}",
+                "[{} {} {}] {}{}",
                 humantime::format_rfc3339(std::time::SystemTime::now()),
                 record.level(),
                 record.target(),
+                span::current(),
                 message
             ))
         })
-        .chain(fern::log_file(file_path)?)
-        .apply()?;
+    };
+    let mut dispatch = fern::Dispatch::new()
+        .level(level.filter())
+        .chain(memory_dispatch);
+    for filter in filters {
+        let ours = concat!(env!("CARGO_CRATE_NAME"), "::");
+        dispatch = dispatch
+            .level_for(filter.module.clone(), filter.level.filter())
+            .level_for(format!("{ours}{}", filter.module), filter.level.filter());
+    }
+    if let Some(file_path) = file_path {
+        dispatch = dispatch.chain(timestamped().chain(fern::log_file(file_path)?));
+    }
+    if stderr {
+        dispatch = dispatch.chain(timestamped().chain(io::stderr()));
+    }
+    dispatch.apply()?;
     Ok(())
 }
@@ -0,0 +1,62 @@
+//! Titles in several languages: the meta info may have, next to the title of the disc and
+//! the name of each song, a map of locale to text (`titles` and `names`), and the player
+//! shows the text of the user's locale, or the plain one without a match
+//!
+//! Locales are like `ja` or `zh-TW` (`zh_TW` works too), and match case-insensitively;
+//! `zh-TW` is also taken for `zh`, and `zh` for `zh-TW`.
+
+use std::collections::BTreeMap;
+
+/// Text by locale
+pub type Localized = BTreeMap<String, String>;
+
+/// Read, in order, for the user's locales
+const LOCALE_ENV: [&str; 4] = ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// `zh_TW.UTF-8@x` to `zh-tw`; `None` for the C locale
+fn normalize(locale: &str) -> Option<String> {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    match locale {
+        "" | "C" | "POSIX" => None,
+        x => Some(x.replace('_', "-").to_lowercase()),
+    }
+}
+
+fn language(locale: &str) -> &str {
+    locale.split('-').next().unwrap_or_default()
+}
+
+/// The user's locales, most preferred first, normalized, from `getenv` (see
+/// [`LOCALE_ENV`]); `LANGUAGE` may list several, separated by colons
+pub fn user_locales(getenv: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let mut locales = Vec::new();
+    for name in LOCALE_ENV {
+        let Some(value) = getenv(name) else {
+            continue;
+        };
+        for locale in value.split(':').filter_map(normalize) {
+            if !locales.contains(&locale) {
+                locales.push(locale);
+            }
+        }
+    }
+    locales
+}
+
+/// The text in `texts` for the first of `locales` with one: of the locale itself, or
+/// else of its language, or else of another locale of its language
+pub fn pick<'a>(texts: &'a Localized, locales: &[String]) -> Option<&'a str> {
+    let texts = texts
+        .iter()
+        .filter_map(|(k, v)| Some((normalize(k)?, v.as_str())))
+        .collect::<Vec<_>>();
+    locales.iter().find_map(|locale| {
+        let lang = language(locale);
+        let find = |matches: &dyn Fn(&str) -> bool| {
+            texts.iter().find(|(k, _)| matches(k)).map(|(_, v)| *v)
+        };
+        find(&|k| k == locale)
+            .or_else(|| find(&|k| k == lang))
+            .or_else(|| find(&|k| language(k) == lang))
+    })
+}
@@ -0,0 +1,236 @@
+//! Measuring how loud songs are, so the player can play them all at the same loudness
+//!
+//! `scan-loudness` measures the integrated loudness of each song as in ITU-R BS.1770
+//! (K-weighted, in 400 ms blocks, gated) and its sample peak, and keeps a [`SongGain`]
+//! for it, in the meta info or in the [`crate::sidecar`]. Songs are turned up or down to
+//! [`REFERENCE_LOUDNESS`], but never so much that their peak would clip.
+
+use std::f64::consts::PI;
+use std::io;
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use crate::playback::{AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+
+/// Songs are played at this loudness, in LUFS, as with ReplayGain 2.0
+pub const REFERENCE_LOUDNESS: f64 = -18.0;
+/// Blocks quieter than this, in LUFS, aren't counted
+const ABSOLUTE_GATE: f64 = -70.0;
+/// Blocks this many LU quieter than the ungated loudness aren't counted
+const RELATIVE_GATE: f64 = 10.0;
+/// Blocks are 400 ms, starting every 100 ms
+const STEPS_PER_BLOCK: usize = 4;
+const FRAMES_PER_STEP: usize = AUDIO_SAMPLE_RATE as usize / 10;
+
+/// How much to turn a song up or down
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SongGain {
+    /// In dB, to bring the song to [`REFERENCE_LOUDNESS`]
+    pub gain_db: f64,
+    /// The largest sample, in 0..=1 of full scale
+    pub peak: f64,
+}
+
+impl SongGain {
+    /// The factor samples are scaled by: the gain, lowered so the peak stays at full
+    /// scale or below
+    pub fn level(&self) -> f64 {
+        let level = 10_f64.powf(self.gain_db / 20.0);
+        match self.peak > 0.0 {
+            true => level.min(1.0 / self.peak),
+            false => level,
+        }
+    }
+}
+
+/// What [`measure`] found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness in LUFS; `None` if the song is silent throughout
+    pub integrated: Option<f64>,
+    /// The largest sample, in 0..=1 of full scale
+    pub peak: f64,
+}
+
+impl Loudness {
+    /// The gain bringing the song to [`REFERENCE_LOUDNESS`]; none for silence, which isn't
+    /// turned up
+    pub fn gain(&self) -> SongGain {
+        SongGain {
+            gain_db: self
+                .integrated
+                .map(|x| REFERENCE_LOUDNESS - x)
+                .unwrap_or_default(),
+            peak: self.peak,
+        }
+    }
+}
+
+/// A second-order IIR filter in direct form I
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 4],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let [x1, x2, y1, y2] = self.state;
+        let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+        self.state = [x, x1, y, y1];
+        y
+    }
+}
+
+/// The two stages of the K-weighting filter for the disc's sample rate, as designed
+/// by libebur128: a high shelf modelling the head, then a high-pass
+fn k_weighting() -> [Biquad; 2] {
+    let rate = AUDIO_SAMPLE_RATE as f64;
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10_f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        state: [0.0; 4],
+    };
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        state: [0.0; 4],
+    };
+    [shelf, high_pass]
+}
+
+/// Loudness of a mean square summed over the channels
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// Takes interleaved stereo samples, any number at a time
+pub struct LoudnessMeter {
+    filters: [[Biquad; 2]; AUDIO_CHANNELS as usize],
+    /// Sum of the squares of the filtered samples of the running step, and its frames
+    step_sum: f64,
+    step_frames: usize,
+    /// Sums of the last steps, to make up a block
+    steps: Vec<f64>,
+    /// Mean square of each block
+    blocks: Vec<f64>,
+    peak: i32,
+    channel: usize,
+}
+
+impl Default for LoudnessMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoudnessMeter {
+    pub fn new() -> Self {
+        Self {
+            filters: [k_weighting(); AUDIO_CHANNELS as usize],
+            step_sum: 0.0,
+            step_frames: 0,
+            steps: Vec::new(),
+            blocks: Vec::new(),
+            peak: 0,
+            channel: 0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[i16]) {
+        for &x in samples {
+            self.peak = self.peak.max((x as i32).abs());
+            let [shelf, high_pass] = &mut self.filters[self.channel];
+            let y = high_pass.process(shelf.process(x as f64 / 32768.0));
+            self.step_sum += y * y;
+            self.channel += 1;
+            if self.channel < AUDIO_CHANNELS as usize {
+                continue;
+            }
+            self.channel = 0;
+            self.step_frames += 1;
+            if self.step_frames == FRAMES_PER_STEP {
+                self.end_step();
+            }
+        }
+    }
+
+    fn end_step(&mut self) {
+        self.steps.push(self.step_sum);
+        self.step_sum = 0.0;
+        self.step_frames = 0;
+        if self.steps.len() > STEPS_PER_BLOCK {
+            self.steps.remove(0);
+        }
+        if self.steps.len() == STEPS_PER_BLOCK {
+            let frames = (STEPS_PER_BLOCK * FRAMES_PER_STEP) as f64;
+            self.blocks.push(self.steps.iter().sum::<f64>() / frames);
+        }
+    }
+
+    pub fn finish(&self) -> Loudness {
+        let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+        let audible = self
+            .blocks
+            .iter()
+            .copied()
+            .filter(|&x| lufs(x) > ABSOLUTE_GATE)
+            .collect::<Vec<_>>();
+        let integrated = (!audible.is_empty()).then(|| {
+            let threshold = lufs(mean(&audible)) - RELATIVE_GATE;
+            let gated = audible
+                .iter()
+                .copied()
+                .filter(|&x| lufs(x) > threshold)
+                .collect::<Vec<_>>();
+            lufs(mean(&gated))
+        });
+        Loudness {
+            integrated,
+            peak: self.peak as f64 / 32768.0,
+        }
+    }
+}
+
+/// Measures the 16-bit stereo PCM read from `reader`
+pub fn measure(mut reader: impl Read) -> io::Result<Loudness> {
+    let mut meter = LoudnessMeter::new();
+    let mut buf = vec![0_u8; 64 * 1024];
+    // a byte of a sample split between reads
+    let mut carry = None;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let mut bytes = buf[..n].to_vec();
+        if let Some(x) = carry.take() {
+            bytes.insert(0, x);
+        }
+        if bytes.len() % 2 == 1 {
+            carry = bytes.pop();
+        }
+        let samples = bytes
+            .chunks_exact(2)
+            .map(|x| i16::from_le_bytes([x[0], x[1]]))
+            .collect::<Vec<_>>();
+        meter.push(&samples);
+    }
+    Ok(meter.finish())
+}
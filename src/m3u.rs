@@ -0,0 +1,32 @@
+//! Extended M3U playlists, in UTF-8 (`.m3u8`), of the files `rip` writes, so they drop
+//! straight into other players and sync tools
+
+use std::fmt::Write;
+
+pub struct PlaylistEntry {
+    /// Path of the file, relative to the playlist
+    pub path: String,
+    /// Like `Performer - Title`
+    pub title: String,
+    /// In seconds
+    pub duration: u32,
+}
+
+/// The playlist of `entries`, in order, named `name` if given
+pub fn m3u8(name: Option<&str>, entries: &[PlaylistEntry]) -> String {
+    let mut playlist = String::from("#EXTM3U\n");
+    if let Some(name) = name {
+        writeln!(playlist, "#PLAYLIST:{name}").unwrap();
+    }
+    for entry in entries {
+        // a line break would end the entry
+        let title = entry.title.replace(['\r', '\n'], " ");
+        writeln!(
+            playlist,
+            "#EXTINF:{},{title}\n{}",
+            entry.duration, entry.path
+        )
+        .unwrap();
+    }
+    playlist
+}
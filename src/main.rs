@@ -1,15 +1,26 @@
-#![feature(yeet_expr)]
-
-use clap::Parser;
-use log::info;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use log::{info, warn};
 use std::io::stdout;
 use std::panic;
 use std::panic::take_hook;
+use std::process::exit;
 
 use std::thread::spawn;
 
-use pseudo_cd_player::cli::{Args, ARGS};
-use pseudo_cd_player::{mutex_lock, set_up_logging};
+#[cfg(unix)]
+use pseudo_cd_player::control_socket;
+
+use pseudo_cd_player::author::AuthorPlan;
+use pseudo_cd_player::cli::{Args, AuthorCommand, Commands};
+use pseudo_cd_player::config::{load_config, update_config};
+use pseudo_cd_player::context::AppContext;
+use pseudo_cd_player::set_up_logging;
+use pseudo_cd_player::silence::SilenceOptions;
+use pseudo_cd_player::{
+    author, burn, commands, demo, door_lock, failure, headless, history, http, mpd, playback,
+    scrobble, verify,
+};
 use ratatui::prelude::*;
 
 use pseudo_cd_player::tui::{clean_up_and_exit, clean_up_tui, Tui};
@@ -25,9 +36,9 @@ fn register_signal_hooks() {
     }
 }
 
-fn run_tui() -> anyhow::Result<()> {
+fn run_tui(context: AppContext) -> anyhow::Result<()> {
     let backend = CrosstermBackend::new(stdout());
-    let mut tui = Tui::new(backend)?;
+    let mut tui = Tui::new(backend, context)?;
     loop {
         tui.tick()?;
     }
@@ -37,23 +48,185 @@ fn set_up_panic_hook() {
     let default_hook = take_hook();
     panic::set_hook(Box::new(move |x| {
         let _ = clean_up_tui();
+        door_lock::unlock();
         default_hook(x);
     }));
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+/// Remembers `name` (from `--audio-device`) as the device to play on, or forgets the
+/// remembered one for `default`
+fn choose_audio_device(name: &str) -> anyhow::Result<()> {
+    let device = match name {
+        "default" => None,
+        _ => {
+            if !playback::output_device_names()?.iter().any(|x| x == name) {
+                anyhow::bail!("No audio output device named {name:?}; `devices` lists them");
+            }
+            Some(name.to_string())
+        }
+    };
+    update_config(|x| x.audio_device = device);
+    Ok(())
+}
 
-    if let Some(ref f) = args.log_file {
-        set_up_logging(f)?;
+fn main() {
+    let mut args = Args::parse();
+    args.hoist_subcommand_args();
+    let errors_json = args.errors_json;
+    if let Err(e) = run(args) {
+        exit(failure::report(&e, errors_json));
     }
+}
+
+fn run(mut args: Args) -> anyhow::Result<()> {
+    if args.log_stderr && args.runs_tui() {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--log-stderr can't be used with the TUI; log to a file, or play with --no-tui",
+            )
+            .exit();
+    }
+    let (log_level, log_filters) = args.log_filters().map_err(anyhow::Error::msg)?;
+    set_up_logging(
+        args.log_file.as_ref(),
+        log_level,
+        &log_filters,
+        args.log_stderr,
+    )?;
 
     info!("Args: {:?}", args);
-    *mutex_lock!(ARGS) = args;
+    let command = args.command.take();
+    let mut context = AppContext::from(&args);
+    load_config();
+    if context.disc.demo {
+        demo::set_up(&mut context.disc)?;
+    }
+    let disc = &context.disc;
+
+    match command {
+        None | Some(Commands::Play { .. }) => {}
+        Some(Commands::List { format, .. }) => return commands::list(disc, format),
+        Some(Commands::Info { format, .. }) => return commands::info(disc, format),
+        Some(Commands::Rip {
+            output_dir,
+            format,
+            jobs,
+            cover,
+            ..
+        }) => return commands::rip(disc, &output_dir, format, jobs, cover.as_deref()),
+        Some(Commands::Cue {
+            output_dir, image, ..
+        }) => return commands::cue(disc, &output_dir, image),
+        Some(Commands::Burn { input_dir, .. }) => return burn::burn(disc, &input_dir),
+        Some(Commands::Verify { input_dir, .. }) => return verify::verify(disc, &input_dir),
+        Some(Commands::Check { .. }) => return commands::check(disc),
+        Some(Commands::Split {
+            song,
+            threshold,
+            min_gap,
+            save,
+            ..
+        }) => {
+            let options = SilenceOptions {
+                threshold_db: threshold,
+                min_gap,
+            };
+            return commands::split(disc, song, &options, save);
+        }
+        Some(Commands::ScanLoudness { export, .. }) => {
+            return commands::scan_loudness(disc, export.as_deref())
+        }
+        Some(Commands::Stats { top, format }) => return commands::stats(top, format),
+        Some(Commands::Devices) => return commands::devices(),
+        Some(Commands::Author {
+            command:
+                Some(AuthorCommand::Init {
+                    files,
+                    no_tags,
+                    output_dir,
+                }),
+            ..
+        }) => return author::init::init(&files, !no_tags, &output_dir),
+        Some(Commands::Author {
+            command: None,
+            files,
+            plan,
+            title,
+            performer,
+            output_dir,
+        }) => {
+            let mut plan = match plan {
+                Some(path) => AuthorPlan::read(&path)?,
+                None => AuthorPlan::from_files(&files),
+            };
+            // the command line wins over the plan
+            plan.title = title.or(plan.title);
+            plan.performer = performer.or(plan.performer);
+            return author::author(&plan, &output_dir);
+        }
+        Some(Commands::Attach { control_socket }) => {
+            #[cfg(unix)]
+            return pseudo_cd_player::tui::attach::run(
+                &control_socket.unwrap_or_else(control_socket::default_socket_path),
+            );
+            #[cfg(not(unix))]
+            anyhow::bail!("attach needs Unix sockets");
+        }
+    }
+
+    if let Some(name) = &context.ui.audio_device {
+        choose_audio_device(name)?;
+    }
+    #[cfg(not(target_os = "linux"))]
+    if context.ui.media_keys {
+        anyhow::bail!("--media-keys needs Linux");
+    }
+
+    let daemon = context.ui.daemon;
+    if daemon {
+        #[cfg(unix)]
+        pseudo_cd_player::daemon::daemonize(context.errors_json)?;
+        #[cfg(not(unix))]
+        anyhow::bail!("--daemon needs Unix");
+    }
 
     set_up_panic_hook();
     #[cfg(unix)]
     spawn(register_signal_hooks);
-    run_tui()?;
+    #[cfg(unix)]
+    {
+        let ui_args = context.ui.clone();
+        if !ui_args.no_mpris {
+            pseudo_cd_player::mpris::start();
+        }
+        #[cfg(target_os = "linux")]
+        if ui_args.media_keys {
+            pseudo_cd_player::media_keys::start();
+        }
+        if !ui_args.no_control_socket {
+            let path = ui_args
+                .control_socket
+                .unwrap_or_else(control_socket::default_socket_path);
+            match control_socket::start(path) {
+                Ok(()) => {}
+                // there's no other way to reach the daemon
+                Err(e) if daemon => return Err(e.into()),
+                Err(e) => warn!("{e}"),
+            }
+        }
+    }
+    scrobble::start();
+    history::start();
+    if let Some(addr) = context.ui.http {
+        http::start(addr)?;
+    }
+    if let Some(addr) = context.ui.mpd {
+        mpd::start(addr)?;
+    }
+    if daemon || context.ui.no_tui {
+        return headless::run(&context);
+    }
+    run_tui(context)?;
     Ok(())
 }
@@ -0,0 +1,113 @@
+//! `--media-keys`: the play, next and previous keys of keyboards, read from their Linux
+//! input devices (`/dev/input/event*`), so they work with no focused terminal and no
+//! desktop passing them on over MPRIS, like under a bare window manager or on the console
+//!
+//! The devices are only listened to, not grabbed, and reading them takes being in the
+//! `input` group. Keyboards plugged in after the start aren't seen. Commands go through
+//! [`crate::remote`].
+
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::thread::spawn;
+
+use log::{debug, info, warn};
+
+use crate::remote::{send_command, RemoteCommand};
+
+const INPUT_DIR: &str = "/dev/input";
+/// The size of a `struct input_event`, which ends in `type`, `code` and `value`
+pub const EVENT_SIZE: usize = size_of::<libc::input_event>();
+
+const EV_KEY: u16 = 0x01;
+const KEY_NEXTSONG: u16 = 163;
+const KEY_PLAYPAUSE: u16 = 164;
+const KEY_PREVIOUSSONG: u16 = 165;
+const KEY_STOPCD: u16 = 166;
+const KEY_PLAYCD: u16 = 200;
+const KEY_PAUSECD: u16 = 201;
+/// `value` of a key going down; releases and autorepeats are ignored
+const KEY_PRESSED: i32 = 1;
+
+/// The command of an input event, if it's a media key pressed
+pub fn key_command(event: &[u8; EVENT_SIZE]) -> Option<RemoteCommand> {
+    let tail = &event[EVENT_SIZE - 8..];
+    let event_type = u16::from_ne_bytes([tail[0], tail[1]]);
+    let code = u16::from_ne_bytes([tail[2], tail[3]]);
+    let value = i32::from_ne_bytes([tail[4], tail[5], tail[6], tail[7]]);
+    if event_type != EV_KEY || value != KEY_PRESSED {
+        return None;
+    }
+    Some(match code {
+        KEY_PLAYPAUSE => RemoteCommand::TogglePause,
+        KEY_NEXTSONG => RemoteCommand::Next,
+        KEY_PREVIOUSSONG => RemoteCommand::Previous,
+        KEY_PLAYCD => RemoteCommand::Play,
+        // there's no stopping short of quitting
+        KEY_PAUSECD | KEY_STOPCD => RemoteCommand::Pause,
+        _ => return None,
+    })
+}
+
+fn listen(mut device: File) -> io::Result<()> {
+    let mut event = [0_u8; EVENT_SIZE];
+    loop {
+        // the kernel hands out whole events
+        device.read_exact(&mut event)?;
+        if let Some(command) = key_command(&event) {
+            debug!("Media key: {:?}", command);
+            send_command(command);
+        }
+    }
+}
+
+/// The input devices, sorted
+fn event_devices() -> io::Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(INPUT_DIR)?
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| {
+            x.file_name()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| x.starts_with("event"))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Listens to each input device that can be read on a thread of its own; with none,
+/// this only logs a warning
+pub fn start() {
+    let paths = match event_devices() {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("Media keys are unavailable: {}: {}", INPUT_DIR, e);
+            return;
+        }
+    };
+    let mut listening = 0;
+    for path in paths {
+        match File::open(&path) {
+            Ok(device) => {
+                listening += 1;
+                spawn(move || {
+                    if let Err(e) = listen(device) {
+                        debug!("Media keys: stopped reading {}: {}", path.display(), e);
+                    }
+                });
+            }
+            Err(e) => debug!("Media keys: can't read {}: {}", path.display(), e),
+        }
+    }
+    match listening {
+        0 => warn!(
+            "Media keys are unavailable: none of the devices in {} can be read \
+            (reading them takes being in the `input` group)",
+            INPUT_DIR
+        ),
+        n => info!("Media keys: listening to {} input devices", n),
+    }
+}
@@ -1,60 +1,138 @@
-use std::io;
-use crate::{execute_command_with_output, lazy_regex, mutex_lock, Track};
-use crate::cli::ARGS;
+use log::debug;
+use crate::{lazy_regex, Track};
+#[cfg(feature = "external")]
+use crate::cli::DiscArgs;
+use crate::error::Error;
+#[cfg(feature = "external")]
+use crate::external::{cache_version, cached_version, minfo_program, run_minfo};
+#[cfg(feature = "external")]
+use crate::http_source;
+#[cfg(feature = "external")]
+use crate::ssh_source;
+#[cfg(feature = "external")]
+use crate::span::Span;
 
 lazy_regex!(
     TRACKS_HEADER_REGEX,
     r"Track +Sess +Type +Start Addr +End Addr +Size"
 );
+lazy_regex!(TRACKS_SEPARATOR_REGEX, r"^=+ *$");
 lazy_regex!(
     TRACK_CAPTURING_REGEX,
-    r"^ *(\d+) +(\d+) +Data +(\d+) +(\d+) +(\d+) *$"
+    r"^ *(\d+) +(\d+) +(\w+) +(\d+) +(\d+) +(\d+) *$"
 );
 
-pub fn check_version_line()->io::Result<String> {
-    let output = execute_command_with_output(&[minfo_cli!(), "--version"])?;
-    let line1 = output.lines().next();
-    Ok(line1.map(String::from).unwrap_or_default())
+/// The first line of `--version` output, remembered in the config while the program
+/// is unchanged
+#[cfg(feature = "external")]
+pub fn check_version_line(disc: &DiscArgs)->Result<String, Error> {
+    if crate::demo::tracks().is_some() {
+        return Ok("not needed for the demo disc".into());
+    }
+    if disc.source.is_some() {
+        return Ok("not needed for --source".into());
+    }
+    if let Some(version) = cached_version(disc) {
+        return Ok(version);
+    }
+    let output = run_minfo(disc, &["--version"])?;
+    let version = output.lines().next().unwrap_or_default();
+    cache_version(disc, version);
+    Ok(version.into())
+}
+
+#[cfg(feature = "external")]
+fn minfo_string(disc: &DiscArgs) -> Result<String, Error> {
+    let dev_arg = format!("dev={}", disc.drive.display());
+    run_minfo(disc, &[&dev_arg, "-minfo"])
+}
+
+#[cfg(feature = "external")]
+pub fn minfo_track_info(disc: &DiscArgs) -> Result<Vec<Track>, Error> {
+    if let Some(tracks) = crate::demo::tracks() {
+        return Ok(tracks);
+    }
+    if let Some(source) = &disc.source {
+        return image_track_info(disc, source);
+    }
+    let drive = &disc.drive;
+    let _span = Span::enter(format!("minfo{{drive={}}}", drive.display()));
+    if !drive.exists() {
+        return Err(Error::NoDrive(drive.clone()));
+    }
+    let tracks = parse_minfo(&minfo_string(disc)?)?;
+    debug!("{} tracks: {:?}", tracks.len(), tracks);
+    Ok(tracks)
 }
 
-fn minfo_string() -> io::Result<String> {
-    let dev_arg = format!("dev={}", mutex_lock!(ARGS).drive.display());
-    execute_command_with_output(&[
-        minfo_cli!(),
-        &dev_arg,
-        "-minfo",
-    ])
+/// The tracks table of `--source`: from the minfo program run on the machine of an
+/// `ssh://` URL, or else saved next to the image, as `<image>.minfo`
+#[cfg(feature = "external")]
+fn image_track_info(disc: &DiscArgs, source: &str) -> Result<Vec<Track>, Error> {
+    let _span = Span::enter(format!("minfo{{source={source}}}"));
+    let (path, output) = if ssh_source::is_ssh(source) {
+        let program = minfo_program(disc).name();
+        let output = ssh_source::minfo_output(source, program);
+        (source.to_string(), output)
+    } else {
+        let path = format!("{source}.minfo");
+        let output = match http_source::is_url(source) {
+            true => http_source::fetch_text(&path),
+            false => std::fs::read_to_string(&path),
+        };
+        (path, output)
+    };
+    let tracks = parse_minfo(&output.map_err(|e| Error::SourceFetch(path, e))?)?;
+    debug!("{} tracks: {:?}", tracks.len(), tracks);
+    Ok(tracks)
 }
 
-pub fn minfo_track_info() -> io::Result<Vec<Track>> {
-    let output = minfo_string()?;
-    let filtered = output
+/// Reads the tracks table of `-minfo` output
+///
+/// Data tracks are taken, and then the audio tracks of a mixed-mode disc, so the data
+/// tracks are numbered as on a disc of only them (as the meta info refers to them);
+/// others, like the blank one an appendable disc ends with, are skipped.
+pub fn parse_minfo(output: &str) -> Result<Vec<Track>, Error> {
+    let mut lines = output
         .lines()
-        .skip_while(|&x| !TRACKS_HEADER_REGEX.is_match(x))
-        .skip(2)
-        .take_while(|&x| !x.is_empty())
-        .collect::<Vec<_>>();
+        .enumerate()
+        .skip_while(|(_, x)| !TRACKS_HEADER_REGEX.is_match(x));
+    if lines.next().is_none() {
+        return Err(Error::MinfoNoTrackTable);
+    }
     let mut tracks = Vec::new();
-    for x in filtered {
-        let _: Option<_> = try {
-            let captures = TRACK_CAPTURING_REGEX.captures_iter(x).next()?;
-            let track = Track {
-                track_no: captures.get(1)?.as_str().parse().unwrap(), /* the RegExp asserts it's a `\d` */
-                session_no: captures.get(2)?.as_str().parse().unwrap(),
-                start_addr: captures.get(3)?.as_str().parse().unwrap(),
-                end_addr: captures.get(4)?.as_str().parse().unwrap(),
-                size: captures.get(5)?.as_str().parse().unwrap(),
-            };
-            tracks.push(track);
+    let mut audio_tracks = Vec::new();
+    for (i, line) in lines.take_while(|(_, x)| !x.trim().is_empty()) {
+        if TRACKS_SEPARATOR_REGEX.is_match(line) {
+            continue;
+        }
+        let Some((track_type, track)) = parse_track_line(line) else {
+            return Err(Error::MinfoBadTrackLine {
+                line_no: i + 1,
+                line: line.into(),
+            });
         };
+        match track_type {
+            "Data" => tracks.push(track),
+            "Audio" => audio_tracks.push(track),
+            _ => debug!("Not a data or audio track: {:?}", line),
+        }
     }
+    tracks.extend(audio_tracks);
     Ok(tracks)
 }
 
-pub macro minfo_cli() {
-    mutex_lock!(ARGS).minfo_program.name()
-}
-
-pub fn minfo_cli() -> String {
-    minfo_cli!().into()
+/// The type (like `Data` or `Blank`) and the track of a line of the tracks table
+fn parse_track_line(line: &str) -> Option<(&str, Track)> {
+    let captures = TRACK_CAPTURING_REGEX.captures(line)?;
+    let number = |i: usize| -> Option<u64> { captures.get(i)?.as_str().parse().ok() };
+    let track = Track {
+        track_no: number(1)?.try_into().ok()?,
+        session_no: number(2)?.try_into().ok()?,
+        start_addr: number(4)?,
+        end_addr: number(5)?,
+        size: number(6)?,
+        audio: captures.get(3)?.as_str() == "Audio",
+    };
+    Some((captures.get(3)?.as_str(), track))
 }
@@ -0,0 +1,456 @@
+//! `--mpd`: a subset of the MPD protocol, so MPD clients (ncmpcpp, MALP, `mpc`, ...) can act
+//! as remotes
+//!
+//! The disc's songs make up the (fixed) queue; song IDs are the song numbers. Editing the
+//! queue, the database and stored playlists are not there. See
+//! <https://mpd.readthedocs.io/en/latest/protocol.html>.
+
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread::spawn;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+
+use crate::play_mode::RepeatMode;
+use crate::playback::{AUDIO_BIT_DEPTH, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+use crate::remote::{player_status, send_command, PlaybackState, PlayerStatus, RemoteCommand};
+
+const GREETING: &str = "OK MPD 0.23.0";
+/// While idling, the status is checked for changes once per this interval
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+const SUPPORTED_COMMANDS: &[&str] = &[
+    "close",
+    "command_list_begin",
+    "command_list_end",
+    "command_list_ok_begin",
+    "commands",
+    "currentsong",
+    "getvol",
+    "idle",
+    "next",
+    "noidle",
+    "notcommands",
+    "outputs",
+    "pause",
+    "ping",
+    "play",
+    "playid",
+    "playlistid",
+    "playlistinfo",
+    "previous",
+    "seek",
+    "seekcur",
+    "seekid",
+    "setvol",
+    "stats",
+    "status",
+    "stop",
+    "tagtypes",
+];
+
+/// `ACK` error codes
+mod ack {
+    pub const ARG: u32 = 2;
+    pub const UNKNOWN: u32 = 5;
+    pub const NO_EXIST: u32 = 50;
+}
+
+/// An `ACK` reply: the error code and the message
+type Ack = (u32, String);
+
+/// What a command did, other than printing its response lines
+enum Outcome {
+    Done,
+    Close,
+    /// `idle` with its subsystems (empty means every one)
+    Idle(Vec<String>),
+}
+
+/// Splits a command line into words; double-quoted words may contain spaces and
+/// backslash escapes
+fn split_arguments(line: &str) -> Result<Vec<String>, Ack> {
+    let mut words = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => word.extend(chars.next()),
+                    Some(c) => word.push(c),
+                    None => return Err((ack::ARG, "Missing closing '\"'".into())),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek().filter(|x| !x.is_whitespace()) {
+                word.push(c);
+                chars.next();
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn parse_number<T: std::str::FromStr>(argument: Option<&String>) -> Result<T, Ack> {
+    let argument = argument.ok_or_else(|| (ack::ARG, "Missing argument".into()))?;
+    argument
+        .parse()
+        .map_err(|_| (ack::ARG, format!("Invalid number: {argument}")))
+}
+
+/// Checks that `position` (from zero) is in the queue and returns its song number
+fn song_number_at(status: &PlayerStatus, position: usize) -> Result<usize, Ack> {
+    match position < status.songs.len() {
+        true => Ok(position + 1),
+        false => Err((ack::NO_EXIST, "No such song".into())),
+    }
+}
+
+fn song_lines(status: &PlayerStatus, number: usize, out: &mut Vec<String>) {
+    let song = &status.songs[number - 1];
+    out.push(format!("file: cd/{number:02}"));
+    out.push(format!("Title: {}", song.name));
+    if let Some(title) = &status.disc_title {
+        out.push(format!("Album: {title}"));
+    }
+    out.push(format!("Track: {number}"));
+    out.push(format!("Time: {}", song.duration));
+    out.push(format!("duration: {}.000", song.duration));
+    out.push(format!("Pos: {}", number - 1));
+    out.push(format!("Id: {number}"));
+}
+
+fn status_lines(status: &PlayerStatus, out: &mut Vec<String>) {
+    let state = match status.state {
+        PlaybackState::Stopped => "stop",
+        PlaybackState::Playing => "play",
+        PlaybackState::Paused => "pause",
+    };
+    out.extend([
+        format!("volume: {}", (status.volume * 100.0).round()),
+        format!(
+            "repeat: {}",
+            matches!(status.repeat, RepeatMode::All | RepeatMode::One) as u8
+        ),
+        format!("random: {}", status.shuffle as u8),
+        format!(
+            "single: {}",
+            matches!(status.repeat, RepeatMode::One | RepeatMode::Single) as u8
+        ),
+        "consume: 0".into(),
+        "playlist: 1".into(),
+        format!("playlistlength: {}", status.songs.len()),
+        format!("state: {state}"),
+    ]);
+    if let (Some(number), Some(song)) = (status.song_number, status.playing_song()) {
+        out.extend([
+            format!("song: {}", number - 1),
+            format!("songid: {number}"),
+            format!("time: {}:{}", status.position, song.duration),
+            format!("elapsed: {}.000", status.position),
+            format!("duration: {}.000", song.duration),
+            format!(
+                "bitrate: {}",
+                AUDIO_SAMPLE_RATE * AUDIO_CHANNELS * AUDIO_BIT_DEPTH / 1000
+            ),
+            format!("audio: {AUDIO_SAMPLE_RATE}:{AUDIO_BIT_DEPTH}:{AUDIO_CHANNELS}"),
+        ]);
+        if number < status.songs.len() {
+            out.push(format!("nextsong: {number}"));
+            out.push(format!("nextsongid: {}", number + 1));
+        }
+    }
+}
+
+/// Subsystems whose state differs between the two statuses, as `idle` names them
+fn changed_subsystems(old: &PlayerStatus, new: &PlayerStatus) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if old.songs != new.songs || old.disc_title != new.disc_title {
+        changed.push("playlist");
+    }
+    if old.state != new.state || old.song_number != new.song_number {
+        changed.push("player");
+    }
+    if old.volume != new.volume {
+        changed.push("mixer");
+    }
+    if old.shuffle != new.shuffle || old.repeat != new.repeat {
+        changed.push("options");
+    }
+    changed
+}
+
+fn run_command(words: &[String], out: &mut Vec<String>) -> Result<Outcome, Ack> {
+    let status = player_status();
+    let name = words.first().map(String::as_str).unwrap_or_default();
+    let argument = words.get(1);
+    let command = match name {
+        "ping" => return Ok(Outcome::Done),
+        "close" => return Ok(Outcome::Close),
+        "idle" => return Ok(Outcome::Idle(words[1..].to_vec())),
+        "status" => {
+            status_lines(&status, out);
+            return Ok(Outcome::Done);
+        }
+        "currentsong" => {
+            if let Some(number) = status.playing_song().and(status.song_number) {
+                song_lines(&status, number, out);
+            }
+            return Ok(Outcome::Done);
+        }
+        "playlistinfo" | "playlistid" => {
+            let only = match argument {
+                None => None,
+                Some(_) if name == "playlistinfo" => {
+                    Some(song_number_at(&status, parse_number(argument)?)?)
+                }
+                Some(_) => Some(song_number_at(
+                    &status,
+                    parse_number::<usize>(argument)?.wrapping_sub(1),
+                )?),
+            };
+            for number in 1..=status.songs.len() {
+                if only.is_none_or(|x| x == number) {
+                    song_lines(&status, number, out);
+                }
+            }
+            return Ok(Outcome::Done);
+        }
+        "stats" => {
+            let total: u32 = status.songs.iter().map(|x| x.duration).sum();
+            out.extend([
+                "artists: 0".into(),
+                format!("albums: {}", status.disc_title.is_some() as u8),
+                format!("songs: {}", status.songs.len()),
+                format!("db_playtime: {total}"),
+            ]);
+            return Ok(Outcome::Done);
+        }
+        "getvol" => {
+            out.push(format!("volume: {}", (status.volume * 100.0).round()));
+            return Ok(Outcome::Done);
+        }
+        "outputs" => {
+            out.extend([
+                "outputid: 0".into(),
+                "outputname: Pseudo-CD Player".into(),
+                "outputenabled: 1".into(),
+            ]);
+            return Ok(Outcome::Done);
+        }
+        "commands" => {
+            out.extend(SUPPORTED_COMMANDS.iter().map(|x| format!("command: {x}")));
+            return Ok(Outcome::Done);
+        }
+        "notcommands" => return Ok(Outcome::Done),
+        "tagtypes" => {
+            out.extend(["Title", "Album", "Track"].map(|x| format!("tagtype: {x}")));
+            return Ok(Outcome::Done);
+        }
+        "play" => match argument {
+            None => RemoteCommand::Play,
+            Some(_) => RemoteCommand::Goto(song_number_at(&status, parse_number(argument)?)?),
+        },
+        "playid" => match argument {
+            None => RemoteCommand::Play,
+            Some(_) => {
+                let id: usize = parse_number(argument)?;
+                RemoteCommand::Goto(song_number_at(&status, id.wrapping_sub(1))?)
+            }
+        },
+        "pause" => match argument.map(String::as_str) {
+            None => RemoteCommand::TogglePause,
+            Some("1") => RemoteCommand::Pause,
+            Some("0") => RemoteCommand::Play,
+            Some(x) => return Err((ack::ARG, format!("Invalid argument: {x}"))),
+        },
+        // there's no stopped state once a disc is loaded
+        "stop" => RemoteCommand::Pause,
+        "next" => RemoteCommand::Next,
+        "previous" => RemoteCommand::Previous,
+        "seek" | "seekid" => {
+            let key: usize = parse_number(argument)?;
+            let position = if name == "seek" {
+                key
+            } else {
+                key.wrapping_sub(1)
+            };
+            let number = song_number_at(&status, position)?;
+            let time: f64 = parse_number(words.get(2))?;
+            if status.song_number != Some(number) {
+                send_command(RemoteCommand::Goto(number));
+            }
+            RemoteCommand::Seek(time)
+        }
+        "seekcur" => {
+            let time: f64 = parse_number(argument)?;
+            match argument.unwrap().starts_with(['+', '-']) {
+                true => RemoteCommand::SeekBy(time),
+                false => RemoteCommand::Seek(time),
+            }
+        }
+        "setvol" => {
+            let volume: f64 = parse_number(argument)?;
+            RemoteCommand::SetVolume(volume.clamp(0.0, 100.0) / 100.0)
+        }
+        _ => return Err((ack::UNKNOWN, format!("unknown command \"{name}\""))),
+    };
+    debug!("MPD command: {:?}", command);
+    send_command(command);
+    Ok(Outcome::Done)
+}
+
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// A partly received line, kept while idling with a read timeout
+    pending_line: String,
+}
+
+impl Client {
+    /// The next line, or `None` at the end of the stream
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        if self.reader.read_line(&mut self.pending_line)? == 0 && self.pending_line.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(std::mem::take(&mut self.pending_line)))
+    }
+
+    fn send(&mut self, lines: &[String], end: &str) -> io::Result<()> {
+        let mut text = String::new();
+        for line in lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.push_str(end);
+        text.push('\n');
+        self.writer.write_all(text.as_bytes())
+    }
+
+    /// Waits until a subsystem in `subsystems` (or any, if empty) changes, or until `noidle`
+    ///
+    /// Returns whether the connection is still open.
+    fn idle(&mut self, subsystems: &[String]) -> io::Result<bool> {
+        let start = player_status();
+        self.reader
+            .get_ref()
+            .set_read_timeout(Some(IDLE_POLL_INTERVAL))?;
+        let result = loop {
+            let changed = changed_subsystems(&start, &player_status())
+                .into_iter()
+                .filter(|x| subsystems.is_empty() || subsystems.iter().any(|s| s == x))
+                .map(|x| format!("changed: {x}"))
+                .collect::<Vec<_>>();
+            if !changed.is_empty() {
+                break self.send(&changed, "OK").map(|_| true);
+            }
+            match self.read_line() {
+                // anything other than `noidle` is an error in idle mode; MPD drops the client
+                Ok(Some(line)) => break self.send(&[], "OK").map(|_| line.trim() == "noidle"),
+                Ok(None) => break Ok(false),
+                // the read timeout; check the status again
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.reader.get_ref().set_read_timeout(None)?;
+        result
+    }
+
+    fn serve(&mut self) -> io::Result<()> {
+        self.send(&[], GREETING)?;
+        // `Some(list_ok)` while a command list is being received
+        let mut list: Option<(bool, Vec<Vec<String>>)> = None;
+        while let Some(line) = self.read_line()? {
+            let words = match split_arguments(&line) {
+                Ok(x) => x,
+                Err((code, message)) => {
+                    self.send(&[], &format!("ACK [{code}@0] {{}} {message}"))?;
+                    continue;
+                }
+            };
+            let name = words.first().map(String::as_str).unwrap_or_default();
+            let commands = match (&mut list, name) {
+                // it's only meaningful while idling; MPD ignores it otherwise, too
+                (None, "noidle") => continue,
+                (None, "command_list_begin" | "command_list_ok_begin") => {
+                    list = Some((name == "command_list_ok_begin", Vec::new()));
+                    continue;
+                }
+                (Some((_, commands)), name) if name != "command_list_end" => {
+                    commands.push(words);
+                    continue;
+                }
+                (Some(_), _) => list.take().unwrap(),
+                (None, _) => (false, vec![words]),
+            };
+
+            let (list_ok, commands) = commands;
+            let mut out = Vec::new();
+            let mut end = String::from("OK");
+            let mut idle = None;
+            for (i, words) in commands.iter().enumerate() {
+                match run_command(words, &mut out) {
+                    Ok(Outcome::Done) => {}
+                    Ok(Outcome::Close) => return Ok(()),
+                    Ok(Outcome::Idle(subsystems)) => idle = Some(subsystems),
+                    Err((code, message)) => {
+                        let name = words.first().cloned().unwrap_or_default();
+                        end = format!("ACK [{code}@{i}] {{{name}}} {message}");
+                        break;
+                    }
+                }
+                if list_ok {
+                    out.push("list_OK".into());
+                }
+            }
+            match idle {
+                Some(subsystems) => {
+                    if !self.idle(&subsystems)? {
+                        return Ok(());
+                    }
+                }
+                None => self.send(&out, &end)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serves on `addr` on a thread of its own
+pub fn start(addr: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    info!("MPD protocol: {}", listener.local_addr()?);
+    spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| {
+                Ok(Client {
+                    reader: BufReader::new(stream.try_clone()?),
+                    writer: stream,
+                    pending_line: String::new(),
+                })
+            });
+            match result {
+                Ok(mut client) => {
+                    spawn(move || {
+                        if let Err(e) = client.serve() {
+                            debug!("MPD client: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("MPD protocol: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,914 @@
+//! MPRIS (`org.mpris.MediaPlayer2`) on the D-Bus session bus, so desktop media keys and tools
+//! like `playerctl` can control the player and show the playing song
+//!
+//! No D-Bus library is used: this speaks the small part of the wire protocol that's needed
+//! (EXTERNAL authentication, and little-endian method calls, replies and signals).
+//! Commands go through [`crate::remote`].
+
+use std::io;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+
+use log::{debug, info, warn};
+
+use crate::mutex_lock;
+use crate::play_mode::RepeatMode;
+use crate::remote::{player_status, send_command, PlaybackState, PlayerStatus, RemoteCommand};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.pseudo_cd";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+/// Track IDs are `<this>/<song number>`
+const TRACK_ID_PREFIX: &str = "/pseudo_cd/track";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+const IDENTITY: &str = "Pseudo-CD Player";
+/// How often the player status is checked for changes to signal
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+const INTROSPECTION_XML: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect"><arg name="xml" type="s" direction="out"/></method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Properties">
+    <method name="Get">
+      <arg name="interface" type="s" direction="in"/><arg name="name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="GetAll">
+      <arg name="interface" type="s" direction="in"/>
+      <arg name="properties" type="a{sv}" direction="out"/>
+    </method>
+    <method name="Set">
+      <arg name="interface" type="s" direction="in"/><arg name="name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="in"/>
+    </method>
+    <signal name="PropertiesChanged">
+      <arg name="interface" type="s"/><arg name="changed" type="a{sv}"/>
+      <arg name="invalidated" type="as"/>
+    </signal>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2">
+    <method name="Raise"/>
+    <method name="Quit"/>
+    <property name="CanQuit" type="b" access="read"/>
+    <property name="CanRaise" type="b" access="read"/>
+    <property name="HasTrackList" type="b" access="read"/>
+    <property name="Identity" type="s" access="read"/>
+    <property name="SupportedUriSchemes" type="as" access="read"/>
+    <property name="SupportedMimeTypes" type="as" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="Next"/>
+    <method name="Previous"/>
+    <method name="Pause"/>
+    <method name="PlayPause"/>
+    <method name="Stop"/>
+    <method name="Play"/>
+    <method name="Seek"><arg name="offset" type="x" direction="in"/></method>
+    <method name="SetPosition">
+      <arg name="track_id" type="o" direction="in"/><arg name="position" type="x" direction="in"/>
+    </method>
+    <method name="OpenUri"><arg name="uri" type="s" direction="in"/></method>
+    <property name="PlaybackStatus" type="s" access="read"/>
+    <property name="Rate" type="d" access="read"/>
+    <property name="Metadata" type="a{sv}" access="read"/>
+    <property name="Volume" type="d" access="readwrite"/>
+    <property name="Shuffle" type="b" access="read"/>
+    <property name="LoopStatus" type="s" access="read"/>
+    <property name="Position" type="x" access="read"/>
+    <property name="MinimumRate" type="d" access="read"/>
+    <property name="MaximumRate" type="d" access="read"/>
+    <property name="CanGoNext" type="b" access="read"/>
+    <property name="CanGoPrevious" type="b" access="read"/>
+    <property name="CanPlay" type="b" access="read"/>
+    <property name="CanPause" type="b" access="read"/>
+    <property name="CanSeek" type="b" access="read"/>
+    <property name="CanControl" type="b" access="read"/>
+  </interface>
+</node>
+"#;
+
+mod message_type {
+    pub const METHOD_CALL: u8 = 1;
+    pub const METHOD_RETURN: u8 = 2;
+    pub const ERROR: u8 = 3;
+    pub const SIGNAL: u8 = 4;
+}
+
+mod header_field {
+    pub const PATH: u8 = 1;
+    pub const INTERFACE: u8 = 2;
+    pub const MEMBER: u8 = 3;
+    pub const ERROR_NAME: u8 = 4;
+    pub const REPLY_SERIAL: u8 = 5;
+    pub const DESTINATION: u8 = 6;
+    pub const SENDER: u8 = 7;
+    pub const SIGNATURE: u8 = 8;
+}
+
+const PROTOCOL_VERSION: u8 = 1;
+/// `RequestName` flag: fail rather than wait in line for the name
+const DO_NOT_QUEUE: u32 = 0x4;
+/// `RequestName` replies that mean the name is ours
+const PRIMARY_OWNER: u32 = 1;
+const ALREADY_OWNER: u32 = 4;
+/// Message flag: the caller doesn't want a reply
+const NO_REPLY_EXPECTED: u8 = 0x1;
+
+/// A D-Bus value of the types this module uses
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    Int32(i32),
+    Uint32(u32),
+    Int64(i64),
+    Double(f64),
+    Str(String),
+    ObjectPath(String),
+    Signature(String),
+    /// Signature of the elements, and the elements
+    Array(String, Vec<Value>),
+    Struct(Vec<Value>),
+    DictEntry(Box<Value>, Box<Value>),
+    Variant(Box<Value>),
+}
+
+fn pad_to(buf: &mut Vec<u8>, alignment: usize) {
+    while !buf.len().is_multiple_of(alignment) {
+        buf.push(0);
+    }
+}
+
+/// Alignment of the type starting `signature`
+fn alignment(signature: &str) -> usize {
+    match signature.as_bytes().first() {
+        Some(b'n' | b'q') => 2,
+        Some(b'b' | b'i' | b'u' | b's' | b'o' | b'a' | b'h') => 4,
+        Some(b'x' | b't' | b'd' | b'(' | b'{') => 8,
+        _ => 1,
+    }
+}
+
+/// Splits the first complete type off `signature`
+fn split_first_type(signature: &str) -> io::Result<(&str, &str)> {
+    let bytes = signature.as_bytes();
+    let len = match bytes.first() {
+        None => return Err(io::Error::other("Empty signature")),
+        Some(b'a') => 1 + split_first_type(&signature[1..])?.0.len(),
+        Some(&open @ (b'(' | b'{')) => {
+            let close = if open == b'(' { b')' } else { b'}' };
+            let mut depth = 0;
+            let end = bytes.iter().position(|&b| {
+                if b == open {
+                    depth += 1;
+                } else if b == close {
+                    depth -= 1;
+                }
+                depth == 0
+            });
+            end.ok_or_else(|| io::Error::other("Unbalanced signature"))? + 1
+        }
+        Some(_) => 1,
+    };
+    Ok(signature.split_at(len))
+}
+
+impl Value {
+    pub fn signature(&self) -> String {
+        match self {
+            Value::Byte(_) => "y".into(),
+            Value::Bool(_) => "b".into(),
+            Value::Int32(_) => "i".into(),
+            Value::Uint32(_) => "u".into(),
+            Value::Int64(_) => "x".into(),
+            Value::Double(_) => "d".into(),
+            Value::Str(_) => "s".into(),
+            Value::ObjectPath(_) => "o".into(),
+            Value::Signature(_) => "g".into(),
+            Value::Array(element, _) => format!("a{element}"),
+            Value::Struct(fields) => {
+                let fields = fields.iter().map(Value::signature).collect::<String>();
+                format!("({fields})")
+            }
+            Value::DictEntry(k, v) => format!("{{{}{}}}", k.signature(), v.signature()),
+            Value::Variant(_) => "v".into(),
+        }
+    }
+
+    /// Appends the value to `buf`, which has to start at a message (or body) start
+    pub fn marshal(&self, buf: &mut Vec<u8>) {
+        pad_to(buf, alignment(&self.signature()));
+        match self {
+            Value::Byte(x) => buf.push(*x),
+            Value::Bool(x) => buf.extend((*x as u32).to_le_bytes()),
+            Value::Int32(x) => buf.extend(x.to_le_bytes()),
+            Value::Uint32(x) => buf.extend(x.to_le_bytes()),
+            Value::Int64(x) => buf.extend(x.to_le_bytes()),
+            Value::Double(x) => buf.extend(x.to_le_bytes()),
+            Value::Str(x) | Value::ObjectPath(x) => {
+                buf.extend((x.len() as u32).to_le_bytes());
+                buf.extend(x.as_bytes());
+                buf.push(0);
+            }
+            Value::Signature(x) => {
+                buf.push(x.len() as u8);
+                buf.extend(x.as_bytes());
+                buf.push(0);
+            }
+            Value::Array(element, items) => {
+                let len_pos = buf.len();
+                buf.extend(0_u32.to_le_bytes());
+                // the padding before the first element doesn't count in the length
+                pad_to(buf, alignment(element));
+                let start = buf.len();
+                for x in items {
+                    x.marshal(buf);
+                }
+                let len = (buf.len() - start) as u32;
+                buf[len_pos..len_pos + 4].copy_from_slice(&len.to_le_bytes());
+            }
+            Value::Struct(fields) => {
+                for x in fields {
+                    x.marshal(buf);
+                }
+            }
+            Value::DictEntry(k, v) => {
+                k.marshal(buf);
+                v.marshal(buf);
+            }
+            Value::Variant(x) => {
+                Value::Signature(x.signature()).marshal(buf);
+                x.marshal(buf);
+            }
+        }
+    }
+
+    /// Reads a value of the single complete type `signature` at `*pos`
+    ///
+    /// Malformed input, including an empty or incomplete signature, is an error.
+    pub fn unmarshal(signature: &str, buf: &[u8], pos: &mut usize) -> io::Result<Value> {
+        fn take<'a>(buf: &'a [u8], pos: &mut usize, n: usize) -> io::Result<&'a [u8]> {
+            let bytes = buf
+                .get(*pos..*pos + n)
+                .ok_or_else(|| io::Error::other("Truncated message"))?;
+            *pos += n;
+            Ok(bytes)
+        }
+        fn take_array<const N: usize>(buf: &[u8], pos: &mut usize) -> io::Result<[u8; N]> {
+            Ok(take(buf, pos, N)?.try_into().unwrap())
+        }
+        fn take_string(buf: &[u8], pos: &mut usize, len: usize) -> io::Result<String> {
+            let bytes = take(buf, pos, len + 1 /* the trailing NUL */)?;
+            String::from_utf8(bytes[..len].to_vec()).map_err(io::Error::other)
+        }
+
+        let &code = signature
+            .as_bytes()
+            .first()
+            .ok_or_else(|| io::Error::other("Empty signature"))?;
+        let align = alignment(signature);
+        *pos = pos.div_ceil(align) * align;
+        let value = match code {
+            b'y' => Value::Byte(take(buf, pos, 1)?[0]),
+            b'b' => Value::Bool(u32::from_le_bytes(take_array(buf, pos)?) != 0),
+            b'i' => Value::Int32(i32::from_le_bytes(take_array(buf, pos)?)),
+            b'u' => Value::Uint32(u32::from_le_bytes(take_array(buf, pos)?)),
+            b'x' => Value::Int64(i64::from_le_bytes(take_array(buf, pos)?)),
+            b'd' => Value::Double(f64::from_le_bytes(take_array(buf, pos)?)),
+            b's' | b'o' => {
+                let len = u32::from_le_bytes(take_array(buf, pos)?) as usize;
+                let x = take_string(buf, pos, len)?;
+                match code {
+                    b's' => Value::Str(x),
+                    _ => Value::ObjectPath(x),
+                }
+            }
+            b'g' => {
+                let len = take(buf, pos, 1)?[0] as usize;
+                Value::Signature(take_string(buf, pos, len)?)
+            }
+            b'a' => {
+                let element = &signature[1..];
+                if element.is_empty() {
+                    return Err(io::Error::other("Array without an element type"));
+                }
+                let len = u32::from_le_bytes(take_array(buf, pos)?) as usize;
+                let element_align = alignment(element);
+                *pos = pos.div_ceil(element_align) * element_align;
+                let end = *pos + len;
+                let mut items = Vec::new();
+                while *pos < end {
+                    items.push(Value::unmarshal(element, buf, pos)?);
+                }
+                Value::Array(element.into(), items)
+            }
+            b'(' | b'{' => {
+                // also makes sure the closing bracket is there
+                let (whole, _) = split_first_type(signature)?;
+                let mut rest = &whole[1..whole.len() - 1];
+                if rest.is_empty() {
+                    // it would take no bytes, so an array of them would never end
+                    return Err(io::Error::other("Empty struct in signature"));
+                }
+                let mut fields = Vec::new();
+                while !rest.is_empty() {
+                    let (first, remaining) = split_first_type(rest)?;
+                    fields.push(Value::unmarshal(first, buf, pos)?);
+                    rest = remaining;
+                }
+                match (code, <[Value; 2]>::try_from(fields)) {
+                    (b'{', Ok([k, v])) => Value::DictEntry(Box::new(k), Box::new(v)),
+                    (b'{', Err(_)) => return Err(io::Error::other("Bad dict entry")),
+                    (_, Ok(fields)) => Value::Struct(fields.into()),
+                    (_, Err(fields)) => Value::Struct(fields),
+                }
+            }
+            b'v' => {
+                let Value::Signature(inner) = Value::unmarshal("g", buf, pos)? else {
+                    unreachable!()
+                };
+                Value::Variant(Box::new(Value::unmarshal(&inner, buf, pos)?))
+            }
+            x => {
+                return Err(io::Error::other(format!(
+                    "Unsupported type in signature: {}",
+                    x as char
+                )))
+            }
+        };
+        Ok(value)
+    }
+}
+
+fn dict(entries: Vec<(&str, Value)>) -> Value {
+    let entries = entries
+        .into_iter()
+        .map(|(k, v)| {
+            Value::DictEntry(
+                Box::new(Value::Str(k.into())),
+                Box::new(Value::Variant(Box::new(v))),
+            )
+        })
+        .collect();
+    Value::Array("{sv}".into(), entries)
+}
+
+#[derive(Debug)]
+struct Message {
+    msg_type: u8,
+    flags: u8,
+    serial: u32,
+    fields: Vec<(u8, Value)>,
+    body: Vec<Value>,
+}
+
+impl Message {
+    fn field_str(&self, code: u8) -> Option<&str> {
+        self.fields.iter().find_map(|(c, v)| match v {
+            Value::Str(x) | Value::ObjectPath(x) if *c == code => Some(x.as_str()),
+            _ => None,
+        })
+    }
+
+    fn read_from(reader: &mut impl Read) -> io::Result<Message> {
+        let mut fixed = [0_u8; 16];
+        reader.read_exact(&mut fixed)?;
+        if fixed[0] != b'l' {
+            return Err(io::Error::other("Big-endian messages are not supported"));
+        }
+        let body_len = u32::from_le_bytes(fixed[4..8].try_into().unwrap()) as usize;
+        let serial = u32::from_le_bytes(fixed[8..12].try_into().unwrap());
+        let fields_len = u32::from_le_bytes(fixed[12..16].try_into().unwrap()) as usize;
+        let header_len = (16 + fields_len).div_ceil(8) * 8;
+
+        let mut header = fixed.to_vec();
+        header.resize(header_len, 0);
+        reader.read_exact(&mut header[16..])?;
+        let mut body = vec![0_u8; body_len];
+        reader.read_exact(&mut body)?;
+
+        let Value::Array(_, items) = Value::unmarshal("a(yv)", &header, &mut 12)? else {
+            unreachable!()
+        };
+        let mut fields = Vec::new();
+        for x in items {
+            if let Value::Struct(x) = x {
+                if let [Value::Byte(code), Value::Variant(value)] = &x[..] {
+                    fields.push((*code, (**value).clone()));
+                }
+            }
+        }
+
+        let mut message = Message {
+            msg_type: fixed[1],
+            flags: fixed[2],
+            serial,
+            fields,
+            body: Vec::new(),
+        };
+        let mut signature = match message
+            .fields
+            .iter()
+            .find(|(c, _)| *c == header_field::SIGNATURE)
+        {
+            Some((_, Value::Signature(x))) => x.clone(),
+            _ => String::new(),
+        };
+        let mut pos = 0;
+        while !signature.is_empty() {
+            let (first, rest) = split_first_type(&signature)?;
+            message.body.push(Value::unmarshal(first, &body, &mut pos)?);
+            signature = rest.into();
+        }
+        Ok(message)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for x in &self.body {
+            x.marshal(&mut body);
+        }
+        let mut fields = self.fields.clone();
+        if !self.body.is_empty() {
+            let signature = self.body.iter().map(Value::signature).collect();
+            fields.push((header_field::SIGNATURE, Value::Signature(signature)));
+        }
+        let fields = fields
+            .into_iter()
+            .map(|(code, v)| Value::Struct(vec![Value::Byte(code), Value::Variant(Box::new(v))]))
+            .collect();
+
+        let mut buf = vec![b'l', self.msg_type, self.flags, PROTOCOL_VERSION];
+        buf.extend((body.len() as u32).to_le_bytes());
+        buf.extend(self.serial.to_le_bytes());
+        Value::Array("(yv)".into(), fields).marshal(&mut buf);
+        pad_to(&mut buf, 8);
+        buf.extend(body);
+        buf
+    }
+}
+
+struct Connection {
+    writer: Mutex<UnixStream>,
+    next_serial: AtomicU32,
+}
+
+impl Connection {
+    /// Sends a message, returning its serial
+    fn send(&self, msg_type: u8, fields: Vec<(u8, Value)>, body: Vec<Value>) -> io::Result<u32> {
+        let serial = self.next_serial.fetch_add(1, Ordering::SeqCst);
+        let message = Message {
+            msg_type,
+            flags: 0,
+            serial,
+            fields,
+            body,
+        };
+        mutex_lock!(self.writer).write_all(&message.to_bytes())?;
+        Ok(serial)
+    }
+
+    fn call_bus(&self, member: &str, body: Vec<Value>) -> io::Result<u32> {
+        self.send(
+            message_type::METHOD_CALL,
+            vec![
+                (
+                    header_field::PATH,
+                    Value::ObjectPath("/org/freedesktop/DBus".into()),
+                ),
+                (
+                    header_field::INTERFACE,
+                    Value::Str("org.freedesktop.DBus".into()),
+                ),
+                (header_field::MEMBER, Value::Str(member.into())),
+                (
+                    header_field::DESTINATION,
+                    Value::Str("org.freedesktop.DBus".into()),
+                ),
+            ],
+            body,
+        )
+    }
+
+    fn reply(&self, call: &Message, result: Result<Vec<Value>, (&str, String)>) -> io::Result<()> {
+        if call.flags & NO_REPLY_EXPECTED != 0 {
+            return Ok(());
+        }
+        let mut fields = vec![(header_field::REPLY_SERIAL, Value::Uint32(call.serial))];
+        if let Some(sender) = call.field_str(header_field::SENDER) {
+            fields.push((header_field::DESTINATION, Value::Str(sender.into())));
+        }
+        match result {
+            Ok(body) => self.send(message_type::METHOD_RETURN, fields, body)?,
+            Err((name, text)) => {
+                fields.push((header_field::ERROR_NAME, Value::Str(name.into())));
+                self.send(message_type::ERROR, fields, vec![Value::Str(text)])?
+            }
+        };
+        Ok(())
+    }
+
+    fn emit_properties_changed(&self, changed: Value) -> io::Result<()> {
+        self.send(
+            message_type::SIGNAL,
+            vec![
+                (header_field::PATH, Value::ObjectPath(OBJECT_PATH.into())),
+                (
+                    header_field::INTERFACE,
+                    Value::Str(PROPERTIES_INTERFACE.into()),
+                ),
+                (header_field::MEMBER, Value::Str("PropertiesChanged".into())),
+            ],
+            vec![
+                Value::Str(PLAYER_INTERFACE.into()),
+                changed,
+                Value::Array("s".into(), Vec::new()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Reads messages up to the reply to the call `serial`, handling the ones before it
+    fn wait_reply(&self, reader: &mut impl Read, serial: u32) -> io::Result<Vec<Value>> {
+        loop {
+            let message = Message::read_from(reader)?;
+            let reply_serial = message.fields.iter().find_map(|(c, v)| match v {
+                Value::Uint32(x) if *c == header_field::REPLY_SERIAL => Some(*x),
+                _ => None,
+            });
+            if reply_serial != Some(serial) {
+                self.dispatch(&message)?;
+                continue;
+            }
+            return match message.msg_type {
+                message_type::ERROR => Err(io::Error::other(format!(
+                    "{}: {:?}",
+                    message
+                        .field_str(header_field::ERROR_NAME)
+                        .unwrap_or_default(),
+                    message.body
+                ))),
+                _ => Ok(message.body),
+            };
+        }
+    }
+
+    /// Asks the bus for `name`, without queueing; returns whether it's ours
+    fn request_name(&self, reader: &mut impl Read, name: &str) -> io::Result<bool> {
+        let serial = self.call_bus(
+            "RequestName",
+            vec![Value::Str(name.into()), Value::Uint32(DO_NOT_QUEUE)],
+        )?;
+        match self.wait_reply(reader, serial)?[..] {
+            [Value::Uint32(code)] => Ok(code == PRIMARY_OWNER || code == ALREADY_OWNER),
+            _ => Err(io::Error::other("Unexpected reply to RequestName")),
+        }
+    }
+
+    fn dispatch(&self, message: &Message) -> io::Result<()> {
+        match message.msg_type {
+            message_type::METHOD_CALL
+                if message.field_str(header_field::PATH) == Some(OBJECT_PATH) =>
+            {
+                self.reply(message, handle_call(message))?;
+            }
+            message_type::METHOD_CALL => {
+                self.reply(
+                    message,
+                    Err((
+                        "org.freedesktop.DBus.Error.UnknownObject",
+                        "No such object".into(),
+                    )),
+                )?;
+            }
+            message_type::ERROR => {
+                warn!("MPRIS: D-Bus error: {:?}", message.body);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Opens the session bus given by `DBUS_SESSION_BUS_ADDRESS` and authenticates
+fn connect() -> io::Result<UnixStream> {
+    let address = std::env::var("DBUS_SESSION_BUS_ADDRESS")
+        .map_err(|_| io::Error::other("DBUS_SESSION_BUS_ADDRESS is not set"))?;
+    let mut stream = None;
+    // like `unix:path=/run/user/1000/bus` or `unix:abstract=/tmp/dbus-x,guid=...`; there
+    // may be several, separated by `;`
+    for x in address.split(';') {
+        let Some(params) = x.strip_prefix("unix:") else {
+            continue;
+        };
+        for (key, value) in params.split(',').filter_map(|x| x.split_once('=')) {
+            let value = unescape_address_value(value);
+            stream = match key {
+                "path" => UnixStream::connect(value).ok(),
+                #[cfg(target_os = "linux")]
+                "abstract" => {
+                    use std::os::linux::net::SocketAddrExt;
+                    let addr = std::os::unix::net::SocketAddr::from_abstract_name(value)?;
+                    UnixStream::connect_addr(&addr).ok()
+                }
+                _ => None,
+            };
+            if stream.is_some() {
+                break;
+            }
+        }
+        if stream.is_some() {
+            break;
+        }
+    }
+    let mut stream =
+        stream.ok_or_else(|| io::Error::other(format!("Can't connect to bus {address}")))?;
+
+    // SAFETY: `getuid` always succeeds
+    let uid = unsafe { libc::getuid() };
+    let uid_hex = uid
+        .to_string()
+        .bytes()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    stream.write_all(format!("\0AUTH EXTERNAL {uid_hex}\r\n").as_bytes())?;
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n") {
+        let mut byte = [0_u8];
+        stream.read_exact(&mut byte)?;
+        response.push(byte[0]);
+    }
+    if !response.starts_with(b"OK") {
+        return Err(io::Error::other(format!(
+            "Authentication failed: {}",
+            String::from_utf8_lossy(&response).trim_end()
+        )));
+    }
+    stream.write_all(b"BEGIN\r\n")?;
+    Ok(stream)
+}
+
+/// Decodes `%xx` escapes in a D-Bus address value
+fn unescape_address_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|x| u8::from_str_radix(x, 16).ok());
+        match escaped {
+            Some(b) => {
+                result.push(b);
+                i += 3;
+            }
+            None => {
+                result.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).into()
+}
+
+fn track_id(status: &PlayerStatus) -> String {
+    match status.song_number {
+        Some(n) => format!("{TRACK_ID_PREFIX}/{n}"),
+        None => NO_TRACK.into(),
+    }
+}
+
+fn metadata(status: &PlayerStatus) -> Value {
+    let mut entries = vec![("mpris:trackid", Value::ObjectPath(track_id(status)))];
+    if let (Some(n), Some(song)) = (status.song_number, status.playing_song()) {
+        entries.push(("xesam:title", Value::Str(song.name.clone())));
+        entries.push(("xesam:trackNumber", Value::Int32(n as i32)));
+        entries.push((
+            "mpris:length",
+            Value::Int64(song.duration as i64 * 1_000_000),
+        ));
+    }
+    if let Some(title) = &status.disc_title {
+        entries.push(("xesam:album", Value::Str(title.clone())));
+    }
+    dict(entries)
+}
+
+fn properties(interface: &str, status: &PlayerStatus) -> Option<Vec<(&'static str, Value)>> {
+    let loaded = status.state != PlaybackState::Stopped;
+    let properties = match interface {
+        ROOT_INTERFACE => vec![
+            ("CanQuit", Value::Bool(true)),
+            ("CanRaise", Value::Bool(false)),
+            ("HasTrackList", Value::Bool(false)),
+            ("Identity", Value::Str(IDENTITY.into())),
+            ("SupportedUriSchemes", Value::Array("s".into(), Vec::new())),
+            ("SupportedMimeTypes", Value::Array("s".into(), Vec::new())),
+        ],
+        PLAYER_INTERFACE => vec![
+            (
+                "PlaybackStatus",
+                Value::Str(
+                    match status.state {
+                        PlaybackState::Stopped => "Stopped",
+                        PlaybackState::Playing => "Playing",
+                        PlaybackState::Paused => "Paused",
+                    }
+                    .into(),
+                ),
+            ),
+            ("Rate", Value::Double(1.0)),
+            ("MinimumRate", Value::Double(1.0)),
+            ("MaximumRate", Value::Double(1.0)),
+            ("Metadata", metadata(status)),
+            ("Volume", Value::Double(status.volume)),
+            ("Shuffle", Value::Bool(status.shuffle)),
+            (
+                "LoopStatus",
+                Value::Str(
+                    match status.repeat {
+                        RepeatMode::Off | RepeatMode::Single => "None",
+                        RepeatMode::All => "Playlist",
+                        RepeatMode::One => "Track",
+                    }
+                    .into(),
+                ),
+            ),
+            ("Position", Value::Int64(status.position as i64 * 1_000_000)),
+            ("CanGoNext", Value::Bool(loaded)),
+            ("CanGoPrevious", Value::Bool(loaded)),
+            ("CanPlay", Value::Bool(loaded)),
+            ("CanPause", Value::Bool(loaded)),
+            ("CanSeek", Value::Bool(loaded)),
+            ("CanControl", Value::Bool(true)),
+        ],
+        _ => return None,
+    };
+    Some(properties)
+}
+
+fn handle_call(message: &Message) -> Result<Vec<Value>, (&'static str, String)> {
+    let member = message.field_str(header_field::MEMBER).unwrap_or_default();
+    let interface = message.field_str(header_field::INTERFACE);
+    let status = player_status();
+    let unknown_method = || {
+        (
+            "org.freedesktop.DBus.Error.UnknownMethod",
+            format!("No such method: {member}"),
+        )
+    };
+    let invalid_args = || {
+        (
+            "org.freedesktop.DBus.Error.InvalidArgs",
+            format!("Invalid arguments to {member}"),
+        )
+    };
+
+    let command = match (interface, member) {
+        (Some("org.freedesktop.DBus.Introspectable") | None, "Introspect") => {
+            return Ok(vec![Value::Str(INTROSPECTION_XML.into())]);
+        }
+        (Some("org.freedesktop.DBus.Peer") | None, "Ping") => return Ok(Vec::new()),
+        (Some(PROPERTIES_INTERFACE) | None, "Get") => {
+            let [Value::Str(interface), Value::Str(name)] = &message.body[..] else {
+                return Err(invalid_args());
+            };
+            let value = properties(interface, &status)
+                .and_then(|x| x.into_iter().find(|(k, _)| k == name))
+                .map(|(_, v)| v);
+            return match value {
+                Some(v) => Ok(vec![Value::Variant(Box::new(v))]),
+                None => Err((
+                    "org.freedesktop.DBus.Error.UnknownProperty",
+                    format!("No such property: {interface}.{name}"),
+                )),
+            };
+        }
+        (Some(PROPERTIES_INTERFACE) | None, "GetAll") => {
+            let [Value::Str(interface)] = &message.body[..] else {
+                return Err(invalid_args());
+            };
+            let properties = properties(interface, &status).unwrap_or_default();
+            return Ok(vec![dict(properties)]);
+        }
+        (Some(PROPERTIES_INTERFACE) | None, "Set") => match &message.body[..] {
+            [Value::Str(_), Value::Str(name), Value::Variant(v)] if name == "Volume" => {
+                let Value::Double(volume) = **v else {
+                    return Err(invalid_args());
+                };
+                RemoteCommand::SetVolume(volume.clamp(0.0, 1.0))
+            }
+            _ => {
+                return Err((
+                    "org.freedesktop.DBus.Error.PropertyReadOnly",
+                    "Only Volume can be set".into(),
+                ))
+            }
+        },
+        (Some(ROOT_INTERFACE) | None, "Raise") => return Ok(Vec::new()),
+        (Some(ROOT_INTERFACE) | None, "Quit") => RemoteCommand::Quit,
+        (Some(PLAYER_INTERFACE) | None, member) => match (member, &message.body[..]) {
+            ("Next", []) => RemoteCommand::Next,
+            ("Previous", []) => RemoteCommand::Previous,
+            // there's no stopped state once a disc is loaded
+            ("Pause" | "Stop", []) => RemoteCommand::Pause,
+            ("PlayPause", []) => RemoteCommand::TogglePause,
+            ("Play", []) => RemoteCommand::Play,
+            ("Seek", [Value::Int64(offset)]) => RemoteCommand::SeekBy(*offset as f64 / 1e6),
+            ("SetPosition", [Value::ObjectPath(track), Value::Int64(position)]) => {
+                // ignored if the song changed in the meantime, as the spec says
+                if *track != track_id(&status) {
+                    return Ok(Vec::new());
+                }
+                RemoteCommand::Seek(*position as f64 / 1e6)
+            }
+            ("OpenUri", _) => {
+                return Err((
+                    "org.freedesktop.DBus.Error.NotSupported",
+                    "Opening URIs is not supported".into(),
+                ))
+            }
+            _ => return Err(unknown_method()),
+        },
+        _ => return Err(unknown_method()),
+    };
+    debug!("MPRIS command: {:?}", command);
+    send_command(command);
+    Ok(Vec::new())
+}
+
+/// Signals the player properties that changed, checking every [`STATUS_POLL_INTERVAL`]
+fn watch_status(connection: &Connection) -> io::Result<()> {
+    let mut last = Vec::new();
+    loop {
+        sleep(STATUS_POLL_INTERVAL);
+        let status = player_status();
+        // Position changes all the time; it's not signalled, as the spec says
+        let current = properties(PLAYER_INTERFACE, &status)
+            .unwrap()
+            .into_iter()
+            .filter(|(k, _)| *k != "Position")
+            .collect::<Vec<_>>();
+        let changed = current
+            .iter()
+            .filter(|x| !last.contains(*x))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !changed.is_empty() {
+            connection.emit_properties_changed(dict(changed))?;
+        }
+        last = current;
+    }
+}
+
+fn run() -> io::Result<()> {
+    let stream = connect()?;
+    let mut reader = stream.try_clone()?;
+    let connection = Arc::new(Connection {
+        writer: Mutex::new(stream),
+        next_serial: AtomicU32::new(1),
+    });
+    // its reply, our unique name, isn't needed
+    connection.call_bus("Hello", Vec::new())?;
+    let name = if connection.request_name(&mut reader, BUS_NAME)? {
+        BUS_NAME.to_string()
+    } else {
+        // another player has it; the spec has the others add an instance suffix
+        let name = format!("{BUS_NAME}.instance{}", std::process::id());
+        if !connection.request_name(&mut reader, &name)? {
+            return Err(io::Error::other(format!("Bus name {name} is taken")));
+        }
+        name
+    };
+    info!("MPRIS: owning bus name {}", name);
+
+    let watcher_connection = Arc::clone(&connection);
+    spawn(move || {
+        if let Err(e) = watch_status(&watcher_connection) {
+            warn!("MPRIS: stopped signalling changes: {}", e);
+        }
+    });
+
+    loop {
+        connection.dispatch(&Message::read_from(&mut reader)?)?;
+    }
+}
+
+/// Serves MPRIS on a thread of its own; without a session bus, this only logs a warning
+pub fn start() {
+    spawn(|| {
+        if let Err(e) = run() {
+            warn!("MPRIS is unavailable: {}", e);
+        }
+    });
+}
@@ -0,0 +1,84 @@
+//! `--on-finish`: what's done once the playlist has played through (its last song ending
+//! with repeat off), like ejecting the disc after listening to it at night
+//!
+//! The player quits for `exit` and `eject`, and stays paused at the first song for the
+//! rest; their programs run without it waiting for them.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread::spawn;
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+
+use crate::cli::DiscArgs;
+use crate::mutex_lock;
+use crate::remote::{send_command, RemoteCommand};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnFinish {
+    Exit,
+    Eject,
+    /// With `systemctl suspend`
+    Suspend,
+    /// Run with `sh -c`
+    Command(String),
+}
+
+/// The drive to eject while quitting, once the player has let go of it
+static PENDING_EJECT: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Runs `program` on a thread of its own, logging how it went
+fn run_in_background(program: &'static str, args: Vec<String>) {
+    spawn(move || {
+        let status = Command::new(program)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match status {
+            Ok(x) if x.success() => info!("On finish: {} {:?} done", program, args),
+            Ok(x) => warn!("On finish: {} {:?} failed ({})", program, args, x),
+            Err(e) => warn!("On finish: failed to run {}: {}", program, e),
+        }
+    });
+}
+
+/// Does `action`; for when the last song has ended and nothing is played next
+pub fn playlist_finished(action: &OnFinish, disc: &DiscArgs) {
+    info!("Playlist finished; on finish: {:?}", action);
+    match action {
+        OnFinish::Exit => send_command(RemoteCommand::Quit),
+        OnFinish::Eject => {
+            match disc.source.is_some() || disc.demo {
+                true => warn!("Not ejecting {}, which isn't a drive", disc.location()),
+                false => *mutex_lock!(PENDING_EJECT) = Some(disc.drive.clone()),
+            }
+            send_command(RemoteCommand::Quit);
+        }
+        OnFinish::Suspend => run_in_background("systemctl", vec!["suspend".into()]),
+        OnFinish::Command(command) => {
+            run_in_background("sh", vec!["-c".into(), command.clone()]);
+        }
+    }
+}
+
+/// Ejects the drive if the playlist finished with [`OnFinish::Eject`]; for right before
+/// exiting, once the player has stopped reading the drive
+pub fn eject_pending() {
+    let Some(drive) = mutex_lock!(PENDING_EJECT).take() else {
+        return;
+    };
+    info!("Ejecting {}", drive.display());
+    match Command::new("eject")
+        .arg(&drive)
+        .stdin(Stdio::null())
+        .status()
+    {
+        Ok(x) if x.success() => {}
+        Ok(x) => warn!("Failed to eject {} ({})", drive.display(), x),
+        Err(e) => warn!("Failed to run eject: {}", e),
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(feature = "tui")]
+use ratatui::prelude::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// Built-in color palettes of the TUI
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Tuned for dark terminal backgrounds
+    #[default]
+    Default,
+    HighContrast,
+    /// Tuned for light terminal backgrounds
+    Light,
+}
+
+/// Styles of the UI elements that are colored
+#[cfg(feature = "tui")]
+pub struct PaletteStyles {
+    pub selected_row: Style,
+    pub playing_row: Style,
+    pub gauge_filled: Style,
+    /// Of what's read ahead of the position
+    pub gauge_buffered: Style,
+    pub gauge_unfilled: Style,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Default, Palette::HighContrast, Palette::Light];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Default => "Default",
+            Palette::HighContrast => "High contrast",
+            Palette::Light => "Light background",
+        }
+    }
+
+    /// The palette after this one in [`Palette::ALL`], wrapping around
+    pub fn next(&self) -> Palette {
+        let idx = Self::ALL.iter().position(|x| x == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn styles(&self) -> PaletteStyles {
+        let row_style = |bg: Color, fg: Color, add_modifier: Modifier| Style {
+            bg: Some(bg),
+            fg: Some(fg),
+            add_modifier,
+            ..Default::default()
+        };
+        match self {
+            Palette::Default => PaletteStyles {
+                selected_row: row_style(Color::LightBlue, Color::White, Modifier::BOLD),
+                playing_row: row_style(Color::White, Color::Black, Modifier::BOLD),
+                gauge_filled: Style::default().fg(Color::Blue),
+                gauge_buffered: Style::default().fg(Color::DarkGray),
+                gauge_unfilled: Style::default().fg(Color::Gray),
+            },
+            Palette::HighContrast => PaletteStyles {
+                selected_row: row_style(Color::Yellow, Color::Black, Modifier::BOLD),
+                playing_row: row_style(
+                    Color::White,
+                    Color::Black,
+                    Modifier::BOLD | Modifier::UNDERLINED,
+                ),
+                gauge_filled: Style::default().fg(Color::White),
+                gauge_buffered: Style::default().fg(Color::Gray),
+                gauge_unfilled: Style::default().fg(Color::DarkGray),
+            },
+            Palette::Light => PaletteStyles {
+                selected_row: row_style(Color::Blue, Color::White, Modifier::BOLD),
+                playing_row: row_style(Color::Black, Color::White, Modifier::BOLD),
+                gauge_filled: Style::default().fg(Color::Blue),
+                gauge_buffered: Style::default().fg(Color::Gray),
+                gauge_unfilled: Style::default().fg(Color::DarkGray),
+            },
+        }
+    }
+}
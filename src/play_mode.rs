@@ -0,0 +1,121 @@
+//! Shuffle and repeat: which song plays after which
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::UiArgs;
+use crate::config::CONFIG;
+use crate::mutex_lock;
+
+/// What happens when a song ends
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RepeatMode {
+    /// Stop after the last song
+    Off,
+    /// Start over after the last song
+    #[default]
+    All,
+    /// Play the same song again
+    One,
+    /// Stop after each song, at the next one, for auditioning songs one at a time
+    Single,
+}
+
+impl RepeatMode {
+    pub const ALL: [RepeatMode; 4] = [
+        RepeatMode::Off,
+        RepeatMode::All,
+        RepeatMode::One,
+        RepeatMode::Single,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RepeatMode::Off => "off",
+            RepeatMode::All => "all",
+            RepeatMode::One => "one",
+            RepeatMode::Single => "single",
+        }
+    }
+
+    /// The mode after this one in [`RepeatMode::ALL`], wrapping around
+    pub fn next(&self) -> RepeatMode {
+        let idx = Self::ALL.iter().position(|x| x == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+/// A random order of the song indices `0..count`; `first`, if given, is put at the front
+pub fn shuffled_order(count: usize, first: Option<usize>) -> Vec<usize> {
+    shuffled((0..count).collect(), first)
+}
+
+/// `songs` in a random order; `first`, if one of them, is put at the front
+pub fn shuffled(mut order: Vec<usize>, first: Option<usize>) -> Vec<usize> {
+    order.shuffle(&mut rand::thread_rng());
+    if let Some(pos) = first.and_then(|x| order.iter().position(|&i| i == x)) {
+        order.swap(0, pos);
+    }
+    order
+}
+
+/// Shuffle on or off and the repeat mode to start with: the command line options, or else
+/// the config file
+pub fn initial_modes(ui: &UiArgs) -> (bool, RepeatMode) {
+    let config = mutex_lock!(CONFIG).clone();
+    (
+        ui.shuffle || config.shuffle,
+        ui.repeat.unwrap_or(config.repeat),
+    )
+}
+
+/// Whether to play only the favorite songs: the command line option, or else the config
+/// file
+pub fn initial_favorites_only(ui: &UiArgs) -> bool {
+    ui.favorites_only || mutex_lock!(CONFIG).favorites_only
+}
+
+/// Whether next and previous wrap around: not with the command line option, or else as
+/// the config file says
+pub fn initial_wrap_around(ui: &UiArgs) -> bool {
+    !ui.no_wrap_around && mutex_lock!(CONFIG).wrap_around
+}
+
+/// The song `delta` songs after `playing` in `order` (before it, if negative), for next
+/// and previous
+///
+/// Past either end of `order`, it wraps around with `wrap`, and is `None` without it.
+pub fn skipped_song(order: &[usize], playing: usize, delta: isize, wrap: bool) -> Option<usize> {
+    let position = order.iter().position(|&x| x == playing).unwrap_or_default() as isize + delta;
+    match wrap {
+        true => order.get(position.rem_euclid(order.len().max(1) as isize) as usize),
+        false => usize::try_from(position).ok().and_then(|x| order.get(x)),
+    }
+    .copied()
+}
+
+/// The song to go to when `playing` ends, and whether to play it
+///
+/// After the last song in `order` with [`RepeatMode::Off`], the first one is only paused
+/// at, as is the next one after any song with [`RepeatMode::Single`]; with no songs in
+/// `order`, `playing` is.
+pub fn song_after_end(order: &[usize], playing: usize, repeat: RepeatMode) -> (usize, bool) {
+    let position = order.iter().position(|&x| x == playing).unwrap_or_default();
+    let Some(&next) = order.get((position + 1) % order.len().max(1)) else {
+        return (playing, false);
+    };
+    match repeat {
+        RepeatMode::One => (playing, true),
+        RepeatMode::Single => (next, false),
+        RepeatMode::Off if position + 1 == order.len() => (next, false),
+        _ => (next, true),
+    }
+}
+
+/// Whether the playlist has played through once `playing` ends: it's the last song in
+/// `order`, with [`RepeatMode::Off`]
+pub fn playlist_finished(order: &[usize], playing: usize, repeat: RepeatMode) -> bool {
+    let position = order.iter().position(|&x| x == playing).unwrap_or_default();
+    repeat == RepeatMode::Off && position + 1 == order.len()
+}
@@ -1,83 +1,236 @@
-use std::fs::File;
+#[cfg(feature = "audio")]
+use std::fmt::Display;
+use std::io;
 use std::io::{BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
-use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TryRecvError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{
+    channel, sync_channel, Receiver, RecvTimeoutError, Sender, SyncSender, TryRecvError,
+    TrySendError,
+};
 use std::sync::{Arc, Mutex};
-use std::thread::spawn;
+use std::thread::{sleep, spawn, JoinHandle};
+use std::time::{Duration, Instant};
 
-use anyhow::anyhow;
 use byteorder::{ReadBytesExt, LE};
+#[cfg(feature = "audio")]
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+#[cfg(feature = "audio")]
 use cpal::{Sample, SampleFormat, SampleRate, Stream};
 
+use log::{debug, info, warn};
 use once_cell::sync::Lazy;
 
-use crate::{mutex_lock, Track};
+#[cfg(feature = "audio")]
+use crate::config::CONFIG;
+use crate::effects::{AudioEffect, EffectChain, Equalizer};
+#[cfg(feature = "audio")]
+use crate::error::Error;
+use crate::sniff::{non_audio_content, read_head};
+use crate::source::{DiscSource, SourceReader};
+use crate::span::Span;
+use crate::{mutex_lock, Track, SECTOR_SIZE};
 
 /// We place [`Stream`] here just to prevent it from dropping
+#[cfg(feature = "audio")]
 pub static AUDIO_STREAM: Lazy<Mutex<Option<StreamSendWrapper>>> = Lazy::new(|| Mutex::new(None));
 pub static PLAYBACK_HANDLE: Lazy<Mutex<Option<PlaybackHandle>>> = Lazy::new(|| Mutex::new(None));
 pub const AUDIO_SAMPLE_RATE: u32 = 44100;
 pub const AUDIO_BIT_DEPTH: u32 = 16;
 pub const AUDIO_CHANNELS: u32 = 2;
 
+/// The remembered audio device (`audio_device` in the config file) that wasn't found by
+/// the last [`create_audio_stream`], which played on the default one instead; frontends
+/// take it to tell
+#[cfg(feature = "audio")]
+pub static MISSING_AUDIO_DEVICE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// What the audio device was opened with by [`create_audio_stream`], to be shown
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioOutput {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Like `i16`
+    pub sample_format: String,
+}
+
+/// The output of the last [`create_audio_stream`]
+#[cfg(feature = "audio")]
+pub static AUDIO_OUTPUT: Lazy<Mutex<Option<AudioOutput>>> = Lazy::new(|| Mutex::new(None));
+
+/// How an audio stream and the player thread feeding it keep up; each player has its own
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    /// Frames the audio device asked for in its last callback: the buffer size the audio
+    /// stack settled on, which may change as it plays
+    pub callback_frames: AtomicU64,
+    /// Times the audio device ran out of samples while playing, filling the rest with
+    /// silence; the player thread reads further ahead each time it goes up
+    pub underruns: AtomicU64,
+    /// Bytes of the playing song the player thread has read ahead of its position, as of
+    /// the last frame it played
+    pub read_ahead: AtomicU64,
+}
+
+/// Bytes the player thread reads ahead of what it plays, at first and at most
+const READ_AHEAD_MIN: usize = 8 * 1024;
+const READ_AHEAD_MAX: usize = 4 * 1024 * 1024;
+
+/// A failing read is tried this many more times before its sector is skipped
+const READ_RETRIES: u32 = 2;
+
+/// How long [`PlaybackHandle::stop_and_wait`] waits for the player thread, which may be
+/// stuck reading a bad disc
+const STOP_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long [`PlaybackHandle::position`] and [`PlaybackHandle::is_paused`] wait for the
+/// player thread's answer
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
 const SAMPLES_ONE_SEC: u64 = AUDIO_SAMPLE_RATE as u64 * AUDIO_CHANNELS as u64;
 const BYTES_ONE_SEC: u64 = SAMPLES_ONE_SEC * AUDIO_BIT_DEPTH as u64 / 8;
+/// Bytes of a frame: a sample for each channel
+pub const FRAME_SIZE: u64 = AUDIO_CHANNELS as u64 * AUDIO_BIT_DEPTH as u64 / 8;
+
+/// `pos` moved back to the start of its frame, with frames counted from `start`
+///
+/// A song's PCM is read in whole frames from where its track starts; a partial frame at
+/// its end isn't played, and seeks and skips land on frame starts.
+pub fn frame_start(start: u64, pos: u64) -> u64 {
+    pos - pos.saturating_sub(start) % FRAME_SIZE
+}
+
+/// `volume` changed by `steps` of `step`, kept in 0..1
+pub fn stepped_volume(volume: f64, steps: i32, step: f64) -> f64 {
+    // round to whole percents so repeated steps don't drift
+    ((volume + steps as f64 * step).clamp(0.0, 1.0) * 100.0).round() / 100.0
+}
 
 pub fn duration_from_bytes(size: u64) -> f64 {
     size as f64 / BYTES_ONE_SEC as f64
 }
 
-pub fn create_audio_stream() -> anyhow::Result<(Stream, SyncSender<i16>)> {
+/// Names of the audio output devices, for `--audio-device`
+#[cfg(feature = "audio")]
+pub fn output_device_names() -> Result<Vec<String>, Error> {
+    let devices = cpal::default_host()
+        .output_devices()
+        .map_err(|e| Error::AudioInit(e.to_string()))?;
+    Ok(devices.filter_map(|x| x.name().ok()).collect())
+}
+
+/// Name of the system's default audio output device
+#[cfg(feature = "audio")]
+pub fn default_output_device_name() -> Option<String> {
+    cpal::default_host().default_output_device()?.name().ok()
+}
+
+/// The output device named `name`, or the default one if there's none of that name
+#[cfg(feature = "audio")]
+fn output_device(host: &cpal::Host, name: Option<&str>) -> Option<cpal::Device> {
+    if let Some(name) = name {
+        let mut devices = host.output_devices().ok()?;
+        match devices.find(|x| x.name().ok().as_deref() == Some(name)) {
+            Some(x) => return Some(x),
+            None => {
+                warn!(
+                    "Audio device {:?} not found; playing on the default one",
+                    name
+                );
+                *mutex_lock!(MISSING_AUDIO_DEVICE) = Some(name.into());
+            }
+        }
+    }
+    host.default_output_device()
+}
+
+/// Opens the audio device remembered in the config file (or the default one), playing
+/// the samples sent to the returned sender; how it keeps up goes to `stats`
+#[cfg(feature = "audio")]
+pub fn create_audio_stream(stats: Arc<StreamStats>) -> Result<(Stream, SyncSender<i16>), Error> {
     let (tx, rx) = sync_channel(AUDIO_SAMPLE_RATE as usize);
+    let audio_error = |e: &dyn Display| Error::AudioInit(e.to_string());
 
     let host = cpal::default_host();
-    let device = host
-        .default_output_device()
-        .ok_or_else(|| anyhow!("No audio output device found"))?;
-    let configs = device.supported_output_configs()?;
+    let remembered = mutex_lock!(CONFIG).audio_device.clone();
+    let device = output_device(&host, remembered.as_deref())
+        .ok_or_else(|| audio_error(&"No audio output device found"))?;
+    let configs = device
+        .supported_output_configs()
+        .map_err(|e| audio_error(&e))?;
     let mut configs =
         configs.filter(|x| x.channels() == 2 && x.sample_format() == SampleFormat::I16);
     let first = configs
         .next()
-        .ok_or_else(|| anyhow!("No audio output profile found"))?;
+        .ok_or_else(|| audio_error(&"No audio output profile found"))?;
 
     let output_config = first
         .try_with_sample_rate(SampleRate(AUDIO_SAMPLE_RATE))
         .ok_or_else(|| {
-            anyhow!(
+            audio_error(&format_args!(
                 "No audio output profile with sample rate {} found",
                 AUDIO_SAMPLE_RATE
-            )
+            ))
         })?;
+    debug!(
+        "Audio output: {} ({:?})",
+        device.name().unwrap_or_default(),
+        output_config
+    );
+    *mutex_lock!(AUDIO_OUTPUT) = Some(AudioOutput {
+        device: device.name().unwrap_or_default(),
+        sample_rate: output_config.sample_rate().0,
+        channels: output_config.channels(),
+        sample_format: output_config.sample_format().to_string(),
+    });
 
     // Why here there's no multiple-move encountering?? this `play_fn` should be called
     // multiple times, and `rx` will be "moved" many times?
     let play_fn = move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
+        // running out after some samples is an underrun; getting none is just paused
+        let mut received = false;
+        let mut ran_out = false;
         for x in data.iter_mut() {
-            *x = rx.try_recv().unwrap_or(i16::EQUILIBRIUM);
+            match rx.try_recv() {
+                Ok(sample) => {
+                    *x = sample;
+                    received = true;
+                    ran_out = false;
+                }
+                Err(_) => {
+                    *x = i16::EQUILIBRIUM;
+                    ran_out = received;
+                }
+            }
+        }
+        if ran_out {
+            stats.underruns.fetch_add(1, Ordering::Relaxed);
         }
+        let frames = data.len() as u64 / AUDIO_CHANNELS as u64;
+        stats.callback_frames.store(frames, Ordering::Relaxed);
     };
-    let stream = device.build_output_stream(
-        &output_config.config(),
-        play_fn,
-        move |err| {
-            println!("{}", err);
-        },
-        None, /* blocking */
-    )?;
-    stream.play()?;
+    let stream = device
+        .build_output_stream(
+            &output_config.config(),
+            play_fn,
+            move |err| {
+                println!("{}", err);
+            },
+            None, /* blocking */
+        )
+        .map_err(|e| audio_error(&e))?;
+    stream.play().map_err(|e| audio_error(&e))?;
     Ok((stream, tx))
 }
 
 pub enum PlayerCommand {
     /// Go to a track
     ///
-    /// The second parameter indicates autoplay
+    /// The second parameter indicates autoplay; on `false`, the track is paused at its start
     Goto(Track, bool),
     /// Seek to a position with duration in seconds
     Seek(f64),
-    /// Open the file and start playing
+    /// Start reading the disc source
     Start,
     Pause,
     Play,
@@ -86,34 +239,113 @@ pub enum PlayerCommand {
     SetPaused(bool),
     /// Volume level is in 0..1
     ChangeVolume(f64),
-    /// Get the current position in seconds
-    GetPosition,
-    /// Get if in paused state
-    GetIsPaused,
-    /// This issues a "stop" command and the player thread will emit
-    /// a [`PlayerCallbackEvent::Stopped`] event.
+    SetEqualizer(Equalizer),
+    SetLimiter(bool),
+    /// Effects run after the built-in ones, in this order; see [`EffectChain`]
+    SetEffects(Vec<Box<dyn AudioEffect>>),
+    /// Levels the songs in these tracks are scaled by when gone to, to play at the same
+    /// loudness (see [`crate::MetaInfo::song_levels`]); the others play as they are
+    SetSongLevels(Vec<(Track, f64)>),
+    /// After how long paused the drive is spun down ([`DiscSource::spin_down`]); it's
+    /// never on `None`, which is the default
+    SetSpinDown(Option<Duration>),
+    /// Reply the current position in seconds; see [`PlaybackHandle::position`]
+    GetPosition(SyncSender<f64>),
+    /// Reply if in paused state; see [`PlaybackHandle::is_paused`]
+    GetIsPaused(SyncSender<bool>),
+    /// Stop the player thread, replying just before it ends
     ///
-    /// This is useful to wait the player thread to be terminated.
-    StopAndWait,
+    /// This is useful to wait the player thread to be terminated; see
+    /// [`PlaybackHandle::stop_and_wait`].
+    StopAndWait(SyncSender<()>),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlayerCallbackEvent {
+    /// The playing song reached its end; it's sent once, until the next
+    /// [`PlayerCommand::Goto`] or [`PlayerCommand::Seek`]
     Finished,
     Paused(bool),
     /// (current, total), in seconds
     Progress(u32, u32),
+    /// A sector couldn't be read and playback continued after it
+    ///
+    /// The parameter is the sector number.
+    SectorSkipped(u64),
+    /// The counts of read errors so far changed
+    ReadErrors(ReadErrorStats),
+    /// The track gone to looks like data rather than audio, which is what's given, so
+    /// it's left paused instead of played; see [`crate::sniff::non_audio_content`]
+    NotAudio(&'static str),
+}
+
+/// Sectors that failed to read since the player thread started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadErrorStats {
+    /// Read on a retry
+    pub retried: u64,
+    /// Given up on after [`READ_RETRIES`] retries
+    pub skipped: u64,
+}
+
+impl ReadErrorStats {
+    /// Like `12 sectors skipped, 3 re-read`, or `None` without any errors
+    pub fn summary(&self) -> Option<String> {
+        let count = |n: u64, what: &str| match n {
+            0 => None,
+            1 => Some(format!("1 sector {what}")),
+            n => Some(format!("{n} sectors {what}")),
+        };
+        match (count(self.skipped, "skipped"), self.retried) {
+            (None, 0) => None,
+            (None, _) => count(self.retried, "re-read"),
+            (Some(skipped), 0) => Some(skipped),
+            (Some(skipped), retried) => Some(format!("{skipped}, {retried} re-read")),
+        }
+    }
+}
+
+type Subscriber = Box<dyn Fn(PlayerCallbackEvent) + Send>;
+
+/// Where the player thread tells what it does; every subscriber hears every event
+///
+/// Subscribers are called on the player thread in the order they subscribed, so they
+/// should be quick; ones with more to do can take a [`EventBus::channel`] instead.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    channels: Arc<Mutex<Vec<Sender<PlayerCallbackEvent>>>>,
 }
 
-pub enum PlayerResult {
-    None,
-    IsPaused(bool),
-    /// Current position in seconds
-    Position(f64),
-    Stopped,
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, subscriber: impl Fn(PlayerCallbackEvent) + Send + 'static) {
+        mutex_lock!(self.subscribers).push(Box::new(subscriber));
+    }
+
+    /// A channel of the events from now on; it's left off once the receiver is dropped
+    pub fn channel(&self) -> Receiver<PlayerCallbackEvent> {
+        let (tx, rx) = channel();
+        mutex_lock!(self.channels).push(tx);
+        rx
+    }
+
+    pub fn publish(&self, event: PlayerCallbackEvent) {
+        for x in mutex_lock!(self.subscribers).iter() {
+            x(event);
+        }
+        mutex_lock!(self.channels).retain(|x| x.send(event).is_ok());
+    }
 }
 
-pub struct StreamSendWrapper(Stream);
+/// The field is never read: it only keeps the stream, which plays as long as it lives
+#[cfg(feature = "audio")]
+pub struct StreamSendWrapper(#[allow(dead_code)] Stream);
 
+#[cfg(feature = "audio")]
 impl From<Stream> for StreamSendWrapper {
     fn from(value: Stream) -> Self {
         Self(value)
@@ -121,121 +353,245 @@ impl From<Stream> for StreamSendWrapper {
 }
 
 // TODO: safety is not investigated for multiple platforms
+#[cfg(feature = "audio")]
 unsafe impl Send for StreamSendWrapper {}
 
+#[derive(Clone)]
 pub struct PlaybackHandle {
     command_tx: SyncSender<PlayerCommand>,
-    result_rx: Arc<Mutex<Receiver<PlayerResult>>>,
+    /// Taken by the first [`Self::stop_and_wait`] that sees the thread stop
+    thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+    stats: Arc<StreamStats>,
 }
 
 impl PlaybackHandle {
+    /// How the audio output of this player keeps up
+    pub fn stats(&self) -> &StreamStats {
+        &self.stats
+    }
+
     pub fn send(&self, cmd: PlayerCommand) {
         self.command_tx.send(cmd).unwrap()
     }
 
+    /// Sends `cmd` unless another one is waiting to be taken; returns whether it's sent
+    ///
+    /// Unlike [`Self::send`], it never blocks, so event callbacks can use it.
+    pub fn try_send(&self, cmd: PlayerCommand) -> bool {
+        self.command_tx.try_send(cmd).is_ok()
+    }
+
     pub fn send_commands(&self, cmds: impl IntoIterator<Item = PlayerCommand>) {
         for c in cmds {
             self.send(c);
         }
     }
 
-    pub fn player_result(&self) -> PlayerResult {
-        let guard = mutex_lock!(self.result_rx);
+    /// Sends `cmd`, waiting for the player thread to take it until `deadline` at most, as
+    /// a stuck thread doesn't take commands; returns `false` if it's not taken by then
+    ///
+    /// A thread that's gone counts as having taken it; its reply never comes.
+    fn send_by(&self, mut cmd: PlayerCommand, deadline: Instant) -> bool {
+        loop {
+            match self.command_tx.try_send(cmd) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => return true,
+                Err(TrySendError::Full(x)) if Instant::now() < deadline => {
+                    cmd = x;
+                    sleep(Duration::from_millis(10));
+                }
+                Err(TrySendError::Full(_)) => return false,
+            }
+        }
+    }
+
+    /// Sends the command made with a reply channel of its own, and waits for the reply;
+    /// `None` if there's none in [`QUERY_TIMEOUT`]
+    fn ask<T>(&self, cmd: impl FnOnce(SyncSender<T>) -> PlayerCommand) -> Option<T> {
+        let deadline = Instant::now() + QUERY_TIMEOUT;
+        let (reply_tx, reply_rx) = sync_channel(1);
+        let reply = match self.send_by(cmd(reply_tx), deadline) {
+            true => reply_rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .ok(),
+            false => None,
+        };
+        if reply.is_none() {
+            warn!("The player thread didn't answer in {QUERY_TIMEOUT:?}");
+        }
+        reply
+    }
+
+    /// Current position in seconds; `None` if the player thread doesn't answer
+    pub fn position(&self) -> Option<f64> {
+        self.ask(PlayerCommand::GetPosition)
+    }
 
-        guard.recv().unwrap()
+    /// `None` if the player thread doesn't answer
+    pub fn is_paused(&self) -> Option<bool> {
+        self.ask(PlayerCommand::GetIsPaused)
     }
 
-    pub fn send_recv(&self, cmd: PlayerCommand) -> PlayerResult {
-        self.send(cmd);
-        self.player_result()
+    /// Stops the player thread; returns once it's stopped, or `false` if it doesn't in
+    /// [`STOP_TIMEOUT`]
+    ///
+    /// A thread not stopping in time is left running (and warned about), so quitting is
+    /// never held up by it.
+    pub fn stop_and_wait(&self) -> bool {
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        let (reply_tx, reply_rx) = sync_channel(1);
+        if !self.send_by(PlayerCommand::StopAndWait(reply_tx), deadline) {
+            warn!("The player thread didn't take the stop command in {STOP_TIMEOUT:?}");
+            return false;
+        }
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if let Err(RecvTimeoutError::Timeout) = reply_rx.recv_timeout(timeout) {
+            warn!("The player thread didn't stop in {STOP_TIMEOUT:?}");
+            return false;
+        }
+        if let Some(thread) = mutex_lock!(self.thread).take() {
+            let _ = thread.join();
+        }
+        true
     }
 }
 
+/// The handle in [`PLAYBACK_HANDLE`], cloned out of it; `None` before the player starts
+///
+/// Sending to the player thread may wait for it, and its event callbacks take the lock on
+/// [`PLAYBACK_HANDLE`], so the lock mustn't be held meanwhile.
+pub fn playback_handle() -> Option<PlaybackHandle> {
+    mutex_lock!(PLAYBACK_HANDLE).clone()
+}
+
 pub fn set_global_playback_handle(playback_handle: PlaybackHandle) {
     mutex_lock!(PLAYBACK_HANDLE).replace(playback_handle);
 }
 
-pub fn start_global_playback_thread<D, F>(
-    drive: PathBuf,
-    callback_data: D,
-    event_callback: Option<F>,
-) -> anyhow::Result<PlaybackHandle>
-where
-    D: Send + 'static,
-    F: Fn(PlayerCallbackEvent, &D) + Send + 'static,
-{
+/// Like [`start_playback_thread`], keeping the audio stream in [`AUDIO_STREAM`]
+#[cfg(feature = "audio")]
+pub fn start_global_playback_thread(
+    source: Arc<dyn DiscSource>,
+    events: EventBus,
+) -> Result<PlaybackHandle, Error> {
+    let (handle, stream) = start_playback_thread(source, events)?;
+    mutex_lock!(AUDIO_STREAM).replace(stream);
+    Ok(handle)
+}
+
+/// Starts a player thread reading `source` and publishing to `events`; the audio plays
+/// as long as the returned stream is kept
+#[cfg(feature = "audio")]
+pub fn start_playback_thread(
+    source: Arc<dyn DiscSource>,
+    events: EventBus,
+) -> Result<(PlaybackHandle, StreamSendWrapper), Error> {
+    let stats = Arc::<StreamStats>::default();
+    let (stream, sample_tx) = create_audio_stream(Arc::clone(&stats))?;
+    let handle = start_player_thread(source, sample_tx, events, stats);
+    Ok((handle, StreamSendWrapper(stream)))
+}
+
+/// The level of the song whose track starts at `start_pos`, of `levels`; one if it has none
+fn song_level(levels: &[(Track, f64)], start_pos: u64) -> f64 {
+    levels
+        .iter()
+        .find(|(x, _)| x.start_offset() == start_pos)
+        .map_or(1.0, |&(_, level)| level)
+}
+
+/// Starts a player thread sending its samples (interleaved, in the disc's format) to
+/// `sample_tx`, the sender of an audio stream reporting to `stats`, or of anything else,
+/// like for tests
+pub fn start_player_thread(
+    source: Arc<dyn DiscSource>,
+    sample_tx: SyncSender<i16>,
+    events: EventBus,
+    stats: Arc<StreamStats>,
+) -> PlaybackHandle {
     let (cmd_tx, cmd_rx) = sync_channel::<PlayerCommand>(1);
-    let (result_tx, result_rx) = sync_channel::<PlayerResult>(1);
-    let result_rx = Arc::new(Mutex::new(result_rx));
 
-    let (stream, sample_tx) = create_audio_stream()?;
-    mutex_lock!(AUDIO_STREAM).replace(StreamSendWrapper(stream));
-    spawn(move || {
+    let thread_stats = Arc::clone(&stats);
+    let thread = spawn(move || {
+        let stats = thread_stats;
         let mut paused = true;
-        let mut reader: Option<BufReader<File>> = None;
+        let mut reader: Option<BufReader<SourceReader>> = None;
         let mut start_pos = 0_u64;
         let mut end_pos = 0_u64;
         let mut song_seconds = 0_u32;
-        let mut volume = 1.0;
-        let event_callback = event_callback;
-        let callback_data = callback_data;
-        macro event_callback($($arg:tt)*) {
-            if let Some(x) = event_callback.as_ref() { x($($arg)*, &callback_data) }
-        }
+        let mut effects = EffectChain::default();
+        let mut song_levels = Vec::new();
+        let mut finished = false;
+        let mut read_errors = ReadErrorStats::default();
+        // the sector failing to read, and how many times it's been retried
+        let mut failing: Option<(u64, u32)> = None;
+        let mut seen_underruns = stats.underruns.load(Ordering::Relaxed);
+        let mut spin_down: Option<Duration> = None;
+        // when the pause began, and if the drive is spun down since
+        let mut paused_since: Option<(Instant, bool)> = None;
         loop {
             match cmd_rx.try_recv() {
                 Ok(PlayerCommand::Start) => {
-                    reader = Some(BufReader::new(File::open(&drive).unwrap()));
+                    let source = SourceReader::new(Arc::clone(&source));
+                    reader = Some(BufReader::with_capacity(READ_AHEAD_MIN, source));
                 }
                 Ok(PlayerCommand::Goto(track, play)) => {
+                    let _span = Span::enter(format!("goto{{track={}}}", track.track_no));
+                    debug!("Going to {:?}, playing: {}", track, play);
                     if let Some(ref mut r) = reader {
                         r.seek(SeekFrom::Start(track.start_offset())).unwrap();
-                        if play {
-                            paused = false;
-                            event_callback!(PlayerCallbackEvent::Paused(false))
+                        let not_audio = non_audio_content(&read_head(&*source, &track));
+                        paused = !play || not_audio.is_some();
+                        events.publish(PlayerCallbackEvent::Paused(paused));
+                        if let Some(content) = not_audio {
+                            warn!("{:?} looks like {}; not playing it", track, content);
+                            events.publish(PlayerCallbackEvent::NotAudio(content));
                         }
                     }
                     start_pos = track.start_offset();
-                    end_pos = track.end_offset();
+                    effects.normalization.level = song_level(&song_levels, start_pos);
+                    end_pos = frame_start(start_pos, track.end_offset()).max(start_pos);
+                    finished = false;
+                    failing = None;
                     song_seconds = ((end_pos - start_pos) / BYTES_ONE_SEC) as u32;
-                    event_callback!(PlayerCallbackEvent::Progress(0, song_seconds));
+                    events.publish(PlayerCallbackEvent::Progress(0, song_seconds));
                 }
                 Ok(PlayerCommand::Pause) => {
                     paused = true;
-                    event_callback!(PlayerCallbackEvent::Paused(paused))
+                    events.publish(PlayerCallbackEvent::Paused(paused))
                 }
                 Ok(PlayerCommand::Play) => {
                     paused = false;
-                    event_callback!(PlayerCallbackEvent::Paused(paused))
+                    events.publish(PlayerCallbackEvent::Paused(paused))
                 }
                 Ok(PlayerCommand::SetPaused(p)) => {
                     paused = p;
-                    event_callback!(PlayerCallbackEvent::Paused(paused));
+                    events.publish(PlayerCallbackEvent::Paused(paused));
                 }
-                Ok(PlayerCommand::GetIsPaused) => {
-                    result_tx.send(PlayerResult::IsPaused(paused)).unwrap();
+                Ok(PlayerCommand::GetIsPaused(reply)) => {
+                    let _ = reply.send(paused);
                 }
-                Ok(PlayerCommand::GetPosition) => {
+                Ok(PlayerCommand::GetPosition(reply)) => {
                     let position = match &mut reader {
                         None => 0.0,
                         Some(r) => {
                             (r.stream_position().unwrap() - start_pos) as f64 / BYTES_ONE_SEC as f64
                         }
                     };
-                    result_tx.send(PlayerResult::Position(position)).unwrap();
+                    let _ = reply.send(position);
                 }
                 Ok(PlayerCommand::Seek(p)) => {
+                    let _span = Span::enter(format!("seek{{position={p}}}"));
+                    debug!("Seeking to {}s", p);
                     if let Some(reader) = &mut reader {
-                        let mut one_sec_samples = (SAMPLES_ONE_SEC as f64 * p) as u64;
-                        // For two-channel audio streams, only skip even samples
-                        if one_sec_samples % 2 == 1 {
-                            one_sec_samples -= 1;
-                        }
-                        let seek_pos = start_pos + one_sec_samples * AUDIO_BIT_DEPTH as u64 / 8;
+                        // past the end, it's finished at once
+                        let bytes = (BYTES_ONE_SEC as f64 * p) as u64;
+                        let seek_pos = frame_start(start_pos, start_pos + bytes).min(end_pos);
                         reader.seek(SeekFrom::Start(seek_pos)).unwrap();
-                        event_callback!(PlayerCallbackEvent::Progress(
+                        finished = false;
+                        failing = None;
+                        events.publish(PlayerCallbackEvent::Progress(
                             ((seek_pos - start_pos) / BYTES_ONE_SEC) as u32,
-                            song_seconds
+                            song_seconds,
                         ));
                     }
                 }
@@ -245,33 +601,149 @@ where
                     }
                 }
                 Ok(PlayerCommand::ChangeVolume(v)) => {
-                    volume = v;
+                    debug!("Volume: {}", v);
+                    effects.gain.level = v;
+                }
+                Ok(PlayerCommand::SetEqualizer(x)) => {
+                    effects.equalizer = x;
+                }
+                Ok(PlayerCommand::SetLimiter(x)) => {
+                    debug!("Limiter: {}", x);
+                    effects.limiter.enabled = x;
+                }
+                Ok(PlayerCommand::SetEffects(x)) => {
+                    debug!("{} effects", x.len());
+                    effects.user = x;
+                }
+                Ok(PlayerCommand::SetSongLevels(x)) => {
+                    debug!("Levels of {} songs", x.len());
+                    song_levels = x;
+                    effects.normalization.level = song_level(&song_levels, start_pos);
                 }
-                Ok(PlayerCommand::StopAndWait) => {
-                    result_tx.send(PlayerResult::Stopped).unwrap();
+                Ok(PlayerCommand::SetSpinDown(x)) => {
+                    debug!("Spin down after: {:?}", x);
+                    spin_down = x;
+                }
+                Ok(PlayerCommand::StopAndWait(reply)) => {
+                    debug!("Stopping");
+                    let _ = reply.send(());
                     break;
                 }
             }
-            if !paused && let Some(ref mut r) = reader {
-                let pos = r.stream_position().unwrap();
+            let underruns = stats.underruns.load(Ordering::Relaxed);
+            if underruns != seen_underruns {
+                seen_underruns = underruns;
+                // the samples left running out after pausing or finishing is expected
+                if !paused && !finished {
+                    reader = reader.map(grow_read_ahead);
+                }
+            }
+            match (paused && reader.is_some(), &mut paused_since) {
+                (true, None) => paused_since = Some((Instant::now(), false)),
+                (true, Some((since, spun_down @ false)))
+                    if spin_down.is_some_and(|x| since.elapsed() >= x) =>
+                {
+                    *spun_down = true;
+                    spin_down_drive(&*source, reader.as_mut().unwrap());
+                }
+                (false, Some(_)) => paused_since = None,
+                _ => {}
+            }
+            let Some(r) = reader.as_mut().filter(|_| !paused) else {
+                continue;
+            };
+            let pos = r.stream_position().unwrap();
+            stats.read_ahead.store(
+                (r.buffer().len() as u64).min(end_pos.saturating_sub(pos)),
+                Ordering::Relaxed,
+            );
 
-                if pos >= end_pos {
-                    // reach the end of the playing song
-                    event_callback!(PlayerCallbackEvent::Finished);
+            // a frame cut short by the end isn't read, so it can't run into the next track
+            if pos + FRAME_SIZE > end_pos {
+                // reach the end of the playing song
+                if !finished {
+                    debug!("Song finished at byte {}", pos);
+                    finished = true;
+                    events.publish(PlayerCallbackEvent::Finished);
+                }
+                continue;
+            }
+            let mut frame = [0_i16; AUDIO_CHANNELS as usize];
+            let sector = pos / SECTOR_SIZE;
+            if let Err(e) = r.read_i16_into::<LE>(&mut frame) {
+                let retries = match failing {
+                    Some((x, retries)) if x == sector => retries,
+                    _ => 0,
+                };
+                if retries < READ_RETRIES {
+                    debug!("Read error at sector {}: {}; retrying", sector, e);
+                    failing = Some((sector, retries + 1));
+                    r.seek(SeekFrom::Start(pos)).unwrap();
                     continue;
                 }
-                let sample = r.read_i16::<LE>().unwrap();
-                let sample = (sample as f64 * volume) as i16;
+                warn!("Read error at sector {}: {}; skipping it", sector, e);
+                failing = None;
+                read_errors.skipped += 1;
+                // on to the first whole frame after the sector
+                let next = frame_start(start_pos, (sector + 1) * SECTOR_SIZE + FRAME_SIZE - 1);
+                r.seek(SeekFrom::Start(next)).unwrap();
+                events.publish(PlayerCallbackEvent::SectorSkipped(sector));
+                events.publish(PlayerCallbackEvent::ReadErrors(read_errors));
+                continue;
+            }
+            if failing.take().is_some_and(|(x, _)| x == sector) {
+                read_errors.retried += 1;
+                events.publish(PlayerCallbackEvent::ReadErrors(read_errors));
+            }
+            effects.process(&mut frame);
+            for sample in frame {
                 sample_tx.send(sample).unwrap();
+            }
 
-                if (pos - start_pos) % (BYTES_ONE_SEC) == 0 {
-                    event_callback!(PlayerCallbackEvent::Progress(((pos - start_pos) / BYTES_ONE_SEC) as u32, song_seconds));
-                }
+            if (pos - start_pos).is_multiple_of(BYTES_ONE_SEC) {
+                events.publish(PlayerCallbackEvent::Progress(
+                    ((pos - start_pos) / BYTES_ONE_SEC) as u32,
+                    song_seconds,
+                ));
             }
         }
     });
-    Ok(PlaybackHandle {
+    PlaybackHandle {
         command_tx: cmd_tx,
-        result_rx,
-    })
+        thread: Arc::new(Mutex::new(Some(thread))),
+        stats,
+    }
+}
+
+/// Spins the drive down while the player is paused, dropping what `reader` read ahead;
+/// it's read again from the disc, which spins it up, once playing on
+fn spin_down_drive(source: &dyn DiscSource, reader: &mut BufReader<SourceReader>) {
+    let pos = reader.stream_position().unwrap();
+    reader.seek(SeekFrom::Start(pos)).unwrap();
+    match source.spin_down() {
+        Ok(()) => info!("Paused for long; drive spun down"),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+        Err(e) => warn!("Failed to spin the drive down: {}", e),
+    }
+}
+
+/// Doubles the read-ahead of `reader`, up to [`READ_AHEAD_MAX`], keeping its position
+fn grow_read_ahead(mut reader: BufReader<SourceReader>) -> BufReader<SourceReader> {
+    let capacity = reader.capacity();
+    if capacity >= READ_AHEAD_MAX {
+        debug!(
+            "Audio underrun; already reading {} KiB ahead",
+            capacity / 1024
+        );
+        return reader;
+    }
+    let pos = reader.stream_position().unwrap();
+    let mut source = reader.into_inner();
+    source.seek(SeekFrom::Start(pos)).unwrap();
+    let capacity = (capacity * 2).min(READ_AHEAD_MAX);
+    info!(
+        "Audio underrun; reading {} KiB ahead from now on",
+        capacity / 1024
+    );
+    BufReader::with_capacity(capacity, source)
 }
@@ -0,0 +1,207 @@
+//! A disc player for other frontends (GTK, web, ...), without the TUI
+//!
+//! [`Player`] holds everything it plays with; there are no globals to set up and
+//! nothing exits the process. What the player does is sent to [`Player::events`].
+//!
+//! Several players can be loaded at once, each with its own audio stream. What they share
+//! is the process's: the minfo program found to work, which [`Player::load`] records in
+//! the config file like the TUI does, and the last audio output opened
+//! ([`AUDIO_OUTPUT`](crate::playback::AUDIO_OUTPUT)).
+//!
+//! All it needs is a [`DiscArgs`]; for the demo disc, it's passed through
+//! [`demo::set_up`](crate::demo::set_up) first.
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::cli::DiscArgs;
+use crate::effects::{AudioEffect, Equalizer};
+use crate::error::Error;
+use crate::playback::{
+    start_playback_thread, EventBus, PlaybackHandle, PlayerCallbackEvent, PlayerCommand,
+    StreamSendWrapper,
+};
+use crate::{minfo, read_meta_info, source, MetaInfo, Track};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerEvent {
+    /// The playing song reached its end; the player is paused until another one is played
+    Finished,
+    Paused(bool),
+    /// Position and duration of the playing song, in seconds
+    Progress {
+        position: u32,
+        duration: u32,
+    },
+    /// A sector couldn't be read and playback continued after it
+    SectorSkipped(u64),
+    /// Sectors read on a retry and skipped so far
+    ReadErrors {
+        retried: u64,
+        skipped: u64,
+    },
+    /// The song looks like data, like `an ISO 9660 file system`, so it's left paused
+    NotAudio(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Song {
+    pub name: String,
+    /// In seconds; zero if its session isn't on the disc
+    pub duration: u32,
+}
+
+/// Turns the player thread's events into [`PlayerEvent`]s
+fn forward_event(event: PlayerCallbackEvent, tx: &Sender<PlayerEvent>, handle: &PlaybackHandle) {
+    let event = match event {
+        PlayerCallbackEvent::Finished => {
+            // if a command is already waiting, the frontend is on it
+            handle.try_send(PlayerCommand::Pause);
+            PlayerEvent::Finished
+        }
+        PlayerCallbackEvent::Paused(p) => PlayerEvent::Paused(p),
+        PlayerCallbackEvent::Progress(position, duration) => {
+            PlayerEvent::Progress { position, duration }
+        }
+        PlayerCallbackEvent::SectorSkipped(sector) => PlayerEvent::SectorSkipped(sector),
+        PlayerCallbackEvent::ReadErrors(x) => PlayerEvent::ReadErrors {
+            retried: x.retried,
+            skipped: x.skipped,
+        },
+        PlayerCallbackEvent::NotAudio(content) => PlayerEvent::NotAudio(content),
+    };
+    // nobody listening is fine
+    let _ = tx.send(event);
+}
+
+pub struct Player {
+    tracks: Vec<Track>,
+    meta_info: MetaInfo,
+    handle: PlaybackHandle,
+    events: Receiver<PlayerEvent>,
+    /// The audio plays as long as it's kept
+    _stream: StreamSendWrapper,
+}
+
+impl Player {
+    /// Reads the disc in `disc.drive` and sets up the audio output; nothing plays yet
+    ///
+    /// The minfo program that worked is recorded in the config file, to be tried first
+    /// next time.
+    pub fn load(disc: &DiscArgs) -> Result<Self, Error> {
+        let tracks = minfo::minfo_track_info(disc)?;
+        let source = source::open_disc(disc, &tracks)?;
+        let meta_info = read_meta_info(disc, &source, &tracks)?;
+
+        let bus = EventBus::new();
+        let (handle, stream) = start_playback_thread(source, bus.clone())?;
+        let (events_tx, events) = channel();
+        let handle_for_events = handle.clone();
+        bus.subscribe(move |event| forward_event(event, &events_tx, &handle_for_events));
+        handle.send(PlayerCommand::Start);
+        handle.send(PlayerCommand::SetSongLevels(meta_info.song_levels(&tracks)));
+        Ok(Self {
+            tracks,
+            meta_info,
+            handle,
+            events,
+            _stream: stream,
+        })
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.meta_info.title.as_deref()
+    }
+
+    /// The songs in the order of the meta info; indices into it are what [`Self::play`]
+    /// takes
+    pub fn songs(&self) -> Vec<Song> {
+        self.meta_info
+            .list
+            .iter()
+            .zip(self.meta_info.song_durations(&self.tracks))
+            .map(|(x, duration)| Song {
+                name: x.name.clone(),
+                duration,
+            })
+            .collect()
+    }
+
+    /// Plays song `idx` from its start; returns `false` if there's no such song on the
+    /// disc, or its track is empty (and would finish at once)
+    pub fn play(&self, idx: usize) -> bool {
+        let Ok(track) = self.meta_info.playable_track(idx, &self.tracks) else {
+            return false;
+        };
+        self.handle.send(PlayerCommand::Goto(track, true));
+        true
+    }
+
+    pub fn pause(&self) {
+        self.handle.send(PlayerCommand::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.handle.send(PlayerCommand::Play);
+    }
+
+    /// Seeks, in seconds from the start of the song
+    pub fn seek(&self, position: f64) {
+        self.handle.send(PlayerCommand::Seek(position));
+    }
+
+    /// Volume level is in 0..1
+    pub fn set_volume(&self, volume: f64) {
+        self.handle
+            .send(PlayerCommand::ChangeVolume(volume.clamp(0.0, 1.0)));
+    }
+
+    /// Turns the bass and treble up or down, in dB; zero for both is flat
+    pub fn set_equalizer(&self, bass_db: f64, treble_db: f64) {
+        let equalizer = Equalizer::new(bass_db, treble_db);
+        self.handle.send(PlayerCommand::SetEqualizer(equalizer));
+    }
+
+    /// Whether songs with a measured gain are played at the same loudness, as they are
+    /// from the start; see [`crate::loudness`]
+    pub fn set_loudness_normalization(&self, enabled: bool) {
+        let levels = match enabled {
+            true => self.meta_info.song_levels(&self.tracks),
+            false => Vec::new(),
+        };
+        self.handle.send(PlayerCommand::SetSongLevels(levels));
+    }
+
+    /// Whether peaks are turned down so they don't clip, like after boosting the bass
+    pub fn set_limiter(&self, enabled: bool) {
+        self.handle.send(PlayerCommand::SetLimiter(enabled));
+    }
+
+    /// Effects of the frontend's, run after the built-in ones, in this order
+    pub fn set_effects(&self, effects: Vec<Box<dyn AudioEffect>>) {
+        self.handle.send(PlayerCommand::SetEffects(effects));
+    }
+
+    /// In seconds from the start of the song; `None` if the player thread doesn't answer
+    pub fn position(&self) -> Option<f64> {
+        self.handle.position()
+    }
+
+    /// `None` if the player thread doesn't answer
+    pub fn is_paused(&self) -> Option<bool> {
+        self.handle.is_paused()
+    }
+
+    /// What the player does, as it happens
+    ///
+    /// Events queue up until they're received, so a frontend not interested in them
+    /// should still drain it now and then.
+    pub fn events(&self) -> &Receiver<PlayerEvent> {
+        &self.events
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.handle.stop_and_wait();
+    }
+}
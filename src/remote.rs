@@ -0,0 +1,110 @@
+//! The interface remote controls (MPRIS, ...) use to drive whichever frontend is playing
+//!
+//! Frontends publish a [`PlayerStatus`] as playback goes on and apply the
+//! [`RemoteCommand`]s remote controls send.
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::mutex_lock;
+use crate::play_mode::RepeatMode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteCommand {
+    Play,
+    Pause,
+    TogglePause,
+    Next,
+    Previous,
+    /// Play a song by its number (starting from one)
+    Goto(usize),
+    /// Seek to a position in seconds
+    Seek(f64),
+    /// Seek by an offset in seconds
+    SeekBy(f64),
+    /// Volume level is in 0..1
+    SetVolume(f64),
+    /// Change the volume by this many volume steps (see `--volume-step`); negative ones
+    /// turn it down
+    StepVolume(i32),
+    Quit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackState {
+    /// Nothing is loaded yet
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SongStatus {
+    pub name: String,
+    /// In seconds
+    pub duration: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PlayerStatus {
+    pub state: PlaybackState,
+    pub disc_title: Option<String>,
+    /// See [`crate::disc_id`]
+    pub disc_id: Option<String>,
+    /// Number (starting from one) of the playing song
+    pub song_number: Option<usize>,
+    /// In seconds
+    pub position: u32,
+    /// Volume level is in 0..1
+    pub volume: f64,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+    pub songs: Vec<SongStatus>,
+}
+
+impl PlayerStatus {
+    pub fn playing_song(&self) -> Option<&SongStatus> {
+        self.songs.get(self.song_number?.checked_sub(1)?)
+    }
+
+    /// Checks there's a song `n` (from one) to [`RemoteCommand::Goto`]; the error is for
+    /// the remote control to show
+    pub fn check_song_number(&self, n: usize) -> Result<(), String> {
+        match self.songs.len() {
+            _ if self.state == PlaybackState::Stopped => Err("No disc is loaded yet".into()),
+            count if !(1..=count).contains(&n) => Err(format!("No song {n}; the disc has {count}")),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub static PLAYER_STATUS: Lazy<Mutex<PlayerStatus>> = Lazy::new(|| Mutex::new(Default::default()));
+
+type CommandChannel = (Mutex<Sender<RemoteCommand>>, Mutex<Receiver<RemoteCommand>>);
+
+static COMMAND_CHANNEL: Lazy<CommandChannel> = Lazy::new(|| {
+    let (tx, rx) = channel();
+    (Mutex::new(tx), Mutex::new(rx))
+});
+
+pub fn send_command(cmd: RemoteCommand) {
+    mutex_lock!(COMMAND_CHANNEL.0).send(cmd).unwrap();
+}
+
+/// Waits for the next command
+pub fn recv_command() -> RemoteCommand {
+    mutex_lock!(COMMAND_CHANNEL.1).recv().unwrap()
+}
+
+pub fn publish_status(status: PlayerStatus) {
+    *mutex_lock!(PLAYER_STATUS) = status;
+}
+
+pub fn player_status() -> PlayerStatus {
+    mutex_lock!(PLAYER_STATUS).clone()
+}
@@ -0,0 +1,138 @@
+//! Where playback of each disc was left off, so it can be resumed on the next start
+//!
+//! Points are kept in `resume.json` next to the config file, keyed by [`crate::disc_id`].
+//! Frontends [`record`] the position as it goes; it's written out every
+//! [`SAVE_INTERVAL`] and by [`save`] on exit.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_dir;
+use crate::mutex_lock;
+
+const RESUME_FILE_NAME: &str = "resume.json";
+/// Recorded points are written to the file at most once per this interval
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+/// Only the newest this many discs are remembered
+const MAX_DISCS: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ResumePoint {
+    /// Index into [`crate::MetaInfo::list`]
+    pub song_idx: usize,
+    /// In seconds
+    pub position: u32,
+    /// Unix timestamp of when this was recorded
+    saved_at: u64,
+}
+
+impl ResumePoint {
+    /// Whether it's worth offering; the very start of a disc isn't
+    pub fn is_worth_resuming(&self) -> bool {
+        self.song_idx != 0 || self.position != 0
+    }
+}
+
+struct Recorder {
+    /// The disc ID and the newest recorded point
+    latest: Option<(String, ResumePoint)>,
+    last_saved_at: Option<Instant>,
+}
+
+static RECORDER: Lazy<Mutex<Recorder>> = Lazy::new(|| {
+    Mutex::new(Recorder {
+        latest: None,
+        last_saved_at: None,
+    })
+});
+
+fn resume_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(RESUME_FILE_NAME))
+}
+
+fn read_points() -> io::Result<HashMap<String, ResumePoint>> {
+    let Some(path) = resume_file_path().filter(|x| x.exists()) else {
+        return Ok(Default::default());
+    };
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(io::Error::other)
+}
+
+fn write_point(disc_id: &str, point: ResumePoint) -> io::Result<()> {
+    let Some(path) = resume_file_path() else {
+        return Ok(());
+    };
+    // a broken file is just started over
+    let mut points = read_points().unwrap_or_default();
+    points.insert(disc_id.into(), point);
+    if points.len() > MAX_DISCS {
+        let mut by_age = points
+            .iter()
+            .map(|(k, v)| (v.saved_at, k.clone()))
+            .collect::<Vec<_>>();
+        by_age.sort();
+        for (_, id) in &by_age[..points.len() - MAX_DISCS] {
+            points.remove(id);
+        }
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &points).map_err(io::Error::other)
+}
+
+/// Where playback of the disc was left off last time
+pub fn load(disc_id: &str) -> Option<ResumePoint> {
+    match read_points() {
+        Ok(mut points) => points.remove(disc_id),
+        Err(e) => {
+            warn!("Failed to read resume points: {}", e);
+            None
+        }
+    }
+}
+
+/// Notes the current playback point, writing it out if [`SAVE_INTERVAL`] has passed
+pub fn record(disc_id: &str, song_idx: usize, position: u32) {
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut guard = mutex_lock!(RECORDER);
+    guard.latest = Some((
+        disc_id.into(),
+        ResumePoint {
+            song_idx,
+            position,
+            saved_at,
+        },
+    ));
+    if guard
+        .last_saved_at
+        .is_none_or(|x| x.elapsed() >= SAVE_INTERVAL)
+    {
+        guard.last_saved_at = Some(Instant::now());
+        drop(guard);
+        save();
+    }
+}
+
+/// Writes out the newest recorded point, if any
+pub fn save() {
+    let latest = mutex_lock!(RECORDER).latest.clone();
+    if let Some((disc_id, point)) = latest {
+        if let Err(e) = write_point(&disc_id, point) {
+            warn!("Failed to write resume point: {}", e);
+        }
+    }
+}
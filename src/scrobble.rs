@@ -0,0 +1,455 @@
+//! Opt-in scrobbling to ListenBrainz and Last.fm, configured in the `scrobble` section of
+//! the config file
+//!
+//! The published [`PlayerStatus`] is watched: a new song is sent as "now playing", and a
+//! song counts as listened once it has played for half its duration or four minutes. Listens
+//! that fail to submit (no network, ...) are queued in `scrobble-queue.json` and retried
+//! after the next successful submission. Requests are made with `curl`.
+
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread::{sleep, spawn};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::config::{config_dir, CONFIG};
+use crate::mutex_lock;
+use crate::remote::{player_status, PlaybackState, PlayerStatus};
+
+const QUEUE_FILE_NAME: &str = "scrobble-queue.json";
+const LISTENBRAINZ_URL: &str = "https://api.listenbrainz.org/1/submit-listens";
+const LASTFM_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+const MEDIA_PLAYER: &str = "Pseudo-CD Player";
+/// The player status is checked once per this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Songs shorter than this are never scrobbled (a Last.fm rule)
+const MIN_SONG_DURATION: u32 = 30;
+/// A song counts as listened after playing this long, even if it's not half through
+pub(crate) const LISTEN_THRESHOLD: u32 = 4 * 60;
+/// Timeout of each request, in seconds
+const REQUEST_TIMEOUT: u32 = 15;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ScrobbleConfig {
+    /// User token from <https://listenbrainz.org/settings/>
+    pub listenbrainz_token: Option<String>,
+    pub lastfm: Option<LastfmConfig>,
+    /// Artist sent with every song, as the meta info seldom has one; the disc title if
+    /// unset
+    pub artist: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastfmConfig {
+    pub api_key: String,
+    pub api_secret: String,
+    /// Session key of an authenticated user (see <https://www.last.fm/api/authentication>)
+    pub session_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Service {
+    Listenbrainz,
+    Lastfm,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Listen {
+    artist: String,
+    track: String,
+    album: Option<String>,
+    track_number: usize,
+    /// In seconds
+    duration: u32,
+    /// Unix timestamp of when the song started
+    started_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct QueuedListen {
+    service: Service,
+    listen: Listen,
+}
+
+/// The song being followed, and how long it has played
+struct Playing {
+    listen: Listen,
+    played: u32,
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Quotes `value` for a curl config file
+fn curl_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("\"{escaped}\"")
+}
+
+/// POSTs `body` to `url`
+///
+/// Everything goes to curl through its config on stdin, so tokens don't show up in the
+/// process list.
+fn post(url: &str, headers: &[String], body: &str) -> io::Result<()> {
+    let mut config = format!(
+        "url = {}\nrequest = \"POST\"\nsilent\nshow-error\nfail\nmax-time = {REQUEST_TIMEOUT}\n\
+         output = \"/dev/null\"\ndata-binary = {}\n",
+        curl_quote(url),
+        curl_quote(body)
+    );
+    for header in headers {
+        config.push_str(&format!("header = {}\n", curl_quote(header)));
+    }
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(config.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "curl failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Percent-encodes `value` for `application/x-www-form-urlencoded`
+fn form_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn listenbrainz_submit(token: &str, listen: &Listen, now_playing: bool) -> io::Result<()> {
+    let mut entry = json!({
+        "track_metadata": {
+            "artist_name": listen.artist,
+            "track_name": listen.track,
+            "additional_info": {
+                "duration": listen.duration,
+                "tracknumber": listen.track_number,
+                "media_player": MEDIA_PLAYER,
+            },
+        },
+    });
+    if let Some(album) = &listen.album {
+        entry["track_metadata"]["release_name"] = json!(album);
+    }
+    if !now_playing {
+        entry["listened_at"] = json!(listen.started_at);
+    }
+    let body = json!({
+        "listen_type": if now_playing { "playing_now" } else { "single" },
+        "payload": [entry],
+    });
+    post(
+        LISTENBRAINZ_URL,
+        &[
+            format!("Authorization: Token {token}"),
+            "Content-Type: application/json".into(),
+        ],
+        &body.to_string(),
+    )
+}
+
+fn lastfm_submit(config: &LastfmConfig, listen: &Listen, now_playing: bool) -> io::Result<()> {
+    let method = if now_playing {
+        "track.updateNowPlaying"
+    } else {
+        "track.scrobble"
+    };
+    let mut params = vec![
+        ("method", method.to_string()),
+        ("artist", listen.artist.clone()),
+        ("track", listen.track.clone()),
+        ("trackNumber", listen.track_number.to_string()),
+        ("duration", listen.duration.to_string()),
+        ("api_key", config.api_key.clone()),
+        ("sk", config.session_key.clone()),
+    ];
+    if let Some(album) = &listen.album {
+        params.push(("album", album.clone()));
+    }
+    if !now_playing {
+        params.push(("timestamp", listen.started_at.to_string()));
+    }
+    // the signature covers the sorted parameters, concatenated, and the secret
+    params.sort();
+    let mut signed = params
+        .iter()
+        .map(|(k, v)| format!("{k}{v}"))
+        .collect::<String>();
+    signed.push_str(&config.api_secret);
+    params.push(("api_sig", md5_hex(signed.as_bytes())));
+    params.push(("format", "json".into()));
+
+    let body = params
+        .iter()
+        .map(|(k, v)| format!("{k}={}", form_encode(v)))
+        .collect::<Vec<_>>()
+        .join("&");
+    post(
+        LASTFM_URL,
+        &["Content-Type: application/x-www-form-urlencoded".into()],
+        &body,
+    )
+}
+
+fn submit(
+    config: &ScrobbleConfig,
+    service: Service,
+    listen: &Listen,
+    now_playing: bool,
+) -> io::Result<()> {
+    match service {
+        Service::Listenbrainz => match &config.listenbrainz_token {
+            Some(token) => listenbrainz_submit(token, listen, now_playing),
+            None => Ok(()),
+        },
+        Service::Lastfm => match &config.lastfm {
+            Some(lastfm) => lastfm_submit(lastfm, listen, now_playing),
+            None => Ok(()),
+        },
+    }
+}
+
+fn services(config: &ScrobbleConfig) -> Vec<Service> {
+    let mut services = Vec::new();
+    if config.listenbrainz_token.is_some() {
+        services.push(Service::Listenbrainz);
+    }
+    if config.lastfm.is_some() {
+        services.push(Service::Lastfm);
+    }
+    services
+}
+
+fn queue_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(QUEUE_FILE_NAME))
+}
+
+fn read_queue() -> io::Result<Vec<QueuedListen>> {
+    let Some(path) = queue_file_path().filter(|x| x.exists()) else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_reader(BufReader::new(File::open(path)?)).map_err(io::Error::other)
+}
+
+fn write_queue(queue: &[QueuedListen]) -> io::Result<()> {
+    let Some(path) = queue_file_path() else {
+        return Ok(());
+    };
+    if queue.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, queue).map_err(io::Error::other)
+}
+
+fn enqueue(item: QueuedListen) {
+    let result = read_queue().and_then(|mut queue| {
+        queue.push(item);
+        write_queue(&queue)
+    });
+    if let Err(e) = result {
+        warn!("Failed to queue a listen: {}", e);
+    }
+}
+
+/// Retries the queued listens, keeping the ones that fail again
+fn flush_queue(config: &ScrobbleConfig) {
+    let queue = match read_queue() {
+        Ok(x) if x.is_empty() => return,
+        Ok(x) => x,
+        Err(e) => {
+            warn!("Failed to read the scrobble queue: {}", e);
+            return;
+        }
+    };
+    let count = queue.len();
+    let remaining = queue
+        .into_iter()
+        .filter(|x| submit(config, x.service, &x.listen, false).is_err())
+        .collect::<Vec<_>>();
+    info!(
+        "Scrobbler: submitted {} of {} queued listens",
+        count - remaining.len(),
+        count
+    );
+    if let Err(e) = write_queue(&remaining) {
+        warn!("Failed to write the scrobble queue: {}", e);
+    }
+}
+
+/// The song playing in `status`, as a listen started now
+fn playing_listen(config: &ScrobbleConfig, status: &PlayerStatus) -> Option<Listen> {
+    let song = status.playing_song()?;
+    let artist = config
+        .artist
+        .clone()
+        .or_else(|| status.disc_title.clone())
+        .unwrap_or_else(|| "Unknown Artist".into());
+    Some(Listen {
+        artist,
+        track: song.name.clone(),
+        album: status.disc_title.clone(),
+        track_number: status.song_number?,
+        duration: song.duration,
+        started_at: unix_time(),
+    })
+}
+
+fn finish_listen(config: &ScrobbleConfig, playing: Playing) {
+    let Playing { listen, played } = playing;
+    let listened = listen.duration >= MIN_SONG_DURATION
+        && (played >= listen.duration / 2 || played >= LISTEN_THRESHOLD);
+    if !listened {
+        return;
+    }
+    let mut submitted = false;
+    for service in services(config) {
+        match submit(config, service, &listen, false) {
+            Ok(_) => {
+                debug!("Scrobbled {:?} to {:?}", listen.track, service);
+                submitted = true;
+            }
+            Err(e) => {
+                warn!("Scrobbling to {:?} failed, queued: {}", service, e);
+                enqueue(QueuedListen {
+                    service,
+                    listen: listen.clone(),
+                });
+            }
+        }
+    }
+    // the network is back, probably
+    if submitted {
+        flush_queue(config);
+    }
+}
+
+fn run(config: ScrobbleConfig) {
+    flush_queue(&config);
+    let mut playing: Option<Playing> = None;
+    let mut last_song = None;
+    loop {
+        sleep(POLL_INTERVAL);
+        let status = player_status();
+        let song = status.song_number.zip(status.playing_song().cloned());
+        if song != last_song {
+            last_song = song;
+            if let Some(x) = playing.take() {
+                finish_listen(&config, x);
+            }
+            if let Some(listen) = playing_listen(&config, &status) {
+                for service in services(&config) {
+                    if let Err(e) = submit(&config, service, &listen, true) {
+                        debug!("Now playing to {:?} failed: {}", service, e);
+                    }
+                }
+                playing = Some(Playing { listen, played: 0 });
+            }
+        }
+        if let Some(x) = playing
+            .as_mut()
+            .filter(|_| status.state == PlaybackState::Playing)
+        {
+            x.played += POLL_INTERVAL.as_secs() as u32;
+        }
+    }
+}
+
+/// Starts the scrobbler thread, if any service is configured
+pub fn start() {
+    let config = mutex_lock!(CONFIG).scrobble.clone();
+    if services(&config).is_empty() {
+        return;
+    }
+    info!("Scrobbler: submitting to {:?}", services(&config));
+    spawn(move || run(config));
+}
+
+/// MD5 of `data` in lowercase hex, for Last.fm request signatures
+pub fn md5_hex(data: &[u8]) -> String {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5,
+        9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10,
+        15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    let constants: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32)
+        .collect();
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64).wrapping_mul(8).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words: Vec<u32> = chunk
+            .chunks(4)
+            .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f
+                .wrapping_add(a)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFTS[i]));
+        }
+        for (s, x) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(x);
+        }
+    }
+    state
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
@@ -0,0 +1,93 @@
+//! Additions to the meta info of each disc kept on this machine, for what can't be
+//! written to a disc once it's burnt, like the chapters `split` found or the gains
+//! `scan-loudness` measured
+//!
+//! They're kept in `sidecar.json` next to the config file, keyed by [`crate::disc_id`],
+//! and applied to the meta info whenever it's read.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::chapters::Chapter;
+use crate::config::config_dir;
+use crate::loudness::SongGain;
+use crate::MetaInfo;
+
+const SIDECAR_FILE_NAME: &str = "sidecar.json";
+
+/// What's kept for a song
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct SongSidecar {
+    /// Sorted by offset; these replace those of the meta info
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+    /// Replaces that of the meta info
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gain: Option<SongGain>,
+}
+
+/// The songs of a disc with something kept, by index into [`MetaInfo::list`]
+pub type DiscSidecar = BTreeMap<usize, SongSidecar>;
+
+fn sidecar_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(SIDECAR_FILE_NAME))
+}
+
+fn read_all() -> io::Result<HashMap<String, DiscSidecar>> {
+    let Some(path) = sidecar_file_path().filter(|x| x.exists()) else {
+        return Ok(Default::default());
+    };
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(io::Error::other)
+}
+
+/// What's kept for the disc
+pub fn load(disc_id: &str) -> DiscSidecar {
+    match read_all() {
+        Ok(mut all) => all.remove(disc_id).unwrap_or_default(),
+        Err(e) => {
+            warn!("Failed to read the sidecar file: {}", e);
+            Default::default()
+        }
+    }
+}
+
+/// Replaces what's kept for the disc with `sidecar`
+pub fn store(disc_id: &str, sidecar: &DiscSidecar) -> io::Result<()> {
+    let Some(path) = sidecar_file_path() else {
+        return Ok(());
+    };
+    let mut all = read_all()?;
+    match sidecar.is_empty() {
+        true => all.remove(disc_id),
+        false => all.insert(disc_id.into(), sidecar.clone()),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &all).map_err(io::Error::other)
+}
+
+/// Applies `sidecar` to `meta_info`; songs it has that aren't in the list are left out
+pub fn apply(meta_info: &mut MetaInfo, sidecar: &DiscSidecar) {
+    for (&idx, song) in sidecar {
+        let Some(info) = meta_info.list.get_mut(idx) else {
+            continue;
+        };
+        if !song.chapters.is_empty() {
+            info.chapters = song.chapters.clone();
+            info.chapters.sort_by_key(|x| x.offset);
+        }
+        if song.gain.is_some() {
+            info.gain = song.gain;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Finding the silent gaps in a song, for discs authored with a whole album or concert
+//! as one continuous session; `split` proposes chapters starting after them
+
+use std::io;
+use std::io::Read;
+use std::time::Duration;
+
+use crate::chapters::Chapter;
+use crate::playback::{AUDIO_SAMPLE_RATE, FRAME_SIZE};
+
+/// Audio is measured in windows of this many milliseconds
+const WINDOW_MS: u64 = 50;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SilenceOptions {
+    /// Windows quieter than this (RMS, in dBFS) are silent
+    pub threshold_db: f64,
+    /// Shorter silences, like pauses within a song, aren't gaps
+    pub min_gap: Duration,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        Self {
+            threshold_db: -50.0,
+            min_gap: Duration::from_secs(2),
+        }
+    }
+}
+
+/// A silence between two stretches of sound, from the start of the song
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// RMS level of 16-bit PCM in dBFS; `-inf` for digital silence
+fn level_db(pcm: &[u8]) -> f64 {
+    let samples = pcm.len() / 2;
+    if samples == 0 {
+        return f64::NEG_INFINITY;
+    }
+    let sum = pcm
+        .chunks_exact(2)
+        .map(|x| i16::from_le_bytes([x[0], x[1]]) as f64)
+        .map(|x| x * x)
+        .sum::<f64>();
+    10.0 * (sum / samples as f64 / (32768.0 * 32768.0)).log10()
+}
+
+/// The gaps in the PCM read from `reader`, in order; silence before the first sound and
+/// after the last isn't one
+pub fn find_gaps(mut reader: impl Read, options: &SilenceOptions) -> io::Result<Vec<Gap>> {
+    let window_size = (AUDIO_SAMPLE_RATE as u64 * WINDOW_MS / 1000 * FRAME_SIZE) as usize;
+    let mut buf = vec![0_u8; window_size];
+    let mut gaps = Vec::new();
+    let mut heard = false;
+    // the window the running silence started at
+    let mut silent_since = None;
+    for window in 0_u64.. {
+        let mut n = 0;
+        while n < window_size {
+            match reader.read(&mut buf[n..]) {
+                Ok(0) => break,
+                Ok(x) => n += x,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if n == 0 {
+            break;
+        }
+        if level_db(&buf[..n]) < options.threshold_db {
+            silent_since.get_or_insert(window);
+            continue;
+        }
+        if let Some(start) = silent_since.take().filter(|_| heard) {
+            let gap = Gap {
+                start: Duration::from_millis(start * WINDOW_MS),
+                end: Duration::from_millis(window * WINDOW_MS),
+            };
+            if gap.end - gap.start >= options.min_gap {
+                gaps.push(gap);
+            }
+        }
+        heard = true;
+    }
+    Ok(gaps)
+}
+
+/// A chapter at the start, then one where the sound comes back after each of `gaps`,
+/// named `Part 1`, `Part 2` and so on
+pub fn proposed_chapters(gaps: &[Gap]) -> Vec<Chapter> {
+    std::iter::once(0)
+        .chain(gaps.iter().map(|x| x.end.as_secs() as u32))
+        .enumerate()
+        .map(|(i, offset)| Chapter {
+            name: format!("Part {}", i + 1),
+            offset,
+        })
+        .collect()
+}
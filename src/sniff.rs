@@ -0,0 +1,76 @@
+//! Telling a track that holds data apart from PCM audio, before it's played
+//!
+//! A meta info mapping a song to the wrong session, like the one with the file system
+//! or the meta info itself, would otherwise be played as loud noise. Audio is anything
+//! that doesn't look like data; the checks are only for what's obviously not audio.
+
+use crate::source::DiscSource;
+use crate::{Track, SECTOR_SIZE};
+
+/// How much of a track's beginning [`non_audio_content`] looks at: up to the UDF volume
+/// recognition sequence, in sectors 16 to 18
+pub const SNIFF_SIZE: usize = 19 * SECTOR_SIZE as usize;
+
+/// How much of a track is looked at for text
+const TEXT_SIZE: usize = 2048;
+/// How long the text has to be, so a few loud samples before silence aren't taken for it
+const MIN_TEXT: usize = 8;
+
+/// Magic numbers at the very beginning of files, as (magic, name)
+const MAGICS: &[(&[u8], &str)] = &[
+    (b"\x1f\x8b", "gzip data"),
+    (b"\x28\xb5\x2f\xfd", "zstd data"),
+    (b"PK\x03\x04", "a ZIP archive"),
+    (b"\x7fELF", "an ELF executable"),
+    (b"fLaC", "FLAC data"),
+    (b"RIFF", "a RIFF file"),
+    (b"\xfd7zXZ\x00", "xz data"),
+    (b"7z\xbc\xaf\x27\x1c", "a 7z archive"),
+];
+
+/// What the beginning of a track, `head`, holds if it's something other than audio,
+/// like `an ISO 9660 file system`
+///
+/// `head` is up to [`SNIFF_SIZE`] bytes; shorter only checks what's there.
+pub fn non_audio_content(head: &[u8]) -> Option<&'static str> {
+    let sector = |n: usize| head.get(n * SECTOR_SIZE as usize..(n + 1) * SECTOR_SIZE as usize);
+    // volume descriptors start at sector 16, each with its identifier at byte 1
+    let identifier = |n: usize| sector(n).map(|x| &x[1..6]);
+    if identifier(16) == Some(b"CD001") {
+        return Some("an ISO 9660 file system");
+    }
+    if (16..19).any(|n| matches!(identifier(n), Some(b"BEA01" | b"NSR02" | b"NSR03"))) {
+        return Some("a UDF file system");
+    }
+    if let Some((_, name)) = MAGICS.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(name);
+    }
+    // text, like the meta info, padded with NULs to the end of its sector
+    let window = &head[..head.len().min(TEXT_SIZE)];
+    let (text, padding) =
+        window.split_at(window.iter().position(|&x| x == 0).unwrap_or(window.len()));
+    let printable = |x: &u8| x.is_ascii_graphic() || x.is_ascii_whitespace();
+    if text.len() >= MIN_TEXT && text.iter().all(printable) && padding.iter().all(|&x| x == 0) {
+        return match text.iter().find(|x| !x.is_ascii_whitespace()) {
+            Some(b'{' | b'[') => Some("JSON text"),
+            _ => Some("text"),
+        };
+    }
+    None
+}
+
+/// Up to [`SNIFF_SIZE`] bytes from where `track` starts, fewer if it's shorter or a read
+/// fails; the player reads them again when playing, so errors are left to it
+pub fn read_head(source: &dyn DiscSource, track: &Track) -> Vec<u8> {
+    let len = SNIFF_SIZE.min(track.end_offset().saturating_sub(track.start_offset()) as usize);
+    let mut head = vec![0_u8; len];
+    let mut read = 0;
+    while read < len {
+        match source.read_at(&mut head[read..], track.start_offset() + read as u64) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => read += n,
+        }
+    }
+    head.truncate(read);
+    head
+}
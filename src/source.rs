@@ -0,0 +1,316 @@
+//! Where the bytes of a disc come from: the drive, an image file of it, or memory (or a
+//! web server or another machine, see [`crate::http_source`] and [`crate::ssh_source`])
+//!
+//! Everything reading the disc goes through a [`DiscSource`]; [`SourceReader`] makes one
+//! a `Read + Seek` for code that wants a stream, like the player.
+
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+#[cfg(unix)]
+use log::debug;
+
+#[cfg(feature = "external")]
+use crate::cdda::WithCdda;
+#[cfg(feature = "external")]
+use crate::cli::DiscArgs;
+#[cfg(feature = "external")]
+use crate::compressed::CompressedImage;
+use crate::error::Error;
+#[cfg(feature = "external")]
+use crate::http_source::{is_url, HttpSource};
+#[cfg(feature = "external")]
+use crate::ssh_source::{is_ssh, SshSource};
+#[cfg(feature = "external")]
+use crate::Track;
+
+pub trait DiscSource: Send + Sync {
+    /// Reads into `buf` from `offset`; returns how many bytes were read, which is zero
+    /// at the end
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+
+    /// Size in bytes
+    fn len(&self) -> u64;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Locks or unlocks the tray of the drive, if it's one; see [`crate::door_lock`]
+    fn lock_door(&self, _locked: bool) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Stops the disc spinning, if it's in a drive; the next read spins it up again
+    fn spin_down(&self) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Opens `path` as a [`BlockDevice`] or an image file, as it is
+///
+/// Image files are mapped into memory ([`MappedImage`]) where they can be, and read
+/// as an [`ImageFile`] otherwise.
+pub fn open(path: &Path) -> Result<Arc<dyn DiscSource>, Error> {
+    let open = || -> io::Result<Arc<dyn DiscSource>> {
+        let file = File::open(path)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            let file_type = file.metadata()?.file_type();
+            if file_type.is_block_device() {
+                return Ok(Arc::new(BlockDevice::new(file)?));
+            }
+            if file_type.is_file() {
+                match MappedImage::new(&file) {
+                    Ok(x) => return Ok(Arc::new(x)),
+                    Err(e) => debug!("Not mapping {}: {}", path.display(), e),
+                }
+            }
+        }
+        Ok(Arc::new(ImageFile::new(file)?))
+    };
+    open().map_err(|e| Error::DriveOpen(path.into(), e))
+}
+
+/// Opens the disc of `disc`, with `tracks`: the image of `--source` if given, or else
+/// the drive
+///
+/// Images compressed in windows ([`CompressedImage`]) are decompressed as they're read,
+/// and the audio tracks of the drive are read with cdparanoia ([`WithCdda`]).
+#[cfg(feature = "external")]
+pub fn open_disc(disc: &DiscArgs, tracks: &[Track]) -> Result<Arc<dyn DiscSource>, Error> {
+    let Some(location) = &disc.source else {
+        let drive = open(&disc.drive)?;
+        let path = disc.drive.display().to_string();
+        return Ok(match WithCdda::new(&path, Arc::clone(&drive), tracks) {
+            Some(x) => Arc::new(x),
+            None => drive,
+        });
+    };
+    let fetch_error = |e| Error::SourceFetch(location.clone(), e);
+    let file: Arc<dyn DiscSource> = if is_url(location) {
+        Arc::new(HttpSource::new(location).map_err(fetch_error)?)
+    } else if is_ssh(location) {
+        Arc::new(SshSource::new(location).map_err(fetch_error)?)
+    } else {
+        open(Path::new(location))?
+    };
+    match CompressedImage::new(location, Arc::clone(&file)) {
+        Ok(Some(x)) => Ok(Arc::new(x)),
+        Ok(None) => Ok(file),
+        Err(e) => Err(fetch_error(e)),
+    }
+}
+
+fn read_file_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileExt;
+        file.read_at(buf, offset)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileExt;
+        file.seek_read(buf, offset)
+    }
+}
+
+/// An optical drive, like `/dev/sr0`
+///
+/// Its size isn't in its metadata, so it's found by seeking to the end on opening.
+pub struct BlockDevice {
+    file: File,
+    len: u64,
+}
+
+impl BlockDevice {
+    pub fn new(mut file: File) -> io::Result<Self> {
+        let len = file.seek(SeekFrom::End(0))?;
+        Ok(Self { file, len })
+    }
+
+    /// A CD-ROM ioctl taking an integer, on the file the disc is read from
+    #[cfg(target_os = "linux")]
+    fn ioctl(&self, request: libc::c_ulong, arg: libc::c_int) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+        match unsafe { libc::ioctl(self.file.as_raw_fd(), request as _, arg) } {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DiscSource for BlockDevice {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        read_file_at(&self.file, buf, offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// With the `CDROM_LOCKDOOR` ioctl, on the drive's file the disc is read from: the
+    /// kernel refuses to unlock a drive opened more than once, but for root
+    #[cfg(target_os = "linux")]
+    fn lock_door(&self, locked: bool) -> io::Result<()> {
+        const CDROM_LOCKDOOR: libc::c_ulong = 0x5329;
+        self.ioctl(CDROM_LOCKDOOR, libc::c_int::from(locked))
+    }
+
+    /// With the `CDROMSTOP` ioctl
+    #[cfg(target_os = "linux")]
+    fn spin_down(&self) -> io::Result<()> {
+        const CDROMSTOP: libc::c_ulong = 0x5308;
+        self.ioctl(CDROMSTOP, 0)
+    }
+}
+
+/// A disc image, like one `dd` made of the drive
+pub struct ImageFile {
+    file: File,
+    len: u64,
+}
+
+impl ImageFile {
+    pub fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len();
+        Ok(Self { file, len })
+    }
+}
+
+impl DiscSource for ImageFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        read_file_at(&self.file, buf, offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+/// A disc image mapped into memory, so reads are copies out of it instead of system
+/// calls, and seeking costs nothing
+///
+/// The file mustn't shrink while it's mapped; reading past its new end would crash.
+#[cfg(unix)]
+pub struct MappedImage {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// the map is read-only, and only unmapped on drop
+#[cfg(unix)]
+unsafe impl Send for MappedImage {}
+#[cfg(unix)]
+unsafe impl Sync for MappedImage {}
+
+#[cfg(unix)]
+impl MappedImage {
+    /// Maps all of `file`, which may be closed afterwards; empty files can't be mapped
+    pub fn new(file: &File) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+        let len = usize::try_from(file.metadata()?.len()).map_err(io::Error::other)?;
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for MappedImage {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+#[cfg(unix)]
+impl DiscSource for MappedImage {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        Ok(read_bytes_at(self.bytes(), buf, offset))
+    }
+
+    fn len(&self) -> u64 {
+        self.len as u64
+    }
+}
+
+pub(crate) fn read_bytes_at(data: &[u8], buf: &mut [u8], offset: u64) -> usize {
+    let data = usize::try_from(offset)
+        .ok()
+        .and_then(|x| data.get(x..))
+        .unwrap_or_default();
+    let n = buf.len().min(data.len());
+    buf[..n].copy_from_slice(&data[..n]);
+    n
+}
+
+/// A disc kept in memory, for tests
+pub struct MemorySource(pub Vec<u8>);
+
+impl DiscSource for MemorySource {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        Ok(read_bytes_at(&self.0, buf, offset))
+    }
+
+    fn len(&self) -> u64 {
+        self.0.len() as u64
+    }
+}
+
+/// Reads a [`DiscSource`] as a stream, from a position of its own
+pub struct SourceReader {
+    source: Arc<dyn DiscSource>,
+    position: u64,
+}
+
+impl SourceReader {
+    pub fn new(source: Arc<dyn DiscSource>) -> Self {
+        Self {
+            source,
+            position: 0,
+        }
+    }
+}
+
+impl Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.source.read_at(buf, self.position)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SourceReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(x) => Some(x),
+            SeekFrom::End(x) => self.source.len().checked_add_signed(x),
+            SeekFrom::Current(x) => self.position.checked_add_signed(x),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seeking before the start")
+        })?;
+        Ok(self.position)
+    }
+}
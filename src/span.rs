@@ -0,0 +1,47 @@
+//! Spans: named stretches of work, like reading the tracks table or a seek of the
+//! player, that are timed and whose names prefix the lines logged inside them
+//!
+//! Only the log file and stderr show the prefixes; `--log-filter span=debug` logs when
+//! each span starts and how long it took.
+
+use std::cell::RefCell;
+use std::time::Instant;
+
+use log::debug;
+
+thread_local! {
+    /// Names of the spans entered on this thread, outermost first
+    static SPANS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A span, left when dropped
+///
+/// Spans are per thread, so this isn't `Send`.
+pub struct Span {
+    start: Instant,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl Span {
+    /// Enters a span named `name`, like `minfo{drive=/dev/sr0}`
+    pub fn enter(name: impl Into<String>) -> Self {
+        SPANS.with(|x| x.borrow_mut().push(name.into()));
+        debug!("started");
+        Self {
+            start: Instant::now(),
+            _not_send: Default::default(),
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        debug!("done in {:?}", self.start.elapsed());
+        SPANS.with(|x| x.borrow_mut().pop());
+    }
+}
+
+/// The spans this thread is in, to put before a message, like `minfo{drive=/dev/sr0}: `
+pub fn current() -> String {
+    SPANS.with(|x| x.borrow().iter().map(|name| format!("{name}: ")).collect())
+}
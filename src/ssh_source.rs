@@ -0,0 +1,145 @@
+//! `--source ssh://[user@]host[:port]/dev/sr0`: the drive (or a disc image) of another
+//! machine, read over SSH
+//!
+//! Blocks are read by running `dd` there, kept by [`crate::block_cache`], and the tracks
+//! table comes from running the minfo program there. `ssh` runs in batch mode, so it
+//! needs a key or an agent rather than a password; with `ControlMaster` set up in the
+//! SSH config, the reads share one connection instead of logging in each time.
+
+use std::io;
+use std::process::{Command, Stdio};
+
+use log::debug;
+
+use crate::block_cache::{BlockCache, Fetch};
+use crate::source::DiscSource;
+use crate::SECTOR_SIZE;
+
+/// Whether `--source` is an `ssh://` URL
+pub fn is_ssh(source: &str) -> bool {
+    source.starts_with("ssh://")
+}
+
+/// `path` quoted for the remote shell
+fn quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+/// The parts of an `ssh://` URL
+struct Remote {
+    url: String,
+    /// `host` or `user@host`
+    destination: String,
+    port: Option<String>,
+    path: String,
+}
+
+impl Remote {
+    fn parse(url: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{url} isn't like ssh://[user@]host[:port]/path"),
+            )
+        };
+        let rest = url.strip_prefix("ssh://").ok_or_else(invalid)?;
+        let (authority, path) = rest.split_once('/').ok_or_else(invalid)?;
+        let (destination, port) = match authority.rsplit_once(':') {
+            Some((x, port)) => (x, Some(port.to_string())),
+            None => (authority, None),
+        };
+        if destination.is_empty() || path.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            url: url.into(),
+            destination: destination.into(),
+            port,
+            path: format!("/{path}"),
+        })
+    }
+
+    /// Runs `command` on the remote machine; returns its stdout
+    fn run(&self, command: &str) -> io::Result<Vec<u8>> {
+        debug!("Running {:?} on {}", command, self.destination);
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "BatchMode=yes"]);
+        if let Some(port) = &self.port {
+            ssh.args(["-p", port]);
+        }
+        let output = ssh
+            .arg("--")
+            .arg(&self.destination)
+            .arg(command)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to run ssh: {e}")))?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "ssh failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+
+    /// The size of the drive, or of the image file
+    fn len(&self) -> io::Result<u64> {
+        let path = quote(&self.path);
+        let output = self.run(&format!(
+            "blockdev --getsize64 {path} 2>/dev/null || stat -L -c %s {path}"
+        ))?;
+        let output = String::from_utf8_lossy(&output);
+        output
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::other(format!("Bad size of {}: {:?}", self.url, output)))
+    }
+}
+
+impl Fetch for Remote {
+    fn fetch(&self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        // blocks start at whole sectors; the count is rounded up for an image's last one
+        let sector = SECTOR_SIZE;
+        self.run(&format!(
+            "dd if={} bs={sector} skip={} count={} status=none",
+            quote(&self.path),
+            offset / sector,
+            len.div_ceil(sector)
+        ))
+    }
+
+    fn location(&self) -> &str {
+        &self.url
+    }
+}
+
+/// A drive, or a disc image, at an `ssh://` URL
+pub struct SshSource(BlockCache<Remote>);
+
+impl SshSource {
+    /// Logs in to learn the size of the drive
+    pub fn new(url: &str) -> io::Result<Self> {
+        let remote = Remote::parse(url)?;
+        let len = remote.len()?;
+        Ok(Self(BlockCache::new(remote, len)))
+    }
+}
+
+impl DiscSource for SshSource {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.0.read_at(buf, offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.0.len()
+    }
+}
+
+/// The `-minfo` output of `program` run on the machine of `url`, for its drive
+pub fn minfo_output(url: &str, program: &str) -> io::Result<String> {
+    let remote = Remote::parse(url)?;
+    let output = remote.run(&format!("{program} dev={} -minfo", quote(&remote.path)))?;
+    Ok(String::from_utf8_lossy(&output).into())
+}
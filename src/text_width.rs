@@ -0,0 +1,70 @@
+//! Laying text out in terminal columns, which isn't counting its characters: CJK
+//! characters and most emoji take two columns, and combining marks none
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Blank columns between the end of a marquee text and its repeated start
+pub const MARQUEE_GAP: usize = 4;
+
+/// Columns `text` takes
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or_default()
+}
+
+/// The start of `text` that fits in `width` columns; a wide character that would only
+/// half fit is left out
+pub fn truncate(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += char_width(c);
+        if used > width {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// `text` with blanks after it up to `width` columns
+pub fn pad(text: &str, width: usize) -> String {
+    format!(
+        "{text}{}",
+        " ".repeat(width.saturating_sub(self::width(text)))
+    )
+}
+
+/// Returns a `width`-column window of `text`, scrolled by `step` characters
+///
+/// Text that fits is returned as is; otherwise it scrolls cyclically, with
+/// [`MARQUEE_GAP`] blanks between the end and the repeated start. A wide character cut by
+/// the end of the window is left blank, so the window is always `width` columns.
+pub fn marquee(text: &str, width: usize, step: usize) -> String {
+    if self::width(text) <= width {
+        return text.into();
+    }
+    let chars = text
+        .chars()
+        .chain(std::iter::repeat_n(' ', MARQUEE_GAP))
+        .collect::<Vec<_>>();
+    // one cycle is wider than the window, as the text alone is
+    let window = chars
+        .iter()
+        .cycle()
+        .skip(step % chars.len())
+        .take(chars.len())
+        .collect::<String>();
+    pad(truncate(&window, width), width)
+}
+
+/// Lays out `left` and `right` on one row of `width` columns, with `right` flushed to the end
+///
+/// `left` is truncated if both don't fit.
+pub fn right_aligned_row(left: &str, right: &str, width: usize) -> String {
+    let right_width = self::width(right);
+    let left = truncate(left, width.saturating_sub(right_width + 1));
+    let padding = width.saturating_sub(self::width(left) + right_width);
+    format!("{left}{}{right}", " ".repeat(padding))
+}
@@ -1,40 +1,165 @@
+use std::collections::BTreeSet;
 use std::io;
-use std::io::stdout;
+use std::io::{stdout, Write};
+use std::path::Path;
 use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex};
-use std::thread::{sleep, spawn};
-use std::time::Duration;
+use std::thread::{scope, sleep, spawn};
+use std::time::{Duration, Instant};
 
-use anyhow::anyhow;
-use log::debug;
-use ratatui::{Frame, Terminal};
+use anyhow::bail;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use ratatui::{Frame, Terminal, TerminalOptions, Viewport};
 use ratatui::backend::Backend;
 use ratatui::crossterm::{event, ExecutableCommand};
-use ratatui::crossterm::event::{Event, KeyCode, KeyModifiers};
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use ratatui::layout::{Alignment, Constraint, Rect};
-use ratatui::prelude::{Color, Layout, Modifier, Style};
-use ratatui::widgets::{Block, LineGauge, List, ListItem, Padding, Paragraph};
-use yeet_ops::yeet;
+use ratatui::prelude::{Layout, Modifier, Style};
+use ratatui::widgets::{Block, Clear, List, ListItem, Padding, Paragraph};
 
-use crate::{extract_meta_info, MetaInfo, minfo, mutex_lock, SongInfo, Track};
-use crate::cli::ARGS;
-use crate::minfo::minfo_cli;
+use crate::{
+    disc_id, external, failure, format_duration, kill_running_commands, minfo, mutex_lock,
+    read_meta_info, MetaInfo, Track,
+};
+use crate::context::AppContext;
+use crate::disc_wait;
+use crate::door_lock;
+use crate::on_finish;
+use crate::error::Error;
+use crate::integrity::check_song;
+use crate::source;
+use crate::source::DiscSource;
+use crate::config::{seek_step, song_levels, spin_down_after, update_config, volume_step, CONFIG};
+use crate::palette::Palette;
+use crate::play_mode::{
+    initial_favorites_only, initial_modes, initial_wrap_around, playlist_finished, shuffled,
+    skipped_song, song_after_end, RepeatMode,
+};
+use crate::remote;
+use crate::resume;
+use crate::resume::ResumePoint;
+use crate::alarm;
+use crate::bookmarks;
+use crate::favorites;
+use crate::bookmarks::Bookmark;
+use crate::chapters::{chapter_at, skip_target, Chapter};
+use crate::clipboard;
+use crate::clipboard::Copied;
+use crate::text_width::{marquee, pad, right_aligned_row, width};
+use crate::remote::{PlaybackState, PlayerStatus, RemoteCommand, SongStatus};
 use crate::playback::{
-    AUDIO_STREAM, duration_from_bytes, PLAYBACK_HANDLE,
-    PlayerCallbackEvent, PlayerCommand, PlayerResult, set_global_playback_handle, start_global_playback_thread,
+    AUDIO_STREAM, create_audio_stream, duration_from_bytes, playback_handle, EventBus,
+    MISSING_AUDIO_DEVICE, PlayerCallbackEvent, PlayerCommand, ReadErrorStats,
+    set_global_playback_handle, start_player_thread, stepped_volume, StreamSendWrapper,
+    StreamStats,
 };
 
+use gauge::ProgressGauge;
+use pages::Page;
+use screen_reader::Announced;
+
+#[cfg(unix)]
+pub mod attach;
+mod gauge;
+mod pages;
+mod screen_reader;
+
 const TUI_APP_TITLE: &str = "Pseudo-CD Player";
 
+/// Set when quitting; the background initialization thread stops at its next step
+static BACKGROUND_CANCELLED: AtomicBool = AtomicBool::new(false);
+/// Whether [`set_up_tui`] entered the alternate screen, which needs to be left on clean-up
+static IN_ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+/// What [`Tui::tick`] waits for, sent by the threads forwarding them
+static TUI_EVENTS: Lazy<TuiEventChannel> = Lazy::new(|| {
+    let (tx, rx) = channel();
+    (Mutex::new(tx), Mutex::new(rx))
+});
+/// The terminal is waited on in slices this long; see [`forward_terminal_events`]
+const TERMINAL_POLL_SLICE: Duration = Duration::from_millis(100);
+/// The second quit key press must come within this duration
+const QUIT_CONFIRM_TIMEOUT: Duration = Duration::from_secs(1);
+/// Marquee text scrolls one character per this interval
+const MARQUEE_STEP_INTERVAL: Duration = Duration::from_millis(300);
+/// Marks the playing song in the list
+const PLAYING_INDICATOR: char = '▶';
+/// Marks the selected song in the list
+const SELECTED_INDICATOR: char = '›';
+/// Marks favorite songs in the list
+const FAVORITE_INDICATOR: char = '*';
+/// How long a toast message stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+/// The highest [`crate::config::Config::max_fps`] taken
+const MAX_FPS_LIMIT: u32 = 60;
+/// Even if nothing changes, the terminal is redrawn once per this interval
+const REDRAW_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// Volume steps (see [`volume_step`]) of a single `<`/`>` (Shift + `,`/`.`) press
+const VOLUME_COARSE_STEPS: i32 = 5;
+/// Seek key presses no further apart than this are key repeats, i.e. the key is held
+const SCRUB_REPEAT_WINDOW: Duration = Duration::from_millis(200);
+/// While scrubbing, the player is told to seek at most once per this interval
+const SCRUB_SEEK_INTERVAL: Duration = Duration::from_millis(200);
+/// Scrubbing speeds, in seconds of audio per second held
+const SCRUB_INITIAL_SPEED: f64 = 20.0;
+const SCRUB_MAX_SPEED: f64 = 120.0;
+/// Scrubbing speed gain per second held
+const SCRUB_ACCELERATION: f64 = 40.0;
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum AppUiState {
     /// Shows a starting centered text, indicating initialization
     Starting,
     Player,
     Error,
+    /// The meta info lists no songs; there's nothing to play
+    NoSongs,
+}
+
+/// Shown on [`AppUiState::NoSongs`]
+const NO_SONGS_LINES: [&str; 2] = [
+    "No songs found on this disc",
+    "Its meta info lists none that can be played. Press q to quit.",
+];
+
+enum TuiEvent {
+    Terminal(io::Result<Event>),
+    Remote(RemoteCommand),
+    /// The playing song reached its end; see [`Tui::song_finished`]
+    Finished,
+    /// Another thread changed what's shown (see [`UiData::mark_dirty`])
+    Redraw,
+}
+
+type TuiEventChannel = (Mutex<Sender<TuiEvent>>, Mutex<Receiver<TuiEvent>>);
+
+fn send_tui_event(event: TuiEvent) {
+    let _ = mutex_lock!(TUI_EVENTS.0).send(event);
+}
+
+/// Sends the terminal's events to [`Tui::tick`]
+///
+/// crossterm holds its reader while waiting for input, and the `--mini` viewport needs it
+/// to ask the terminal for the cursor position, so this waits in short slices rather than
+/// for good.
+fn forward_terminal_events() {
+    loop {
+        let event = match event::poll(TERMINAL_POLL_SLICE) {
+            Ok(true) => event::read(),
+            Ok(false) => continue,
+            Err(e) => Err(e),
+        };
+        let failed = event.is_err();
+        send_tui_event(TuiEvent::Terminal(event));
+        if failed {
+            return;
+        }
+    }
 }
 
 pub struct Tui<B: Backend> {
@@ -42,24 +167,134 @@ pub struct Tui<B: Backend> {
     should_quit: bool,
     ui_data: Arc<Mutex<UiData>>,
     bg_thread_started: bool,
+    scrub: Option<ScrubState>,
+    last_draw_at: Option<Instant>,
+    /// Frames are drawn at least this far apart; see [`crate::config::Config::max_fps`]
+    frame_interval: Duration,
+}
+
+/// Holding a seek key "scrubs": the target position moves with a growing speed
+/// and is sent to the player periodically, instead of one round trip per key repeat.
+#[derive(Clone, Copy, Debug)]
+struct ScrubState {
+    /// -1.0 for backwards, 1.0 for forward
+    direction: f64,
+    /// Target position in seconds
+    target: f64,
+    /// In seconds of audio per second held
+    speed: f64,
+    last_key_at: Instant,
+    last_seek_at: Instant,
+}
+
+/// What's done before playing; the ones not depending on each other run at once
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StartStep {
+    MinfoVersion,
+    Tracks,
+    MetaInfo,
+    Audio,
+}
+
+impl StartStep {
+    const ALL: [StartStep; 4] = [
+        StartStep::MinfoVersion,
+        StartStep::Tracks,
+        StartStep::MetaInfo,
+        StartStep::Audio,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            StartStep::MinfoVersion => "Checking the minfo program",
+            StartStep::Tracks => "Fetching tracks info",
+            StartStep::MetaInfo => "Extracting meta info",
+            StartStep::Audio => "Initializing audio sink",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StepState {
+    Waiting,
+    Running,
+    Done,
+    Failed,
+}
+
+impl StepState {
+    fn name(&self) -> &'static str {
+        match self {
+            StepState::Waiting => "waiting",
+            StepState::Running => "running",
+            StepState::Done => "done",
+            StepState::Failed => "failed",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 struct StartingUiData {
-    info_text: String,
+    /// Labels and states, in the order of [`StartStep::ALL`]
+    steps: [(String, StepState); 4],
+}
+
+impl Default for StartingUiData {
+    fn default() -> Self {
+        Self {
+            steps: StartStep::ALL.map(|x| (x.label().into(), StepState::Waiting)),
+        }
+    }
 }
 
 impl StartingUiData {
+    fn set_step(&mut self, step: StartStep, state: StepState) {
+        self.steps[step as usize].1 = state;
+    }
+
+    /// A line for each step, like `[x] Fetching tracks info`
+    fn lines(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .map(|(label, state)| {
+                let mark = match state {
+                    StepState::Waiting => ' ',
+                    StepState::Running => '~',
+                    StepState::Done => 'x',
+                    StepState::Failed => '!',
+                };
+                format!("[{mark}] {label}")
+            })
+            .collect()
+    }
+
+    /// The steps running, for a single line
+    fn info_text(&self) -> String {
+        let running = self
+            .steps
+            .iter()
+            .filter(|x| x.1 == StepState::Running)
+            .map(|x| &*x.0)
+            .collect::<Vec<_>>();
+        match running.is_empty() {
+            true => "Initializing...".into(),
+            false => format!("{}...", running.join(", ")),
+        }
+    }
+
     fn draw_to(&self, frame: &mut Frame, rect: Rect) {
-        let padding = Padding::new(
-            0,
-            0,
-            (rect.height - 1/* the center text takes up one line */) / 2,
-            0,
-        );
+        let lines = self.lines();
+        let padding = Padding::new(0, 0, rect.height.saturating_sub(lines.len() as u16) / 2, 0);
+        // padded to the same width so the centered lines still line up
+        let lines_width = lines.iter().map(|x| width(x)).max().unwrap_or_default();
+        let text = lines
+            .iter()
+            .map(|x| pad(x, lines_width))
+            .collect::<Vec<_>>()
+            .join("\n");
 
         frame.render_widget(
-            Paragraph::new(&*self.info_text)
+            Paragraph::new(text)
                 .block(Block::bordered().padding(padding))
                 .alignment(Alignment::Center),
             frame.size(),
@@ -82,15 +317,103 @@ impl PlayerState {
     }
 }
 
+/// Order of the displayed song list
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SortOrder {
+    /// As listed in the meta info
+    Disc,
+    Name,
+    Duration,
+}
+
+impl SortOrder {
+    const ALL: [SortOrder; 3] = [SortOrder::Disc, SortOrder::Name, SortOrder::Duration];
+
+    fn name(&self) -> &'static str {
+        match self {
+            SortOrder::Disc => "Disc order",
+            SortOrder::Name => "Name",
+            SortOrder::Duration => "Duration",
+        }
+    }
+}
+
+/// `selected_song_idx` and `playing_song_idx` are always indices into [`MetaInfo::list`];
+/// `display_order` only decides where each song is shown and what "next" and "previous" are.
+/// How a song compares with its hash, once checked with `c`; see [`crate::integrity`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SongCheck {
+    /// Not checked yet, or with no hash to check against
+    Unchecked,
+    Checking,
+    Intact,
+    Failed,
+}
+
+impl SongCheck {
+    /// Shown in a column of the song list
+    fn mark(&self) -> char {
+        match self {
+            SongCheck::Unchecked => ' ',
+            SongCheck::Checking => '…',
+            SongCheck::Intact => '✓',
+            SongCheck::Failed => '✗',
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct PlayerUiData {
     player_state: PlayerState,
     selected_song_idx: usize,
     playing_song_idx: usize,
     meta_info: Arc<MetaInfo>,
+    /// Durations in seconds, one for each song in [`MetaInfo::list`]
+    song_durations: Vec<u32>,
     current_position: u32,
     total_duration: u32,
     volume: f64,
+    /// Show `-remaining/total` instead of `elapsed/total` on the gauge
+    show_remaining_time: bool,
+    /// Time of the first quit key press, waiting for a confirming one
+    quit_requested_at: Option<Instant>,
+    /// Marquee scrolling offsets are counted from this
+    marquee_epoch: Instant,
+    /// Digits typed so far for jumping to a song by its number
+    pending_song_number: String,
+    sort_order: SortOrder,
+    /// Song indices in displayed order; only the favorites with [`Self::favorites_only`]
+    display_order: Vec<usize>,
+    /// The highlighted entry in [`SortOrder::ALL`] while the sort menu is open
+    sort_menu: Option<usize>,
+    /// A transient message, and when it was shown
+    toast: Option<(String, Instant)>,
+    palette: Palette,
+    /// Where this disc was left off last time, while asking whether to resume there
+    resume_offer: Option<ResumePoint>,
+    shuffle: bool,
+    /// Song indices in play order while shuffling, starting from the song played when
+    /// shuffle was turned on
+    shuffle_order: Vec<usize>,
+    repeat: RepeatMode,
+    /// Whether next and previous wrap around at the ends of the play order
+    wrap_around: bool,
+    read_errors: ReadErrorStats,
+    /// One for each song in [`MetaInfo::list`]; empty if the meta info has no hashes
+    song_checks: Vec<SongCheck>,
+    /// Of this disc, in disc order (see [`crate::bookmarks`])
+    bookmarks: Vec<Bookmark>,
+    /// Index into [`Self::bookmarks`] on [`Page::Bookmarks`]
+    selected_bookmark: usize,
+    /// A bookmark of the playing position while its name is typed in
+    bookmark_input: Option<Bookmark>,
+    /// Song indices marked with `*` (see [`crate::favorites`])
+    favorites: BTreeSet<usize>,
+    /// List and play only [`Self::favorites`], if there are any
+    favorites_only: bool,
+    /// Songs in sessions the disc hasn't, or in empty tracks (see
+    /// [`MetaInfo::playable_track`]); they aren't listed
+    unplayable_songs: BTreeSet<usize>,
 }
 
 impl PlayerUiData {
@@ -98,49 +421,351 @@ impl PlayerUiData {
         &self.meta_info.list[idx].name
     }
 
-    fn next_song_idx(&self) -> usize {
-        let idx = self.playing_song_idx;
-        if idx == self.meta_info.list.len() - 1 {
-            0
-        } else {
-            idx + 1
+    /// Like `3 intact, 1 failed, 2 not checked`; `None` without hashes
+    fn song_checks_summary(&self) -> Option<String> {
+        if self.song_checks.is_empty() {
+            return None;
         }
+        let with_hashes = self.meta_info.list.iter().map(|x| x.hash.is_some());
+        let counts = [
+            (SongCheck::Intact, "intact"),
+            (SongCheck::Failed, "failed"),
+            (SongCheck::Checking, "checking"),
+            (SongCheck::Unchecked, "not checked"),
+        ]
+        .map(|(check, name)| {
+            let count = self
+                .song_checks
+                .iter()
+                .zip(with_hashes.clone())
+                .filter(|&(&x, hash)| hash && x == check)
+                .count();
+            (count, name)
+        });
+        let parts = counts
+            .iter()
+            .filter(|(count, _)| *count != 0)
+            .map(|(count, name)| format!("{count} {name}"))
+            .collect::<Vec<_>>();
+        Some(parts.join(", "))
     }
 
-    fn draw_to(&self, frame: &mut Frame, rect: Rect) {
+    fn marquee_step(&self) -> usize {
+        (self.marquee_epoch.elapsed().as_millis() / MARQUEE_STEP_INTERVAL.as_millis()) as usize
+    }
+
+    /// Shows `message` for [`TOAST_DURATION`], replacing any previous one
+    fn show_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some((message.into(), Instant::now()));
+    }
+
+    fn active_toast(&self) -> Option<&str> {
+        self.toast
+            .as_ref()
+            .filter(|(_, shown_at)| shown_at.elapsed() < TOAST_DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Like `Resume at 2: <name>, 01:23? (y/n)`
+    fn resume_offer_text(&self) -> Option<String> {
+        let point = self.resume_offer?;
+        Some(format!(
+            "Resume at {}: {}, {}? (y/n)",
+            point.song_idx + 1,
+            self.song_name_by_song_idx(point.song_idx),
+            format_duration(point.position)
+        ))
+    }
+
+    /// Like `Bookmark 3 01:23 as (Enter to save, Esc to cancel): <typed name>`
+    fn bookmark_input_text(&self) -> Option<String> {
+        let input = self.bookmark_input.as_ref()?;
+        Some(format!(
+            "Bookmark {} as (Enter to save, Esc to cancel): {}",
+            Bookmark::default_name(input.song_idx, input.position),
+            input.name
+        ))
+    }
+
+    /// The chapter of the playing song at the playing position, and its index
+    fn playing_chapter(&self) -> Option<(usize, &Chapter)> {
+        let chapters = &self.meta_info.list.get(self.playing_song_idx)?.chapters;
+        let i = chapter_at(chapters, self.current_position)?;
+        Some((i, &chapters[i]))
+    }
+
+    /// Like `Chapter 2/5: Second track`
+    fn chapter_text(&self) -> Option<String> {
+        let (i, chapter) = self.playing_chapter()?;
+        let count = self.meta_info.list[self.playing_song_idx].chapters.len();
+        Some(format!("Chapter {}/{count}: {}", i + 1, chapter.name))
+    }
+
+    fn quit_pending(&self) -> bool {
+        self.quit_requested_at
+            .is_some_and(|x| x.elapsed() < QUIT_CONFIRM_TIMEOUT)
+    }
+
+    /// Song indices in play order: [`Self::shuffle_order`] while shuffling, or else
+    /// the displayed order
+    fn play_order(&self) -> &[usize] {
+        match self.shuffle {
+            true => &self.shuffle_order,
+            false => &self.display_order,
+        }
+    }
+
+    fn play_position(&self, song_idx: usize) -> usize {
+        self.play_order()
+            .iter()
+            .position(|&x| x == song_idx)
+            .unwrap_or_default()
+    }
+
+    /// The song played after `song_idx` when skipping forward, wrapping around
+    fn play_next(&self, song_idx: usize) -> usize {
+        let order = self.play_order();
+        order[(self.play_position(song_idx) + 1) % order.len()]
+    }
+
+    /// The song next (`delta` 1) or previous (-1) goes to from the playing one; at an end of
+    /// the play order without [`Self::wrap_around`], none, which is told
+    fn skip_song(&mut self, delta: isize) -> Option<usize> {
+        let order = self.play_order();
+        let song = skipped_song(order, self.playing_song_idx, delta, self.wrap_around);
+        if song.is_none() {
+            self.show_toast(match delta > 0 {
+                true => "Already at the last song",
+                false => "Already at the first song",
+            });
+        }
+        song
+    }
+
+    /// The song to go to when the playing one ends, and whether to play it
+    /// (after the last song with [`RepeatMode::Off`], the first one is only paused at)
+    fn song_after_end(&self) -> (usize, bool) {
+        song_after_end(self.play_order(), self.playing_song_idx, self.repeat)
+    }
+
+    /// Whether the playlist has played through once the playing song ends
+    fn playlist_finished(&self) -> bool {
+        playlist_finished(self.play_order(), self.playing_song_idx, self.repeat)
+    }
+
+    /// Turns shuffle on or off; a new random order starts from the playing song
+    fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        self.shuffle_order = match shuffle {
+            true => shuffled(self.display_order.clone(), Some(self.playing_song_idx)),
+            false => Vec::new(),
+        };
+    }
+
+    /// Lists the songs again after [`Self::favorites`] or [`Self::favorites_only`]
+    /// changed, keeping the selection where it was and the shuffled order of the songs
+    /// still listed
+    fn refresh_listing(&mut self) {
+        let old_position = self.display_position(self.selected_song_idx);
+        self.set_sort_order(self.sort_order);
+        if !self.display_order.contains(&self.selected_song_idx) {
+            let position = old_position.min(self.display_order.len().saturating_sub(1));
+            if let Some(&x) = self.display_order.get(position) {
+                self.selected_song_idx = x;
+            }
+        }
+        if self.shuffle {
+            let listed = &self.display_order;
+            self.shuffle_order.retain(|x| listed.contains(x));
+            let new = shuffled(
+                listed
+                    .iter()
+                    .copied()
+                    .filter(|x| !self.shuffle_order.contains(x))
+                    .collect(),
+                None,
+            );
+            self.shuffle_order.extend(new);
+        }
+    }
+
+    /// Position of the song in the displayed list
+    fn display_position(&self, song_idx: usize) -> usize {
+        self.display_order
+            .iter()
+            .position(|&x| x == song_idx)
+            .unwrap_or_default()
+    }
+
+    /// The song displayed after `song_idx`, wrapping around
+    fn display_next(&self, song_idx: usize) -> usize {
+        if self.display_order.is_empty() {
+            return song_idx;
+        }
+        let pos = self.display_position(song_idx);
+        self.display_order[(pos + 1) % self.display_order.len()]
+    }
+
+    /// The song displayed before `song_idx`, wrapping around
+    fn display_prev(&self, song_idx: usize) -> usize {
+        let len = self.display_order.len();
+        if len == 0 {
+            return song_idx;
+        }
+        let pos = self.display_position(song_idx);
+        self.display_order[(pos + len - 1) % len]
+    }
+
+    /// Moves the selection, and with it the list, to the playing song
+    fn select_playing(&mut self) {
+        if !self.display_order.contains(&self.playing_song_idx) {
+            self.show_toast("The playing song isn't listed");
+            return;
+        }
+        self.selected_song_idx = self.playing_song_idx;
+    }
+
+    fn set_sort_order(&mut self, order: SortOrder) {
+        self.sort_order = order;
+        let mut display_order = favorites::playable_songs(
+            self.meta_info.list.len(),
+            &self.favorites,
+            self.favorites_only,
+        );
+        display_order.retain(|x| !self.unplayable_songs.contains(x));
+        match order {
+            SortOrder::Disc => {}
+            SortOrder::Name => {
+                display_order.sort_by_cached_key(|&i| self.meta_info.list[i].name.to_lowercase())
+            }
+            SortOrder::Duration => display_order
+                .sort_by_key(|&i| self.song_durations.get(i).copied().unwrap_or_default()),
+        }
+        self.display_order = display_order;
+    }
+
+    /// Handles a key while the sort menu is open
+    fn sort_menu_key(&mut self, code: KeyCode) {
+        let Some(highlighted) = self.sort_menu else {
+            return;
+        };
+        let count = SortOrder::ALL.len();
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => self.sort_menu = Some((highlighted + 1) % count),
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.sort_menu = Some((highlighted + count - 1) % count)
+            }
+            KeyCode::Enter => {
+                self.set_sort_order(SortOrder::ALL[highlighted]);
+                self.sort_menu = None;
+                self.show_toast(format!("Sorted by: {}", self.sort_order.name()));
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                if let Some(&order) = SortOrder::ALL.get(c as usize - '1' as usize) {
+                    self.set_sort_order(order);
+                    self.sort_menu = None;
+                    self.show_toast(format!("Sorted by: {}", order.name()));
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('s') => self.sort_menu = None,
+            _ => {}
+        }
+    }
+
+    /// Returns whether anything drawn is animated (scrolling text, timed popups)
+    /// and thus needs redrawing even without state changes
+    fn draw_to(&self, frame: &mut Frame, rect: Rect) -> bool {
+        let mut animating = false;
+        let has_chapters = self
+            .meta_info
+            .list
+            .get(self.playing_song_idx)
+            .is_some_and(|x| !x.chapters.is_empty());
         let layout = Layout::vertical([
+            Constraint::Length(1),
             Constraint::Min(0),
             Constraint::Length(1),
             Constraint::Length(1),
+            Constraint::Length(has_chapters as u16),
         ])
         .split(rect);
+        let (header_rect, layout) = (layout[0], &layout[1..]);
 
+        let total_duration: u32 = self.song_durations.iter().sum();
+        frame.render_widget(
+            Block::new()
+                .title(format!(
+                    "{} songs, {}",
+                    self.meta_info.list.len(),
+                    format_duration(total_duration)
+                ))
+                .title_alignment(Alignment::Right),
+            header_rect,
+        );
+        let mut modes = play_modes_text(self.shuffle, self.repeat);
+        if self.favorites_only && !self.favorites.is_empty() {
+            modes = match modes.is_empty() {
+                true => String::from("Favorites only"),
+                false => format!("Favorites only, {modes}"),
+            };
+        }
+        frame.render_widget(
+            Block::new().title(modes).title_alignment(Alignment::Left),
+            header_rect,
+        );
+        if let Some(summary) = self.read_errors.summary() {
+            frame.render_widget(
+                Block::new()
+                    .title(summary)
+                    .title_alignment(Alignment::Center),
+                header_rect,
+            );
+        }
+
+        let styles = self.palette.styles();
         let list_height = layout[0].height;
-        let list_items = self.meta_info.list.iter().enumerate().map(|(i, x)| {
-            let item_text = format!("{}: {}", i + 1, x.name);
+        let list_width = layout[0].width as usize;
+        let list_items = self.display_order.iter().map(|&i| {
+            let x = &self.meta_info.list[i];
+            let duration = format_duration(self.song_durations.get(i).copied().unwrap_or_default());
+            // state indicators in their own column, so they're visible without colors
+            let playing_mark = match self.playing_song_idx == i {
+                true => PLAYING_INDICATOR,
+                false => ' ',
+            };
+            let selected_mark = match self.selected_song_idx == i {
+                true => SELECTED_INDICATOR,
+                false => ' ',
+            };
+            let check_mark = self.song_checks.get(i).map(SongCheck::mark);
+            // only once there are favorites, like the check marks once there are hashes
+            let favorite_mark = match self.favorites.contains(&i) {
+                true => Some(FAVORITE_INDICATOR),
+                false => (!self.favorites.is_empty()).then_some(' '),
+            };
+            let indicators = format!(
+                "{playing_mark}{selected_mark}{}{} ",
+                favorite_mark.map(String::from).unwrap_or_default(),
+                check_mark.map(String::from).unwrap_or_default()
+            );
+            let mut left = format!("{}: {}", i + 1, x.name);
+            if self.selected_song_idx == i {
+                let left_width =
+                    list_width.saturating_sub(width(&indicators) + width(&duration) + 1);
+                animating |= width(&left) > left_width;
+                left = marquee(&left, left_width, self.marquee_step());
+            }
+            let item_text = right_aligned_row(&(indicators + &left), &duration, list_width);
             let mut item = ListItem::new(item_text);
-            // TODO: not consider terminal themes other than black-background-white-text?
             if self.selected_song_idx == i {
-                let style = Style {
-                    bg: Some(Color::LightBlue),
-                    fg: Some(Color::White),
-                    add_modifier: Modifier::BOLD,
-                    ..Default::default()
-                };
-                item = item.style(style);
+                item = item.style(styles.selected_row);
             }
             if self.playing_song_idx == i {
-                let style = Style {
-                    bg: Some(Color::White),
-                    fg: Some(Color::Black),
-                    add_modifier: Modifier::BOLD,
-                    ..Default::default()
-                };
-                item = item.style(style);
+                item = item.style(styles.playing_row);
             }
             item
         });
-        let page_no = self.selected_song_idx / list_height as usize;
+        let page_no = self.display_position(self.selected_song_idx) / list_height as usize;
         let list = List::new(list_items.skip(page_no * list_height as usize));
         frame.render_widget(list, layout[0]);
 
@@ -148,9 +773,16 @@ impl PlayerUiData {
             PlayerState::Playing => "Playing: ",
             PlayerState::Paused => "Paused: ",
         };
+        let volume_title = format!("Volume: {}", volume_percent(self.volume));
+        // keep the centered title clear of the right-aligned volume on both sides
+        let bottom_title_width = (layout[1].width as usize)
+            .saturating_sub(2 * (width(&volume_title) + 1))
+            .saturating_sub(width(state_str));
+        let playing_song_name = self.song_name_by_song_idx(self.playing_song_idx);
+        animating |= width(playing_song_name) > bottom_title_width;
         let bottom_title = format!(
             "{state_str}{}",
-            self.song_name_by_song_idx(self.playing_song_idx)
+            marquee(playing_song_name, bottom_title_width, self.marquee_step())
         );
 
         frame.render_widget(
@@ -162,64 +794,207 @@ impl PlayerUiData {
 
         frame.render_widget(
             Block::new()
-                .title(format!("Volume: {}", (self.volume * 100.0) as u8))
+                .title(volume_title)
                 .title_alignment(Alignment::Right),
             layout[1],
         );
 
-        fn coerce(ratio: f64) -> f64 {
-            match ratio {
-                _ if !ratio.is_finite() => 0.0,
-                _ if ratio < 0.0 => 0.0,
-                _ if ratio > 1.0 => 1.0,
-                _ => ratio,
-            }
+        if !self.pending_song_number.is_empty() {
+            frame.render_widget(
+                Block::new()
+                    .title(format!("Go to: {}", self.pending_song_number))
+                    .title_alignment(Alignment::Left),
+                layout[1],
+            );
         }
 
+        let played = self.current_position as f64 / self.total_duration as f64;
+        let read_ahead = playback_handle()
+            .map_or(0, |x| x.stats().read_ahead.load(Ordering::Relaxed));
+        let read_ahead = duration_from_bytes(read_ahead);
         frame.render_widget(
-            LineGauge::default()
-                .filled_style(Style::default().fg(Color::Blue))
-                .unfilled_style(Style::default().fg(Color::Gray))
-                .label(duration_string((
-                    self.current_position,
-                    self.total_duration,
-                )))
-                .ratio(coerce(
-                    self.current_position as f64 / self.total_duration as f64,
-                )),
+            ProgressGauge {
+                label: duration_string(
+                    (self.current_position, self.total_duration),
+                    self.show_remaining_time,
+                ),
+                played,
+                buffered: played + read_ahead / self.total_duration as f64,
+                styles: &styles,
+            },
             layout[2],
         );
+        if let Some(text) = self.chapter_text() {
+            frame.render_widget(Paragraph::new(text).alignment(Alignment::Center), layout[3]);
+        }
+        animating
     }
-}
 
-fn duration_string((position, total): (u32, u32)) -> String {
-    let pad_zero = |num: u32| {
-        if num < 10 {
-            format!("0{num}")
-        } else {
-            format!("{num}")
+    /// The one-line UI of `--mini`; returns whether anything is animated
+    fn draw_mini_to(&self, frame: &mut Frame, rect: Rect) -> bool {
+        if let Some(text) = self.resume_offer_text().or(self.bookmark_input_text()) {
+            frame.render_widget(Paragraph::new(text), rect);
+            return false;
         }
-    };
-    let make_string = |num: u32| format!("{}:{}", pad_zero(num / 60), pad_zero(num % 60));
-    format!("{}/{}", make_string(position), make_string(total))
+        let state_str = match self.player_state {
+            PlayerState::Playing => "▶ ",
+            PlayerState::Paused => "⏸ ",
+        };
+        let right = format!(
+            "{}  Vol {}",
+            duration_string(
+                (self.current_position, self.total_duration),
+                self.show_remaining_time
+            ),
+            volume_percent(self.volume)
+        );
+        let row_width = rect.width as usize;
+        let name_width = row_width.saturating_sub(width(state_str) + width(&right) + 1);
+        let name = self.song_name_by_song_idx(self.playing_song_idx);
+        let left = format!(
+            "{state_str}{}",
+            marquee(name, name_width, self.marquee_step())
+        );
+        frame.render_widget(
+            Paragraph::new(right_aligned_row(&left, &right, row_width)),
+            rect,
+        );
+        width(name) > name_width
+    }
+
+    fn draw_sort_menu(&self, frame: &mut Frame, rect: Rect, highlighted: usize) {
+        let items = SortOrder::ALL.iter().enumerate().map(|(i, x)| {
+            let current = if *x == self.sort_order { "*" } else { " " };
+            let mut item = ListItem::new(format!("{current} {}. {}", i + 1, x.name()));
+            if i == highlighted {
+                item = item.style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            item
+        });
+        let popup_rect = centered_rect(rect, 24, SortOrder::ALL.len() as u16 + 2);
+        frame.render_widget(Clear, popup_rect);
+        frame.render_widget(
+            List::new(items).block(Block::bordered().title("Sort by")),
+            popup_rect,
+        );
+    }
+}
+
+/// The play modes that differ from the default, for the header
+fn play_modes_text(shuffle: bool, repeat: RepeatMode) -> String {
+    let mut modes = Vec::new();
+    if shuffle {
+        modes.push(String::from("Shuffle"));
+    }
+    if repeat != RepeatMode::All {
+        modes.push(format!("Repeat {}", repeat.name()));
+    }
+    modes.join(", ")
+}
+
+fn volume_percent(volume: f64) -> u8 {
+    (volume * 100.0).round() as u8
+}
+
+/// A `width`x`height` rectangle in the center of `rect`, shrunk to fit if needed
+fn centered_rect(rect: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(rect.width);
+    let height = height.min(rect.height);
+    Rect::new(
+        rect.x + (rect.width - width) / 2,
+        rect.y + (rect.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn draw_resume_popup(frame: &mut Frame, rect: Rect, text: &str) {
+    let popup_rect = centered_rect(rect, width(text) as u16 + 4, 3);
+    frame.render_widget(Clear, popup_rect);
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .block(Block::bordered().title("Resume")),
+        popup_rect,
+    );
+}
+
+fn draw_quit_confirm_popup(frame: &mut Frame, rect: Rect) {
+    let text = "Press q again to quit";
+    let popup_rect = centered_rect(rect, text.len() as u16 + 4, 3);
+    frame.render_widget(Clear, popup_rect);
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .block(Block::bordered().title("Quit")),
+        popup_rect,
+    );
+}
+
+/// Formats `position/total` as `mm:ss/mm:ss`
+///
+/// With `remaining` set, the first part shows the remaining time instead, like `-mm:ss/mm:ss`.
+fn duration_string((position, total): (u32, u32), remaining: bool) -> String {
+    if remaining {
+        format!(
+            "-{}/{}",
+            format_duration(total.saturating_sub(position)),
+            format_duration(total)
+        )
+    } else {
+        format!("{}/{}", format_duration(position), format_duration(total))
+    }
 }
 
 #[derive(Clone, Debug)]
 struct ErrorUiData {
     title: &'static str,
+    /// What to do about it, when it's an [`Error`] of the library
+    guidance: Option<&'static str>,
     content: String,
+    /// The new drive path being typed in, if the user chose to change it
+    drive_input: Option<String>,
+    /// Another track holding the meta info, read from instead with m
+    meta_info_track: Option<usize>,
 }
 
 impl ErrorUiData {
+    /// Like `Press m to read the meta info from track 3`
+    fn meta_info_offer(&self) -> Option<String> {
+        let track = self.meta_info_track?;
+        Some(format!("Press m to read the meta info from track {track}"))
+    }
+
     fn draw_to(&self, frame: &mut Frame, rect: Rect) {
         frame.render_widget(
             Paragraph::new(self.title).alignment(Alignment::Center),
             rect,
         );
-        frame.render_widget(
-            Paragraph::new(self.content.as_str()),
-            Rect::new(rect.x, rect.y + 1, rect.width, rect.height - 1),
-        )
+        let mut content_rect = Rect::new(rect.x, rect.y + 1, rect.width, rect.height - 1);
+        if let Some(guidance) = self.guidance {
+            frame.render_widget(
+                Paragraph::new(guidance).style(Style::default().add_modifier(Modifier::BOLD)),
+                content_rect,
+            );
+            content_rect.y += 2;
+            content_rect.height = content_rect.height.saturating_sub(2);
+        }
+        frame.render_widget(Paragraph::new(self.content.as_str()), content_rect);
+        if let (Some(offer), None) = (self.meta_info_offer(), &self.drive_input) {
+            frame.render_widget(
+                Paragraph::new(offer).style(Style::default().add_modifier(Modifier::REVERSED)),
+                Rect::new(rect.x, rect.bottom().saturating_sub(1), rect.width, 1),
+            );
+        }
+        if let Some(input) = &self.drive_input {
+            frame.render_widget(
+                Paragraph::new(format!(
+                    "New drive path (Enter to retry, Esc to cancel): {input}"
+                ))
+                .style(Style::default().add_modifier(Modifier::REVERSED)),
+                Rect::new(rect.x, rect.bottom().saturating_sub(1), rect.width, 1),
+            );
+        }
     }
 }
 
@@ -228,47 +1003,226 @@ pub struct UiData {
     starting_ui_data: StartingUiData,
     player_ui_data: PlayerUiData,
     error_ui_data: ErrorUiData,
-    any_key_to_exit: bool,
     /// tracks info (precisely for DVDs it's "sessions") from `cdrskin -minfo`
     disc_tracks: Arc<Vec<Track>>,
     meta_info: Arc<MetaInfo>,
+    /// See [`crate::disc_id`]; present once the tracks info is fetched
+    disc_id: Option<String>,
+    /// Draw only one line (see [`crate::cli::UiArgs::mini`])
+    mini: bool,
+    /// Print state changes instead of drawing (see [`crate::cli::UiArgs::screen_reader`])
+    screen_reader: bool,
+    announced: Announced,
+    /// Set on every change that should show up; the terminal redraws only then
+    /// (and on [`REDRAW_KEEP_ALIVE_INTERVAL`])
+    dirty: bool,
+    /// Whether the last drawn frame contains animations
+    animating: bool,
+    /// The version line of the minfo program, once checked
+    minfo_version: Option<String>,
+    page: Page,
+    /// Scrolling offset of the pages other than [`Page::Player`], in lines
+    ///
+    /// It's reset on page switches; [`Page::Logs`] counts it from the newest line.
+    page_scroll: usize,
+    /// What the player was started with; the drive may be changed on the Error screen
+    context: AppContext,
+    /// The disc being played, once opened; songs are checked against their hashes with it
+    disc_source: Option<Arc<dyn DiscSource>>,
 }
 
 impl Default for UiData {
     fn default() -> Self {
         Self {
             ui_state: AppUiState::Starting,
-            starting_ui_data: StartingUiData {
-                info_text: "Initializing...".into(),
-            },
+            starting_ui_data: Default::default(),
             player_ui_data: PlayerUiData {
                 playing_song_idx: 0,
                 selected_song_idx: 0,
                 player_state: PlayerState::Playing,
                 meta_info: Default::default(),
+                song_durations: Default::default(),
                 current_position: 0,
                 total_duration: 0,
                 volume: 1.0,
+                show_remaining_time: false,
+                quit_requested_at: None,
+                marquee_epoch: Instant::now(),
+                pending_song_number: String::new(),
+                sort_order: SortOrder::Disc,
+                display_order: Default::default(),
+                sort_menu: None,
+                toast: None,
+                palette: Palette::Default,
+                resume_offer: None,
+                shuffle: false,
+                shuffle_order: Default::default(),
+                repeat: RepeatMode::All,
+                wrap_around: true,
+                read_errors: Default::default(),
+                song_checks: Default::default(),
+                bookmarks: Default::default(),
+                selected_bookmark: 0,
+                bookmark_input: None,
+                favorites: Default::default(),
+                favorites_only: false,
+                unplayable_songs: Default::default(),
             },
-            any_key_to_exit: false,
             disc_tracks: Default::default(),
             error_ui_data: ErrorUiData {
                 title: "",
+                guidance: None,
                 content: "".into(),
+                drive_input: None,
+                meta_info_track: None,
             },
             meta_info: Arc::new(Default::default()),
+            disc_id: None,
+            mini: false,
+            screen_reader: false,
+            announced: Default::default(),
+            dirty: true,
+            animating: false,
+            minfo_version: None,
+            page: Page::Player,
+            page_scroll: 0,
+            context: Default::default(),
+            disc_source: None,
         }
     }
 }
 
 impl UiData {
-    pub fn new() -> Self {
-        Default::default()
+    pub fn new(context: AppContext) -> Self {
+        let (shuffle, repeat) = initial_modes(&context.ui);
+        let mut data = Self {
+            mini: context.ui.mini,
+            screen_reader: context.ui.screen_reader,
+            context,
+            ..Default::default()
+        };
+        let config = mutex_lock!(CONFIG).clone();
+        data.player_ui_data.show_remaining_time = config.show_remaining_time;
+        data.player_ui_data.palette = config.palette;
+        data.player_ui_data.shuffle = shuffle;
+        data.player_ui_data.repeat = repeat;
+        data.player_ui_data.wrap_around = initial_wrap_around(&data.context.ui);
+        data.player_ui_data.favorites_only = initial_favorites_only(&data.context.ui);
+        data
     }
 }
 
 impl UiData {
-    pub fn draw_to(&self, frame: &mut Frame) {
+    /// Sets [`UiData::dirty`], waking up [`Tui::tick`] to redraw; for changes made off its
+    /// thread
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+        send_tui_event(TuiEvent::Redraw);
+    }
+
+    fn draw_mini_to(&mut self, frame: &mut Frame) {
+        let rect = frame.size();
+        match self.ui_state {
+            AppUiState::Starting => {
+                frame.render_widget(Paragraph::new(self.starting_ui_data.info_text()), rect);
+            }
+            AppUiState::Player => {
+                self.animating = self.player_ui_data.draw_mini_to(frame, rect);
+            }
+            AppUiState::Error => {
+                let first_line = self
+                    .error_ui_data
+                    .content
+                    .lines()
+                    .next()
+                    .unwrap_or_default();
+                let text = match &self.error_ui_data.drive_input {
+                    Some(input) => format!("New drive path: {input}"),
+                    None => format!("{} {first_line}", self.error_ui_data.title),
+                };
+                frame.render_widget(Paragraph::new(text), rect);
+            }
+            AppUiState::NoSongs => {
+                frame.render_widget(Paragraph::new(NO_SONGS_LINES[0]), rect);
+            }
+        }
+    }
+
+    /// Like `Pseudo-CD Player - /dev/sr0 - <disc title> [<disc ID>]`, with the parts
+    /// not known yet left out
+    fn app_title(&self) -> String {
+        let mut title = format!("{TUI_APP_TITLE} - {}", self.context.disc.location());
+        if let Some(disc_title) = &self.meta_info.title {
+            title.push_str(" - ");
+            title.push_str(disc_title);
+        }
+        if let Some(id) = &self.disc_id {
+            title.push_str(&format!(" [{id}]"));
+        }
+        title
+    }
+
+    fn playing_track(&self) -> Option<Track> {
+        let data = &self.player_ui_data;
+        data.meta_info
+            .song_track(data.playing_song_idx, &self.disc_tracks)
+    }
+
+    /// Makes `song_idx` the playing song and returns its track; one that can't be played
+    /// is refused with a toast, and the playing song stays
+    fn switch_song(&mut self, song_idx: usize) -> Option<Track> {
+        let data = &mut self.player_ui_data;
+        match data.meta_info.playable_track(song_idx, &self.disc_tracks) {
+            Ok(track) => {
+                data.playing_song_idx = song_idx;
+                Some(track)
+            }
+            Err(problem) => {
+                data.show_toast(format!("Song {} {problem}", song_idx + 1));
+                None
+            }
+        }
+    }
+
+    /// What remote controls see (see [`crate::remote`])
+    fn player_status(&self) -> PlayerStatus {
+        if self.ui_state != AppUiState::Player {
+            return Default::default();
+        }
+        let data = &self.player_ui_data;
+        PlayerStatus {
+            state: match data.player_state {
+                PlayerState::Playing => PlaybackState::Playing,
+                PlayerState::Paused => PlaybackState::Paused,
+            },
+            disc_title: data.meta_info.title.clone(),
+            disc_id: self.disc_id.clone(),
+            song_number: Some(data.playing_song_idx + 1),
+            position: data.current_position,
+            volume: data.volume,
+            shuffle: data.shuffle,
+            repeat: data.repeat,
+            songs: data
+                .meta_info
+                .list
+                .iter()
+                .zip(&data.song_durations)
+                .map(|(song, &duration)| SongStatus {
+                    name: song.name.clone(),
+                    duration,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn draw_to(&mut self, frame: &mut Frame) {
+        self.dirty = false;
+        self.animating = false;
+        if self.mini {
+            self.draw_mini_to(frame);
+            return;
+        }
+
         let frame_rect = frame.size();
         let app_block_inner_rect = Rect::new(1, 1, frame_rect.width - 2, frame_rect.height - 2);
 
@@ -277,164 +1231,411 @@ impl UiData {
                 self.starting_ui_data.draw_to(frame, app_block_inner_rect);
             }
             AppUiState::Player => {
-                self.player_ui_data.draw_to(frame, app_block_inner_rect);
+                self.draw_pages(frame, app_block_inner_rect);
             }
             AppUiState::Error => {
                 self.error_ui_data.draw_to(frame, app_block_inner_rect);
             }
+            AppUiState::NoSongs => {
+                let rect = app_block_inner_rect;
+                let top = rect.height.saturating_sub(NO_SONGS_LINES.len() as u16) / 2;
+                frame.render_widget(
+                    Paragraph::new(NO_SONGS_LINES.join("\n"))
+                        .block(Block::new().padding(Padding::top(top)))
+                        .alignment(Alignment::Center),
+                    rect,
+                );
+            }
         }
 
         frame.render_widget(
             Block::bordered()
-                .title(TUI_APP_TITLE)
+                .title(self.app_title())
                 .title_alignment(Alignment::Center),
             frame_rect,
         );
     }
 }
 
-pub fn set_up_tui() -> io::Result<()> {
+pub fn set_up_tui(alternate_screen: bool) -> io::Result<()> {
     enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?;
+    if alternate_screen {
+        stdout().execute(EnterAlternateScreen)?;
+        IN_ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+    }
     Ok(())
 }
 
 pub fn clean_up_tui() -> io::Result<()> {
     disable_raw_mode()?;
-    stdout().execute(LeaveAlternateScreen)?;
+    if IN_ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        stdout().execute(LeaveAlternateScreen)?;
+    } else {
+        // leave the inline viewport's line intact
+        println!();
+    }
     Ok(())
 }
 
+/// Works in every UI state: initialization may still be running, and the player
+/// may not be started yet.
 pub fn clean_up_and_exit() {
-    let PlayerResult::Stopped = mutex_lock!(PLAYBACK_HANDLE)
-        .as_ref()
-        .unwrap()
-        .send_recv(PlayerCommand::StopAndWait)
-    else {
-        panic!("Unexpected player result");
-    };
+    BACKGROUND_CANCELLED.store(true, Ordering::SeqCst);
+    kill_running_commands();
 
+    if let Some(handle) = playback_handle() {
+        handle.stop_and_wait();
+    }
+
+    resume::save();
     let _ = clean_up_tui();
     drop(mutex_lock!(AUDIO_STREAM).take());
-    exit(0);
+    door_lock::unlock();
+    on_finish::eject_pending();
+    #[cfg(unix)]
+    crate::control_socket::remove_socket_file();
+    // quitting from the Error screen exits with the code of its error
+    exit(failure::report_pending());
 }
 
 impl<B: Backend> Tui<B> {
-    pub fn new(backend: B) -> io::Result<Self> {
-        set_up_tui()?;
-        let terminal = Terminal::new(backend)?;
+    pub fn new(backend: B, context: AppContext) -> io::Result<Self> {
+        let (mini, screen_reader) = (context.ui.mini, context.ui.screen_reader);
+        set_up_tui(!mini && !screen_reader)?;
+        let terminal = if mini {
+            Terminal::with_options(
+                backend,
+                TerminalOptions {
+                    viewport: Viewport::Inline(1),
+                },
+            )?
+        } else {
+            Terminal::new(backend)?
+        };
+        let max_fps = mutex_lock!(CONFIG).max_fps.clamp(1, MAX_FPS_LIMIT);
+        // once the viewport has asked for the cursor position
+        spawn(forward_terminal_events);
+        spawn(|| loop {
+            send_tui_event(TuiEvent::Remote(remote::recv_command()));
+        });
         Ok(Self {
             terminal,
-            ui_data: Arc::new(Mutex::new(UiData::new())),
+            ui_data: Arc::new(Mutex::new(UiData::new(context))),
             should_quit: false,
             bg_thread_started: false,
+            scrub: None,
+            last_draw_at: None,
+            frame_interval: Duration::from_secs(1) / max_fps,
         })
     }
 
-    fn background_thread(ui_data: &Arc<Mutex<UiData>>) -> anyhow::Result<()> {
-        macro starting_info_text($($arg:tt)*) {
-        mutex_lock!(ui_data).starting_ui_data.info_text = format!($($arg)*)
+    /// Shows `step` running on the Starting screen, runs `f`, then shows how it went
+    fn run_step<T, E: Into<anyhow::Error>>(
+        ui_data: &Mutex<UiData>,
+        step: StartStep,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> anyhow::Result<T> {
+        let set_state = |state| {
+            let mut guard = mutex_lock!(ui_data);
+            guard.starting_ui_data.set_step(step, state);
+            guard.mark_dirty();
+        };
+        if BACKGROUND_CANCELLED.load(Ordering::SeqCst) {
+            bail!("Cancelled");
         }
-       
-        starting_info_text!("Checking {}...", minfo_cli!());
+        set_state(StepState::Running);
+        let result = f().map_err(Into::into);
+        set_state(match result {
+            Ok(_) => StepState::Done,
+            Err(_) => StepState::Failed,
+        });
+        result
+    }
 
-        let version = minfo::check_version_line();
-        let version = match version {
-            Err(_) => {
-                yeet!(anyhow!("Command `{}` not found", minfo_cli!()))
-            }
-            Ok(version) => version,
+    /// Has the tracks step say it's waiting while `drive` has no disc
+    fn wait_for_disc(ui_data: &Mutex<UiData>, drive: &Path) {
+        let set_label = |label: String, state| {
+            let mut guard = mutex_lock!(ui_data);
+            guard.starting_ui_data.steps[StartStep::Tracks as usize] = (label, state);
+            guard.mark_dirty();
         };
+        let waiting = format!("Waiting for a disc in {}", drive.display());
+        set_label(waiting, StepState::Running);
+        disc_wait::wait_for_disc(drive, &BACKGROUND_CANCELLED);
+        set_label(StartStep::Tracks.label().into(), StepState::Waiting);
+    }
 
-        starting_info_text!(
-            "{} version: {version}; Fetching tracks info...",
-            minfo_cli!()
-        );
-        let tracks = minfo::minfo_track_info()?;
-        let tracks = Arc::new(tracks);
-        mutex_lock!(ui_data).disc_tracks = Arc::clone(&tracks);
-
-        let no_meta = mutex_lock!(ARGS).no_meta;
-        let meta_info = if !no_meta {
-            starting_info_text!("Tracks fetched. Extracting meta info...");
-
-            let meta_info_track = tracks
-                .get(
-                    mutex_lock!(ARGS).meta_info_track - 1, /* track number starts from one */
-                )
-                .ok_or_else(|| {
-                    anyhow!(
-                    "Meta info track is out-of-index; Number of tracks: {}",
-                    tracks.len()
-                )
-                })?;
-            extract_meta_info(*meta_info_track)?
-        } else {
-            let song_list = tracks.iter().enumerate().map(|(i, _)| {
-                SongInfo {
-                    name: format!("{}", i + 1),
-                    session_no: i + 1,
-                }
-            }).collect::<Vec<_>>();
-            MetaInfo {
-                list: song_list,
-                ..Default::default()
+    fn background_thread(ui_data: &Arc<Mutex<UiData>>) -> anyhow::Result<()> {
+        let context = mutex_lock!(ui_data).context.clone();
+        let disc = &context.disc;
+        let stats = Arc::<StreamStats>::default();
+        // only the meta info waits, for the tracks
+        let (version, tracks, meta_info, audio) = scope(|s| {
+            let version = s.spawn(|| {
+                Self::run_step(ui_data, StartStep::MinfoVersion, || {
+                    minfo::check_version_line(disc)
+                })
+            });
+            let audio = s.spawn(|| {
+                Self::run_step(ui_data, StartStep::Audio, || {
+                    create_audio_stream(Arc::clone(&stats))
+                        .map(|(stream, tx)| (StreamSendWrapper::from(stream), tx))
+                })
+            });
+            if context.ui.wait_for_disc && !disc_wait::has_disc(&disc.drive) {
+                Self::wait_for_disc(ui_data, &disc.drive);
             }
-        };
+            let tracks =
+                Self::run_step(ui_data, StartStep::Tracks, || minfo::minfo_track_info(disc));
+            let meta_info = tracks.as_ref().ok().map(|tracks| {
+                Self::run_step(ui_data, StartStep::MetaInfo, || {
+                    let source = source::open_disc(disc, tracks)?;
+                    read_meta_info(disc, &source, tracks).map(|x| (source, x))
+                })
+            });
+            (
+                version.join().unwrap(),
+                tracks,
+                meta_info,
+                audio.join().unwrap(),
+            )
+        });
+
+        let version = version?;
+        {
+            // another one than asked for is used if that one isn't installed
+            let minfo_cli = external::minfo_program(disc).name();
+            let mut guard = mutex_lock!(ui_data);
+            guard.starting_ui_data.steps[StartStep::MinfoVersion as usize].0 =
+                format!("{minfo_cli} version: {version}");
+            guard.minfo_version = Some(version);
+        }
+
+        let tracks = Arc::new(tracks?);
+        {
+            let mut guard = mutex_lock!(ui_data);
+            guard.disc_tracks = Arc::clone(&tracks);
+            guard.disc_id = Some(disc_id(&tracks));
+        }
+
+        let (source, meta_info) = meta_info.unwrap()?;
+        let song_durations = meta_info.song_durations(&tracks);
 
         let meta_info = Arc::new(meta_info);
         mutex_lock!(ui_data).meta_info = Arc::clone(&meta_info);
         mutex_lock!(ui_data).player_ui_data.meta_info = Arc::clone(&meta_info);
+        {
+            let mut guard = mutex_lock!(ui_data);
+            guard.disc_source = Some(Arc::clone(&source));
+            guard.player_ui_data.song_durations = song_durations;
+            guard.player_ui_data.song_checks = match meta_info.has_hashes() {
+                true => vec![SongCheck::Unchecked; meta_info.list.len()],
+                false => Vec::new(),
+            };
+            guard.player_ui_data.favorites = favorites::load(&disc_id(&tracks))
+                .into_iter()
+                .filter(|&x| x < meta_info.list.len())
+                .collect();
+            guard.player_ui_data.unplayable_songs = (0..meta_info.list.len())
+                .filter(|&i| meta_info.playable_track(i, &tracks).is_err())
+                .collect();
+            guard.player_ui_data.set_sort_order(SortOrder::Disc);
+            let skipped = guard.player_ui_data.unplayable_songs.len();
+            if skipped > 0 {
+                guard
+                    .player_ui_data
+                    .show_toast(format!("{skipped} songs skipped, not on the disc or empty"));
+            }
+            if let Some(name) = mutex_lock!(MISSING_AUDIO_DEVICE).take() {
+                guard.player_ui_data.show_toast(format!(
+                    "{name} not found; playing on the default audio device"
+                ));
+            }
+            guard.player_ui_data.bookmarks = bookmarks::load(&disc_id(&tracks))
+                .into_iter()
+                .filter(|x| x.song_idx < meta_info.list.len())
+                .collect();
+        }
+        if mutex_lock!(ui_data).player_ui_data.display_order.is_empty() {
+            let mut guard = mutex_lock!(ui_data);
+            guard.ui_state = AppUiState::NoSongs;
+            guard.mark_dirty();
+            return Ok(());
+        }
 
-        starting_info_text!("Initializing audio sink...");
-        let ui_data_for_player_callback = Arc::clone(ui_data);
-        let playback_handle = start_global_playback_thread(
-            mutex_lock!(ARGS).drive.clone(),
-            ui_data_for_player_callback,
-            Some(|event, ui_data: &Arc<Mutex<UiData>>| match event {
+        let (stream, sample_tx) = audio?;
+        let events = EventBus::new();
+        let ui_data_for_player_events = Arc::clone(ui_data);
+        events.subscribe(move |event| {
+            let mut guard = mutex_lock!(ui_data_for_player_events);
+            guard.mark_dirty();
+            match event {
                 PlayerCallbackEvent::Finished => {
-                    let mut guard = mutex_lock!(ui_data);
-                    let next_song_idx = guard.player_ui_data.next_song_idx();
-                    let next_song = &guard.player_ui_data.meta_info.list[next_song_idx];
-                    let next_track = guard.disc_tracks[next_song.session_no - 1];
-                    guard.player_ui_data.playing_song_idx = next_song_idx;
-                    mutex_lock!(PLAYBACK_HANDLE)
-                        .as_ref()
-                        .unwrap()
-                        .send(PlayerCommand::Goto(next_track, true));
+                    // this is the player thread, which would wait on itself sending it the
+                    // next song while another command is waiting
+                    send_tui_event(TuiEvent::Finished);
                 }
                 PlayerCallbackEvent::Paused(paused) => {
-                    let mut guard = mutex_lock!(ui_data);
                     guard.player_ui_data.player_state = PlayerState::from_paused(paused);
                 }
                 PlayerCallbackEvent::Progress(current, total) => {
-                    let mut guard = mutex_lock!(ui_data);
                     guard.player_ui_data.current_position = current;
                     guard.player_ui_data.total_duration = total;
                 }
-            }),
-        )?;
-        set_global_playback_handle(playback_handle);
+                PlayerCallbackEvent::SectorSkipped(sector) => {
+                    guard
+                        .player_ui_data
+                        .show_toast(format!("Read error, sector {sector} skipped"));
+                }
+                PlayerCallbackEvent::ReadErrors(x) => {
+                    guard.player_ui_data.read_errors = x;
+                }
+                PlayerCallbackEvent::NotAudio(content) => {
+                    let song = guard.player_ui_data.playing_song_idx + 1;
+                    guard.player_ui_data.show_toast(format!(
+                        "Song {song} looks like {content}, not audio; paused"
+                    ));
+                }
+            }
+        });
+        door_lock::follow(&events, &source);
+        let handle = start_player_thread(source, sample_tx, events, stats);
+        let spin_down = spin_down_after(context.ui.spin_down_after);
+        handle.send(PlayerCommand::SetSpinDown(spin_down));
+        handle.send(PlayerCommand::SetSongLevels(song_levels(&meta_info, &tracks)));
+        mutex_lock!(AUDIO_STREAM).replace(stream);
+        set_global_playback_handle(handle);
 
-        starting_info_text!("Done.");
+        if BACKGROUND_CANCELLED.load(Ordering::SeqCst) {
+            bail!("Cancelled");
+        }
+        // the steps all done stay for a moment
         sleep(Duration::from_secs_f64(0.1));
 
-        mutex_lock!(ui_data).ui_state = AppUiState::Player;
+        let ui = &context.ui;
+        let (mut start_song_idx, start_track_given, start_paused) = (
+            ui.start_song_idx(meta_info.list.len())?,
+            ui.start_track.is_some(),
+            ui.paused || ui.at.is_some(),
+        );
+        {
+            let mut guard = mutex_lock!(ui_data);
+            let data = &mut guard.player_ui_data;
+            let start_problem = meta_info.playable_track(start_song_idx, &tracks).err();
+            if let Some(problem) = start_problem.filter(|_| start_track_given) {
+                data.show_toast(format!("Song {} {problem}", start_song_idx + 1));
+            }
+            let start_missing = start_problem.is_some();
+            if data.shuffle {
+                // without a song asked for, shuffling starts at a random one
+                let first = start_track_given.then_some(start_song_idx);
+                data.shuffle_order = shuffled(data.display_order.clone(), first);
+                start_song_idx = data.shuffle_order.first().copied().unwrap_or_default();
+            } else if (!start_track_given || start_missing)
+                && !data.display_order.contains(&start_song_idx)
+            {
+                // only favorites are listed, or the song asked for can't be played
+                start_song_idx = data.display_order.first().copied().unwrap_or_default();
+            }
+        }
+        // an explicit start song (or an alarm's) beats the old point
+        let resume_offer = resume::load(&disc_id(&tracks))
+            .filter(|x| {
+                x.is_worth_resuming() && meta_info.playable_track(x.song_idx, &tracks).is_ok()
+            })
+            .filter(|_| !start_track_given && ui.at.is_none());
+        {
+            let mut guard = mutex_lock!(ui_data);
+            guard.ui_state = AppUiState::Player;
+            let data = &mut guard.player_ui_data;
+            data.resume_offer = resume_offer;
+            data.playing_song_idx = start_song_idx;
+            data.selected_song_idx = start_song_idx;
+            data.player_state = PlayerState::from_paused(start_paused);
+            guard.mark_dirty();
+        }
 
-        // play the first track initially
-        if let Some(first_song) = meta_info.list.first() {
-            mutex_lock!(PLAYBACK_HANDLE)
-                .as_ref()
-                .unwrap()
-                .send_commands([
-                    PlayerCommand::Start,
-                    PlayerCommand::Goto(tracks[first_song.session_no - 1], true),
-                ]);
+        // play the first (or the asked) song initially
+        if let Ok(start_track) = meta_info.playable_track(start_song_idx, &tracks) {
+            playback_handle().unwrap().send_commands([
+                PlayerCommand::Start,
+                PlayerCommand::Goto(start_track, !start_paused),
+            ]);
+        }
+        if let Some(at) = ui.at {
+            let toast = format!("Playing at {at}, in {}", alarm::time_until(at));
+            mutex_lock!(ui_data).player_ui_data.show_toast(toast);
+            alarm::start(at);
         }
 
         Ok(())
     }
 
+    /// Reads the songs with hashes back on a thread of their own, marking each in the song
+    /// list once it's checked
+    fn start_song_checks(&self) {
+        let mut guard = mutex_lock!(self.ui_data);
+        let Some(source) = guard.disc_source.clone() else {
+            return;
+        };
+        let tracks = Arc::clone(&guard.disc_tracks);
+        let data = &mut guard.player_ui_data;
+        if data.song_checks.is_empty() {
+            data.show_toast("No song hashes to check against");
+            return;
+        }
+        if data.song_checks.contains(&SongCheck::Checking) {
+            data.show_toast("Already checking");
+            return;
+        }
+        let songs = data
+            .meta_info
+            .list
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| Some((i, x.session_no, x.hash.clone()?)))
+            .collect::<Vec<_>>();
+        for &(i, _, _) in &songs {
+            data.song_checks[i] = SongCheck::Checking;
+        }
+        data.show_toast(format!("Checking {} songs", songs.len()));
+        guard.dirty = true;
+        drop(guard);
+
+        let ui_data = Arc::clone(&self.ui_data);
+        spawn(move || {
+            let mut failed = 0;
+            for (i, session_no, hash) in &songs {
+                let track = session_no.checked_sub(1).and_then(|x| tracks.get(x));
+                let problem = match track {
+                    None => Some(format!("track #{session_no} isn't on the disc")),
+                    Some(&track) => {
+                        let check = check_song(&source, track, hash);
+                        (!check.is_intact()).then(|| check.to_string())
+                    }
+                };
+                if let Some(problem) = &problem {
+                    warn!("Song {} failed the check: {}", i + 1, problem);
+                    failed += 1;
+                }
+                let mut guard = mutex_lock!(ui_data);
+                guard.player_ui_data.song_checks[*i] = match problem {
+                    None => SongCheck::Intact,
+                    Some(_) => SongCheck::Failed,
+                };
+                guard.mark_dirty();
+            }
+            let summary = match failed {
+                0 => format!("All {} songs are intact", songs.len()),
+                _ => format!("{failed} of {} songs failed the check", songs.len()),
+            };
+            info!("{}", summary);
+            let mut guard = mutex_lock!(ui_data);
+            guard.player_ui_data.show_toast(summary);
+            guard.mark_dirty();
+        });
+    }
+
     pub fn tick(&mut self) -> io::Result<()> {
         if !self.bg_thread_started {
             self.bg_thread_started = true;
@@ -443,18 +1644,60 @@ impl<B: Backend> Tui<B> {
                 let result = Self::background_thread(&arc);
                 if let Err(e) = result {
                     let mut guard = mutex_lock!(arc);
-                    guard.any_key_to_exit = true;
+                    failure::set_pending(&e, guard.context.errors_json);
                     guard.ui_state = AppUiState::Error;
-                    guard.error_ui_data.title = "Error occurred. Press any key to exit.";
+                    guard.error_ui_data.title =
+                        "Error occurred. Press r to retry, d to change the drive, or any other key to exit.";
+                    let error = e.chain().find_map(|x| x.downcast_ref::<Error>());
+                    guard.error_ui_data.guidance = error.map(Error::guidance);
+                    guard.error_ui_data.meta_info_track = match error {
+                        Some(Error::MetadataElsewhere { candidates, .. }) => {
+                            candidates.first().copied()
+                        }
+                        _ => None,
+                    };
                     guard.error_ui_data.content = format!("{:?}", e);
+                    guard.mark_dirty();
                 }
             });
         }
 
-        self.terminal.draw(|frame| {
-            mutex_lock!(self.ui_data).draw_to(frame);
-        })?;
-        self.handle_events()?;
+        if mutex_lock!(self.ui_data).screen_reader {
+            Self::announce(&mut mutex_lock!(self.ui_data))?;
+        } else if self.needs_redraw() {
+            self.terminal.draw(|frame| {
+                mutex_lock!(self.ui_data).draw_to(frame);
+            })?;
+            self.last_draw_at = Some(Instant::now());
+        }
+        // sleeps until something happens, or until something drawn changes by itself
+        let first = match mutex_lock!(TUI_EVENTS.1).recv_timeout(self.next_redraw_in()) {
+            Ok(x) => Some(x),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => unreachable!(),
+        };
+        // the rest of what came meanwhile, all before the next draw
+        let queued = std::iter::from_fn(|| mutex_lock!(TUI_EVENTS.1).try_recv().ok());
+        for event in first.into_iter().chain(queued) {
+            match event {
+                TuiEvent::Terminal(event) => self.handle_event(event?)?,
+                TuiEvent::Remote(command) => self.remote_command(command),
+                TuiEvent::Finished => self.song_finished(),
+                TuiEvent::Redraw => {}
+            }
+        }
+        self.finish_scrub();
+        {
+            let guard = mutex_lock!(self.ui_data);
+            remote::publish_status(guard.player_status());
+            let data = &guard.player_ui_data;
+            // the old point stays until it's decided whether to resume at it
+            if guard.ui_state == AppUiState::Player && data.resume_offer.is_none() {
+                if let Some(id) = &guard.disc_id {
+                    resume::record(id, data.playing_song_idx, data.current_position);
+                }
+            }
+        }
         if self.should_quit {
             clean_up_and_exit();
         }
@@ -462,194 +1705,798 @@ impl<B: Backend> Tui<B> {
         Ok(())
     }
 
-    /// ## Key bindings
+    /// Prints the changes (see [`UiData::announcements`]) instead of drawing, for `--screen-reader`
+    fn announce(ui_data: &mut UiData) -> io::Result<()> {
+        let lines = ui_data.announcements();
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let mut stdout = stdout().lock();
+        for line in lines {
+            // raw mode: no implicit carriage return
+            write!(stdout, "{}\r\n", line.trim_end())?;
+        }
+        stdout.flush()
+    }
+
+    /// How long until the next draw without anything happening: of changes held back by
+    /// the frame interval, a marquee step or the keep-alive redraw; or until a scrub ends
+    fn next_redraw_in(&self) -> Duration {
+        let since_draw = self.last_draw_at.map_or(Duration::ZERO, |x| x.elapsed());
+        let interval = {
+            let guard = mutex_lock!(self.ui_data);
+            // changes not drawn yet wait for the frame interval; they aren't drawn at all
+            // for the screen reader
+            match (guard.dirty && !guard.screen_reader, guard.animating) {
+                (true, _) => Duration::ZERO,
+                (false, true) => MARQUEE_STEP_INTERVAL,
+                (false, false) => REDRAW_KEEP_ALIVE_INTERVAL,
+            }
+        };
+        let mut timeout = interval.max(self.frame_interval).saturating_sub(since_draw);
+        if let Some(scrub) = &self.scrub {
+            timeout = timeout.min(SCRUB_REPEAT_WINDOW.saturating_sub(scrub.last_key_at.elapsed()));
+        }
+        timeout
+    }
+
+    fn needs_redraw(&self) -> bool {
+        let Some(last_draw_at) = self.last_draw_at else {
+            return true;
+        };
+        let elapsed = last_draw_at.elapsed();
+        if elapsed < self.frame_interval {
+            return false;
+        }
+        let guard = mutex_lock!(self.ui_data);
+        guard.dirty
+            || (guard.animating && elapsed >= MARQUEE_STEP_INTERVAL)
+            || elapsed >= REDRAW_KEEP_ALIVE_INTERVAL
+    }
+
+    /// Changes the volume by `steps` volume steps, keeping it in 0..1
+    fn change_volume(&mut self, steps: i32) {
+        let volume = {
+            let mut guard = mutex_lock!(self.ui_data);
+            let step = volume_step(guard.context.ui.volume_step);
+            let data = &mut guard.player_ui_data;
+            data.volume = stepped_volume(data.volume, steps, step);
+            data.show_toast(format!("Volume {} %", volume_percent(data.volume)));
+            data.volume
+        };
+        playback_handle().unwrap().send(PlayerCommand::ChangeVolume(volume));
+    }
+
+    /// Handles a seek key press; `direction` is -1.0 for backwards and 1.0 for forward
     ///
-    /// <pre>
-    /// Space: Play/Pause
-    /// n: Next
-    /// p: Previous
-    /// j, ArrowDown: Selection move up
-    /// k, ArrowUp: Selection move down
-    /// g, Home: Move selection to the first
-    /// G, End: Move selection to the last
-    /// h, ArrowLeft: Seek backwards 5 seconds
-    /// l, ArrowRight: Seek forward 5 seconds
-    /// Enter: Play the selection
-    /// ,: Volume down
-    /// .: Volume up
-    /// </pre>
-    pub fn handle_events(&mut self) -> io::Result<()> {
-        if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                macro ui_data_guard() {
-                    mutex_lock!(self.ui_data)
-                }
-                let song_number = ui_data_guard!().meta_info.list.len();
+    /// A single press seeks a [`seek_step`], a `long` one with Shift. Key repeats of a
+    /// held key turn into scrubbing (see [`ScrubState`]).
+    fn seek_key(&mut self, direction: f64, long: bool) {
+        let now = Instant::now();
+        let (track, step) = {
+            let guard = mutex_lock!(self.ui_data);
+            let ui = &guard.context.ui;
+            let cli = match long {
+                true => ui.long_seek_step,
+                false => ui.seek_step,
+            };
+            (guard.playing_track(), seek_step(cli, long))
+        };
+        let max_position =
+            duration_from_bytes(track.map(|x| x.size_bytes()).unwrap_or_default()) - 1.0;
+        let clamp = |p: f64| p.min(max_position).max(0.0);
+
+        if let Some(scrub) = self
+            .scrub
+            .as_mut()
+            .filter(|x| x.direction == direction && now - x.last_key_at < SCRUB_REPEAT_WINDOW)
+        {
+            let dt = (now - scrub.last_key_at).as_secs_f64();
+            scrub.speed = (scrub.speed + SCRUB_ACCELERATION * dt).min(SCRUB_MAX_SPEED);
+            scrub.target = clamp(scrub.target + direction * scrub.speed * dt);
+            scrub.last_key_at = now;
+            // update the gauge live; the player only catches up every `SCRUB_SEEK_INTERVAL`
+            {
+                let mut guard = mutex_lock!(self.ui_data);
+                guard.player_ui_data.current_position = scrub.target as u32;
+                guard
+                    .player_ui_data
+                    .show_toast(format!("Scrub to {}", format_duration(scrub.target as u32)));
+            }
+            if now - scrub.last_seek_at >= SCRUB_SEEK_INTERVAL {
+                scrub.last_seek_at = now;
+                let target = scrub.target;
+                playback_handle().unwrap().send(PlayerCommand::Seek(target));
+            }
+            return;
+        }
 
-                if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-                    // Ctrl-C pressed
-                    self.should_quit = true;
+        let Some(p) = playback_handle().unwrap().position() else {
+            return;
+        };
+        let target = clamp(p + direction * step);
+        playback_handle().unwrap().send(PlayerCommand::Seek(target));
+        let sign = if direction < 0.0 { '-' } else { '+' };
+        mutex_lock!(self.ui_data)
+            .player_ui_data
+            .show_toast(format!("Seek {sign}{step} s"));
+        self.scrub = Some(ScrubState {
+            direction,
+            target,
+            speed: SCRUB_INITIAL_SPEED,
+            last_key_at: now,
+            last_seek_at: now,
+        });
+    }
+
+    /// Ends scrubbing once the seek key is released, sending the final position
+    fn finish_scrub(&mut self) {
+        let Some(scrub) = self.scrub else {
+            return;
+        };
+        if scrub.last_key_at.elapsed() < SCRUB_REPEAT_WINDOW {
+            return;
+        }
+        if scrub.last_seek_at < scrub.last_key_at {
+            playback_handle().unwrap().send(PlayerCommand::Seek(scrub.target));
+        }
+        self.scrub = None;
+    }
+
+    /// Goes to the song after the one that ended, as the play mode says
+    fn song_finished(&mut self) {
+        // not holding the lock while sending, as the player thread may be waiting for it
+        let player_command = {
+            let mut guard = mutex_lock!(self.ui_data);
+            let finished = guard.player_ui_data.playlist_finished();
+            let (next_song_idx, play) = guard.player_ui_data.song_after_end();
+            if let Some(action) = guard.context.ui.on_finish.as_ref().filter(|_| finished) {
+                on_finish::playlist_finished(action, &guard.context.disc);
+            }
+            let Some(next_track) = guard.switch_song(next_song_idx) else {
+                return;
+            };
+            PlayerCommand::Goto(next_track, play)
+        };
+        if let Some(handle) = playback_handle() {
+            handle.send(player_command);
+        }
+    }
+
+    /// Applies a command of a remote control; ignored until the disc is ready
+    fn remote_command(&mut self, command: RemoteCommand) {
+        debug!("Remote command: {:?}", command);
+        if command == RemoteCommand::Quit {
+            self.should_quit = true;
+            return;
+        }
+        // not holding the lock while sending, as the player thread may be waiting for it
+        let player_command = {
+            let mut guard = mutex_lock!(self.ui_data);
+            if guard.ui_state != AppUiState::Player {
+                return;
+            }
+            guard.dirty = true;
+            let track = guard.playing_track();
+            let max_position =
+                duration_from_bytes(track.map(|x| x.size_bytes()).unwrap_or_default()) - 1.0;
+            let volume_step = volume_step(guard.context.ui.volume_step);
+            let data = &mut guard.player_ui_data;
+            let paused = matches!(data.player_state, PlayerState::Paused);
+            let song_idx = match command {
+                RemoteCommand::Next => data.skip_song(1),
+                RemoteCommand::Previous => data.skip_song(-1),
+                RemoteCommand::Goto(n) if (1..=data.meta_info.list.len()).contains(&n) => {
+                    Some(n - 1)
+                }
+                // no such song; the playing one goes on as it is
+                RemoteCommand::Goto(_) => return,
+                _ => Some(data.playing_song_idx),
+            };
+            match command {
+                RemoteCommand::Play => PlayerCommand::SetPaused(false),
+                RemoteCommand::Pause => PlayerCommand::SetPaused(true),
+                RemoteCommand::TogglePause => PlayerCommand::SetPaused(!paused),
+                RemoteCommand::Next | RemoteCommand::Previous | RemoteCommand::Goto(_) => {
+                    match song_idx.and_then(|x| guard.switch_song(x)) {
+                        Some(track) => PlayerCommand::Goto(track, true),
+                        None => return,
+                    }
+                }
+                RemoteCommand::Seek(p) => PlayerCommand::Seek(p.min(max_position).max(0.0)),
+                RemoteCommand::SeekBy(delta) => {
+                    let target = data.current_position as f64 + delta;
+                    PlayerCommand::Seek(target.min(max_position).max(0.0))
+                }
+                RemoteCommand::SetVolume(volume) => {
+                    data.volume = volume.clamp(0.0, 1.0);
+                    data.show_toast(format!("Volume {} %", volume_percent(data.volume)));
+                    PlayerCommand::ChangeVolume(data.volume)
                 }
-                if key.kind == event::KeyEventKind::Press && key.code == KeyCode::Char('q') {
-                    self.should_quit = true;
+                RemoteCommand::StepVolume(steps) => {
+                    data.volume = stepped_volume(data.volume, steps, volume_step);
+                    data.show_toast(format!("Volume {} %", volume_percent(data.volume)));
+                    PlayerCommand::ChangeVolume(data.volume)
                 }
-                if ui_data_guard!().any_key_to_exit {
-                    self.should_quit = true;
+                RemoteCommand::Quit => unreachable!(),
+            }
+        };
+        playback_handle().unwrap().send(player_command);
+    }
+
+    /// While asking whether to resume (see [`PlayerUiData::resume_offer`]):
+    /// y or Enter resumes, n or Esc starts from the beginning
+    fn resume_offer_key(&mut self, code: KeyCode) {
+        let commands = {
+            let mut guard = mutex_lock!(self.ui_data);
+            let Some(point) = guard.player_ui_data.resume_offer else {
+                return;
+            };
+            match code {
+                KeyCode::Char('y') | KeyCode::Enter => {}
+                KeyCode::Char('n') | KeyCode::Esc => {
+                    guard.player_ui_data.resume_offer = None;
+                    return;
                 }
+                _ => return,
+            }
+            guard.player_ui_data.resume_offer = None;
+            let Some(track) = guard.switch_song(point.song_idx) else {
+                return;
+            };
+            let data = &mut guard.player_ui_data;
+            data.selected_song_idx = point.song_idx;
+            data.show_toast(format!("Resumed at {}", format_duration(point.position)));
+            [
+                PlayerCommand::Goto(track, true),
+                PlayerCommand::Seek(point.position as f64),
+            ]
+        };
+        playback_handle().unwrap().send_commands(commands);
+    }
 
-                let wrapping_next = |song_idx: usize| {
-                    if song_idx == song_number - 1 {
-                        0
-                    } else {
-                        song_idx + 1
-                    }
-                };
-                let wrapping_prev = |song_idx: usize| {
-                    if song_idx == 0 {
-                        song_number - 1
-                    } else {
-                        song_idx - 1
-                    }
+    /// While naming a bookmark (see [`PlayerUiData::bookmark_input`]): Enter adds it, with
+    /// the default name if none is typed, and Esc drops it
+    fn bookmark_input_key(&mut self, code: KeyCode) {
+        let mut guard = mutex_lock!(self.ui_data);
+        let data = &mut guard.player_ui_data;
+        let Some(input) = &mut data.bookmark_input else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.name.push(c),
+            KeyCode::Backspace => {
+                input.name.pop();
+            }
+            KeyCode::Esc => data.bookmark_input = None,
+            KeyCode::Enter => {
+                let mut bookmark = data.bookmark_input.take().unwrap();
+                bookmark.name = match bookmark.name.trim() {
+                    "" => Bookmark::default_name(bookmark.song_idx, bookmark.position),
+                    name => name.into(),
                 };
-                macro player_send($cmd:expr) {
-                    mutex_lock!(PLAYBACK_HANDLE).as_ref().unwrap().send($cmd);
+                data.show_toast(format!("Bookmarked: {}", bookmark.name));
+                data.selected_bookmark = bookmarks::insert(&mut data.bookmarks, bookmark);
+                drop(guard);
+                self.save_bookmarks();
+            }
+            _ => {}
+        }
+    }
+
+    /// On [`Page::Bookmarks`]: j/k (and the like) select a bookmark, Enter jumps to it and
+    /// d deletes it
+    fn bookmarks_page_key(&mut self, code: KeyCode) {
+        let commands = {
+            let mut guard = mutex_lock!(self.ui_data);
+            let data = &mut guard.player_ui_data;
+            let count = data.bookmarks.len();
+            if count == 0 {
+                return;
+            }
+            let selected = data.selected_bookmark.min(count - 1);
+            match code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    data.selected_bookmark = (selected + 1) % count;
+                    return;
                 }
-                macro index_inc($tt:tt) {{
-                    let mut guard = ui_data_guard!();
-                    let idx = &mut guard.player_ui_data.$tt;
-                    *idx = wrapping_next(*idx);
-                }}
-                macro selection_move_first() {
-                    ui_data_guard!().player_ui_data.selected_song_idx = 0;
+                KeyCode::Char('k') | KeyCode::Up => {
+                    data.selected_bookmark = (selected + count - 1) % count;
+                    return;
                 }
-                macro selection_move_last() {
-                    ui_data_guard!().player_ui_data.selected_song_idx = song_number - 1;
+                KeyCode::Char('g') | KeyCode::Home => {
+                    data.selected_bookmark = 0;
+                    return;
                 }
-                macro index_dec($tt:tt) {{
+                KeyCode::Char('G') | KeyCode::End => {
+                    data.selected_bookmark = count - 1;
+                    return;
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    let removed = data.bookmarks.remove(selected);
+                    data.selected_bookmark = selected.min(count.saturating_sub(2));
+                    data.show_toast(format!("Deleted bookmark: {}", removed.name));
+                    drop(guard);
+                    self.save_bookmarks();
+                    return;
+                }
+                KeyCode::Enter => {}
+                _ => return,
+            }
+            let bookmark = data.bookmarks[selected].clone();
+            let Some(track) = guard.switch_song(bookmark.song_idx) else {
+                return;
+            };
+            let data = &mut guard.player_ui_data;
+            data.selected_song_idx = bookmark.song_idx;
+            data.show_toast(format!("Jumped to {}", bookmark.name));
+            [
+                PlayerCommand::Goto(track, true),
+                PlayerCommand::Seek(bookmark.position as f64),
+            ]
+        };
+        playback_handle().unwrap().send_commands(commands);
+    }
+
+    /// Seeks to the next chapter of the playing song, or back to the previous one
+    fn skip_chapter(&self, forward: bool) {
+        let target = {
+            let mut guard = mutex_lock!(self.ui_data);
+            let data = &mut guard.player_ui_data;
+            let meta_info = Arc::clone(&data.meta_info);
+            let chapters = &meta_info.list[data.playing_song_idx].chapters;
+            if chapters.is_empty() {
+                data.show_toast("No chapters in this song");
+                return;
+            }
+            let Some(target) = skip_target(chapters, data.current_position, forward) else {
+                data.show_toast("At the last chapter");
+                return;
+            };
+            data.current_position = target;
+            // a chapter is shown under the gauge, and announced, once it's playing
+            if chapter_at(chapters, target).is_none() {
+                data.show_toast("Start of the song");
+            }
+            target
+        };
+        playback_handle().unwrap().send(PlayerCommand::Seek(target as f64));
+    }
+
+    /// Copies the playing song, like `Disc title – Song (01:23)`, to the clipboard
+    fn copy_playing_song(&self) {
+        let text = {
+            let guard = mutex_lock!(self.ui_data);
+            let data = &guard.player_ui_data;
+            let name = data.song_name_by_song_idx(data.playing_song_idx);
+            let song = match &data.meta_info.title {
+                Some(title) => format!("{title} – {name}"),
+                None => name.into(),
+            };
+            format!("{song} ({})", format_duration(data.current_position))
+        };
+        // the clipboard programs could take a moment, so it's without the lock
+        let message = match clipboard::copy(&text, &mut stdout()) {
+            Ok(Copied::Program(_)) => format!("Copied: {text}"),
+            Ok(Copied::Osc52) => format!("Copied through the terminal: {text}"),
+            Err(e) => format!("Failed to copy: {e}"),
+        };
+        mutex_lock!(self.ui_data).player_ui_data.show_toast(message);
+    }
+
+    /// Marks the selected song as a favorite, or not, and writes the favorites out
+    fn toggle_favorite(&self) {
+        let (disc_id, favorites) = {
+            let mut guard = mutex_lock!(self.ui_data);
+            let data = &mut guard.player_ui_data;
+            let song_idx = data.selected_song_idx;
+            let name = data.song_name_by_song_idx(song_idx).to_string();
+            let message = match data.favorites.insert(song_idx) {
+                true => format!("Favorite: {name}"),
+                false => {
+                    data.favorites.remove(&song_idx);
+                    format!("Not a favorite: {name}")
+                }
+            };
+            data.refresh_listing();
+            data.show_toast(message);
+            (
+                guard.disc_id.clone(),
+                guard.player_ui_data.favorites.clone(),
+            )
+        };
+        let Some(disc_id) = disc_id else {
+            return;
+        };
+        if let Err(e) = favorites::store(&disc_id, &favorites) {
+            warn!("Failed to save the favorites: {}", e);
+            mutex_lock!(self.ui_data)
+                .player_ui_data
+                .show_toast("Failed to save the favorites");
+        }
+    }
+
+    /// Writes out the bookmarks of the disc, telling if that fails
+    fn save_bookmarks(&self) {
+        let (disc_id, list) = {
+            let guard = mutex_lock!(self.ui_data);
+            (
+                guard.disc_id.clone(),
+                guard.player_ui_data.bookmarks.clone(),
+            )
+        };
+        let Some(disc_id) = disc_id else {
+            return;
+        };
+        if let Err(e) = bookmarks::store(&disc_id, &list) {
+            warn!("Failed to save the bookmarks: {}", e);
+            mutex_lock!(self.ui_data)
+                .player_ui_data
+                .show_toast("Failed to save the bookmarks");
+        }
+    }
+
+    /// Restarts initialization from scratch (probing the minfo program, reading the TOC, ...)
+    fn retry_initialization(&mut self) {
+        let mut guard = mutex_lock!(self.ui_data);
+        let context = std::mem::take(&mut guard.context);
+        *guard = UiData::new(context);
+        drop(guard);
+        failure::clear_pending();
+        self.bg_thread_started = false;
+    }
+
+    /// On the Error screen: r retries, d asks for a new drive path, m reads the meta info
+    /// from the track offered, anything else exits
+    fn error_screen_key(&mut self, code: KeyCode) {
+        let mut guard = mutex_lock!(self.ui_data);
+        let Some(input) = &mut guard.error_ui_data.drive_input else {
+            match code {
+                KeyCode::Char('r') => {
+                    drop(guard);
+                    self.retry_initialization();
+                }
+                KeyCode::Char('d') => {
+                    let drive = guard.context.disc.drive.display().to_string();
+                    guard.error_ui_data.drive_input = Some(drive);
+                }
+                KeyCode::Char('m') if guard.error_ui_data.meta_info_track.is_some() => {
+                    guard.context.disc.meta_info_track =
+                        guard.error_ui_data.meta_info_track.unwrap();
+                    drop(guard);
+                    self.retry_initialization();
+                }
+                _ => self.should_quit = true,
+            }
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let drive = std::mem::take(input);
+                guard.context.disc.drive = drive.into();
+                drop(guard);
+                self.retry_initialization();
+            }
+            KeyCode::Esc => guard.error_ui_data.drive_input = None,
+            _ => {}
+        }
+    }
+
+    /// The page to switch to, if `key` is Tab, Shift-Tab or Alt + a page number
+    fn page_switch_key(current: Page, key: KeyEvent) -> Option<Page> {
+        match key.code {
+            KeyCode::Tab => Some(current.next()),
+            KeyCode::BackTab => Some(current.prev()),
+            KeyCode::Char(c @ '1'..='9') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Page::ALL.get(c as usize - '1' as usize).copied()
+            }
+            _ => None,
+        }
+    }
+
+    /// Quits at once, or asks for a second press when music is playing
+    /// (see [`crate::config::Config::confirm_quit`])
+    fn request_quit(&mut self) {
+        let mut guard = mutex_lock!(self.ui_data);
+        let playing = guard.ui_state == AppUiState::Player
+            && matches!(guard.player_ui_data.player_state, PlayerState::Playing);
+        if !playing || !mutex_lock!(CONFIG).confirm_quit || guard.player_ui_data.quit_pending() {
+            self.should_quit = true;
+            return;
+        }
+        guard.player_ui_data.quit_requested_at = Some(Instant::now());
+    }
+
+    /// ## Key bindings
+    ///
+    /// See [`pages::KEY_BINDINGS_HELP`], which is also shown on the Help page.
+    ///
+    /// On the Error screen, `r` retries and `d` changes the drive path.
+    fn handle_event(&mut self, event: Event) -> io::Result<()> {
+        // key presses and resizes all possibly change what's shown
+        mutex_lock!(self.ui_data).dirty = true;
+        if let Event::Key(key) = event {
+            macro_rules! ui_data_guard {
+                () => {
+                    mutex_lock!(self.ui_data)
+                };
+            }
+            let song_number = ui_data_guard!().meta_info.list.len();
+
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                // Ctrl-C pressed
+                self.request_quit();
+                return Ok(());
+            }
+            if ui_data_guard!().ui_state == AppUiState::Error {
+                self.error_screen_key(key.code);
+                return Ok(());
+            }
+            let naming_bookmark = ui_data_guard!().player_ui_data.bookmark_input.is_some();
+            if key.kind == event::KeyEventKind::Press
+                && key.code == KeyCode::Char('q')
+                && !naming_bookmark
+            {
+                self.request_quit();
+            }
+
+            macro_rules! player_send {
+                ($cmd:expr) => {{
+                    playback_handle().unwrap().send($cmd);
+                }};
+            }
+            macro_rules! index_inc {
+                ($tt:tt) => {{
+                    let mut guard = ui_data_guard!();
+                    let data = &mut guard.player_ui_data;
+                    data.$tt = data.display_next(data.$tt);
+                }};
+            }
+            macro_rules! selection_move_first {
+                () => {{
+                    let mut guard = ui_data_guard!();
+                    let data = &mut guard.player_ui_data;
+                    if let Some(&first) = data.display_order.first() {
+                        data.selected_song_idx = first;
+                    }
+                }};
+            }
+            macro_rules! selection_move_last {
+                () => {{
                     let mut guard = ui_data_guard!();
-                    let idx = &mut guard.player_ui_data.$tt;
-                    *idx = wrapping_prev(*idx);
-                }}
-                macro player_goto_playing_one() {{
-                    let song_track = {
-                        let guard = ui_data_guard!();
-                        let playing_song_idx = guard.player_ui_data.playing_song_idx;
-                        guard.disc_tracks[guard.meta_info.list[playing_song_idx].session_no - 1]
+                    let data = &mut guard.player_ui_data;
+                    if let Some(&last) = data.display_order.last() {
+                        data.selected_song_idx = last;
+                    }
+                }};
+            }
+            macro_rules! index_dec {
+                ($tt:tt) => {{
+                    let mut guard = ui_data_guard!();
+                    let data = &mut guard.player_ui_data;
+                    data.$tt = data.display_prev(data.$tt);
+                }};
+            }
+            // plays the song `$idx` picks from `$data`, the player UI data, if it picks one and
+            // it's on the disc
+            macro_rules! player_goto {
+                (|$data:ident| $idx:expr) => {{
+                    let track = {
+                        let mut guard = ui_data_guard!();
+                        let $data = &mut guard.player_ui_data;
+                        let song_idx: Option<usize> = $idx;
+                        song_idx.and_then(|x| guard.switch_song(x))
                     };
-                    player_send!(PlayerCommand::Goto(song_track, true));
-                }}
-                macro playing_track() {{
-                    let guard = ui_data_guard!();
-                    guard.disc_tracks
-                        [guard.meta_info.list[guard.player_ui_data.playing_song_idx].session_no - 1]
-                }}
-
-                if ui_data_guard!().ui_state == AppUiState::Player {
-                    match key.code {
-                        KeyCode::Char('n') => {
-                            // next
-                            index_inc!(playing_song_idx);
-                            player_goto_playing_one!();
-                        }
-                        KeyCode::Char('p') => {
-                            // previous
-                            index_dec!(playing_song_idx);
-                            player_goto_playing_one!();
-                        }
-                        KeyCode::Char('j') | KeyCode::Down => {
-                            // move down
-                            index_inc!(selected_song_idx);
-                        }
-                        KeyCode::Char('k') | KeyCode::Up => {
-                            // move up
-                            index_dec!(selected_song_idx);
-                        }
-                        KeyCode::Char('h') | KeyCode::Left => {
-                            //seek backwards
-                            let PlayerResult::Position(mut p) = mutex_lock!(PLAYBACK_HANDLE)
-                                .as_ref()
-                                .unwrap()
-                                .send_recv(PlayerCommand::GetPosition)
-                            else {
-                                panic!("Unexpected player result")
-                            };
-                            p -= 5.0;
-                            if p < 0.0 {
-                                p = 0.0;
-                            }
-                            player_send!(PlayerCommand::Seek(p));
-                        }
-                        KeyCode::Char('l') | KeyCode::Right => {
-                            let PlayerResult::Position(mut p) = mutex_lock!(PLAYBACK_HANDLE)
-                                .as_ref()
-                                .unwrap()
-                                .send_recv(PlayerCommand::GetPosition)
-                            else {
-                                panic!("Unexpected player result")
-                            };
-                            let song_track = playing_track!();
-                            let duration = duration_from_bytes(song_track.size_bytes());
-                            p += 5.0;
-                            if p >= duration {
-                                p = duration - 1.0;
-                            }
-                            player_send!(PlayerCommand::Seek(p));
-                        }
-                        KeyCode::Enter => {
-                            {
-                                let mut guard = ui_data_guard!();
-                                guard.player_ui_data.playing_song_idx =
-                                    guard.player_ui_data.selected_song_idx;
+                    if let Some(track) = track {
+                        player_send!(PlayerCommand::Goto(track, true));
+                    }
+                }};
+            }
+
+            let sort_menu_open = ui_data_guard!().player_ui_data.sort_menu.is_some();
+            let resume_offered = ui_data_guard!().player_ui_data.resume_offer.is_some();
+            let in_player = ui_data_guard!().ui_state == AppUiState::Player;
+            let page = ui_data_guard!().page;
+            if in_player && resume_offered {
+                self.resume_offer_key(key.code);
+            } else if in_player && naming_bookmark {
+                self.bookmark_input_key(key.code);
+            } else if in_player && sort_menu_open {
+                ui_data_guard!().player_ui_data.sort_menu_key(key.code);
+            } else if let Some(page) = Self::page_switch_key(page, key).filter(|_| in_player) {
+                ui_data_guard!().switch_page(page);
+            } else if in_player && page == Page::Bookmarks && Page::is_bookmarks_key(key.code) {
+                self.bookmarks_page_key(key.code);
+            } else if in_player && page != Page::Player && Page::is_scroll_key(key.code) {
+                ui_data_guard!().scroll_page(key.code);
+            } else if in_player {
+                match key.code {
+                    KeyCode::Char('n') => {
+                        // next
+                        player_goto!(|data| data.skip_song(1));
+                    }
+                    KeyCode::Char('p') => {
+                        // previous
+                        player_goto!(|data| data.skip_song(-1));
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        // move down
+                        index_inc!(selected_song_idx);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        // move up
+                        index_dec!(selected_song_idx);
+                    }
+                    KeyCode::Char(c @ ('h' | 'H')) => {
+                        // seek backwards
+                        self.seek_key(-1.0, c == 'H');
+                    }
+                    KeyCode::Char(c @ ('l' | 'L')) => {
+                        // seek forward
+                        self.seek_key(1.0, c == 'L');
+                    }
+                    KeyCode::Left => {
+                        // further with Shift
+                        self.seek_key(-1.0, key.modifiers.contains(KeyModifiers::SHIFT));
+                    }
+                    KeyCode::Right => {
+                        self.seek_key(1.0, key.modifiers.contains(KeyModifiers::SHIFT));
+                    }
+                    KeyCode::Char(c @ '0'..='9') => {
+                        ui_data_guard!().player_ui_data.pending_song_number.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        ui_data_guard!().player_ui_data.pending_song_number.pop();
+                    }
+                    KeyCode::Esc => {
+                        ui_data_guard!().player_ui_data.pending_song_number.clear();
+                    }
+                    KeyCode::Enter => {
+                        let valid = {
+                            let mut guard = ui_data_guard!();
+                            let pending =
+                                std::mem::take(&mut guard.player_ui_data.pending_song_number);
+                            if pending.is_empty() {
+                                true
+                            } else {
+                                // song numbers start from one
+                                match pending.parse::<usize>() {
+                                    Ok(n) if (1..=song_number).contains(&n) => {
+                                        guard.player_ui_data.selected_song_idx = n - 1;
+                                        true
+                                    }
+                                    _ => false,
+                                }
                             }
-                            player_goto_playing_one!();
+                        };
+                        if valid {
+                            player_goto!(|data| Some(data.selected_song_idx));
                         }
-                        KeyCode::Char(' ') => {
-                            let PlayerResult::IsPaused(paused) = mutex_lock!(PLAYBACK_HANDLE)
-                                .as_ref()
-                                .unwrap()
-                                .send_recv(PlayerCommand::GetIsPaused)
-                            else {
-                                panic!("Unexpected player result")
-                            };
-                            let toggle = !paused;
-                            player_send!(PlayerCommand::SetPaused(toggle));
-                        }
-                        KeyCode::Char(',') => {
-                            // volume down
-                            let volume = {
-                                let mut guard = ui_data_guard!();
-                                let volume = &mut guard.player_ui_data.volume;
-                                *volume -= 0.01;
-                                if *volume <= 0.0 {
-                                    *volume = 0.0;
-                                }
-                                *volume
-                            };
-                            player_send!(PlayerCommand::ChangeVolume(volume));
+                    }
+                    KeyCode::Char(' ') => {
+                        if let Some(paused) = playback_handle().unwrap().is_paused() {
+                            player_send!(PlayerCommand::SetPaused(!paused));
                         }
-                        KeyCode::Char('.') => {
-                            // volume up
-                            let volume = {
-                                let mut guard = ui_data_guard!();
-                                let volume = &mut guard.player_ui_data.volume;
-                                *volume += 0.01;
-                                if *volume >= 1.0 {
-                                    *volume = 1.0;
+                    }
+                    KeyCode::Char(',') => self.change_volume(-1),
+                    KeyCode::Char('.') => self.change_volume(1),
+                    KeyCode::Char('<') => self.change_volume(-VOLUME_COARSE_STEPS),
+                    KeyCode::Char('>') => self.change_volume(VOLUME_COARSE_STEPS),
+                    KeyCode::Char('g') | KeyCode::Home => {
+                        selection_move_first!();
+                    }
+                    KeyCode::Char('G') | KeyCode::End => {
+                        selection_move_last!();
+                    }
+                    KeyCode::Char('o') => ui_data_guard!().player_ui_data.select_playing(),
+                    KeyCode::Char('s') => {
+                        // open the sort menu, highlighting the current order
+                        let mut guard = ui_data_guard!();
+                        let data = &mut guard.player_ui_data;
+                        let current = SortOrder::ALL.iter().position(|&x| x == data.sort_order);
+                        data.sort_menu = current;
+                    }
+                    KeyCode::Char('z') => {
+                        let shuffle = {
+                            let mut guard = ui_data_guard!();
+                            let data = &mut guard.player_ui_data;
+                            data.set_shuffle(!data.shuffle);
+                            data.show_toast(match data.shuffle {
+                                true => "Shuffle: on",
+                                false => "Shuffle: off",
+                            });
+                            data.shuffle
+                        };
+                        update_config(|c| c.shuffle = shuffle);
+                    }
+                    KeyCode::Char('R') => {
+                        // switch to the next repeat mode
+                        let repeat = {
+                            let mut guard = ui_data_guard!();
+                            let data = &mut guard.player_ui_data;
+                            data.repeat = data.repeat.next();
+                            data.show_toast(format!("Repeat: {}", data.repeat.name()));
+                            data.repeat
+                        };
+                        update_config(|c| c.repeat = repeat);
+                    }
+                    KeyCode::Char('T') => {
+                        // switch to the next palette
+                        let palette = {
+                            let mut guard = ui_data_guard!();
+                            let data = &mut guard.player_ui_data;
+                            data.palette = data.palette.next();
+                            data.show_toast(format!("Palette: {}", data.palette.name()));
+                            data.palette
+                        };
+                        update_config(|c| c.palette = palette);
+                    }
+                    KeyCode::Char('i') => {
+                        let mut guard = ui_data_guard!();
+                        let data = &mut guard.player_ui_data;
+                        let info = format!(
+                            "{}: {}, {}, volume {} %",
+                            data.playing_song_idx + 1,
+                            data.song_name_by_song_idx(data.playing_song_idx),
+                            duration_string((data.current_position, data.total_duration), false),
+                            volume_percent(data.volume)
+                        );
+                        data.show_toast(info);
+                    }
+                    KeyCode::Char('y') => self.copy_playing_song(),
+                    KeyCode::Char('c') => self.start_song_checks(),
+                    KeyCode::Char('[') => self.skip_chapter(false),
+                    KeyCode::Char(']') => self.skip_chapter(true),
+                    KeyCode::Char('*') => self.toggle_favorite(),
+                    KeyCode::Char('F') => {
+                        let favorites_only = {
+                            let mut guard = ui_data_guard!();
+                            let data = &mut guard.player_ui_data;
+                            data.favorites_only = !data.favorites_only;
+                            data.refresh_listing();
+                            let message = match data.favorites_only {
+                                true if data.favorites.is_empty() => {
+                                    "No favorites yet; * marks the selected song"
                                 }
-                                *volume
+                                true => "Favorites only: on",
+                                false => "Favorites only: off",
                             };
-                            player_send!(PlayerCommand::ChangeVolume(volume));
-                        }
-                        KeyCode::Char('g') | KeyCode::Home => {
-                            selection_move_first!();
-                        }
-                        KeyCode::Char('G') | KeyCode::End => {
-                            selection_move_last!();
-                        }
-                        _ => {}
+                            data.show_toast(message);
+                            data.favorites_only
+                        };
+                        update_config(|c| c.favorites_only = favorites_only);
+                    }
+                    KeyCode::Char('b') => {
+                        let mut guard = ui_data_guard!();
+                        let data = &mut guard.player_ui_data;
+                        data.bookmark_input = Some(Bookmark {
+                            name: String::new(),
+                            song_idx: data.playing_song_idx,
+                            position: data.current_position,
+                        });
+                    }
+                    KeyCode::Char('t') => {
+                        // toggle elapsed/remaining time
+                        let show_remaining_time = {
+                            let mut guard = ui_data_guard!();
+                            let data = &mut guard.player_ui_data;
+                            data.show_remaining_time = !data.show_remaining_time;
+                            let show_remaining_time = data.show_remaining_time;
+                            data.show_toast(match show_remaining_time {
+                                true => "Showing remaining time",
+                                false => "Showing elapsed time",
+                            });
+                            show_remaining_time
+                        };
+                        update_config(|c| c.show_remaining_time = show_remaining_time);
                     }
-                    debug!("{:?}", key);
+                    _ => {}
                 }
+                debug!("{:?}", key);
             }
         }
         Ok(())
@@ -0,0 +1,288 @@
+//! `attach`: a TUI for a player playing elsewhere (like with `--daemon`), over its control
+//! socket
+//!
+//! It shows what the player publishes and sends it commands, so quitting it leaves the
+//! player playing. Any number of them can attach at once.
+
+use std::io::{stdout, BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use ratatui::crossterm::event;
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Alignment, Constraint};
+use ratatui::prelude::{CrosstermBackend, Layout};
+use ratatui::widgets::{Block, List, ListItem, Padding, Paragraph};
+use ratatui::{Frame, Terminal};
+use serde::Deserialize;
+
+use super::gauge::ProgressGauge;
+use super::{
+    clean_up_tui, duration_string, play_modes_text, set_up_tui, volume_percent, PLAYING_INDICATOR,
+    SELECTED_INDICATOR, VOLUME_COARSE_STEPS,
+};
+use crate::config::{seek_step, volume_step, CONFIG};
+use crate::format_duration;
+use crate::mutex_lock;
+use crate::palette::PaletteStyles;
+use crate::playback::stepped_volume;
+use crate::remote::{PlaybackState, PlayerStatus};
+use crate::text_width::right_aligned_row;
+
+/// How often the status is fetched
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+const KEYS_HELP: &str =
+    "Space: Pause  n/p: Next/Prev  h/l: Seek  ,/.: Volume  Enter: Play  q: Detach  Q: Stop player";
+
+/// A reply line of the control socket
+#[derive(Deserialize)]
+struct Response {
+    ok: bool,
+    error: Option<String>,
+    status: Option<PlayerStatus>,
+}
+
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Connection {
+    fn connect(path: &Path) -> anyhow::Result<Self> {
+        let stream = UnixStream::connect(path).map_err(|e| {
+            anyhow!(
+                "Can't connect to {} ({e}); is a player running, like with `--daemon`?",
+                path.display()
+            )
+        })?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    /// Sends a command line; returns the reply
+    fn request(&mut self, line: &str) -> anyhow::Result<Response> {
+        writeln!(self.writer, "{line}")?;
+        let mut reply = String::new();
+        if self.reader.read_line(&mut reply)? == 0 {
+            bail!("The player is gone");
+        }
+        Ok(serde_json::from_str(&reply)?)
+    }
+
+    fn status(&mut self) -> anyhow::Result<PlayerStatus> {
+        self.request("status")?
+            .status
+            .ok_or_else(|| anyhow!("The player sent no status"))
+    }
+}
+
+struct Client {
+    connection: Connection,
+    status: PlayerStatus,
+    /// Index of the selected song
+    selected: usize,
+    /// The error of the last command
+    error: Option<String>,
+    styles: PaletteStyles,
+}
+
+impl Client {
+    fn draw(&self, frame: &mut Frame) {
+        let status = &self.status;
+        let [header_rect, list_rect, title_rect, gauge_rect, help_rect] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.size());
+
+        let title = status.disc_title.as_deref().unwrap_or("Pseudo-CD");
+        frame.render_widget(
+            Block::new().title(title).title_alignment(Alignment::Center),
+            header_rect,
+        );
+        frame.render_widget(
+            Block::new()
+                .title(play_modes_text(status.shuffle, status.repeat))
+                .title_alignment(Alignment::Left),
+            header_rect,
+        );
+
+        let playing_idx = status.song_number.and_then(|x| x.checked_sub(1));
+        let list_width = list_rect.width.saturating_sub(2) as usize;
+        let items = status.songs.iter().enumerate().map(|(i, song)| {
+            let playing_mark = match playing_idx == Some(i) {
+                true => PLAYING_INDICATOR,
+                false => ' ',
+            };
+            let selected_mark = match self.selected == i {
+                true => SELECTED_INDICATOR,
+                false => ' ',
+            };
+            let left = format!("{playing_mark}{selected_mark} {}: {}", i + 1, song.name);
+            let mut item = ListItem::new(right_aligned_row(
+                &left,
+                &format_duration(song.duration),
+                list_width,
+            ));
+            if self.selected == i {
+                item = item.style(self.styles.selected_row);
+            }
+            if playing_idx == Some(i) {
+                item = item.style(self.styles.playing_row);
+            }
+            item
+        });
+        let list_height = list_rect.height.max(1) as usize;
+        let page_no = self.selected / list_height;
+        frame.render_widget(
+            List::new(items.skip(page_no * list_height))
+                .block(Block::new().padding(Padding::horizontal(1))),
+            list_rect,
+        );
+
+        let state_str = match status.state {
+            PlaybackState::Playing => "Playing: ",
+            PlaybackState::Paused => "Paused: ",
+            PlaybackState::Stopped => "Stopped",
+        };
+        let song_name = status.playing_song().map(|x| x.name.as_str());
+        frame.render_widget(
+            Block::new()
+                .title(format!("{state_str}{}", song_name.unwrap_or_default()))
+                .title_alignment(Alignment::Center),
+            title_rect,
+        );
+        frame.render_widget(
+            Block::new()
+                .title(format!("Volume: {}", volume_percent(status.volume)))
+                .title_alignment(Alignment::Right),
+            title_rect,
+        );
+
+        let duration = status.playing_song().map_or(0, |x| x.duration);
+        let ratio = match duration {
+            0 => 0.0,
+            x => (status.position as f64 / x as f64).clamp(0.0, 1.0),
+        };
+        // the player's read-ahead isn't in its status
+        frame.render_widget(
+            ProgressGauge {
+                label: duration_string((status.position, duration), false),
+                played: ratio,
+                buffered: ratio,
+                styles: &self.styles,
+            },
+            gauge_rect,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.error.as_deref().unwrap_or(KEYS_HELP)),
+            help_rect,
+        );
+    }
+
+    /// Sends `command` and fetches the new status
+    fn send(&mut self, command: &str) -> anyhow::Result<()> {
+        let response = self.connection.request(command)?;
+        self.error = match response.ok {
+            true => None,
+            false => response.error,
+        };
+        self.status = self.connection.status()?;
+        Ok(())
+    }
+
+    /// Changes the volume by `steps` of the configured volume step
+    fn change_volume(&mut self, steps: i32) -> anyhow::Result<()> {
+        let volume = stepped_volume(self.status.volume, steps, volume_step(None));
+        self.send(&format!("volume {}", volume * 100.0))
+    }
+
+    /// Seeks by a step of the config file, in `direction` (-1.0 or 1.0)
+    fn seek(&mut self, direction: f64, long: bool) -> anyhow::Result<()> {
+        let offset = direction * seek_step(None, long);
+        self.send(&format!("seek {offset:+}"))
+    }
+
+    fn move_selection(&mut self, to: usize) {
+        self.selected = to.min(self.status.songs.len().saturating_sub(1));
+    }
+
+    /// Handles a key; returns whether to quit
+    fn handle_key(&mut self, key: KeyEvent) -> anyhow::Result<bool> {
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+            KeyCode::Char('Q') => {
+                // the player may be gone before it replies
+                let _ = self.connection.request("quit");
+                return Ok(true);
+            }
+            KeyCode::Char(' ') => self.send("toggle")?,
+            KeyCode::Char('n') => self.send("next")?,
+            KeyCode::Char('p') => self.send("prev")?,
+            KeyCode::Char('h') => self.seek(-1.0, false)?,
+            KeyCode::Char('l') => self.seek(1.0, false)?,
+            KeyCode::Char('H') => self.seek(-1.0, true)?,
+            KeyCode::Char('L') => self.seek(1.0, true)?,
+            KeyCode::Left => self.seek(-1.0, shift)?,
+            KeyCode::Right => self.seek(1.0, shift)?,
+            KeyCode::Char(',') => self.change_volume(-1)?,
+            KeyCode::Char('.') => self.change_volume(1)?,
+            KeyCode::Char('<') => self.change_volume(-VOLUME_COARSE_STEPS)?,
+            KeyCode::Char('>') => self.change_volume(VOLUME_COARSE_STEPS)?,
+            KeyCode::Char('j') | KeyCode::Down => self.move_selection(self.selected + 1),
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.move_selection(self.selected.saturating_sub(1))
+            }
+            KeyCode::Char('g') | KeyCode::Home => self.move_selection(0),
+            KeyCode::Char('G') | KeyCode::End => self.move_selection(usize::MAX),
+            KeyCode::Enter if !self.status.songs.is_empty() => {
+                self.send(&format!("goto {}", self.selected + 1))?
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn run(&mut self) -> anyhow::Result<()> {
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(POLL_INTERVAL)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && self.handle_key(key)? {
+                        return Ok(());
+                    }
+                }
+            } else {
+                self.status = self.connection.status()?;
+            }
+        }
+    }
+}
+
+/// Attaches to the player listening on `socket_path` until `q`
+pub fn run(socket_path: &Path) -> anyhow::Result<()> {
+    let mut connection = Connection::connect(socket_path)?;
+    let status = connection.status()?;
+    let mut client = Client {
+        connection,
+        selected: status.song_number.map_or(0, |x| x.saturating_sub(1)),
+        status,
+        error: None,
+        styles: mutex_lock!(CONFIG).palette.styles(),
+    };
+    set_up_tui(true)?;
+    let result = client.run();
+    clean_up_tui()?;
+    result
+}
@@ -0,0 +1,69 @@
+//! The progress gauge, drawn in eighths of a cell with partial block characters, and
+//! with what the player has read ahead of the position in a second shade
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::prelude::Style;
+use ratatui::widgets::Widget;
+
+use crate::palette::PaletteStyles;
+
+/// Blocks filling 1 to 7 eighths of a cell from the left
+const PARTIAL_BLOCKS: [&str; 7] = ["▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+pub struct ProgressGauge<'a> {
+    pub label: String,
+    /// How far the song has played, from 0 to 1
+    pub played: f64,
+    /// How far it has been read, from `played` to 1
+    pub buffered: f64,
+    pub styles: &'a PaletteStyles,
+}
+
+impl Widget for ProgressGauge<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.is_empty() {
+            return;
+        }
+        let (label_end, _) = buf.set_stringn(
+            area.x,
+            area.y,
+            &self.label,
+            area.width as usize,
+            Style::default(),
+        );
+        // a space after the label, as `LineGauge` has
+        let start = label_end.saturating_add(1);
+        if start >= area.right() {
+            return;
+        }
+        let width = area.right() - start;
+        let eighths = |ratio: f64| {
+            let ratio = if ratio.is_finite() { ratio } else { 0.0 };
+            (ratio.clamp(0.0, 1.0) * width as f64 * 8.0).round() as u32
+        };
+        let played = eighths(self.played);
+        let buffered = eighths(self.buffered).max(played);
+        let styles = self.styles;
+        for i in 0..width {
+            let cell_start = i as u32 * 8;
+            let (symbol, style) = if played >= cell_start + 8 {
+                ("█", styles.gauge_filled)
+            } else if played > cell_start {
+                // the rest of the cell in the read-ahead shade, if it's read
+                let style = match styles.gauge_buffered.fg {
+                    Some(color) if buffered >= cell_start + 8 => styles.gauge_filled.bg(color),
+                    _ => styles.gauge_filled,
+                };
+                (PARTIAL_BLOCKS[(played - cell_start - 1) as usize], style)
+            } else if buffered > cell_start {
+                ("█", styles.gauge_buffered)
+            } else {
+                ("─", styles.gauge_unfilled)
+            };
+            buf.get_mut(start + i, area.y)
+                .set_symbol(symbol)
+                .set_style(style);
+        }
+    }
+}
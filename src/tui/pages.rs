@@ -0,0 +1,431 @@
+//! Pages of the player UI, switched between with Tab
+//!
+//! A new view is a new [`Page`] variant plus its draw function here.
+
+use std::sync::atomic::Ordering;
+
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::prelude::{Modifier, Style};
+use ratatui::widgets::{Clear, List, ListItem, Paragraph, Tabs};
+use ratatui::Frame;
+
+use crate::play_mode::RepeatMode;
+use crate::playback::{duration_from_bytes, playback_handle, AUDIO_OUTPUT};
+use crate::text_width;
+use crate::text_width::right_aligned_row;
+use crate::{format_duration, mutex_lock, LOG_LINES};
+
+use super::{
+    draw_quit_confirm_popup, draw_resume_popup, UiData, PLAYING_INDICATOR, SELECTED_INDICATOR,
+};
+
+/// Key bindings, as shown on [`Page::Help`]
+pub(super) const KEY_BINDINGS_HELP: &str = "\
+Space: Play/Pause
+n: Next
+p: Previous
+j, ArrowDown: Selection move up
+k, ArrowUp: Selection move down
+g, Home: Move selection to the first
+G, End: Move selection to the last
+o: Move selection to the playing song
+h, ArrowLeft: Seek backwards, 5 seconds by default (hold to scrub)
+l, ArrowRight: Seek forward, 5 seconds by default (hold to scrub)
+H, Shift+ArrowLeft: Seek backwards further, 30 seconds by default
+L, Shift+ArrowRight: Seek forward further, 30 seconds by default
+Enter: Play the selection
+0-9, Enter: Play the song with the typed number (Esc to cancel)
+,: Volume down
+.: Volume up
+<: Volume down five steps
+>: Volume up five steps
+t: Toggle elapsed/remaining time
+s: Sort the list (by disc order, name or duration)
+z: Toggle shuffle
+R: Switch the repeat mode (all, one, single or off)
+T: Switch the color palette
+i: Tell the playing song, position and volume
+y: Copy the playing song and position to the clipboard
+c: Check the songs against their hashes, reading the disc alongside playback
+b: Bookmark the playing position, naming it (Enter for the default name)
+[, ]: Skip to the previous/next chapter of the playing song
+*: Mark the selected song as a favorite, or not
+F: List and play only the favorites (or all songs)
+Tab, Shift-Tab: Switch to the next/previous page
+Alt-1..Alt-7: Switch to a page by its number
+y/Enter, n/Esc: Resume where the disc was left off, or not (when asked)
+q: Quit
+
+On pages other than Player, j/k/g/G (and the arrow, Home and End keys) scroll the page.
+On the Bookmarks page, they select a bookmark instead; Enter jumps to it, d deletes it.";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Page {
+    Player,
+    /// Songs coming up after the playing one
+    Queue,
+    /// Named positions of the disc to jump back to
+    Bookmarks,
+    DiscInfo,
+    /// The tracks table of the disc, as parsed from the minfo program output
+    Toc,
+    /// Recent program log
+    Logs,
+    Help,
+}
+
+impl Page {
+    pub(super) const ALL: [Page; 7] = [
+        Page::Player,
+        Page::Queue,
+        Page::Bookmarks,
+        Page::DiscInfo,
+        Page::Toc,
+        Page::Logs,
+        Page::Help,
+    ];
+
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Page::Player => "Player",
+            Page::Queue => "Queue",
+            Page::Bookmarks => "Bookmarks",
+            Page::DiscInfo => "Disc Info",
+            Page::Toc => "TOC",
+            Page::Logs => "Logs",
+            Page::Help => "Help",
+        }
+    }
+
+    pub(super) fn index(&self) -> usize {
+        Self::ALL.iter().position(|x| x == self).unwrap_or_default()
+    }
+
+    pub(super) fn next(&self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub(super) fn prev(&self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Keys that scroll the pages other than [`Page::Player`]
+    pub(super) fn is_scroll_key(code: KeyCode) -> bool {
+        matches!(
+            code,
+            KeyCode::Char('j' | 'k' | 'g' | 'G')
+                | KeyCode::Down
+                | KeyCode::Up
+                | KeyCode::Home
+                | KeyCode::End
+        )
+    }
+}
+
+impl Page {
+    /// Keys of [`Page::Bookmarks`], which select instead of scrolling
+    pub(super) fn is_bookmarks_key(code: KeyCode) -> bool {
+        Self::is_scroll_key(code)
+            || matches!(code, KeyCode::Enter | KeyCode::Char('d') | KeyCode::Delete)
+    }
+}
+
+impl UiData {
+    pub(super) fn switch_page(&mut self, page: Page) {
+        self.page = page;
+        self.page_scroll = 0;
+    }
+
+    /// See [`Page::is_scroll_key`]
+    pub(super) fn scroll_page(&mut self, code: KeyCode) {
+        let towards_start = matches!(code, KeyCode::Char('k' | 'g') | KeyCode::Up | KeyCode::Home);
+        let to_edge = matches!(
+            code,
+            KeyCode::Char('g' | 'G') | KeyCode::Home | KeyCode::End
+        );
+        // the Logs page counts its scrolling offset from the end
+        let increase = towards_start == (self.page == Page::Logs);
+        self.page_scroll = match (to_edge, increase) {
+            // clamped when drawn
+            (true, true) => usize::MAX,
+            (true, false) => 0,
+            (false, true) => self.page_scroll.saturating_add(1),
+            (false, false) => self.page_scroll.saturating_sub(1),
+        };
+    }
+
+    /// Draws the tab bar, the current page and the popups on top
+    pub(super) fn draw_pages(&mut self, frame: &mut Frame, rect: Rect) {
+        let layout = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(rect);
+        let (tabs_rect, page_rect) = (layout[0], layout[1]);
+
+        let styles = self.player_ui_data.palette.styles();
+        let titles = Page::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("{} {}", i + 1, x.name()));
+        frame.render_widget(
+            Tabs::new(titles)
+                .select(self.page.index())
+                .highlight_style(styles.selected_row),
+            tabs_rect,
+        );
+
+        match self.page {
+            Page::Player => {
+                self.animating = self.player_ui_data.draw_to(frame, page_rect);
+            }
+            Page::Queue => {
+                let lines = self.queue_lines(page_rect.width as usize);
+                draw_scrolled(frame, page_rect, lines, &mut self.page_scroll, false);
+            }
+            Page::Bookmarks => {
+                let lines = self.bookmark_lines(page_rect.width as usize);
+                // keeps the selected one in view
+                let selected = self.player_ui_data.selected_bookmark;
+                self.page_scroll = selected.saturating_sub(page_rect.height as usize - 1);
+                draw_scrolled(frame, page_rect, lines, &mut self.page_scroll, false);
+            }
+            Page::DiscInfo => {
+                let lines = self.disc_info_lines();
+                draw_scrolled(frame, page_rect, lines, &mut self.page_scroll, false);
+            }
+            Page::Toc => {
+                let lines = self.toc_lines();
+                draw_scrolled(frame, page_rect, lines, &mut self.page_scroll, false);
+            }
+            Page::Logs => {
+                let lines = mutex_lock!(LOG_LINES).iter().cloned().collect();
+                draw_scrolled(frame, page_rect, lines, &mut self.page_scroll, true);
+            }
+            Page::Help => {
+                let lines = KEY_BINDINGS_HELP.lines().map(String::from).collect();
+                draw_scrolled(frame, page_rect, lines, &mut self.page_scroll, false);
+            }
+        }
+
+        if let Some(toast) = self.player_ui_data.active_toast() {
+            // over the right part of the tab bar, so it shows on every page
+            let width = (text_width::width(toast) as u16 + 2).min(tabs_rect.width);
+            let toast_rect = Rect::new(tabs_rect.right() - width, tabs_rect.y, width, 1);
+            frame.render_widget(Clear, toast_rect);
+            frame.render_widget(
+                Paragraph::new(toast)
+                    .alignment(Alignment::Center)
+                    .style(Style::default().add_modifier(Modifier::REVERSED)),
+                toast_rect,
+            );
+            self.animating = true;
+        }
+        if let Some(text) = self.player_ui_data.bookmark_input_text() {
+            frame.render_widget(
+                Paragraph::new(text).style(Style::default().add_modifier(Modifier::REVERSED)),
+                Rect::new(rect.x, rect.bottom().saturating_sub(1), rect.width, 1),
+            );
+        }
+        if let Some(highlighted) = self.player_ui_data.sort_menu {
+            self.player_ui_data.draw_sort_menu(frame, rect, highlighted);
+        }
+        if let Some(text) = self.player_ui_data.resume_offer_text() {
+            draw_resume_popup(frame, rect, &text);
+        }
+        if self.player_ui_data.quit_pending() {
+            draw_quit_confirm_popup(frame, rect);
+            self.animating = true;
+        }
+    }
+
+    /// The playing song and all following ones in play order, each with the time
+    /// until it starts
+    pub(super) fn queue_lines(&self, width: usize) -> Vec<String> {
+        let data = &self.player_ui_data;
+        let mut song_idx = data.playing_song_idx;
+        let mut starts_in = data.total_duration.saturating_sub(data.current_position);
+        let mut lines = Vec::new();
+        // with repeat off, playing stops after the last song; with repeat one or single,
+        // nothing follows
+        let count = match data.repeat {
+            RepeatMode::Off => data.play_order().len() - data.play_position(song_idx),
+            RepeatMode::All => data.play_order().len(),
+            RepeatMode::One | RepeatMode::Single => 1,
+        };
+        for n in 0..count {
+            let name = format!("{}: {}", song_idx + 1, data.song_name_by_song_idx(song_idx));
+            let line = if n == 0 {
+                right_aligned_row(&format!("{PLAYING_INDICATOR} {name}"), "now", width)
+            } else {
+                let right = format!("in {}", format_duration(starts_in));
+                starts_in += data
+                    .song_durations
+                    .get(song_idx)
+                    .copied()
+                    .unwrap_or_default();
+                right_aligned_row(&format!("  {name}"), &right, width)
+            };
+            lines.push(line);
+            song_idx = data.play_next(song_idx);
+        }
+        lines
+    }
+
+    /// The bookmarks of the disc, the selected one marked, each with where it is
+    pub(super) fn bookmark_lines(&self, width: usize) -> Vec<String> {
+        let data = &self.player_ui_data;
+        if data.bookmarks.is_empty() {
+            return vec!["No bookmarks yet; b bookmarks the playing position".into()];
+        }
+        let mut lines = Vec::new();
+        for (i, x) in data.bookmarks.iter().enumerate() {
+            let mark = match i == data.selected_bookmark {
+                true => SELECTED_INDICATOR,
+                false => ' ',
+            };
+            let place = format!(
+                "{}: {}, {}",
+                x.song_idx + 1,
+                data.song_name_by_song_idx(x.song_idx),
+                format_duration(x.position)
+            );
+            lines.push(right_aligned_row(
+                &format!("{mark} {}", x.name),
+                &place,
+                width,
+            ));
+        }
+        lines
+    }
+
+    pub(super) fn disc_info_lines(&self) -> Vec<String> {
+        let disc = &self.context.disc;
+        let unknown = || String::from("unknown");
+        let created = self.meta_info.creation_time_string();
+        let meta_info_track = match disc.no_meta {
+            true => String::from("none (--no-meta)"),
+            false => disc.meta_info_track.to_string(),
+        };
+        let total_duration: u32 = self.player_ui_data.song_durations.iter().sum();
+        let read_errors = self.player_ui_data.read_errors.summary();
+        let mut lines = vec![
+            format!("Drive: {}", disc.location()),
+            format!(
+                "Title: {}",
+                self.meta_info.title.clone().unwrap_or_else(unknown)
+            ),
+            format!("Created: {}", created.unwrap_or_else(unknown)),
+            format!("Disc ID: {}", self.disc_id.clone().unwrap_or_else(unknown)),
+            format!("Tracks: {}", self.disc_tracks.len()),
+            format!(
+                "Songs: {}, {}",
+                self.meta_info.list.len(),
+                format_duration(total_duration)
+            ),
+            format!("Meta info track: {meta_info_track}"),
+            format!(
+                "Minfo program: {}",
+                self.minfo_version.clone().unwrap_or_else(unknown)
+            ),
+            format!(
+                "Read errors: {}",
+                read_errors.unwrap_or_else(|| "none".into())
+            ),
+        ];
+        if let Some(checks) = self.player_ui_data.song_checks_summary() {
+            lines.push(format!("Song hashes: {checks}"));
+        }
+        lines.extend(audio_output_lines());
+        lines
+    }
+
+    /// One row per physical track, with what the meta info puts there
+    ///
+    /// Songs refer to tracks by their position in this table (their `session_no`),
+    /// so songs pointing past its end are listed at the bottom.
+    pub(super) fn toc_lines(&self) -> Vec<String> {
+        let disc = &self.context.disc;
+        let meta_info_track = (!disc.no_meta).then_some(disc.meta_info_track);
+        let mut lines = vec![format!(
+            "{:>3} {:>5} {:>4} {:<5} {:>10} {:>10} {:>8} {:>6}  Content",
+            "#", "Track", "Sess", "Type", "Start", "End", "Size", "Length"
+        )];
+        for (i, t) in self.disc_tracks.iter().enumerate() {
+            let position = i + 1;
+            let mut content = self
+                .meta_info
+                .list
+                .iter()
+                .filter(|x| x.session_no == position)
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>();
+            if meta_info_track == Some(position) {
+                content.insert(0, "(meta info)");
+            }
+            lines.push(format!(
+                "{:>3} {:>5} {:>4} {:<5} {:>10} {:>10} {:>8} {:>6}  {}",
+                position,
+                t.track_no,
+                t.session_no,
+                t.type_name(),
+                t.start_addr,
+                t.end_addr,
+                t.size,
+                format_duration(duration_from_bytes(t.size_bytes()) as u32),
+                content.join(", ")
+            ));
+        }
+        for (i, x) in self.meta_info.list.iter().enumerate() {
+            if !(1..=self.disc_tracks.len()).contains(&x.session_no) {
+                lines.push(format!(
+                    "Song {}: {} refers to track #{}, which doesn't exist",
+                    i + 1,
+                    x.name,
+                    x.session_no
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// Draws `lines` scrolled by `scroll` lines, clamping `scroll` to what's scrollable
+///
+/// With `from_end` set, `scroll` counts from the last line, so new lines stay in view.
+fn draw_scrolled(
+    frame: &mut Frame,
+    rect: Rect,
+    lines: Vec<String>,
+    scroll: &mut usize,
+    from_end: bool,
+) {
+    let max_scroll = lines.len().saturating_sub(rect.height as usize);
+    *scroll = (*scroll).min(max_scroll);
+    let skip = match from_end {
+        true => max_scroll - *scroll,
+        false => *scroll,
+    };
+    let items = lines.into_iter().skip(skip).map(ListItem::new);
+    frame.render_widget(List::new(items), rect);
+}
+
+/// The audio device and its format, as opened, and the buffer size it plays with
+fn audio_output_lines() -> Vec<String> {
+    let Some(output) = mutex_lock!(AUDIO_OUTPUT).clone() else {
+        return vec![String::from("Audio output: not open")];
+    };
+    let frames = playback_handle().map_or(0, |x| x.stats().callback_frames.load(Ordering::Relaxed));
+    let buffer = match frames {
+        0 => String::from("unknown until playing"),
+        frames => format!(
+            "{frames} frames ({:.1} ms)",
+            frames as f64 * 1000.0 / output.sample_rate as f64
+        ),
+    };
+    vec![
+        format!("Audio output: {}", output.device),
+        format!(
+            "Audio format: {} Hz, {} channels, {}",
+            output.sample_rate, output.channels, output.sample_format
+        ),
+        format!("Audio buffer: {buffer}"),
+    ]
+}
@@ -0,0 +1,208 @@
+//! `--screen-reader` output: state changes are printed as plain lines instead of drawing a screen
+
+use std::time::Instant;
+
+use crate::{format_duration, mutex_lock, LOG_LINES};
+
+use super::pages::{Page, KEY_BINDINGS_HELP};
+use super::{AppUiState, PlayerState, SortOrder, StepState, UiData, NO_SONGS_LINES};
+
+/// Width the page contents are laid out in
+const PAGE_LINE_WIDTH: usize = 80;
+/// Only this many of the newest lines are printed for the Logs page
+const ANNOUNCED_LOG_LINES: usize = 20;
+
+/// What has been announced so far; anything differing from it is announced next
+#[derive(Clone, Debug, Default)]
+pub(super) struct Announced {
+    ui_state: Option<AppUiState>,
+    starting_steps: Vec<String>,
+    drive_input_open: bool,
+    playing_song_idx: Option<usize>,
+    paused: Option<bool>,
+    selected_song_idx: Option<usize>,
+    toast_shown_at: Option<Instant>,
+    quit_pending: bool,
+    pending_song_number: String,
+    sort_menu: Option<usize>,
+    bookmark_input_open: bool,
+    selected_bookmark: Option<usize>,
+    /// The playing song and the index of its playing chapter
+    chapter: Option<(usize, usize)>,
+    page: Option<Page>,
+    resume_offered: bool,
+}
+
+impl UiData {
+    /// Lines describing what changed since the last call
+    pub(super) fn announcements(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let last = std::mem::take(&mut self.announced);
+        let entered = last.ui_state.as_ref() != Some(&self.ui_state);
+        self.announced.ui_state = Some(self.ui_state.clone());
+
+        match self.ui_state {
+            AppUiState::Starting => {
+                let steps = self
+                    .starting_ui_data
+                    .steps
+                    .iter()
+                    .filter(|x| x.1 != StepState::Waiting)
+                    .map(|(label, state)| format!("{label}: {}", state.name()))
+                    .collect::<Vec<_>>();
+                for step in &steps {
+                    if !last.starting_steps.contains(step) {
+                        lines.push(step.clone());
+                    }
+                }
+                self.announced.starting_steps = steps;
+            }
+            AppUiState::Error => {
+                if entered {
+                    lines.push(self.error_ui_data.title.into());
+                    lines.extend(self.error_ui_data.guidance.map(String::from));
+                    lines.extend(self.error_ui_data.content.lines().map(String::from));
+                    lines.extend(self.error_ui_data.meta_info_offer());
+                }
+                let drive_input_open = self.error_ui_data.drive_input.is_some();
+                if drive_input_open && !last.drive_input_open {
+                    lines.push("Type the new drive path, Enter to retry, Esc to cancel".into());
+                }
+                self.announced.drive_input_open = drive_input_open;
+            }
+            AppUiState::Player => self.player_announcements(&last, entered, &mut lines),
+            AppUiState::NoSongs => {
+                if entered {
+                    lines.extend(NO_SONGS_LINES.map(String::from));
+                }
+            }
+        }
+        lines
+    }
+
+    fn player_announcements(&mut self, last: &Announced, entered: bool, lines: &mut Vec<String>) {
+        let data = &self.player_ui_data;
+        if entered {
+            let total_duration: u32 = data.song_durations.iter().sum();
+            lines.push(format!(
+                "Ready: {} songs, {}. Press Alt-{} for help.",
+                data.meta_info.list.len(),
+                format_duration(total_duration),
+                Page::Help.index() + 1
+            ));
+        }
+
+        let song_description = |idx: usize| {
+            format!(
+                "{}: {}, {}",
+                idx + 1,
+                data.song_name_by_song_idx(idx),
+                format_duration(data.song_durations.get(idx).copied().unwrap_or_default())
+            )
+        };
+        let paused = matches!(data.player_state, PlayerState::Paused);
+        if last.playing_song_idx != Some(data.playing_song_idx) {
+            lines.push(format!(
+                "{} {}",
+                if paused { "Paused at" } else { "Playing" },
+                song_description(data.playing_song_idx)
+            ));
+        } else if last.paused.is_some_and(|x| x != paused) {
+            lines.push(if paused { "Paused" } else { "Resumed" }.into());
+        }
+        if last
+            .selected_song_idx
+            .is_some_and(|x| x != data.selected_song_idx)
+        {
+            lines.push(format!(
+                "Selected {}",
+                song_description(data.selected_song_idx)
+            ));
+        }
+
+        let toast_shown_at = data.toast.as_ref().map(|(_, shown_at)| *shown_at);
+        if toast_shown_at != last.toast_shown_at {
+            lines.extend(data.active_toast().map(String::from));
+        }
+        let resume_offer = data.resume_offer_text();
+        if let Some(text) = resume_offer.as_ref().filter(|_| !last.resume_offered) {
+            lines.push(text.clone());
+        }
+        let quit_pending = data.quit_pending();
+        if quit_pending && !last.quit_pending {
+            lines.push("Press q again to quit".into());
+        }
+        if !data.pending_song_number.is_empty()
+            && data.pending_song_number != last.pending_song_number
+        {
+            lines.push(format!("Go to: {}", data.pending_song_number));
+        }
+        if let Some(highlighted) = data.sort_menu.filter(|&x| last.sort_menu != Some(x)) {
+            let order = SortOrder::ALL[highlighted].name();
+            lines.push(match last.sort_menu {
+                None => format!("Sort by: {order}. j/k to choose, Enter to apply, Esc to cancel"),
+                Some(_) => order.into(),
+            });
+        }
+
+        let bookmark_input_open = data.bookmark_input.is_some();
+        if let Some(text) = data
+            .bookmark_input_text()
+            .filter(|_| !last.bookmark_input_open)
+        {
+            lines.push(text);
+        }
+        let selected_bookmark =
+            Some(data.selected_bookmark).filter(|_| self.page == Page::Bookmarks);
+        if let Some(i) =
+            selected_bookmark.filter(|&x| last.selected_bookmark.is_some_and(|y| y != x))
+        {
+            lines.extend(self.bookmark_lines(PAGE_LINE_WIDTH).get(i).cloned());
+        }
+
+        let chapter = data
+            .playing_chapter()
+            .map(|(i, _)| (data.playing_song_idx, i));
+        if let Some(text) = data.chapter_text().filter(|_| chapter != last.chapter) {
+            lines.push(text);
+        }
+
+        self.announced = Announced {
+            playing_song_idx: Some(data.playing_song_idx),
+            paused: Some(paused),
+            selected_song_idx: Some(data.selected_song_idx),
+            toast_shown_at,
+            quit_pending,
+            pending_song_number: data.pending_song_number.clone(),
+            sort_menu: data.sort_menu,
+            bookmark_input_open,
+            selected_bookmark,
+            chapter,
+            page: Some(self.page),
+            resume_offered: resume_offer.is_some(),
+            ..std::mem::take(&mut self.announced)
+        };
+
+        if last.page.is_some_and(|x| x != self.page) {
+            lines.push(format!("{} page", self.page.name()));
+            lines.extend(self.page_lines());
+        }
+    }
+
+    /// The contents of the current page, as plain lines
+    fn page_lines(&self) -> Vec<String> {
+        match self.page {
+            Page::Player => Vec::new(),
+            Page::Queue => self.queue_lines(PAGE_LINE_WIDTH),
+            Page::Bookmarks => self.bookmark_lines(PAGE_LINE_WIDTH),
+            Page::DiscInfo => self.disc_info_lines(),
+            Page::Toc => self.toc_lines(),
+            Page::Logs => {
+                let guard = mutex_lock!(LOG_LINES);
+                let skip = guard.len().saturating_sub(ANNOUNCED_LOG_LINES);
+                guard.iter().skip(skip).cloned().collect()
+            }
+            Page::Help => KEY_BINDINGS_HELP.lines().map(String::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! `verify`: reads every session back from the disc and compares it with its image
+//! written by [`crate::author`]
+
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+
+use crate::author::authored_sessions;
+use crate::burn::{check_sessions, session_description};
+use crate::cli::DiscArgs;
+use crate::source;
+use crate::source::SourceReader;
+use crate::{minfo, MetaInfo, Track, SECTOR_SIZE};
+
+/// Bytes compared at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How a session on the disc compares with its image
+enum Comparison {
+    Same,
+    /// Offset of the first differing byte, and how many bytes differ
+    Differs(u64, u64),
+    /// The track has fewer bytes than the image
+    TooShort(u64),
+    /// Reading the disc failed at the offset
+    ReadError(u64, io::Error),
+}
+
+/// Reads as much of `buf` as there is; returns the bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Compares `track` on `disc` with `image`; padding after the image's end isn't compared
+fn compare(disc: &mut SourceReader, track: Track, image: &Path) -> io::Result<Comparison> {
+    let image_size = std::fs::metadata(image)?.len();
+    if track.size_bytes() < image_size {
+        return Ok(Comparison::TooShort(track.size_bytes()));
+    }
+    disc.seek(SeekFrom::Start(track.start_offset()))?;
+    let mut disc_reader = BufReader::new(disc.take(image_size));
+    let mut image_reader = BufReader::new(File::open(image)?);
+    let mut disc_chunk = vec![0_u8; CHUNK_SIZE];
+    let mut image_chunk = vec![0_u8; CHUNK_SIZE];
+    let mut offset = 0_u64;
+    let mut first_difference = None;
+    let mut differing = 0_u64;
+    while offset < image_size {
+        let n = read_full(&mut image_reader, &mut image_chunk)?;
+        if n == 0 {
+            break;
+        }
+        let read = match read_full(&mut disc_reader, &mut disc_chunk[..n]) {
+            Ok(x) => x,
+            Err(e) => return Ok(Comparison::ReadError(offset, e)),
+        };
+        if read < n {
+            return Ok(Comparison::TooShort(offset + read as u64));
+        }
+        for (i, (a, b)) in disc_chunk[..n].iter().zip(&image_chunk[..n]).enumerate() {
+            if a != b {
+                first_difference.get_or_insert(offset + i as u64);
+                differing += 1;
+            }
+        }
+        offset += n as u64;
+    }
+    Ok(match first_difference {
+        None => Comparison::Same,
+        Some(x) => Comparison::Differs(x, differing),
+    })
+}
+
+/// Reads each session on the disc of `disc` back and compares it with its image, printing
+/// OK or FAIL for each; returns how many don't match
+pub(crate) fn compare_sessions(
+    disc: &DiscArgs,
+    tracks: &[Track],
+    sessions: &[PathBuf],
+    meta_info: &MetaInfo,
+) -> anyhow::Result<usize> {
+    let mut disc_reader = SourceReader::new(source::open_disc(disc, tracks)?);
+    let mut failed = 0;
+    for (i, image) in sessions.iter().enumerate() {
+        let session_no = i + 1;
+        let description = session_description(meta_info, session_no, disc.meta_info_track);
+        let problem = match tracks.get(i) {
+            None => Some(String::from("not on the disc")),
+            Some(&track) => match compare(&mut disc_reader, track, image)? {
+                Comparison::Same => None,
+                Comparison::Differs(offset, count) => Some(format!(
+                    "{count} bytes differ, the first at offset {offset} (sector {})",
+                    offset / SECTOR_SIZE
+                )),
+                Comparison::TooShort(size) => Some(format!(
+                    "the track has {size} bytes, the image {}",
+                    std::fs::metadata(image)?.len()
+                )),
+                Comparison::ReadError(offset, e) => {
+                    Some(format!("read error at offset {offset}: {e}"))
+                }
+            },
+        };
+        match problem {
+            None => println!("Session {session_no} ({description}): OK"),
+            Some(problem) => {
+                failed += 1;
+                println!("Session {session_no} ({description}): FAIL, {problem}");
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// Compares each session on the disc of `disc` with its image in `input_dir`
+pub fn verify(disc: &DiscArgs, input_dir: &Path) -> anyhow::Result<()> {
+    let sessions = authored_sessions(input_dir)?;
+    let meta_info = check_sessions(&sessions, disc.meta_info_track)?;
+    let tracks = minfo::minfo_track_info(disc)?;
+    if tracks.len() != sessions.len() {
+        println!(
+            "The disc has {} sessions, the images {}",
+            tracks.len(),
+            sessions.len()
+        );
+    }
+
+    let failed = compare_sessions(disc, &tracks, &sessions, &meta_info)?;
+    if failed != 0 {
+        bail!(
+            "{failed} of {} sessions don't match their images",
+            sessions.len()
+        );
+    }
+    println!("All {} sessions match", sessions.len());
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! A disc made in memory: the tracks a minfo program would list and the image behind
+//! them, for tests and `--demo`
+
+use std::io;
+use std::sync::Arc;
+
+use crate::integrity::SongHash;
+use crate::source::{DiscSource, MemorySource};
+use crate::{MetaInfo, Track, SECTOR_SIZE};
+
+pub struct VirtualDisc {
+    pub tracks: Vec<Track>,
+    pub image: Vec<u8>,
+}
+
+impl VirtualDisc {
+    /// A disc with a session (and track) for each of `sessions`, padded with zeros to
+    /// whole sectors
+    pub fn new(sessions: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        let mut image = Vec::new();
+        let mut tracks = Vec::new();
+        for mut data in sessions {
+            let padded = (data.len() as u64).div_ceil(SECTOR_SIZE) * SECTOR_SIZE;
+            data.resize(padded as usize, 0);
+            let start_addr = image.len() as u64 / SECTOR_SIZE;
+            let size = padded / SECTOR_SIZE;
+            let number = tracks.len() as u32 + 1;
+            tracks.push(Track {
+                track_no: number,
+                session_no: number,
+                start_addr,
+                end_addr: (start_addr + size).saturating_sub(1),
+                size,
+                audio: false,
+            });
+            image.extend(data);
+        }
+        Self { tracks, image }
+    }
+
+    /// A Pseudo-CD: `meta_info` in session 1, then a session of PCM for each song; the
+    /// songs' hashes are added to the meta info, as `author` does
+    pub fn pseudo_cd(
+        meta_info: &MetaInfo,
+        songs: impl IntoIterator<Item = Vec<u8>>,
+    ) -> io::Result<Self> {
+        let songs = songs.into_iter().collect::<Vec<_>>();
+        let mut meta_info = meta_info.clone();
+        for song in &mut meta_info.list {
+            // sessions of songs are from 2 on
+            let data = song.session_no.checked_sub(2).and_then(|i| songs.get(i));
+            song.hash = data.map(|x| SongHash::of(x));
+        }
+        let mut meta_data = serde_json::to_vec_pretty(&meta_info)?;
+        // the player reads the meta info up to a NUL
+        meta_data.push(0);
+        Ok(Self::new(std::iter::once(meta_data).chain(songs)))
+    }
+
+    /// A copy of the image to read from
+    pub fn source(&self) -> Arc<dyn DiscSource> {
+        Arc::new(MemorySource(self.image.clone()))
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use clap::Parser;
+use pseudo_cd_player::alarm::{ramp_volume, AlarmTime, RAMP};
+use pseudo_cd_player::cli::Args;
+
+fn at(arg: &str) -> Result<Option<AlarmTime>, clap::Error> {
+    Args::try_parse_from(["pseudo-cd-player", "--at", arg]).map(|x| x.ui.at)
+}
+
+#[test]
+fn alarm_times() {
+    let seven = AlarmTime { hour: 7, minute: 0 };
+    assert_eq!(at("07:00").unwrap(), Some(seven));
+    assert_eq!(at("7:00").unwrap(), Some(seven));
+    assert_eq!(seven.to_string(), "07:00");
+    assert_eq!(
+        at("23:59").unwrap(),
+        Some(AlarmTime {
+            hour: 23,
+            minute: 59
+        })
+    );
+    for bad in ["24:00", "07:60", "07:5", "0700", "", "seven"] {
+        assert!(at(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn waits_until_the_next_time_of_the_day() {
+    let seven = AlarmTime { hour: 7, minute: 0 };
+    let hour = 3600;
+    assert_eq!(seven.seconds_from(6 * hour), hour);
+    // the whole minute is on time, then it's tomorrow
+    assert_eq!(seven.seconds_from(7 * hour), 0);
+    assert_eq!(seven.seconds_from(7 * hour + 59), 0);
+    assert_eq!(seven.seconds_from(7 * hour + 60), 24 * hour - 60);
+    assert_eq!(seven.seconds_from(23 * hour), 8 * hour);
+}
+
+#[test]
+fn volume_rises_to_where_it_was() {
+    assert_eq!(ramp_volume(Duration::ZERO, 0.8), 0.0);
+    assert_eq!(ramp_volume(RAMP / 2, 0.8), 0.4);
+    assert_eq!(ramp_volume(RAMP, 0.8), 0.8);
+    assert_eq!(ramp_volume(RAMP * 2, 0.8), 0.8);
+}
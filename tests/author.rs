@@ -0,0 +1,240 @@
+#![cfg(feature = "external")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use pseudo_cd_player::author::init::{run, Tags};
+use pseudo_cd_player::author::{author, authored_sessions, AuthorPlan};
+use pseudo_cd_player::burn::BURNT_FILE;
+use pseudo_cd_player::cli::{Args, AuthorCommand, Commands};
+use pseudo_cd_player::{parse_meta_info, SECTOR_SIZE};
+
+/// A 44.1 kHz WAV file of `data`, with format `tag` (1 for integer PCM, 3 for float); as
+/// WAVE_FORMAT_EXTENSIBLE, the tag goes in the sub-format GUID
+fn wav_of(tag: u16, channels: u16, bits: u16, extensible: bool, data: &[u8]) -> Vec<u8> {
+    let block_align = channels * bits / 8;
+    let mut fmt = Vec::new();
+    fmt.extend(if extensible { 0xfffe } else { tag }.to_le_bytes());
+    fmt.extend(channels.to_le_bytes());
+    fmt.extend(44100_u32.to_le_bytes());
+    fmt.extend((44100 * block_align as u32).to_le_bytes());
+    fmt.extend(block_align.to_le_bytes());
+    fmt.extend(bits.to_le_bytes());
+    if extensible {
+        // extension size, valid bits, channel mask, then the GUID
+        fmt.extend(22_u16.to_le_bytes());
+        fmt.extend(bits.to_le_bytes());
+        fmt.extend(0_u32.to_le_bytes());
+        fmt.extend(tag.to_le_bytes());
+        fmt.extend(b"\x00\x00\x00\x00\x10\x00\x80\x00\x00\xaa\x00\x38\x9b\x71");
+    }
+    let mut wav = Vec::new();
+    wav.extend(b"RIFF");
+    wav.extend((4 + 8 + fmt.len() as u32 + 8 + data.len() as u32).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend((fmt.len() as u32).to_le_bytes());
+    wav.extend(fmt);
+    wav.extend(b"data");
+    wav.extend((data.len() as u32).to_le_bytes());
+    wav.extend(data);
+    wav
+}
+
+/// A 44.1 kHz 16-bit stereo WAV file of `frames` frames
+fn wav(frames: u32) -> Vec<u8> {
+    let data = (0..frames * 2)
+        .flat_map(|x| (x as i16).to_le_bytes())
+        .collect::<Vec<_>>();
+    wav_of(1, 2, 16, false, &data)
+}
+
+#[test]
+fn authored_images_are_padded_and_read_back() {
+    let dir = std::env::temp_dir().join(format!("pseudo-cd-author-{}", std::process::id()));
+    let output_dir = dir.join("images");
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Song.wav");
+    // not a whole number of sectors
+    fs::write(&input, wav(1000)).unwrap();
+
+    // images built again aren't the ones burnt
+    let burnt = output_dir.join(BURNT_FILE);
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(&burnt, "").unwrap();
+
+    let mut plan = AuthorPlan::from_files(&[input]);
+    plan.title = Some("Disc".into());
+    author(&plan, &output_dir).unwrap();
+    assert!(!burnt.exists());
+    let sessions = authored_sessions(&output_dir).unwrap();
+    assert_eq!(sessions.len(), 2);
+    for path in &sessions {
+        assert_eq!(fs::metadata(path).unwrap().len() % SECTOR_SIZE, 0);
+    }
+    let meta_data = fs::read(&sessions[0]).unwrap();
+    let json_end = meta_data.iter().position(|&x| x == 0).unwrap();
+    assert!(meta_data[json_end..].iter().all(|&x| x == 0));
+    let meta_info = parse_meta_info(&meta_data).unwrap();
+    assert!(meta_info.has_hashes());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn init_offers_the_tags() {
+    let files = [PathBuf::from("a.flac"), PathBuf::from("b.flac")];
+    let tags = |path: &Path| Tags {
+        title: (path == Path::new("a.flac")).then(|| "Tagged".into()),
+        artist: Some(
+            if path == Path::new("a.flac") {
+                "Band"
+            } else {
+                "Guest"
+            }
+            .into(),
+        ),
+        album: Some("Album".into()),
+    };
+    // the title and the name of the first song as offered; the performer isn't offered, as
+    // the files don't agree on it
+    let answers = "\nBand\n\nSecond\n";
+    let mut output = Vec::new();
+    let plan = run(answers.as_bytes(), &mut output, &files, tags).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("Title of the disc [Album]: Performer of the disc: "));
+    assert!(output.contains("Name of song 2 (b.flac) [b]: "));
+
+    assert_eq!(plan.title.as_deref(), Some("Album"));
+    assert_eq!(plan.performer.as_deref(), Some("Band"));
+    let songs = plan
+        .songs
+        .iter()
+        .map(|x| (x.name.as_str(), x.performer.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(songs, [("Tagged", None), ("Second", Some("Guest"))]);
+
+    // input ending before the questions do
+    assert!(run("\n".as_bytes(), Vec::new(), &files, tags).is_err());
+}
+
+#[test]
+fn author_takes_files_a_plan_or_init() {
+    let parse = |args: &[&str]| {
+        Args::try_parse_from(["pseudo-cd-player", "author"].iter().chain(args)).map(|x| x.command)
+    };
+    assert!(matches!(
+        parse(&["init", "a.flac"]).unwrap(),
+        Some(Commands::Author {
+            command: Some(AuthorCommand::Init { files, .. }),
+            ..
+        }) if files == [PathBuf::from("a.flac")]
+    ));
+    assert!(matches!(
+        parse(&["--plan", "plan.json"]).unwrap(),
+        Some(Commands::Author { plan: Some(_), .. })
+    ));
+    assert!(parse(&[]).is_err());
+    assert!(parse(&["a.flac", "--plan", "plan.json"]).is_err());
+}
+
+#[test]
+fn wav_files_are_converted_without_a_decoder() {
+    let dir = std::env::temp_dir().join(format!("pseudo-cd-author-wav-{}", std::process::id()));
+    let output_dir = dir.join("images");
+    fs::create_dir_all(&dir).unwrap();
+    let bytes = |samples: &[&[u8]]| samples.concat();
+    // the input files, and the stereo 16-bit samples they're converted to
+    let cases: [(Vec<u8>, &[i16]); 6] = [
+        // unsigned 8-bit mono, played on both channels
+        (
+            wav_of(1, 1, 8, false, &[0, 128, 255, 128]),
+            &[-32768, -32768, 0, 0, 32512, 32512, 0, 0],
+        ),
+        // a partial frame at the end is dropped
+        (
+            wav_of(
+                1,
+                2,
+                24,
+                false,
+                &bytes(&[
+                    &[0xff, 0xff, 0x7f],
+                    &[0x00, 0x00, 0x80],
+                    &[0x56, 0x34, 0x12],
+                    &[0xaa, 0xcb, 0xed],
+                    &[0x00, 0x00, 0x01],
+                ]),
+            ),
+            &[32767, -32768, 4660, -4661],
+        ),
+        (
+            wav_of(
+                1,
+                2,
+                32,
+                true,
+                &bytes(&[
+                    &i32::MAX.to_le_bytes(),
+                    &i32::MIN.to_le_bytes(),
+                    &0x10000_i32.to_le_bytes(),
+                    &0_i32.to_le_bytes(),
+                ]),
+            ),
+            &[32767, -32768, 1, 0],
+        ),
+        (
+            wav_of(
+                3,
+                2,
+                32,
+                false,
+                &bytes(&[
+                    &0.5_f32.to_le_bytes(),
+                    &(-1.0_f32).to_le_bytes(),
+                    &2.0_f32.to_le_bytes(),
+                    &(-2.0_f32).to_le_bytes(),
+                ]),
+            ),
+            &[16384, -32767, 32767, -32768],
+        ),
+        (
+            wav_of(
+                3,
+                2,
+                64,
+                true,
+                &bytes(&[&0.25_f64.to_le_bytes(), &0.0_f64.to_le_bytes()]),
+            ),
+            &[8192, 0],
+        ),
+        (
+            wav_of(
+                1,
+                2,
+                16,
+                true,
+                &bytes(&[&1234_i16.to_le_bytes(), &(-1234_i16).to_le_bytes()]),
+            ),
+            &[1234, -1234],
+        ),
+    ];
+    let files = (0..cases.len())
+        .map(|i| dir.join(format!("{i}.wav")))
+        .collect::<Vec<_>>();
+    for (path, (wav, _)) in files.iter().zip(&cases) {
+        fs::write(path, wav).unwrap();
+    }
+
+    author(&AuthorPlan::from_files(&files), &output_dir).unwrap();
+    let sessions = authored_sessions(&output_dir).unwrap();
+    for (i, (_, expected)) in cases.iter().enumerate() {
+        let image = fs::read(&sessions[i + 1]).unwrap();
+        let pcm = expected
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(image[..pcm.len()], pcm, "{}", files[i].display());
+        assert!(image[pcm.len()..].iter().all(|&x| x == 0));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,25 @@
+use pseudo_cd_player::bookmarks::{insert, Bookmark};
+
+fn bookmark(name: &str, song_idx: usize, position: u32) -> Bookmark {
+    Bookmark {
+        name: name.into(),
+        song_idx,
+        position,
+    }
+}
+
+#[test]
+fn bookmarks_stay_in_disc_order() {
+    let mut bookmarks = Vec::new();
+    assert_eq!(insert(&mut bookmarks, bookmark("b", 1, 30)), 0);
+    assert_eq!(insert(&mut bookmarks, bookmark("a", 0, 90)), 0);
+    assert_eq!(insert(&mut bookmarks, bookmark("c", 1, 10)), 1);
+    // after the ones at the same place
+    assert_eq!(insert(&mut bookmarks, bookmark("d", 1, 30)), 3);
+    let names = bookmarks
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "c", "b", "d"]);
+    assert_eq!(Bookmark::default_name(2, 83), "3 01:23");
+}
@@ -0,0 +1,29 @@
+use pseudo_cd_player::chapters::{chapter_at, parse_chapter_list, skip_target};
+
+#[test]
+fn chapter_list() {
+    let chapters =
+        parse_chapter_list("1:02:03 Encore\n\n0:00 Intro\n12:34  Second track\n").unwrap();
+    let offsets = chapters.iter().map(|x| x.offset).collect::<Vec<_>>();
+    assert_eq!(offsets, [0, 754, 3723]);
+    assert_eq!(chapters[1].name, "Second track");
+    assert!(parse_chapter_list("0:00 Intro\nsoon Outro")
+        .unwrap_err()
+        .starts_with("line 2"));
+}
+
+#[test]
+fn skipping_chapters() {
+    let chapters = parse_chapter_list("10 A\n60 B\n120 C").unwrap();
+    assert_eq!(chapter_at(&chapters, 5), None);
+    assert_eq!(chapter_at(&chapters, 60), Some(1));
+    assert_eq!(chapter_at(&chapters, 500), Some(2));
+
+    assert_eq!(skip_target(&chapters, 5, true), Some(10));
+    assert_eq!(skip_target(&chapters, 70, true), Some(120));
+    assert_eq!(skip_target(&chapters, 130, true), None);
+    // back to the playing chapter's start, or right after it, to the one before
+    assert_eq!(skip_target(&chapters, 70, false), Some(60));
+    assert_eq!(skip_target(&chapters, 61, false), Some(10));
+    assert_eq!(skip_target(&chapters, 11, false), Some(0));
+}
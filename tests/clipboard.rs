@@ -0,0 +1,10 @@
+use pseudo_cd_player::clipboard::osc52;
+
+#[test]
+fn osc52_is_base64_with_padding() {
+    assert_eq!(osc52("Concert A"), "\x1b]52;c;Q29uY2VydCBB\x07");
+    assert_eq!(osc52("ab"), "\x1b]52;c;YWI=\x07");
+    assert_eq!(osc52("a"), "\x1b]52;c;YQ==\x07");
+    assert_eq!(osc52(""), "\x1b]52;c;\x07");
+    assert_eq!(osc52("A – B"), "\x1b]52;c;QSDigJMgQg==\x07");
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "external")]
+
+use std::io::ErrorKind;
+
+use pseudo_cd_player::commands::write_wav_header;
+
+#[test]
+fn wav_header_sizes() {
+    let mut header = Vec::new();
+    write_wav_header(&mut header, 1000).unwrap();
+    assert_eq!(header.len(), 44);
+    assert_eq!(&header[4..8], &1036_u32.to_le_bytes());
+    assert_eq!(&header[40..], &1000_u32.to_le_bytes());
+
+    let largest = u32::MAX as u64 - 36;
+    let mut header = Vec::new();
+    write_wav_header(&mut header, largest).unwrap();
+    assert_eq!(&header[4..8], &u32::MAX.to_le_bytes());
+
+    // songs over 4 GiB don't fit
+    for size in [largest + 1, u32::MAX as u64 + 1, 5 << 30] {
+        let mut header = Vec::new();
+        let error = write_wav_header(&mut header, size).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(header.is_empty());
+    }
+}
@@ -0,0 +1,67 @@
+use pseudo_cd_player::cue::{cue_sheet, image_size, CueFiles};
+use pseudo_cd_player::{parse_meta_info, Track};
+
+fn track(sectors: u64) -> Track {
+    Track {
+        track_no: 2,
+        session_no: 2,
+        start_addr: 100,
+        end_addr: 100 + sectors - 1,
+        size: sectors,
+        audio: false,
+    }
+}
+
+#[test]
+fn cue_sheet_of_an_image() {
+    let meta_info = parse_meta_info(
+        br#"{
+            "title": "Live",
+            "performer": "The \"Band\"",
+            "list": [
+                {"name": "Opening", "session_no": 2},
+                {"name": "Medley", "session_no": 3, "performer": "Guest",
+                 "chapters": [{"name": "Start", "offset": 0}, {"name": "Second", "offset": 61}]}
+            ]
+        }"#,
+    )
+    .unwrap();
+    // 10 seconds (and a bit) of audio, padded to whole CD frames
+    let first = track(862);
+    assert_eq!(image_size(first.size_bytes()), 751 * 2352);
+    let sheet = cue_sheet(
+        &meta_info,
+        &[(0, first), (1, track(2000))],
+        &CueFiles::Image("Live.bin".into()),
+    );
+    let expected = r#"REM COMMENT "pseudo-cd-player"
+PERFORMER "The 'Band'"
+TITLE "Live"
+FILE "Live.bin" BINARY
+  TRACK 01 AUDIO
+    TITLE "Opening"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Medley"
+    PERFORMER "Guest"
+    INDEX 01 00:10:01
+    INDEX 02 01:11:01
+"#;
+    assert_eq!(sheet, expected);
+}
+
+#[test]
+fn cue_sheet_of_wave_files() {
+    let meta_info = parse_meta_info(
+        br#"{"list": [{"name": "A", "session_no": 2}, {"name": "B", "session_no": 3}]}"#,
+    )
+    .unwrap();
+    let sheet = cue_sheet(
+        &meta_info,
+        &[(0, track(10)), (1, track(10))],
+        &CueFiles::Wave(vec!["01 - A.wav".into(), "02 - B.wav".into()]),
+    );
+    assert!(sheet.contains(
+        "FILE \"02 - B.wav\" WAVE\n  TRACK 02 AUDIO\n    TITLE \"B\"\n    INDEX 01 00:00:00\n"
+    ));
+}
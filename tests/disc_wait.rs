@@ -0,0 +1,29 @@
+use std::sync::atomic::AtomicBool;
+
+use pseudo_cd_player::disc_wait::{has_disc, is_uevent_of, wait_for_disc};
+
+#[test]
+fn uevents_of_the_drive() {
+    let change =
+        b"change@/devices/pci0000:00/0000:00:17.0/ata2/host1/target1:0:0/1:0:0:0/block/sr0\0\
+        ACTION=change\0DEVNAME=sr0\0DISK_MEDIA_CHANGE=1\0";
+    assert!(is_uevent_of(change, "sr0"));
+    assert!(!is_uevent_of(change, "sr1"));
+    assert!(is_uevent_of(
+        b"remove@/devices/virtual/x\0DEVNAME=sr1\0",
+        "sr1"
+    ));
+    assert!(!is_uevent_of(
+        b"add@/devices/virtual/net/lo\0ACTION=add\0",
+        "sr0"
+    ));
+    assert!(!is_uevent_of(b"", "sr0"));
+}
+
+#[test]
+fn files_arent_waited_for() {
+    let image = std::env::current_exe().unwrap();
+    assert!(has_disc(&image));
+    // it'd wait forever otherwise
+    wait_for_disc(&image, &AtomicBool::new(false));
+}
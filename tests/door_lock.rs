@@ -0,0 +1,41 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use pseudo_cd_player::door_lock;
+use pseudo_cd_player::playback::{EventBus, PlayerCallbackEvent};
+use pseudo_cd_player::source::DiscSource;
+
+/// A drive noting how its door was locked and unlocked
+#[derive(Default)]
+struct Drive {
+    locks: Mutex<Vec<bool>>,
+}
+
+impl DiscSource for Drive {
+    fn read_at(&self, _buf: &mut [u8], _offset: u64) -> io::Result<usize> {
+        Ok(0)
+    }
+
+    fn len(&self) -> u64 {
+        0
+    }
+
+    fn lock_door(&self, locked: bool) -> io::Result<()> {
+        self.locks.lock().unwrap().push(locked);
+        Ok(())
+    }
+}
+
+#[test]
+fn locked_while_playing() {
+    let drive = Arc::new(Drive::default());
+    let events = EventBus::new();
+    door_lock::follow(&events, &(drive.clone() as Arc<dyn DiscSource>));
+    for paused in [true, false, false, true, false] {
+        events.publish(PlayerCallbackEvent::Paused(paused));
+    }
+    door_lock::unlock();
+    door_lock::unlock();
+    // only changes reach the drive
+    assert_eq!(*drive.locks.lock().unwrap(), [true, false, true, false]);
+}
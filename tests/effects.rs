@@ -0,0 +1,67 @@
+use std::f64::consts::TAU;
+
+use pseudo_cd_player::effects::{AudioEffect, Equalizer, Gain, Limiter};
+use pseudo_cd_player::playback::AUDIO_SAMPLE_RATE;
+
+/// A second of a stereo sine of `frequency` at `amplitude` of full scale
+fn sine(frequency: f64, amplitude: f64) -> Vec<i16> {
+    (0..AUDIO_SAMPLE_RATE)
+        .map(|i| {
+            let t = i as f64 / AUDIO_SAMPLE_RATE as f64;
+            ((TAU * frequency * t).sin() * amplitude * i16::MAX as f64) as i16
+        })
+        .flat_map(|x| [x, x])
+        .collect()
+}
+
+/// Peak of the second half, after filters settled
+fn peak(samples: &[i16]) -> f64 {
+    let max = samples[samples.len() / 2..]
+        .iter()
+        .map(|&x| (x as i32).abs())
+        .max()
+        .unwrap();
+    max as f64 / i16::MAX as f64
+}
+
+#[test]
+fn gain() {
+    let mut samples = vec![1000, -1000, 3, i16::MIN];
+    Gain { level: 0.5 }.process(&mut samples);
+    assert_eq!(samples, [500, -500, 1, i16::MIN / 2]);
+}
+
+#[test]
+fn flat_equalizer_changes_nothing() {
+    let original = sine(1000.0, 0.5);
+    let mut samples = original.clone();
+    Equalizer::new(0.0, 0.0).process(&mut samples);
+    assert_eq!(samples, original);
+}
+
+#[test]
+fn bass_boost() {
+    let mut bass = sine(50.0, 0.25);
+    let mut treble = sine(10000.0, 0.25);
+    let mut equalizer = Equalizer::new(6.0, 0.0);
+    equalizer.process(&mut bass);
+    let mut equalizer = Equalizer::new(6.0, 0.0);
+    equalizer.process(&mut treble);
+    // +6 dB is about twice as loud
+    assert!((peak(&bass) - 0.5).abs() < 0.03, "{}", peak(&bass));
+    assert!((peak(&treble) - 0.25).abs() < 0.01, "{}", peak(&treble));
+}
+
+#[test]
+fn limiter() {
+    let loud = sine(440.0, 1.0);
+    let mut samples = loud.clone();
+    Limiter::default().process(&mut samples);
+    assert_eq!(samples, loud, "it's off by default");
+
+    let mut limiter = Limiter::default();
+    limiter.enabled = true;
+    limiter.process(&mut samples);
+    assert!(peak(&samples) <= 0.9 + 1e-4, "{}", peak(&samples));
+    assert!(peak(&samples) > 0.85, "{}", peak(&samples));
+}
@@ -0,0 +1,20 @@
+use std::collections::BTreeSet;
+
+use pseudo_cd_player::favorites::playable_songs;
+use pseudo_cd_player::play_mode::shuffled;
+
+#[test]
+fn favorites_only_plays_favorites() {
+    let favorites = BTreeSet::from([1, 3, 7]);
+    assert_eq!(playable_songs(5, &favorites, true), [1, 3]);
+    assert_eq!(playable_songs(5, &favorites, false), [0, 1, 2, 3, 4]);
+    // without favorites on the disc, everything plays
+    assert_eq!(playable_songs(3, &BTreeSet::from([7]), true), [0, 1, 2]);
+
+    let order = shuffled(vec![1, 3, 4], Some(3));
+    assert_eq!(order[0], 3);
+    assert_eq!(
+        order.iter().copied().collect::<BTreeSet<_>>(),
+        [1, 3, 4].into()
+    );
+}
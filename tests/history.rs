@@ -0,0 +1,29 @@
+use pseudo_cd_player::history::{counts_as_played, DiscHistory, SongHistory};
+
+fn song(plays: u32, listened: u64) -> SongHistory {
+    SongHistory {
+        name: String::new(),
+        plays,
+        listened,
+    }
+}
+
+#[test]
+fn most_played_songs_first() {
+    let disc = DiscHistory {
+        title: None,
+        songs: [(1, song(2, 100)), (2, song(5, 300)), (3, song(2, 150))].into(),
+    };
+    let order = disc
+        .most_played()
+        .into_iter()
+        .map(|(n, _)| n)
+        .collect::<Vec<_>>();
+    assert_eq!(order, [2, 3, 1]);
+    assert_eq!((disc.plays(), disc.listened()), (9, 550));
+
+    assert!(!counts_as_played(600, 200));
+    assert!(counts_as_played(600, 300));
+    // long songs count after four minutes
+    assert!(counts_as_played(3600, 240));
+}
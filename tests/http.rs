@@ -0,0 +1,106 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+use pseudo_cd_player::http;
+
+/// Starts the API on a free port
+fn start() -> SocketAddr {
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    http::start(addr).unwrap();
+    addr
+}
+
+/// The status line of the answer to `request`, sent after `Host: <addr>`
+fn status(addr: SocketAddr, request: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let (request_line, rest) = request.split_once("\r\n").unwrap();
+    write!(stream, "{request_line}\r\nHost: {addr}\r\n{rest}").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response.lines().next().unwrap().into()
+}
+
+#[test]
+fn control_requests_from_browsers_are_checked() {
+    let addr = start();
+    // what a page can send to another site without a preflight
+    assert_eq!(
+        status(
+            addr,
+            "POST /quit HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\n{}"
+        ),
+        "HTTP/1.1 415 Unsupported Media Type"
+    );
+    assert_eq!(
+        status(
+            addr,
+            "POST /quit HTTP/1.1\r\nOrigin: http://example.com\r\n\
+             Content-Type: application/json\r\nContent-Length: 2\r\n\r\n{}"
+        ),
+        "HTTP/1.1 403 Forbidden"
+    );
+    // the control page's own requests get through, as far as checking the body
+    let own = format!(
+        "POST /goto HTTP/1.1\r\nOrigin: http://{addr}\r\n\
+         Content-Type: application/json; charset=utf-8\r\nContent-Length: 10\r\n\r\n{{\"song\":0}}"
+    );
+    assert_eq!(status(addr, &own), "HTTP/1.1 400 Bad Request");
+}
+
+#[test]
+fn malformed_content_length_is_refused() {
+    let addr = start();
+    assert_eq!(
+        status(
+            addr,
+            "POST /goto HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: ten\r\n\r\n"
+        ),
+        "HTTP/1.1 400 Bad Request"
+    );
+}
+
+#[test]
+fn other_host_names_are_refused() {
+    let addr = start();
+    let request = |host: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET /status HTTP/1.1\r\nHost: {host}\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response.lines().next().unwrap().to_string()
+    };
+    // a site's own name resolving here, as with DNS rebinding
+    assert_eq!(
+        request(&format!("rebind.example:{}", addr.port())),
+        "HTTP/1.1 403 Forbidden"
+    );
+    assert_eq!(request("127.0.0.1:1"), "HTTP/1.1 403 Forbidden");
+    assert_eq!(request(&addr.to_string()), "HTTP/1.1 200 OK");
+    assert_eq!(
+        request(&format!("LocalHost:{}", addr.port())),
+        "HTTP/1.1 200 OK"
+    );
+}
+
+#[test]
+fn long_lines_and_many_headers_are_refused() {
+    let addr = start();
+    let long_path = "a".repeat(10000);
+    assert_eq!(
+        status(addr, &format!("GET /{long_path} HTTP/1.1\r\n\r\n")),
+        "HTTP/1.1 414 URI Too Long"
+    );
+    let long_header = format!("X-Filler: {long_path}\r\n");
+    assert_eq!(
+        status(addr, &format!("GET /status HTTP/1.1\r\n{long_header}\r\n")),
+        "HTTP/1.1 431 Request Header Fields Too Large"
+    );
+    let many_headers = "X-Filler: a\r\n".repeat(200);
+    assert_eq!(
+        status(addr, &format!("GET /status HTTP/1.1\r\n{many_headers}\r\n")),
+        "HTTP/1.1 431 Request Header Fields Too Large"
+    );
+}
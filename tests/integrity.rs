@@ -0,0 +1,58 @@
+use pseudo_cd_player::integrity::{check_song, Check, SongHash};
+use pseudo_cd_player::sha256::Sha256;
+use pseudo_cd_player::virtual_disc::VirtualDisc;
+
+fn sha256_hex(data: &[u8]) -> String {
+    SongHash::of(data).sha256
+}
+
+#[test]
+fn sha256_test_vectors() {
+    assert_eq!(
+        sha256_hex(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256_hex(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // 56 bytes, so the length goes into a block of its own
+    assert_eq!(
+        sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+}
+
+#[test]
+fn sha256_in_pieces() {
+    let data = (0..1000).map(|x| x as u8).collect::<Vec<_>>();
+    let mut hasher = Sha256::new();
+    for piece in data.chunks(37) {
+        hasher.update(piece);
+    }
+    assert_eq!(hasher.finish_hex(), sha256_hex(&data));
+}
+
+#[test]
+fn checks_songs_against_their_hashes() {
+    let pcm = (0..10000)
+        .flat_map(|x: i16| x.to_le_bytes())
+        .collect::<Vec<_>>();
+    let hash = SongHash::of(&pcm);
+    let mut disc = VirtualDisc::new([pcm]);
+    let track = disc.tracks[0];
+    assert!(check_song(&disc.source(), track, &hash).is_intact());
+
+    disc.image[track.start_offset() as usize + 1234] ^= 1;
+    assert!(matches!(
+        check_song(&disc.source(), track, &hash),
+        Check::Corrupt
+    ));
+
+    let mut short = track;
+    short.size = hash.size / 2048;
+    assert!(matches!(
+        check_song(&disc.source(), short, &hash),
+        Check::TooShort(_)
+    ));
+}
@@ -0,0 +1,76 @@
+use pseudo_cd_player::locale::{pick, user_locales, Localized};
+use pseudo_cd_player::parse_meta_info;
+
+fn locales(env: &[(&str, &str)]) -> Vec<String> {
+    user_locales(|name| {
+        env.iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.to_string())
+    })
+}
+
+#[test]
+fn user_locales_from_the_environment() {
+    assert_eq!(
+        locales(&[("LANGUAGE", "zh_TW:ja"), ("LANG", "en_US.UTF-8")]),
+        ["zh-tw", "ja", "en-us"]
+    );
+    assert_eq!(
+        locales(&[("LC_ALL", "C"), ("LANG", "C.UTF-8")]),
+        [] as [&str; 0]
+    );
+}
+
+#[test]
+fn picks_the_closest_locale() {
+    let texts = Localized::from([
+        ("en".into(), "Spring".into()),
+        ("zh-CN".into(), "春".into()),
+        ("zh_TW".into(), "春天".into()),
+    ]);
+    let pick = |locales: &[&str]| {
+        let locales = locales.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        pick(&texts, &locales)
+    };
+    assert_eq!(pick(&["zh-tw"]), Some("春天"));
+    assert_eq!(pick(&["en-gb"]), Some("Spring"));
+    // another locale of the language
+    assert_eq!(pick(&["zh"]), Some("春"));
+    // the first locale with a match wins
+    assert_eq!(pick(&["fr", "en-us", "zh-cn"]), Some("Spring"));
+    assert_eq!(pick(&["fr"]), None);
+}
+
+#[test]
+fn localized_meta_info() {
+    let meta_info = || {
+        parse_meta_info(
+            r#"{
+                "title": "Four Seasons",
+                "titles": {"ja": "四季"},
+                "list": [
+                    {"name": "Spring", "names": {"ja": "春"}, "session_no": 2},
+                    {"name": "Summer", "session_no": 3}
+                ]
+            }"#
+            .as_bytes(),
+        )
+        .unwrap()
+    };
+    let mut ja = meta_info();
+    ja.localize(&["ja-jp".into()]);
+    let json = serde_json::to_value(&ja).unwrap();
+    assert_eq!(json["title"], "四季");
+    assert_eq!(json["list"][0]["name"], "春");
+    assert_eq!(json["list"][1]["name"], "Summer");
+
+    let mut fr = meta_info();
+    fr.localize(&["fr".into()]);
+    assert_eq!(serde_json::to_value(&fr).unwrap()["title"], "Four Seasons");
+
+    // without a plain title, any is better than none
+    let mut untitled =
+        parse_meta_info(r#"{"titles": {"ja": "四季"}, "list": []}"#.as_bytes()).unwrap();
+    untitled.localize(&[]);
+    assert_eq!(serde_json::to_value(&untitled).unwrap()["title"], "四季");
+}
@@ -0,0 +1,51 @@
+use std::f64::consts::TAU;
+
+use pseudo_cd_player::loudness::{measure, SongGain, REFERENCE_LOUDNESS};
+use pseudo_cd_player::playback::AUDIO_SAMPLE_RATE;
+
+/// `seconds` of a stereo 997 Hz sine at `amplitude` of full scale, as PCM
+fn sine(seconds: u32, amplitude: f64) -> Vec<u8> {
+    (0..AUDIO_SAMPLE_RATE * seconds)
+        .map(|i| {
+            let t = i as f64 / AUDIO_SAMPLE_RATE as f64;
+            ((TAU * 997.0 * t).sin() * amplitude * 32768.0) as i16
+        })
+        .flat_map(|x| [x, x])
+        .flat_map(i16::to_le_bytes)
+        .collect()
+}
+
+#[test]
+fn measures_a_sine() {
+    // a full-scale 1 kHz sine in both channels reads 0 LUFS
+    let loudness = measure(&sine(5, 0.5)[..]).unwrap();
+    let integrated = loudness.integrated.unwrap();
+    assert!((integrated + 6.02).abs() < 0.1, "{integrated}");
+    assert!((loudness.peak - 0.5).abs() < 0.001);
+
+    // silence doesn't count, so a song with a silent intro reads as loud, but for the few
+    // blocks it fades in over
+    let mut data = vec![0; 10 * AUDIO_SAMPLE_RATE as usize * 4];
+    data.extend(sine(5, 0.5));
+    let gated = measure(&data[..]).unwrap().integrated.unwrap();
+    assert!((gated - integrated).abs() < 0.2, "{gated}");
+
+    let silent = measure(&vec![0; 100_000][..]).unwrap();
+    assert_eq!(silent.integrated, None);
+    assert_eq!(silent.gain().gain_db, 0.0);
+    assert!((loudness.gain().gain_db - (REFERENCE_LOUDNESS - integrated)).abs() < 1e-9);
+}
+
+#[test]
+fn gain_stops_short_of_clipping() {
+    let quiet = SongGain {
+        gain_db: -6.0,
+        peak: 0.9,
+    };
+    assert!((quiet.level() - 0.501).abs() < 0.001);
+    let loud = SongGain {
+        gain_db: 12.0,
+        peak: 0.5,
+    };
+    assert_eq!(loud.level(), 2.0);
+}
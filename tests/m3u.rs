@@ -0,0 +1,22 @@
+use pseudo_cd_player::m3u::{m3u8, PlaylistEntry};
+
+#[test]
+fn m3u8_playlist() {
+    let entries = [
+        PlaylistEntry {
+            path: "01 - Intro.flac".into(),
+            title: "Band - Intro".into(),
+            duration: 62,
+        },
+        PlaylistEntry {
+            path: "02 - Two lines.flac".into(),
+            title: "Two\nlines".into(),
+            duration: 0,
+        },
+    ];
+    assert_eq!(
+        m3u8(Some("Album"), &entries),
+        "#EXTM3U\n#PLAYLIST:Album\n#EXTINF:62,Band - Intro\n01 - Intro.flac\n\
+         #EXTINF:0,Two lines\n02 - Two lines.flac\n"
+    );
+}
@@ -0,0 +1,33 @@
+#![cfg(target_os = "linux")]
+
+use pseudo_cd_player::media_keys::{key_command, EVENT_SIZE};
+use pseudo_cd_player::remote::RemoteCommand;
+
+fn event(event_type: u16, code: u16, value: i32) -> [u8; EVENT_SIZE] {
+    let mut event = [0_u8; EVENT_SIZE];
+    let tail = &mut event[EVENT_SIZE - 8..];
+    tail[..2].copy_from_slice(&event_type.to_ne_bytes());
+    tail[2..4].copy_from_slice(&code.to_ne_bytes());
+    tail[4..].copy_from_slice(&value.to_ne_bytes());
+    event
+}
+
+#[test]
+fn presses_of_media_keys() {
+    assert_eq!(
+        key_command(&event(1, 164, 1)),
+        Some(RemoteCommand::TogglePause)
+    );
+    assert_eq!(key_command(&event(1, 163, 1)), Some(RemoteCommand::Next));
+    assert_eq!(
+        key_command(&event(1, 165, 1)),
+        Some(RemoteCommand::Previous)
+    );
+    assert_eq!(key_command(&event(1, 166, 1)), Some(RemoteCommand::Pause));
+    // releases and autorepeats
+    assert_eq!(key_command(&event(1, 164, 0)), None);
+    assert_eq!(key_command(&event(1, 163, 2)), None);
+    // the A key, and a sync event
+    assert_eq!(key_command(&event(1, 30, 1)), None);
+    assert_eq!(key_command(&event(0, 0, 0)), None);
+}
@@ -0,0 +1,183 @@
+use pseudo_cd_player::error::Error;
+use pseudo_cd_player::minfo::parse_minfo;
+use pseudo_cd_player::{Track, CDDA_OFFSET};
+
+macro_rules! fixture {
+    ($name:literal) => {
+        include_str!(concat!("fixtures/minfo/", $name))
+    };
+}
+
+fn track(number: u32, start_addr: u64, end_addr: u64) -> Track {
+    Track {
+        track_no: number,
+        session_no: number,
+        start_addr,
+        end_addr,
+        size: end_addr - start_addr + 1,
+        audio: false,
+    }
+}
+
+#[test]
+fn cdrskin_appendable() {
+    let tracks = parse_minfo(fixture!("cdrskin-dvd+r-appendable.txt")).unwrap();
+    assert_eq!(
+        tracks,
+        [
+            track(1, 0, 65263),
+            track(2, 93952, 112271),
+            track(3, 118432, 138735),
+            track(4, 144896, 178799),
+            track(5, 184960, 200063),
+            track(6, 206224, 224815),
+        ]
+    );
+}
+
+#[test]
+fn cdrskin_closed() {
+    let tracks = parse_minfo(fixture!("cdrskin-dvd+r-closed.txt")).unwrap();
+    assert_eq!(
+        tracks,
+        [
+            track(1, 0, 351),
+            track(2, 30672, 48991),
+            track(3, 55376, 75679)
+        ]
+    );
+}
+
+#[test]
+fn cdrskin_mixed_mode() {
+    let tracks = parse_minfo(fixture!("cdrskin-cd-extra.txt")).unwrap();
+    let audio_track = |number, session_no, start_addr, end_addr| Track {
+        session_no,
+        audio: true,
+        ..track(number, start_addr, end_addr)
+    };
+    // the data tracks first, as the meta info numbers them
+    assert_eq!(
+        tracks,
+        [
+            Track {
+                session_no: 2,
+                ..track(4, 51600, 51951)
+            },
+            Track {
+                session_no: 3,
+                ..track(5, 63502, 81821)
+            },
+            audio_track(1, 1, 0, 14999),
+            audio_track(2, 1, 15000, 28349),
+            audio_track(3, 1, 28350, 40199),
+        ]
+    );
+    assert_eq!(tracks[3].start_offset(), CDDA_OFFSET + 15000 * 2352);
+    assert_eq!(tracks[3].size_bytes(), 13350 * 2352);
+    assert_eq!(tracks[3].end_offset(), tracks[4].start_offset());
+}
+
+#[test]
+fn cdrskin_blank_disc() {
+    assert_eq!(parse_minfo(fixture!("cdrskin-blank.txt")).unwrap(), []);
+}
+
+#[test]
+fn cdrecord() {
+    let tracks = parse_minfo(fixture!("cdrecord-dvd+r.txt")).unwrap();
+    assert_eq!(
+        tracks,
+        [
+            track(1, 0, 10511),
+            track(2, 40832, 59151),
+            track(3, 65584, 85887)
+        ]
+    );
+}
+
+#[test]
+fn wodim_crlf() {
+    let tracks = parse_minfo(fixture!("wodim-dvd-r-crlf.txt")).unwrap();
+    assert_eq!(tracks, [track(1, 0, 15), track(2, 16384, 34703)]);
+}
+
+#[test]
+fn no_track_table() {
+    let output = "cdrskin 1.5.6 : limited cdrecord compatibility wrapper for libburn\n\
+                  cdrskin: FATAL : No drive found\n";
+    assert!(matches!(parse_minfo(output), Err(Error::MinfoNoTrackTable)));
+    assert!(matches!(parse_minfo(""), Err(Error::MinfoNoTrackTable)));
+}
+
+#[test]
+fn bad_track_line() {
+    let output = "Track  Sess Type   Start Addr End Addr   Size\n\
+                  ==============================================\n\
+                  \x20   1     1 Data   0          65263      65264\n\
+                  \x20   2     2 Data   93952      ???        18320\n";
+    let Err(Error::MinfoBadTrackLine { line_no, line }) = parse_minfo(output) else {
+        panic!("a bad line is taken");
+    };
+    assert_eq!(line_no, 4);
+    assert_eq!(line, "    2     2 Data   93952      ???        18320");
+}
+
+#[test]
+fn number_overflow() {
+    let output = "Track  Sess Type   Start Addr End Addr   Size\n\
+                  ==============================================\n\
+                  \x20   1     1 Data   0          99999999999999999999      1\n";
+    assert!(matches!(
+        parse_minfo(output),
+        Err(Error::MinfoBadTrackLine { line_no: 3, .. })
+    ));
+}
+
+/// A cdrskin failing with a message in Latin-1, like it does in such a locale
+#[cfg(all(unix, feature = "external"))]
+#[test]
+fn failure_output_not_in_utf8() {
+    use std::os::unix::fs::PermissionsExt;
+
+    use pseudo_cd_player::cli::{DiscArgs, MinfoCli};
+    use pseudo_cd_player::external::minfo_program;
+    use pseudo_cd_player::minfo::minfo_track_info;
+
+    let dir = std::env::temp_dir().join(format!("pseudo-cd-minfo-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("cdrskin");
+    std::fs::write(
+        &program,
+        "#!/bin/sh\nprintf 'Kein Medium eingelegt \\344\\n' >&2\nexit 1\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    // the program found is recorded in the config, which goes in there too
+    std::env::set_var("HOME", &dir);
+    std::env::set_var("XDG_CONFIG_HOME", &dir);
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut paths = vec![dir.clone()];
+    paths.extend(std::env::split_paths(&path));
+    std::env::set_var("PATH", std::env::join_paths(paths).unwrap());
+
+    let disc = DiscArgs {
+        drive: "/dev/null".into(),
+        minfo_program: Some(MinfoCli::Cdrskin),
+        ..Default::default()
+    };
+    let error = minfo_track_info(&disc).unwrap_err();
+    let Error::MinfoFailed { stderr, .. } = &error else {
+        panic!("not a failure of the program: {error}");
+    };
+    assert_eq!(stderr, b"Kein Medium eingelegt \xe4\n");
+    assert!(error.to_string().contains("Kein Medium eingelegt \u{fffd}"));
+    // cdrskin is found now, but a disc asking for another program still gets it
+    let other = DiscArgs {
+        minfo_program: Some(MinfoCli::Wodim),
+        ..disc.clone()
+    };
+    assert_eq!(minfo_program(&other), MinfoCli::Wodim);
+    assert_eq!(minfo_program(&DiscArgs::default()), MinfoCli::Cdrskin);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
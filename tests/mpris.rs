@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+use pseudo_cd_player::mpris::Value;
+
+fn round_trip(value: Value) {
+    // an odd start, so alignment padding is needed
+    let mut buf = vec![0xff];
+    value.marshal(&mut buf);
+    let mut pos = 1;
+    let read = Value::unmarshal(&value.signature(), &buf, &mut pos).unwrap();
+    assert_eq!(read, value);
+    assert_eq!(pos, buf.len());
+}
+
+#[test]
+fn values_round_trip() {
+    round_trip(Value::Byte(7));
+    round_trip(Value::Bool(true));
+    round_trip(Value::Int32(-3));
+    round_trip(Value::Uint32(u32::MAX));
+    round_trip(Value::Int64(-180_000_000));
+    round_trip(Value::Double(0.75));
+    round_trip(Value::Str("春の海".into()));
+    round_trip(Value::ObjectPath("/pseudo_cd/track/3".into()));
+    round_trip(Value::Signature("a{sv}".into()));
+    round_trip(Value::Array("s".into(), Vec::new()));
+    round_trip(Value::Array(
+        "x".into(),
+        vec![Value::Int64(1), Value::Int64(2)],
+    ));
+    round_trip(Value::Struct(vec![
+        Value::Byte(1),
+        Value::Variant(Box::new(Value::ObjectPath("/".into()))),
+    ]));
+    round_trip(Value::Array(
+        "{sv}".into(),
+        vec![
+            Value::DictEntry(
+                Box::new(Value::Str("xesam:title".into())),
+                Box::new(Value::Variant(Box::new(Value::Str("Spring".into())))),
+            ),
+            Value::DictEntry(
+                Box::new(Value::Str("mpris:length".into())),
+                Box::new(Value::Variant(Box::new(Value::Int64(1)))),
+            ),
+        ],
+    ));
+}
+
+#[test]
+fn malformed_input_is_an_error() {
+    let unmarshal = |signature: &str, buf: &[u8]| Value::unmarshal(signature, buf, &mut 0);
+    // empty and incomplete signatures
+    assert!(unmarshal("", &[0; 8]).is_err());
+    assert!(unmarshal("a", &[0; 8]).is_err());
+    assert!(unmarshal("(", &[0; 8]).is_err());
+    assert!(unmarshal("(ii", &[0; 8]).is_err());
+    assert!(unmarshal("{sv", &[0; 8]).is_err());
+    assert!(unmarshal("()", &[0; 8]).is_err());
+    assert!(unmarshal("a()", &[8, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    assert!(unmarshal("{s}", &[0; 8]).is_err());
+    assert!(unmarshal("z", &[0; 8]).is_err());
+    // a variant with an empty signature, and one with an incomplete one
+    assert!(unmarshal("v", &[0, 0]).is_err());
+    assert!(unmarshal("v", &[1, b'a', 0]).is_err());
+    assert!(unmarshal("v", &[1, b'(', 0]).is_err());
+    // truncated data
+    assert!(unmarshal("u", &[1, 2]).is_err());
+    assert!(unmarshal("s", &[9, 0, 0, 0, b'a', 0]).is_err());
+    assert!(unmarshal("s", &[1, 0, 0, 0, 0xff, 0]).is_err());
+    assert!(unmarshal("as", &[16, 0, 0, 0, 1, 0, 0, 0]).is_err());
+    assert!(unmarshal("g", &[]).is_err());
+}
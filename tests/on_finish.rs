@@ -0,0 +1,20 @@
+use clap::Parser;
+use pseudo_cd_player::cli::Args;
+use pseudo_cd_player::on_finish::OnFinish;
+
+fn on_finish(arg: &str) -> Result<Option<OnFinish>, clap::Error> {
+    Args::try_parse_from(["pseudo-cd-player", "--on-finish", arg]).map(|x| x.ui.on_finish)
+}
+
+#[test]
+fn on_finish_actions() {
+    assert_eq!(on_finish("exit").unwrap(), Some(OnFinish::Exit));
+    assert_eq!(on_finish("eject").unwrap(), Some(OnFinish::Eject));
+    assert_eq!(on_finish("suspend").unwrap(), Some(OnFinish::Suspend));
+    assert_eq!(
+        on_finish("command:shutdown -h now").unwrap(),
+        Some(OnFinish::Command("shutdown -h now".into()))
+    );
+    assert!(on_finish("command: ").is_err());
+    assert!(on_finish("reboot").is_err());
+}
@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{sleep, spawn};
+use std::time::{Duration, Instant};
+
+use pseudo_cd_player::cli::DiscArgs;
+use pseudo_cd_player::effects::AudioEffect;
+use pseudo_cd_player::error::Error;
+use pseudo_cd_player::play_mode::{playlist_finished, skipped_song, song_after_end, RepeatMode};
+use pseudo_cd_player::playback::{
+    duration_from_bytes, frame_start, start_player_thread, stepped_volume, EventBus,
+    PlaybackHandle, PlayerCallbackEvent, PlayerCommand, ReadErrorStats, AUDIO_BIT_DEPTH,
+    AUDIO_SAMPLE_RATE,
+};
+use pseudo_cd_player::sniff::SNIFF_SIZE;
+use pseudo_cd_player::source::DiscSource;
+use pseudo_cd_player::virtual_disc::VirtualDisc;
+use pseudo_cd_player::{
+    meta_info_candidates, parse_meta_info, read_meta_info, MetaInfo, SECTOR_SIZE,
+};
+
+/// Seconds of each song
+const SONG_LENGTHS: [f64; 3] = [1.0, 0.5, 2.0];
+/// Left channel samples count frames up to this and start over
+const FRAME_COUNTER_WRAP: usize = 20000;
+
+/// Frames of song `tag` (from one) carry the frame number on the left and the tag on the
+/// right, so where playback is can be told from any sample
+fn song_pcm(tag: i16, seconds: f64) -> Vec<u8> {
+    let frames = (seconds * AUDIO_SAMPLE_RATE as f64) as usize;
+    (0..frames)
+        .flat_map(|i| [(i % FRAME_COUNTER_WRAP) as i16, tag])
+        .flat_map(i16::to_le_bytes)
+        .collect()
+}
+
+fn test_disc() -> VirtualDisc {
+    let meta_info = MetaInfo::new(
+        Some("Test Disc".into()),
+        ["One", "Two", "Three"].map(String::from),
+    );
+    let songs = SONG_LENGTHS
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| song_pcm(i as i16 + 1, x));
+    VirtualDisc::pseudo_cd(&meta_info, songs).unwrap()
+}
+
+fn next_frame(samples: &Receiver<i16>) -> (i16, i16) {
+    let timeout = Duration::from_secs(10);
+    let left = samples.recv_timeout(timeout).unwrap();
+    let right = samples.recv_timeout(timeout).unwrap();
+    (left, right)
+}
+
+/// Stops the player, taking samples it's still sending meanwhile
+fn stop(handle: &PlaybackHandle, samples: Receiver<i16>) {
+    let drain = spawn(move || samples.iter().count());
+    assert!(handle.stop_and_wait());
+    drain.join().unwrap();
+}
+
+fn start_player(source: Arc<dyn DiscSource>, events: EventBus) -> (PlaybackHandle, Receiver<i16>) {
+    let (sample_tx, sample_rx) = sync_channel(1024);
+    let handle = start_player_thread(source, sample_tx, events, Arc::default());
+    handle.send(PlayerCommand::Start);
+    (handle, sample_rx)
+}
+
+#[test]
+fn disc_layout() {
+    let disc = test_disc();
+    assert_eq!(disc.tracks.len(), 4);
+    for (a, b) in disc.tracks.iter().zip(&disc.tracks[1..]) {
+        assert_eq!(a.end_addr + 1, b.start_addr);
+    }
+    let last = disc.tracks.last().unwrap();
+    assert_eq!(last.end_offset(), disc.image.len() as u64);
+
+    let meta_info = read_meta_info(&DiscArgs::default(), &disc.source(), &disc.tracks).unwrap();
+    // durations are truncated to whole seconds, after padding to whole sectors
+    assert_eq!(meta_info.song_durations(&disc.tracks), [1, 0, 2]);
+}
+
+#[test]
+fn songs_in_missing_sessions_or_empty_tracks_arent_played() {
+    let disc = test_disc();
+    let meta_info = read_meta_info(&DiscArgs::default(), &disc.source(), &disc.tracks).unwrap();
+    assert_eq!(meta_info.song_track(0, &disc.tracks), Some(disc.tracks[1]));
+    // the disc lost its last session
+    let tracks_lost = &disc.tracks[..3];
+    assert_eq!(meta_info.song_track(2, tracks_lost), None);
+    assert_eq!(meta_info.song_track(3, tracks_lost), None);
+    assert_eq!(meta_info.song_durations(tracks_lost), [1, 0, 0]);
+    let zero = parse_meta_info(br#"{"list": [{"name": "Zero", "session_no": 0}]}"#).unwrap();
+    assert_eq!(zero.song_track(0, &disc.tracks), None);
+
+    let mut tracks = disc.tracks.clone();
+    tracks[2].size = 0;
+    assert!(tracks[2].is_empty());
+    assert_eq!(meta_info.playable_track(1, &tracks), Err("is empty"));
+    assert_eq!(
+        meta_info.playable_track(2, tracks_lost),
+        Err("isn't on the disc")
+    );
+    assert_eq!(meta_info.playable_track(0, &tracks), Ok(disc.tracks[1]));
+}
+
+#[test]
+fn the_track_holding_the_meta_info_is_suggested() {
+    let disc = test_disc();
+    let wrong = DiscArgs {
+        meta_info_track: 2,
+        ..Default::default()
+    };
+    match read_meta_info(&wrong, &disc.source(), &disc.tracks) {
+        Err(Error::MetadataElsewhere {
+            track, candidates, ..
+        }) => {
+            assert_eq!((track, candidates), (2, vec![1]));
+        }
+        x => panic!("{x:?}"),
+    }
+    assert!(meta_info_candidates(&disc.source(), &disc.tracks, 1).is_empty());
+}
+
+#[test]
+fn plays_a_song_from_its_start() {
+    let disc = test_disc();
+    let (handle, samples) = start_player(disc.source(), EventBus::new());
+    handle.send(PlayerCommand::Goto(disc.tracks[1], true));
+    for i in 0..AUDIO_SAMPLE_RATE as usize {
+        assert_eq!(next_frame(&samples), ((i % FRAME_COUNTER_WRAP) as i16, 1));
+    }
+    stop(&handle, samples);
+}
+
+#[test]
+fn a_track_of_data_is_left_paused() {
+    let disc = test_disc();
+    let bus = EventBus::new();
+    let events = bus.channel();
+    let (handle, samples) = start_player(disc.source(), bus);
+    // the meta info's session, mistaken for a song
+    handle.send(PlayerCommand::Goto(disc.tracks[0], true));
+    assert_eq!(handle.is_paused(), Some(true));
+    let events = events.try_iter().collect::<Vec<_>>();
+    assert!(events.contains(&PlayerCallbackEvent::Paused(true)));
+    assert!(events.contains(&PlayerCallbackEvent::NotAudio("JSON text")));
+    assert!(samples.try_recv().is_err());
+    stop(&handle, samples);
+}
+
+#[test]
+fn seeks_within_the_song() {
+    let disc = test_disc();
+    let bus = EventBus::new();
+    let events = bus.channel();
+    let (handle, samples) = start_player(disc.source(), bus);
+    handle.send(PlayerCommand::Goto(disc.tracks[3], false));
+    handle.send(PlayerCommand::Seek(1.0));
+    assert_eq!(handle.position(), Some(1.0));
+    let progress = events
+        .try_iter()
+        .filter(|x| matches!(x, PlayerCallbackEvent::Progress(..)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        progress,
+        [
+            PlayerCallbackEvent::Progress(0, 2),
+            PlayerCallbackEvent::Progress(1, 2)
+        ]
+    );
+
+    handle.send(PlayerCommand::Play);
+    let frame = AUDIO_SAMPLE_RATE as usize;
+    assert_eq!(
+        next_frame(&samples),
+        ((frame % FRAME_COUNTER_WRAP) as i16, 3)
+    );
+    stop(&handle, samples);
+}
+
+#[test]
+fn frames_start_from_the_track() {
+    assert_eq!(frame_start(0, 7), 4);
+    assert_eq!(frame_start(2, 7), 6);
+    assert_eq!(frame_start(2, 6), 6);
+    assert_eq!(frame_start(8, 8), 8);
+    // not moved before the start
+    assert_eq!(frame_start(8, 3), 3);
+}
+
+#[test]
+fn seeks_land_on_whole_frames() {
+    let disc = test_disc();
+    let bus = EventBus::new();
+    let events = bus.channel();
+    let (handle, samples) = start_player(disc.source(), bus);
+    handle.send(PlayerCommand::Goto(disc.tracks[3], false));
+    // a third of a second is 14700 frames, of 4 bytes; a sample more is still in that one
+    handle.send(PlayerCommand::Seek((58800.0 + 2.0) / 176400.0));
+    handle.send(PlayerCommand::Play);
+    assert_eq!(next_frame(&samples), (14700, 3));
+    assert_eq!(next_frame(&samples), (14701, 3));
+
+    // past the end, it's finished without reading on into what's after the track
+    handle.send(PlayerCommand::Seek(100.0));
+    let timeout = Duration::from_secs(10);
+    while events.recv_timeout(timeout).unwrap() != PlayerCallbackEvent::Finished {}
+    assert_eq!(
+        handle.position(),
+        Some(duration_from_bytes(disc.tracks[3].size_bytes()))
+    );
+    stop(&handle, samples);
+}
+
+#[test]
+fn goes_to_the_next_song_when_finished() {
+    let disc = test_disc();
+    let bus = EventBus::new();
+    let events = bus.channel();
+    let (handle, samples) = start_player(disc.source(), bus.clone());
+    let playing = Arc::new(Mutex::new(1));
+    {
+        let tracks = disc.tracks.clone();
+        let playing = Arc::clone(&playing);
+        let handle = handle.clone();
+        bus.subscribe(move |event| {
+            if event != PlayerCallbackEvent::Finished {
+                return;
+            }
+            let mut playing = playing.lock().unwrap();
+            let (next, play) = song_after_end(&[0, 1, 2], *playing, RepeatMode::All);
+            if handle.try_send(PlayerCommand::Goto(tracks[next + 1], play)) {
+                *playing = next;
+            }
+        });
+    }
+    handle.send(PlayerCommand::Goto(disc.tracks[2], true));
+
+    // song 2, then its padding of silence, then song 3 from its start
+    let song_frames = (SONG_LENGTHS[1] * AUDIO_SAMPLE_RATE as f64) as usize;
+    let track_frames = disc.tracks[2].size_bytes() as usize / 4;
+    for i in 0..track_frames {
+        let expected = match i < song_frames {
+            true => ((i % FRAME_COUNTER_WRAP) as i16, 2),
+            false => (0, 0),
+        };
+        assert_eq!(next_frame(&samples), expected);
+    }
+    assert_eq!(next_frame(&samples), (0, 3));
+    assert_eq!(*playing.lock().unwrap(), 2);
+    stop(&handle, samples);
+
+    // every subscriber hears it, once
+    let finished = events
+        .try_iter()
+        .filter(|&x| x == PlayerCallbackEvent::Finished)
+        .count();
+    assert_eq!(finished, 1);
+}
+
+/// Swaps the channels
+struct SwapChannels;
+
+impl AudioEffect for SwapChannels {
+    fn process(&mut self, samples: &mut [i16]) {
+        for frame in samples.chunks_mut(2) {
+            frame.swap(0, 1);
+        }
+    }
+}
+
+#[test]
+fn user_effects_run_after_the_volume() {
+    let disc = test_disc();
+    let (handle, samples) = start_player(disc.source(), EventBus::new());
+    handle.send_commands([
+        PlayerCommand::ChangeVolume(0.5),
+        PlayerCommand::SetEffects(vec![Box::new(SwapChannels)]),
+        PlayerCommand::Goto(disc.tracks[2], true),
+    ]);
+    for i in 0..100 {
+        assert_eq!(next_frame(&samples), (1, i / 2));
+    }
+    stop(&handle, samples);
+}
+
+#[test]
+fn song_after_end_follows_the_repeat_mode() {
+    let order = [2, 0, 1];
+    assert_eq!(song_after_end(&order, 2, RepeatMode::All), (0, true));
+    assert_eq!(song_after_end(&order, 1, RepeatMode::All), (2, true));
+    assert_eq!(song_after_end(&order, 0, RepeatMode::One), (0, true));
+    assert_eq!(song_after_end(&order, 0, RepeatMode::Off), (1, true));
+    // after the last song, the first one is only paused at
+    assert_eq!(song_after_end(&order, 1, RepeatMode::Off), (2, false));
+    // with repeat single, every next song is only paused at
+    assert_eq!(song_after_end(&order, 2, RepeatMode::Single), (0, false));
+    assert_eq!(song_after_end(&order, 1, RepeatMode::Single), (2, false));
+    // nothing to go to without songs
+    assert_eq!(song_after_end(&[], 0, RepeatMode::All), (0, false));
+}
+
+#[test]
+fn skipping_wraps_around_only_if_asked() {
+    let order = [2, 0, 1];
+    assert_eq!(skipped_song(&order, 2, 1, true), Some(0));
+    assert_eq!(skipped_song(&order, 2, -1, false), None);
+    assert_eq!(skipped_song(&order, 2, -1, true), Some(1));
+    assert_eq!(skipped_song(&order, 1, 1, true), Some(2));
+    assert_eq!(skipped_song(&order, 1, 1, false), None);
+    assert_eq!(skipped_song(&order, 1, -1, false), Some(0));
+    assert_eq!(skipped_song(&[], 0, 1, true), None);
+}
+
+#[test]
+fn playlist_finishes_after_the_last_song_with_repeat_off() {
+    let order = [2, 0, 1];
+    assert!(playlist_finished(&order, 1, RepeatMode::Off));
+    assert!(!playlist_finished(&order, 0, RepeatMode::Off));
+    // stopping after each song isn't finishing the playlist
+    assert!(!playlist_finished(&order, 0, RepeatMode::Single));
+    assert!(!playlist_finished(&order, 1, RepeatMode::Single));
+    assert!(!playlist_finished(&order, 1, RepeatMode::All));
+    assert!(!playlist_finished(&order, 1, RepeatMode::One));
+}
+
+/// A disc whose reads hang until it's released, like a bad one can
+struct StuckSource {
+    released: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl DiscSource for StuckSource {
+    fn read_at(&self, _buf: &mut [u8], _offset: u64) -> io::Result<usize> {
+        let guard = self.released.lock().unwrap();
+        drop(self.condvar.wait_while(guard, |x| !*x).unwrap());
+        Err(io::Error::other("released"))
+    }
+
+    fn len(&self) -> u64 {
+        u64::MAX
+    }
+}
+
+#[test]
+fn stopping_gives_up_on_a_stuck_player() {
+    let disc = test_disc();
+    let source = Arc::new(StuckSource {
+        released: Mutex::new(false),
+        condvar: Condvar::new(),
+    });
+    let (sample_tx, _samples) = sync_channel(1024);
+    let handle = start_player_thread(source.clone(), sample_tx, EventBus::new(), Arc::default());
+    handle.send_commands([
+        PlayerCommand::Start,
+        PlayerCommand::Goto(disc.tracks[1], true),
+    ]);
+
+    // nor does asking it wait for ever
+    let start = Instant::now();
+    assert_eq!(handle.position(), None);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let start = Instant::now();
+    assert!(!handle.stop_and_wait());
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    *source.released.lock().unwrap() = true;
+    source.condvar.notify_all();
+}
+
+/// A disc on which reads of some sectors fail, each as many times as given; reads stop
+/// short of them, like a drive reporting what it read before the error
+struct FlakySource {
+    disc: Arc<dyn DiscSource>,
+    failures: Mutex<HashMap<u64, u32>>,
+}
+
+impl DiscSource for FlakySource {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let sector = offset / SECTOR_SIZE;
+        let mut failures = self.failures.lock().unwrap();
+        if let Some(n @ 1..) = failures.get_mut(&sector) {
+            *n -= 1;
+            return Err(io::Error::other("bad sector"));
+        }
+        let next_bad = failures
+            .iter()
+            .filter(|(&x, &n)| x > sector && n > 0)
+            .map(|(&x, _)| x * SECTOR_SIZE)
+            .min();
+        let len = match next_bad {
+            Some(x) => buf.len().min((x - offset) as usize),
+            None => buf.len(),
+        };
+        self.disc.read_at(&mut buf[..len], offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.disc.len()
+    }
+}
+
+#[test]
+fn retries_and_skips_bad_sectors() {
+    let disc = test_disc();
+    let track = disc.tracks[1];
+    // past what's read to see if the track is audio
+    let (recovered, bad) = (track.start_addr + 22, track.start_addr + 25);
+    let source = Arc::new(FlakySource {
+        disc: disc.source(),
+        failures: Mutex::new(HashMap::from([(recovered, 1), (bad, u32::MAX)])),
+    });
+    let bus = EventBus::new();
+    let events = bus.channel();
+    let (handle, samples) = start_player(source, bus);
+    let drain = spawn(move || samples.iter().count());
+    handle.send(PlayerCommand::Goto(track, true));
+
+    let mut read_errors = Vec::new();
+    loop {
+        match events.recv_timeout(Duration::from_secs(10)).unwrap() {
+            PlayerCallbackEvent::Finished => break,
+            PlayerCallbackEvent::SectorSkipped(x) => assert_eq!(x, bad),
+            PlayerCallbackEvent::ReadErrors(x) => read_errors.push(x),
+            _ => {}
+        }
+    }
+    assert!(handle.stop_and_wait());
+    // all but the skipped sector is played
+    let sample_bytes = AUDIO_BIT_DEPTH as u64 / 8;
+    let played = drain.join().unwrap() as u64 * sample_bytes;
+    assert_eq!(played, track.size_bytes() - SECTOR_SIZE);
+    assert_eq!(
+        read_errors,
+        [
+            ReadErrorStats {
+                retried: 1,
+                skipped: 0
+            },
+            ReadErrorStats {
+                retried: 1,
+                skipped: 1
+            }
+        ]
+    );
+    assert_eq!(
+        read_errors[1].summary().unwrap(),
+        "1 sector skipped, 1 re-read"
+    );
+}
+
+/// A disc noting how much each read asks for
+struct RecordingSource {
+    disc: Arc<dyn DiscSource>,
+    read_sizes: Mutex<Vec<usize>>,
+}
+
+impl DiscSource for RecordingSource {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.read_sizes.lock().unwrap().push(buf.len());
+        self.disc.read_at(buf, offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.disc.len()
+    }
+}
+
+#[test]
+fn reads_further_ahead_after_an_underrun() {
+    let disc = test_disc();
+    let source = Arc::new(RecordingSource {
+        disc: disc.source(),
+        read_sizes: Mutex::new(Vec::new()),
+    });
+    let (handle, samples) = start_player(source.clone(), EventBus::new());
+    handle.send(PlayerCommand::Goto(disc.tracks[3], true));
+    next_frame(&samples);
+    handle.stats().underruns.fetch_add(1, Ordering::Relaxed);
+    for _ in 0..AUDIO_SAMPLE_RATE / 2 {
+        next_frame(&samples);
+    }
+    stop(&handle, samples);
+
+    let read_sizes = source.read_sizes.lock().unwrap();
+    // the first read is of the track's beginning, to see if it's audio
+    assert_eq!(read_sizes[0], SNIFF_SIZE);
+    assert_eq!(read_sizes.last(), Some(&(read_sizes[1] * 2)));
+}
+
+#[test]
+fn underruns_of_another_player_dont_count() {
+    let disc = test_disc();
+    let source = Arc::new(RecordingSource {
+        disc: disc.source(),
+        read_sizes: Mutex::new(Vec::new()),
+    });
+    let (handle, samples) = start_player(source.clone(), EventBus::new());
+    let (other, other_samples) = start_player(disc.source(), EventBus::new());
+    handle.send(PlayerCommand::Goto(disc.tracks[3], true));
+    next_frame(&samples);
+    other.stats().underruns.fetch_add(1, Ordering::Relaxed);
+    for _ in 0..AUDIO_SAMPLE_RATE / 2 {
+        next_frame(&samples);
+    }
+    stop(&handle, samples);
+    stop(&other, other_samples);
+
+    let read_sizes = source.read_sizes.lock().unwrap();
+    assert_eq!(read_sizes.last(), Some(&read_sizes[1]));
+}
+
+#[test]
+fn volume_steps_stay_in_range_on_whole_percents() {
+    assert_eq!(stepped_volume(0.5, 1, 0.01), 0.51);
+    assert_eq!(stepped_volume(0.5, -5, 0.02), 0.4);
+    // no drift after many steps
+    let volume = (0..30).fold(0.0, |x, _| stepped_volume(x, 1, 0.03));
+    assert_eq!(volume, 0.9);
+    assert_eq!(stepped_volume(0.98, 5, 0.01), 1.0);
+    assert_eq!(stepped_volume(0.02, -1, 0.05), 0.0);
+}
+
+/// A drive noting how many times it was spun down
+struct SpinningDrive {
+    disc: Arc<dyn DiscSource>,
+    spin_downs: AtomicU32,
+}
+
+impl DiscSource for SpinningDrive {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.disc.read_at(buf, offset)
+    }
+
+    fn len(&self) -> u64 {
+        self.disc.len()
+    }
+
+    fn spin_down(&self) -> io::Result<()> {
+        self.spin_downs.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[test]
+fn spins_down_after_a_long_pause_and_plays_on_where_it_was() {
+    let disc = test_disc();
+    let drive = Arc::new(SpinningDrive {
+        disc: disc.source(),
+        spin_downs: AtomicU32::new(0),
+    });
+    let (handle, samples) = start_player(drive.clone(), EventBus::new());
+    handle.send_commands([
+        PlayerCommand::SetSpinDown(Some(Duration::from_millis(50))),
+        PlayerCommand::Goto(disc.tracks[1], true),
+    ]);
+    for i in 0..100 {
+        assert_eq!(next_frame(&samples), (i, 1));
+    }
+    handle.send(PlayerCommand::Pause);
+    // the samples sent meanwhile come before the reply
+    let frames = (handle.position().unwrap() * AUDIO_SAMPLE_RATE as f64) as usize;
+    let sent = samples.try_iter().count();
+    sleep(Duration::from_millis(300));
+    assert_eq!(drive.spin_downs.load(Ordering::Relaxed), 1);
+
+    handle.send(PlayerCommand::Play);
+    assert_eq!(100 + sent / 2, frames);
+    assert_eq!(next_frame(&samples), (frames as i16, 1));
+    stop(&handle, samples);
+    assert_eq!(drive.spin_downs.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,19 @@
+use pseudo_cd_player::remote::{PlaybackState, PlayerStatus, SongStatus};
+
+#[test]
+fn goto_takes_songs_on_the_disc() {
+    let status = PlayerStatus {
+        state: PlaybackState::Playing,
+        songs: vec![SongStatus::default(); 10],
+        ..Default::default()
+    };
+    assert!(status.check_song_number(1).is_ok());
+    assert!(status.check_song_number(10).is_ok());
+    assert_eq!(
+        status.check_song_number(99),
+        Err("No song 99; the disc has 10".into())
+    );
+    assert!(status.check_song_number(0).is_err());
+    // before the disc is ready
+    assert!(PlayerStatus::default().check_song_number(1).is_err());
+}
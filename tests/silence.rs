@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use pseudo_cd_player::silence::{find_gaps, proposed_chapters, Gap, SilenceOptions};
+
+/// `seconds` of stereo PCM, a square wave if `loud`, or silence
+fn pcm(seconds: f64, loud: bool) -> Vec<u8> {
+    let frames = (44100.0 * seconds) as usize;
+    (0..frames)
+        .flat_map(|i| {
+            let x: i16 = match (loud, i / 50 % 2) {
+                (false, _) => 0,
+                (true, 0) => 8000,
+                (true, _) => -8000,
+            };
+            [x, x]
+        })
+        .flat_map(i16::to_le_bytes)
+        .collect()
+}
+
+#[test]
+fn finds_gaps_between_parts() {
+    let data = [
+        pcm(1.0, false),
+        pcm(3.0, true),
+        pcm(3.0, false),
+        pcm(2.0, true),
+        // a pause within a part
+        pcm(0.5, false),
+        pcm(2.0, true),
+        pcm(4.0, false),
+    ]
+    .concat();
+    let gaps = find_gaps(&data[..], &SilenceOptions::default()).unwrap();
+    assert_eq!(
+        gaps,
+        [Gap {
+            start: Duration::from_secs(4),
+            end: Duration::from_secs(7),
+        }]
+    );
+    let offsets = proposed_chapters(&gaps)
+        .iter()
+        .map(|x| x.offset)
+        .collect::<Vec<_>>();
+    assert_eq!(offsets, [0, 7]);
+
+    let options = SilenceOptions {
+        min_gap: Duration::from_millis(400),
+        ..Default::default()
+    };
+    assert_eq!(find_gaps(&data[..], &options).unwrap().len(), 2);
+}
@@ -0,0 +1,48 @@
+use pseudo_cd_player::sniff::{non_audio_content, SNIFF_SIZE};
+use pseudo_cd_player::SECTOR_SIZE;
+
+/// [`SNIFF_SIZE`] bytes of quiet PCM, with `id` at byte 1 of `sector`
+fn with_identifier(sector: usize, id: &[u8]) -> Vec<u8> {
+    let mut head = (0..SNIFF_SIZE / 2)
+        .flat_map(|i| ((i % 64) as i16 - 32).to_le_bytes())
+        .collect::<Vec<_>>();
+    let at = sector * SECTOR_SIZE as usize + 1;
+    head[at..at + id.len()].copy_from_slice(id);
+    head
+}
+
+#[test]
+fn data_isnt_taken_for_audio() {
+    assert_eq!(
+        non_audio_content(&with_identifier(16, b"CD001")),
+        Some("an ISO 9660 file system")
+    );
+    assert_eq!(
+        non_audio_content(&with_identifier(17, b"NSR02")),
+        Some("a UDF file system")
+    );
+    assert_eq!(
+        non_audio_content(br#"  {"title": "Test Disc", "list": []}"#),
+        Some("JSON text")
+    );
+    assert_eq!(non_audio_content(b"Hello, world\n\0\0"), Some("text"));
+    // the meta info, padded to a sector
+    let mut meta_info = br#"{"list": []}"#.to_vec();
+    meta_info.resize(SECTOR_SIZE as usize, 0);
+    assert_eq!(non_audio_content(&meta_info), Some("JSON text"));
+    assert_eq!(non_audio_content(b"\x1f\x8b\x08\x00"), Some("gzip data"));
+}
+
+#[test]
+fn audio_and_silence_are_audio() {
+    assert_eq!(non_audio_content(&with_identifier(15, b"CD001")), None);
+    assert_eq!(non_audio_content(&[0; SNIFF_SIZE]), None);
+    assert_eq!(non_audio_content(&[]), None);
+    assert_eq!(non_audio_content(b"Hi\0\0"), None);
+    assert_eq!(non_audio_content(b"Hello, world\0\x01"), None);
+    // a sector of the identifier cut short
+    assert_eq!(
+        non_audio_content(&with_identifier(16, b"CD001")[..16 * SECTOR_SIZE as usize + 3]),
+        None
+    );
+}
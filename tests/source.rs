@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use pseudo_cd_player::source;
+
+/// A file of `data` in the temporary directory, removed on drop
+struct TempImage(PathBuf);
+
+impl TempImage {
+    fn new(name: &str, data: &[u8]) -> Self {
+        let path = std::env::temp_dir().join(format!("pseudo-cd-{}-{name}", process::id()));
+        fs::write(&path, data).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempImage {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn image_file() {
+    let data = (0..10000).map(|x| x as u8).collect::<Vec<_>>();
+    let image = TempImage::new("image", &data);
+    let source = source::open(&image.0).unwrap();
+    assert_eq!(source.len(), data.len() as u64);
+
+    let mut buf = [0_u8; 4096];
+    assert_eq!(source.read_at(&mut buf, 5000).unwrap(), 4096);
+    assert_eq!(buf[..], data[5000..9096]);
+    // short at the end, then nothing
+    assert_eq!(source.read_at(&mut buf, 8000).unwrap(), 2000);
+    assert_eq!(buf[..2000], data[8000..]);
+    assert_eq!(source.read_at(&mut buf, 10000).unwrap(), 0);
+    assert_eq!(source.read_at(&mut buf, u64::MAX).unwrap(), 0);
+}
+
+#[test]
+fn empty_image_file() {
+    let image = TempImage::new("empty", &[]);
+    let source = source::open(&image.0).unwrap();
+    assert!(source.is_empty());
+    assert_eq!(source.read_at(&mut [0; 16], 0).unwrap(), 0);
+}
+
+#[cfg(feature = "external")]
+#[test]
+fn source_image_with_its_tracks_table() {
+    use pseudo_cd_player::cli::DiscArgs;
+    use pseudo_cd_player::minfo::minfo_track_info;
+
+    let image = TempImage::new("source.img", &[7; 4096]);
+    let minfo = include_str!("fixtures/minfo/cdrskin-dvd+r-appendable.txt");
+    let table = TempImage(PathBuf::from(format!("{}.minfo", image.0.display())));
+    fs::write(&table.0, minfo).unwrap();
+    let disc = DiscArgs {
+        source: Some(image.0.display().to_string()),
+        ..Default::default()
+    };
+    let source = source::open_disc(&disc, &[]).unwrap();
+    assert_eq!(source.len(), 4096);
+    assert_eq!(minfo_track_info(&disc).unwrap().len(), 6);
+
+    fs::remove_file(&table.0).unwrap();
+    assert!(minfo_track_info(&disc).is_err());
+}
+
+/// CRC-32 of gzip members
+#[cfg(feature = "external")]
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &x in data {
+        crc ^= x as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// A BGZF block of `data`, stored without compression
+#[cfg(feature = "external")]
+fn bgzf_block(data: &[u8]) -> Vec<u8> {
+    let size = 18 + 5 + data.len() + 8;
+    let mut block = vec![
+        0x1f, 0x8b, 8, 4, 0, 0, 0, 0, 0, 0xff, 6, 0, b'B', b'C', 2, 0,
+    ];
+    block.extend_from_slice(&(size as u16 - 1).to_le_bytes());
+    block.push(1);
+    block.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    block.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+    block.extend_from_slice(data);
+    block.extend_from_slice(&crc32(data).to_le_bytes());
+    block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    block
+}
+
+#[cfg(feature = "external")]
+#[test]
+fn bgzf_image() {
+    use pseudo_cd_player::cli::DiscArgs;
+
+    let data = (0..3_000_000_u32)
+        .map(|x| (x % 251) as u8)
+        .collect::<Vec<_>>();
+    let mut compressed = data.chunks(60000).flat_map(bgzf_block).collect::<Vec<_>>();
+    // the end-of-file marker bgzip writes
+    compressed.extend(bgzf_block(&[]));
+    let image = TempImage::new("image.gz", &compressed);
+    let disc = DiscArgs {
+        source: Some(image.0.display().to_string()),
+        ..Default::default()
+    };
+    let source = source::open_disc(&disc, &[]).unwrap();
+    assert_eq!(source.len(), data.len() as u64);
+
+    // across a frame, and a cached block
+    let mut buf = vec![0_u8; 100000];
+    for offset in [0, 59000, 1048000, 2950000] {
+        let n = source.read_at(&mut buf, offset).unwrap();
+        assert!(n > 0);
+        assert_eq!(buf[..n], data[offset as usize..][..n]);
+    }
+}
@@ -0,0 +1,40 @@
+use pseudo_cd_player::text_width::{marquee, pad, right_aligned_row, truncate, width};
+
+#[test]
+fn wide_characters_take_two_columns() {
+    assert_eq!(width("Spring"), 6);
+    assert_eq!(width("春の海"), 6);
+    assert_eq!(width("🎵 Song"), 7);
+    // a combining acute accent
+    assert_eq!(width("Cafe\u{301}"), 4);
+
+    assert_eq!(truncate("春の海", 5), "春の");
+    assert_eq!(truncate("春の海", 6), "春の海");
+    assert_eq!(pad("春", 4), "春  ");
+}
+
+#[test]
+fn rows_line_up_in_columns() {
+    let row = right_aligned_row("1: 春の海", "03:00", 16);
+    assert_eq!(row, "1: 春の海  03:00");
+    assert_eq!(width(&row), 16);
+    // too long for the row: the left side is cut, clear of the right
+    let row = right_aligned_row("1: 春の海の歌", "03:00", 15);
+    assert_eq!(row, "1: 春の海 03:00");
+    // a wide character that would only half fit is left out
+    let row = right_aligned_row("1: 春の海の歌", "03:00", 14);
+    assert_eq!(row, "1: 春の  03:00");
+    assert_eq!(width(&row), 14);
+}
+
+#[test]
+fn marquee_keeps_its_width() {
+    assert_eq!(marquee("春の海", 6, 3), "春の海");
+    assert_eq!(marquee("春の海", 4, 0), "春の");
+    assert_eq!(marquee("春の海", 4, 1), "の海");
+    // the wide character cut by the end is left blank
+    assert_eq!(marquee("春の海", 5, 0), "春の ");
+    for step in 0..20 {
+        assert_eq!(width(&marquee("Spring 春の海", 7, step)), 7, "{step}");
+    }
+}